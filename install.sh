@@ -15,6 +15,27 @@ LIBEXECDIR="$PREFIX/lib/tuxtuner"
 HOOKSDIR="/etc/tuxtuner/hooks"
 POLKIT_DIR="/usr/share/polkit-1/actions"
 
+# NixOS builds /usr, /etc/systemd and the udev rules from its configuration
+if [[ -e /etc/NIXOS ]]; then
+    echo "NixOS detected: install TuxTuner through your configuration instead."
+    echo "Its Diagnostics dialog shows a configuration.nix snippet for the"
+    echo "udev rules, module options and boot services it saves."
+    exit 1
+fi
+
+# /usr is read-only on ostree systems (Silverblue, Kinoite, ...); the
+# helper and polkit policy go into a systemd-sysext extension that is
+# merged over /usr instead. /usr/local and /etc stay writable.
+SYSEXT_DIR=""
+if [[ -e /run/ostree-booted ]]; then
+    SYSEXT_DIR="/var/lib/extensions/tuxtuner"
+    LIBEXECDIR="$SYSEXT_DIR/usr/lib/tuxtuner"
+    POLKIT_DIR="$SYSEXT_DIR/usr/share/polkit-1/actions"
+    # Paths as they appear once the extension is merged
+    MERGED_LIBEXECDIR="/usr/lib/tuxtuner"
+fi
+MERGED_LIBEXECDIR="${MERGED_LIBEXECDIR:-$LIBEXECDIR}"
+
 echo "TuxTuner Installer"
 echo "=================="
 echo ""
@@ -51,7 +72,7 @@ sudo cp src/tuxtuner-helper "$LIBEXECDIR/tuxtuner-helper"
 sudo chmod +x "$LIBEXECDIR/tuxtuner-helper"
 sudo chmod 755 "$LIBEXECDIR/tuxtuner-helper"
 
-ESCAPED_PATH=$(printf '%s\n' "$LIBEXECDIR/tuxtuner-helper" | sed 's/[&/\]/\\&/g')
+ESCAPED_PATH=$(printf '%s\n' "$MERGED_LIBEXECDIR/tuxtuner-helper" | sed 's/[&/\]/\\&/g')
 sudo sed -i "s|HELPER_PATH = .*|HELPER_PATH = \"$ESCAPED_PATH\"|" "$BINDIR/tuxtuner"
 
 echo "Installing polkit policy..."
@@ -61,13 +82,49 @@ POLICY_FILE="$POLKIT_DIR/com.github.xavrir.tuxtuner.policy"
 if [[ -f "data/com.github.xavrir.tuxtuner.policy" ]]; then
     sudo cp "data/com.github.xavrir.tuxtuner.policy" "$POLICY_FILE"
     
-    sudo sed -i "s|/usr/local/lib/tuxtuner/tuxtuner-helper|$LIBEXECDIR/tuxtuner-helper|g" "$POLICY_FILE"
+    sudo sed -i "s|/usr/local/lib/tuxtuner/tuxtuner-helper|$MERGED_LIBEXECDIR/tuxtuner-helper|g" "$POLICY_FILE"
     
     echo "Polkit policy installed."
 else
     echo "WARNING: Polkit policy file not found in data/. Skipping."
 fi
 
+if [[ -n "$SYSEXT_DIR" ]]; then
+    echo "Activating system extension..."
+    sudo mkdir -p "$SYSEXT_DIR/usr/lib/extension-release.d"
+    echo "ID=_any" | sudo tee "$SYSEXT_DIR/usr/lib/extension-release.d/extension-release.tuxtuner" > /dev/null
+    sudo systemctl enable --now systemd-sysext.service 2>/dev/null || true
+    sudo systemd-sysext refresh
+    echo "System extension active."
+fi
+
+echo "Installing wakeup restore service..."
+SERVICE_FILE="/etc/systemd/system/tuxtuner-wakeup.service"
+if [[ -f "data/tuxtuner-wakeup.service" ]]; then
+    sudo cp "data/tuxtuner-wakeup.service" "$SERVICE_FILE"
+    sudo sed -i "s|/usr/local/lib/tuxtuner/tuxtuner-helper|$MERGED_LIBEXECDIR/tuxtuner-helper|g" "$SERVICE_FILE"
+    sudo systemctl daemon-reload 2>/dev/null || true
+    echo "Wakeup service installed."
+fi
+
+echo "Installing undervolt restore service..."
+SERVICE_FILE="/etc/systemd/system/tuxtuner-undervolt.service"
+if [[ -f "data/tuxtuner-undervolt.service" ]]; then
+    sudo cp "data/tuxtuner-undervolt.service" "$SERVICE_FILE"
+    sudo sed -i "s|/usr/local/lib/tuxtuner/tuxtuner-helper|$MERGED_LIBEXECDIR/tuxtuner-helper|g" "$SERVICE_FILE"
+    sudo systemctl daemon-reload 2>/dev/null || true
+    echo "Undervolt service installed."
+fi
+
+echo "Installing GPU fan curve service..."
+SERVICE_FILE="/etc/systemd/system/tuxtuner-gpu-fan.service"
+if [[ -f "data/tuxtuner-gpu-fan.service" ]]; then
+    sudo cp "data/tuxtuner-gpu-fan.service" "$SERVICE_FILE"
+    sudo sed -i "s|/usr/local/lib/tuxtuner/tuxtuner-helper|$MERGED_LIBEXECDIR/tuxtuner-helper|g" "$SERVICE_FILE"
+    sudo systemctl daemon-reload 2>/dev/null || true
+    echo "GPU fan curve service installed."
+fi
+
 echo ""
 echo "Installation complete!"
 echo ""
@@ -0,0 +1,122 @@
+use crate::battery;
+use crate::battery_history::{self, unix_now};
+use crate::config::Config;
+use crate::fwupd::{self, FirmwareKind, FirmwareUpdate};
+use crate::power::{self, PowerSource};
+use crate::profiles;
+use crate::rules::Action;
+use crate::system_info;
+
+/// Rates above this are worth dropping on battery.
+const BATTERY_MAX_HZ: u32 = 60;
+/// CPU load below which a performance platform profile only burns power.
+const IDLE_LOAD: f64 = 0.10;
+/// Hours of battery history, all of it on AC, before the machine counts
+/// as always plugged in.
+const PLUGGED_IN_HOURS: i64 = 12;
+
+/// Something about the current state worth changing, with the rule action
+/// that changes it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub title: String,
+    pub detail: String,
+    pub fix_label: &'static str,
+    pub fix: Action,
+}
+
+/// The readings suggestions are worked out from.
+#[derive(Debug, Clone, Default)]
+pub struct Observation {
+    pub power: PowerSource,
+    /// Busy fraction of the online CPUs, if it has been sampled.
+    pub cpu_load: Option<f64>,
+    pub refresh_hz: Option<u32>,
+    pub platform_profile: Option<String>,
+    pub charge_limit: Option<u32>,
+    pub always_plugged_in: bool,
+    pub firmware_updates: Vec<FirmwareUpdate>,
+}
+
+/// Reads everything `suggestions` looks at. Blocking.
+pub fn observe(cpu_load: Option<f64>) -> Observation {
+    let since = unix_now() - PLUGGED_IN_HOURS * 3600;
+    let samples = battery_history::load_samples(since);
+    let expected = (PLUGGED_IN_HOURS * 3600 / battery_history::SAMPLE_INTERVAL_SECS as i64) as usize;
+    // Allow for the odd missed sample, e.g. while suspended on AC.
+    let always_plugged_in = samples.len() >= expected * 9 / 10 && samples.iter().all(|s| s.power_mw <= 0);
+
+    Observation {
+        power: power::power_source(),
+        cpu_load,
+        refresh_hz: system_info::fetch_monitors().first().map(|monitor| monitor.refresh_hz),
+        platform_profile: profiles::platform_profile(),
+        charge_limit: battery::charge_limit(),
+        always_plugged_in,
+        firmware_updates: fwupd::recent_updates(),
+    }
+}
+
+/// Suggestions for `observed`, skipping anything automation already handles.
+pub fn suggestions(observed: &Observation, config: &Config) -> Vec<Suggestion> {
+    let mut found = Vec::new();
+
+    if let Some(hz) = observed.refresh_hz {
+        let target = config.automation.battery_refresh_hz;
+        if observed.power == PowerSource::Battery
+            && !config.automation.battery_refresh
+            && hz > BATTERY_MAX_HZ
+            && hz > target
+        {
+            found.push(Suggestion {
+                title: format!("{}Hz on battery", hz),
+                detail: format!("Dropping to {}Hz noticeably extends battery life", target),
+                fix_label: "Lower",
+                fix: Action::RefreshRate(target),
+            });
+        }
+    }
+
+    if let (Some("performance"), Some(load)) = (observed.platform_profile.as_deref(), observed.cpu_load) {
+        if load < IDLE_LOAD {
+            found.push(Suggestion {
+                title: "Performance mode while idle".to_string(),
+                detail: format!("The CPU is {:.0}% busy; Balanced runs cooler and quieter", load * 100.0),
+                fix_label: "Use Balanced",
+                fix: Action::Profile("Balanced".to_string()),
+            });
+        }
+    }
+
+    let travelling = config.battery.full_charge_until.is_some_and(|until| until > unix_now());
+    if observed.charge_limit == Some(battery::MAX_CHARGE_LIMIT) && observed.always_plugged_in && !travelling {
+        let limit = battery::RECOMMENDED_CHARGE_LIMIT;
+        found.push(Suggestion {
+            title: "Charging to 100% while always plugged in".to_string(),
+            detail: format!("Stopping at {}% slows battery wear", limit),
+            fix_label: "Limit",
+            fix: Action::ChargeLimit(limit),
+        });
+    }
+
+    // Old EC firmware is behind much of the fan and thermal misbehavior
+    // no tuning can fix.
+    for update in &observed.firmware_updates {
+        let title = match update.kind {
+            FirmwareKind::EmbeddedController => "Embedded controller firmware update",
+            FirmwareKind::Battery => "Battery firmware update",
+            FirmwareKind::System => continue,
+        };
+        found.push(Suggestion {
+            title: title.to_string(),
+            detail: format!(
+                "{}: {} → {}. Updates often fix fan, thermal and charging problems",
+                update.device, update.current, update.available
+            ),
+            fix_label: "Update",
+            fix: Action::OpenFirmwareUpdater,
+        });
+    }
+
+    found
+}
@@ -0,0 +1,155 @@
+use crate::remote;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+const CPU_PATH: &str = "/sys/devices/system/cpu";
+/// CPU lists of the two core types on Intel hybrid CPUs.
+const PERFORMANCE_CORES_PATH: &str = "/sys/devices/cpu_core/cpus";
+const EFFICIENCY_CORES_PATH: &str = "/sys/devices/cpu_atom/cpus";
+
+/// A running process that can be pinned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Process {
+    pub pid: u32,
+    pub name: String,
+    /// CPUs it may run on, as a list like `0-3,8`.
+    pub cpus: String,
+}
+
+/// A named set of CPUs to pin to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpuSet {
+    pub title: &'static str,
+    pub cpus: Vec<u32>,
+}
+
+/// Parses a kernel CPU list such as `0-3,8,10-11`.
+pub fn parse_cpu_list(list: &str) -> Vec<u32> {
+    list.trim()
+        .split(',')
+        .filter_map(|part| match part.split_once('-') {
+            Some((start, end)) => Some(start.trim().parse().ok()?..=end.trim().parse().ok()?),
+            None => {
+                let cpu = part.trim().parse().ok()?;
+                Some(cpu..=cpu)
+            }
+        })
+        .flatten()
+        .collect()
+}
+
+/// Formats sorted CPUs as a kernel CPU list, joining runs into ranges.
+pub fn format_cpu_list(cpus: &[u32]) -> String {
+    let mut parts = Vec::new();
+    let mut iter = cpus.iter().copied().peekable();
+    while let Some(start) = iter.next() {
+        let mut end = start;
+        while iter.peek() == Some(&(end + 1)) {
+            end = iter.next().unwrap_or(end);
+        }
+        parts.push(if start == end {
+            start.to_string()
+        } else {
+            format!("{}-{}", start, end)
+        });
+    }
+    parts.join(",")
+}
+
+fn read_cpu_list(path: impl AsRef<Path>) -> Vec<u32> {
+    fs::read_to_string(path)
+        .map(|list| parse_cpu_list(&list))
+        .unwrap_or_default()
+}
+
+/// Presets for this CPU: every core, each core type on hybrid CPUs, and
+/// one thread per physical core when SMT is on.
+pub fn presets() -> Vec<CpuSet> {
+    let online = read_cpu_list(Path::new(CPU_PATH).join("online"));
+    let mut presets = vec![CpuSet {
+        title: "All Cores",
+        cpus: online.clone(),
+    }];
+
+    for (title, path) in [
+        ("Performance Cores", PERFORMANCE_CORES_PATH),
+        ("Efficiency Cores", EFFICIENCY_CORES_PATH),
+    ] {
+        let cpus: Vec<u32> = read_cpu_list(path)
+            .into_iter()
+            .filter(|cpu| online.contains(cpu))
+            .collect();
+        if !cpus.is_empty() && cpus != online {
+            presets.push(CpuSet { title, cpus });
+        }
+    }
+
+    // The first thread of each core; its siblings share the core's caches.
+    let first_threads: Vec<u32> = online
+        .iter()
+        .copied()
+        .filter(|&cpu| {
+            let siblings = Path::new(CPU_PATH)
+                .join(format!("cpu{}", cpu))
+                .join("topology/thread_siblings_list");
+            read_cpu_list(siblings).first().is_none_or(|&first| first == cpu)
+        })
+        .collect();
+    if first_threads.len() < online.len() {
+        presets.push(CpuSet {
+            title: "One Thread per Core",
+            cpus: first_threads,
+        });
+    }
+
+    presets
+}
+
+fn allowed_cpus(pid: u32) -> Option<String> {
+    let status = fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Cpus_allowed_list:"))
+        .map(|list| list.trim().to_string())
+}
+
+/// This user's processes, by name. Kernel threads have no command line
+/// and are left out.
+pub fn processes() -> Vec<Process> {
+    let Ok(uid) = fs::metadata("/proc/self").map(|meta| meta.uid()) else {
+        return Vec::new();
+    };
+
+    let mut processes: Vec<Process> = fs::read_dir("/proc")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+            if entry.metadata().ok()?.uid() != uid || pid == std::process::id() {
+                return None;
+            }
+            if fs::read(entry.path().join("cmdline")).ok()?.is_empty() {
+                return None;
+            }
+            Some(Process {
+                pid,
+                name: fs::read_to_string(entry.path().join("comm")).ok()?.trim().to_string(),
+                cpus: allowed_cpus(pid)?,
+            })
+        })
+        .collect();
+
+    processes.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()).then(a.pid.cmp(&b.pid)));
+    processes
+}
+
+/// Pins every thread of `pid` to `cpus`. Threads started later inherit it.
+pub fn pin(pid: u32, cpus: &[u32]) -> Result<(), String> {
+    if cpus.is_empty() {
+        return Err("No CPUs selected".to_string());
+    }
+
+    remote::run_helper(&["affinity", &pid.to_string(), &format_cpu_list(cpus)])
+}
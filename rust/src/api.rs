@@ -0,0 +1,141 @@
+use crate::battery;
+use crate::config::{state_dir, Config};
+use crate::gpu;
+use crate::power::{self, PowerSource};
+use crate::profiles;
+use crate::system_info::SystemInfo;
+use crate::thermal;
+use gtk4::glib;
+use serde_json::json;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+const SOCKET_FILE: &str = "tuxtuner.sock";
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// `$XDG_RUNTIME_DIR/tuxtuner.sock`, falling back to the state directory.
+pub fn socket_path() -> PathBuf {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .unwrap_or_else(state_dir)
+        .join(SOCKET_FILE)
+}
+
+fn status() -> serde_json::Value {
+    let (total_cpus, online_cpus) = SystemInfo::fetch_cpu_info();
+    let power_source = match power::power_source() {
+        PowerSource::Ac => "ac",
+        PowerSource::Battery => "battery",
+        PowerSource::Unknown => "unknown",
+    };
+    json!({
+        "profile": Config::load().active_profile,
+        "platform_profile": profiles::platform_profile(),
+        "gpu_mode": gpu::detect().status().mode,
+        "cpu_threads": { "online": online_cpus, "total": total_cpus },
+        "cpu_temp": thermal::cpu_temperature(),
+        "battery": battery::capacity_percent(),
+        "power_w": battery::power_draw_mw().map(|mw| mw as f64 / 1000.0),
+        "power_source": power_source,
+    })
+}
+
+fn respond(stream: &mut UnixStream, code: &str, body: &serde_json::Value) {
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        code,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes());
+}
+
+/// Percent-decodes a path segment, e.g. `Battery%20Saver`.
+fn decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = (bytes[i] == b'%')
+            .then(|| segment.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match hex {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+fn serve(mut stream: UnixStream, commands: &Sender<String>) {
+    let _ = stream.set_read_timeout(Some(CLIENT_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(CLIENT_TIMEOUT));
+
+    let mut request_line = String::new();
+    if BufReader::new(&stream).read_line(&mut request_line).is_err() {
+        return;
+    }
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+
+    match (method, path.strip_prefix("/profile/")) {
+        ("GET", _) if path == "/status" => respond(&mut stream, "200 OK", &status()),
+        ("POST", Some(name)) => {
+            let name = decode(name);
+            match profiles::find_profile(&Config::load(), &name) {
+                Err(e) => respond(&mut stream, "404 Not Found", &json!({ "error": e })),
+                Ok(profile) if commands.send(profile.name.clone()).is_ok() => {
+                    respond(&mut stream, "202 Accepted", &json!({ "profile": profile.name }));
+                }
+                Ok(_) => {
+                    respond(&mut stream, "503 Service Unavailable", &json!({ "error": "Shutting down" }));
+                }
+            }
+        }
+        _ => respond(&mut stream, "404 Not Found", &json!({ "error": "Unknown endpoint" })),
+    }
+}
+
+/// Serves the JSON control API on a Unix socket only the current user can
+/// open: `GET /status` and `POST /profile/{name}`, e.g.
+/// `curl --unix-socket $XDG_RUNTIME_DIR/tuxtuner.sock -X POST localhost/profile/Balanced`.
+/// Requested profile names arrive on the returned channel.
+pub fn start() -> Result<Receiver<String>, String> {
+    let path = socket_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    // A socket left behind by a previous run refuses new binds.
+    if UnixStream::connect(&path).is_ok() {
+        return Err("Another instance is already listening".to_string());
+    }
+    let _ = fs::remove_file(&path);
+
+    let listener = UnixListener::bind(&path).map_err(|e| e.to_string())?;
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).map_err(|e| e.to_string())?;
+    glib::g_debug!(crate::LOG_DOMAIN, "Control API listening on {}", path.display());
+
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            serve(stream, &sender);
+        }
+    });
+
+    Ok(receiver)
+}
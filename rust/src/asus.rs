@@ -0,0 +1,62 @@
+use crate::probe;
+use crate::system_info::command_exists;
+use std::fs;
+
+const ARMOURY_PATH: &str = "/sys/class/firmware-attributes/asus-armoury/attributes";
+const WMI_PATH: &str = "/sys/devices/platform/asus-nb-wmi";
+
+/// ASUS panel features toggled through asusd.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanelFeature {
+    /// Faster pixel response at the cost of some power draw.
+    Overdrive,
+    /// Local dimming on mini-LED panels.
+    MiniLed,
+}
+
+impl PanelFeature {
+    fn attribute(self) -> &'static str {
+        match self {
+            PanelFeature::Overdrive => "panel_od",
+            PanelFeature::MiniLed => "mini_led_mode",
+        }
+    }
+
+    fn asusctl_flag(self) -> &'static str {
+        match self {
+            PanelFeature::Overdrive => "--panel-overdrive",
+            PanelFeature::MiniLed => "--mini-led-mode",
+        }
+    }
+}
+
+/// Current state of `feature`, or `None` when the firmware doesn't expose
+/// it or asusctl isn't installed to change it.
+pub fn panel_feature(feature: PanelFeature) -> Option<bool> {
+    if !command_exists("asusctl") {
+        return None;
+    }
+
+    let paths = [
+        format!("{}/{}/current_value", ARMOURY_PATH, feature.attribute()),
+        format!("{}/{}", WMI_PATH, feature.attribute()),
+    ];
+    paths.iter().find_map(|path| {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|value| value.trim().parse::<u8>().ok())
+            .map(|value| value != 0)
+    })
+}
+
+pub fn apply_panel_feature(feature: PanelFeature, enabled: bool) -> Result<(), String> {
+    let value = if enabled { "true" } else { "false" };
+    let output = probe::run("asusctl", &["bios", feature.asusctl_flag(), value])
+        .map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).to_string())
+    }
+}
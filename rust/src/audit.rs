@@ -0,0 +1,83 @@
+use crate::remote;
+use serde_json::Value;
+
+/// Journal identifier the helper logs its changes under; keep in sync with
+/// the helper's `AUDIT_IDENTIFIER`.
+const IDENTIFIER: &str = "tuxtuner-helper";
+
+/// journalctl's note when the account can only see its own messages.
+const NO_ACCESS_HINT: &str = "not seeing messages from other users and the system";
+
+/// One privileged change the helper made.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    /// Unix time in seconds.
+    pub time: i64,
+    /// Who asked for the change; empty for entries without structured
+    /// fields, e.g. from a system without `logger --journald`.
+    pub user: String,
+    /// The helper command with its arguments, i.e. the new values.
+    pub command: String,
+    /// Helper commands that would restore the values it replaced, when
+    /// the helper could read them.
+    pub old: Option<String>,
+    pub ok: bool,
+}
+
+impl AuditEntry {
+    fn from_json(entry: &Value) -> Option<Self> {
+        let field = |name: &str| entry.get(name).and_then(Value::as_str);
+        let time = field("__REALTIME_TIMESTAMP")?.parse::<i64>().ok()? / 1_000_000;
+
+        match field("TUXTUNER_COMMAND") {
+            Some(command) => Some(Self {
+                time,
+                user: field("TUXTUNER_USER").unwrap_or_default().to_string(),
+                command: command.to_string(),
+                old: field("TUXTUNER_OLD").filter(|old| !old.is_empty()).map(str::to_string),
+                ok: field("TUXTUNER_RESULT") == Some("ok"),
+            }),
+            None => {
+                let message = field("MESSAGE")?;
+                Some(Self {
+                    time,
+                    user: String::new(),
+                    command: message.to_string(),
+                    old: None,
+                    ok: !message.contains(": failed"),
+                })
+            }
+        }
+    }
+}
+
+/// The newest `limit` changes the helper logged, newest first. Blocking.
+pub fn entries(limit: usize) -> Result<Vec<AuditEntry>, String> {
+    let limit = limit.to_string();
+    let output = remote::command(
+        "journalctl",
+        &[
+            "--identifier",
+            IDENTIFIER,
+            "--output",
+            "json",
+            "--no-pager",
+            "--reverse",
+            "--lines",
+            &limit,
+        ],
+    )
+    .output()
+    .map_err(|e| format!("Couldn't run journalctl: {}", e))?;
+
+    let entries: Vec<AuditEntry> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter_map(|entry| AuditEntry::from_json(&entry))
+        .collect();
+
+    if entries.is_empty() && String::from_utf8_lossy(&output.stderr).contains(NO_ACCESS_HINT) {
+        return Err("Your account can't read the system journal. Add it to the systemd-journal group to see the log.".to_string());
+    }
+    Ok(entries)
+}
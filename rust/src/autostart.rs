@@ -0,0 +1,49 @@
+use crate::config::config_dir;
+use std::fs;
+use std::path::PathBuf;
+
+/// Command-line flag that starts TuxTuner without showing its window.
+pub const BACKGROUND_FLAG: &str = "background";
+
+fn entry_path() -> PathBuf {
+    // config_dir() is $XDG_CONFIG_HOME/tuxtuner; autostart sits beside it.
+    let base = config_dir()
+        .parent()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/tmp"));
+    base.join("autostart").join(format!("{}.desktop", crate::APP_ID))
+}
+
+/// Whether TuxTuner starts in the background at login.
+pub fn enabled() -> bool {
+    entry_path().is_file()
+}
+
+/// Installs or removes the XDG autostart entry.
+pub fn set_enabled(on: bool) -> Result<(), String> {
+    let path = entry_path();
+    if !on {
+        return match fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+            _ => Ok(()),
+        };
+    }
+
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let entry = format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=TuxTuner\n\
+         Comment=Run TuxTuner's automation rules in the background\n\
+         Exec=\"{}\" --{}\n\
+         Icon=preferences-system\n\
+         NoDisplay=true\n\
+         X-GNOME-Autostart-enabled=true\n",
+        exe.display(),
+        BACKGROUND_FLAG
+    );
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    fs::write(&path, entry).map_err(|e| e.to_string())
+}
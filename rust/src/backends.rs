@@ -0,0 +1,25 @@
+use crate::gpu::{self, GpuBackend};
+use crate::sysfs::{LocalSysfs, SysfsProvider};
+use crate::system_info::{DisplayBackend, Hyprctl};
+use std::sync::Arc;
+
+/// What the window reads and drives the machine through. Tests hand the
+/// window fakes instead of this machine's.
+#[derive(Clone)]
+pub struct Backends {
+    pub sysfs: Arc<dyn SysfsProvider>,
+    pub gpu: Arc<dyn GpuBackend>,
+    pub display: Arc<dyn DisplayBackend>,
+}
+
+impl Backends {
+    /// This machine's `/sys`, graphics switcher and compositor.
+    pub fn local() -> Self {
+        let sysfs: Arc<dyn SysfsProvider> = Arc::new(LocalSysfs);
+        Self {
+            gpu: gpu::detect_with(sysfs.clone()).into(),
+            display: Arc::new(Hyprctl::new(sysfs.clone())),
+            sysfs,
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use crate::remote;
+use gtk4::gio;
+use gtk4::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const BACKLIGHT_PATH: &str = "/sys/class/backlight";
+/// Auto-dimming never goes below this, so the screen stays readable.
+const MIN_PERCENT: u32 = 5;
+
+/// Dim the screen by `dim_percent` (of full brightness) once the battery
+/// is below `below_percent`. Steps don't add up: the deepest one reached
+/// sets the total.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DimStep {
+    pub below_percent: u32,
+    pub dim_percent: u32,
+}
+
+pub fn default_dim_steps() -> Vec<DimStep> {
+    vec![
+        DimStep { below_percent: 30, dim_percent: 10 },
+        DimStep { below_percent: 15, dim_percent: 20 },
+    ]
+}
+
+/// Total dimming `steps` call for at `capacity` percent.
+pub fn dim_for(steps: &[DimStep], capacity: u32) -> u32 {
+    steps
+        .iter()
+        .filter(|step| capacity < step.below_percent)
+        .map(|step| step.dim_percent)
+        .max()
+        .unwrap_or(0)
+}
+
+/// The panel backlight, preferring the firmware interface over the
+/// platform and raw ones as the kernel documentation advises.
+fn device() -> Option<PathBuf> {
+    let rank = |path: &Path| match fs::read_to_string(path.join("type")).unwrap_or_default().trim() {
+        "firmware" => 0,
+        "platform" => 1,
+        _ => 2,
+    };
+    fs::read_dir(BACKLIGHT_PATH)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .min_by_key(|path| rank(path))
+}
+
+fn read_number(path: &Path, attr: &str) -> Option<u32> {
+    fs::read_to_string(path.join(attr)).ok()?.trim().parse().ok()
+}
+
+/// Whether this machine has a backlight TuxTuner can drive. Brightness
+/// goes through the local login session, so not over SSH.
+pub fn supported() -> bool {
+    remote::host().is_none() && device().is_some()
+}
+
+/// Current brightness as a percentage of the maximum.
+pub fn percent() -> Option<u32> {
+    let device = device()?;
+    let max = read_number(&device, "max_brightness")?.max(1);
+    let current = read_number(&device, "brightness")?;
+    Some((current as u64 * 100 / max as u64) as u32)
+}
+
+/// Sets the brightness through logind, which lets the session's user do
+/// it without root. Blocking.
+pub fn set_percent(percent: u32) -> Result<(), String> {
+    let device = device().ok_or("No backlight found")?;
+    let name = device
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or("No backlight found")?;
+    let max = read_number(&device, "max_brightness").ok_or("Unreadable backlight")?;
+    let value = (max as u64 * percent.min(100) as u64 / 100) as u32;
+
+    let connection = gio::bus_get_sync(gio::BusType::System, None::<&gio::Cancellable>).map_err(|e| e.to_string())?;
+    connection
+        .call_sync(
+            Some("org.freedesktop.login1"),
+            "/org/freedesktop/login1/session/auto",
+            "org.freedesktop.login1.Session",
+            "SetBrightness",
+            Some(&("backlight", name, value).to_variant()),
+            None,
+            gio::DBusCallFlags::NONE,
+            -1,
+            None::<&gio::Cancellable>,
+        )
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Dims relative to whatever brightness the user picked, and gives back
+/// exactly what it took once the battery is charging again, so manual
+/// changes in between are kept.
+#[derive(Debug, Default)]
+pub struct AutoDim {
+    /// Percentage points currently taken off.
+    applied: u32,
+}
+
+impl AutoDim {
+    /// Moves the dimming to `target` percentage points. Blocking.
+    pub fn update(&mut self, target: u32) -> Result<(), String> {
+        if target == self.applied {
+            return Ok(());
+        }
+        let current = percent().ok_or("Unreadable backlight")?;
+        let brightness = (current as i64 - target as i64 + self.applied as i64).clamp(MIN_PERCENT as i64, 100);
+        set_percent(brightness as u32)?;
+        self.applied = target;
+        Ok(())
+    }
+}
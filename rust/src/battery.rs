@@ -0,0 +1,194 @@
+use crate::config::BatteryConfig;
+use crate::remote;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+const POWER_SUPPLY_PATH: &str = "/sys/class/power_supply";
+const THRESHOLD_ATTR: &str = "charge_control_end_threshold";
+
+pub const MIN_CHARGE_LIMIT: u32 = 20;
+pub const MAX_CHARGE_LIMIT: u32 = 100;
+/// A limit that noticeably slows wear on a battery that's mostly plugged in.
+pub const RECOMMENDED_CHARGE_LIMIT: u32 = 80;
+
+/// System batteries exposing a charge end threshold.
+fn threshold_batteries() -> Vec<PathBuf> {
+    batteries()
+        .into_iter()
+        .filter(|path| remote::exists(path.join(THRESHOLD_ATTR)))
+        .collect()
+}
+
+fn battery_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn read_limit(battery: &Path) -> Option<u32> {
+    remote::read_to_string(battery.join(THRESHOLD_ATTR))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Current charge end threshold of the first battery, if supported.
+pub fn charge_limit() -> Option<u32> {
+    read_limit(threshold_batteries().first()?)
+}
+
+/// Current charge end threshold of each battery that has one, by name
+/// (`BAT0`, `BAT1`).
+pub fn charge_limits() -> BTreeMap<String, u32> {
+    threshold_batteries()
+        .iter()
+        .filter_map(|battery| Some((battery_name(battery), read_limit(battery)?)))
+        .collect()
+}
+
+fn read_i64(path: &Path) -> Option<i64> {
+    remote::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// System batteries, in name order. Laptops with a second, swappable
+/// battery (many ThinkPads) have both BAT0 and BAT1.
+fn batteries() -> Vec<PathBuf> {
+    let mut batteries: Vec<PathBuf> = remote::read_dir(POWER_SUPPLY_PATH)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|path| battery_name(path).starts_with("BAT"))
+        .collect();
+    batteries.sort();
+    batteries
+}
+
+/// Sums `read` over all batteries, or `None` if none could be read.
+fn sum_batteries(read: impl Fn(&Path) -> Option<i64>) -> Option<i64> {
+    batteries()
+        .iter()
+        .filter_map(|battery| read(battery))
+        .fold(None, |total, value| Some(total.unwrap_or(0) + value))
+}
+
+/// Battery power flow in milliwatts, across all batteries: positive
+/// while discharging, negative while charging.
+pub fn power_draw_mw() -> Option<i32> {
+    let microwatts = sum_batteries(|battery| {
+        // Some batteries only report current and voltage (µA, µV).
+        let microwatts = read_i64(&battery.join("power_now")).or_else(|| {
+            let current = read_i64(&battery.join("current_now"))?;
+            let voltage = read_i64(&battery.join("voltage_now"))?;
+            Some(current * voltage / 1_000_000)
+        })?;
+        let status = remote::read_to_string(battery.join("status")).unwrap_or_default();
+        Some(if status.trim() == "Charging" { -microwatts.abs() } else { microwatts.abs() })
+    })?;
+    Some((microwatts / 1000) as i32)
+}
+
+/// Energy in µWh read from `energy_<kind>`, or from `charge_<kind>` (µAh)
+/// on charge-based batteries.
+fn energy_uwh(battery: &Path, kind: &str) -> Option<i64> {
+    read_i64(&battery.join(format!("energy_{}", kind))).or_else(|| {
+        let charge = read_i64(&battery.join(format!("charge_{}", kind)))?;
+        let voltage = read_i64(&battery.join("voltage_now"))?;
+        Some(charge * voltage / 1_000_000)
+    })
+}
+
+/// Charge of each battery, in percent, by name.
+pub fn capacities() -> Vec<(String, u32)> {
+    batteries()
+        .iter()
+        .filter_map(|battery| {
+            let percent = read_i64(&battery.join("capacity"))?;
+            Some((battery_name(battery), percent.clamp(0, 100) as u32))
+        })
+        .collect()
+}
+
+/// Combined charge of all batteries, in percent, weighted by their size.
+pub fn capacity_percent() -> Option<u32> {
+    let now = sum_batteries(|battery| energy_uwh(battery, "now"));
+    let full = sum_batteries(|battery| energy_uwh(battery, "full"));
+    match (now, full) {
+        (Some(now), Some(full)) if full > 0 => Some((now * 100 / full).clamp(0, 100) as u32),
+        // Without energy readings, fall back to the plain average.
+        _ => {
+            let capacities = capacities();
+            let count = capacities.len() as u32;
+            (count > 0).then(|| capacities.iter().map(|(_, percent)| percent).sum::<u32>() / count)
+        }
+    }
+}
+
+/// Energy left in all batteries, in milliwatt-hours.
+pub fn energy_remaining_mwh() -> Option<i64> {
+    sum_batteries(|battery| energy_uwh(battery, "now")).map(|uwh| uwh / 1000)
+}
+
+pub fn apply_charge_limit(percent: u32) -> Result<(), String> {
+    run_charge_limit(&[&percent.to_string()], percent)
+}
+
+/// Brings each battery to its limit in `limits`, in a single helper
+/// request. Blocking.
+pub fn apply_charge_limits(limits: &BTreeMap<String, u32>) -> Result<(), String> {
+    let mut values = limits.values();
+    let Some(&first) = values.next() else {
+        return Ok(());
+    };
+    if values.all(|&percent| percent == first) {
+        return apply_charge_limit(first);
+    }
+
+    if limits.values().any(|percent| !(MIN_CHARGE_LIMIT..=MAX_CHARGE_LIMIT).contains(percent)) {
+        return Err("Charge limit out of valid range".to_string());
+    }
+    let percents: Vec<String> = limits.values().map(u32::to_string).collect();
+    let commands: Vec<Vec<&str>> = limits
+        .keys()
+        .zip(&percents)
+        .map(|(battery, percent)| vec!["charge-limit", percent.as_str(), battery.as_str()])
+        .collect();
+    remote::run_helper_batch(&commands, |_, _| {})
+}
+
+fn run_charge_limit(args: &[&str], percent: u32) -> Result<(), String> {
+    if !(MIN_CHARGE_LIMIT..=MAX_CHARGE_LIMIT).contains(&percent) {
+        return Err("Charge limit out of valid range".to_string());
+    }
+
+    let mut helper_args = vec!["charge-limit"];
+    helper_args.extend_from_slice(args);
+    remote::run_helper(&helper_args)
+}
+
+/// The limit that should be in force at `now` (unix seconds): full charge
+/// while a travel override is pending, the configured limit otherwise.
+/// `None` when the user hasn't chosen one, so the firmware's stays.
+pub fn effective_limit(limit: Option<u32>, override_until: Option<i64>, now: i64) -> Option<u32> {
+    match override_until {
+        Some(until) if now < until => Some(MAX_CHARGE_LIMIT),
+        _ => limit,
+    }
+}
+
+/// `effective_limit` for each battery with a threshold, honouring
+/// per-battery overrides. Batteries without a chosen limit are left out.
+pub fn effective_limits(config: &BatteryConfig, now: i64) -> BTreeMap<String, u32> {
+    threshold_batteries()
+        .iter()
+        .filter_map(|battery| {
+            let name = battery_name(battery);
+            let limit = config.battery_limits.get(&name).copied().or(config.charge_limit);
+            Some((name, effective_limit(limit, config.full_charge_until, now)?))
+        })
+        .collect()
+}
+
+/// Names of the batteries with a charge threshold.
+pub fn threshold_battery_names() -> Vec<String> {
+    threshold_batteries().iter().map(|battery| battery_name(battery)).collect()
+}
@@ -0,0 +1,181 @@
+use crate::config::state_dir;
+use crate::profiles::PROFILE_EVENT_PREFIX;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+pub const SAMPLE_INTERVAL_SECS: u32 = 60;
+
+/// One day of samples at one per minute.
+const CAPACITY: u64 = 24 * 60;
+/// u64 count of samples ever written, used to locate the oldest slot.
+const HEADER_SIZE: u64 = 8;
+/// i64 unix time + i32 milliwatts + 4 reserved bytes.
+const RECORD_SIZE: u64 = 16;
+
+const SAMPLES_FILE: &str = "battery-history.bin";
+const EVENTS_FILE: &str = "battery-events.log";
+/// Events older than the sample window are dropped when appending.
+const EVENT_RETENTION_SECS: i64 = CAPACITY as i64 * 60;
+/// Discharging samples needed before a profile's average is trusted.
+const MIN_PROFILE_SAMPLES: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sample {
+    pub time: i64,
+    /// Positive while discharging, negative while charging.
+    pub power_mw: i32,
+}
+
+/// A settings change drawn on top of the chart.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub time: i64,
+    pub label: String,
+}
+
+fn samples_path() -> PathBuf {
+    state_dir().join(SAMPLES_FILE)
+}
+
+fn events_path() -> PathBuf {
+    state_dir().join(EVENTS_FILE)
+}
+
+pub fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn read_count(file: &mut File) -> io::Result<u64> {
+    let mut header = [0u8; HEADER_SIZE as usize];
+    file.seek(SeekFrom::Start(0))?;
+    match file.read_exact(&mut header) {
+        Ok(()) => Ok(u64::from_le_bytes(header)),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(0),
+        Err(e) => Err(e),
+    }
+}
+
+/// Stores a sample in the fixed-size ring, overwriting the oldest one.
+pub fn append_sample(power_mw: i32) -> io::Result<()> {
+    fs::create_dir_all(state_dir())?;
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(samples_path())?;
+
+    let count = read_count(&mut file)?;
+    let mut record = [0u8; RECORD_SIZE as usize];
+    record[..8].copy_from_slice(&unix_now().to_le_bytes());
+    record[8..12].copy_from_slice(&power_mw.to_le_bytes());
+
+    file.seek(SeekFrom::Start(HEADER_SIZE + (count % CAPACITY) * RECORD_SIZE))?;
+    file.write_all(&record)?;
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(&(count + 1).to_le_bytes())
+}
+
+/// Samples newer than `since`, oldest first.
+pub fn load_samples(since: i64) -> Vec<Sample> {
+    let Ok(mut file) = File::open(samples_path()) else {
+        return Vec::new();
+    };
+    let Ok(count) = read_count(&mut file) else {
+        return Vec::new();
+    };
+
+    let mut data = Vec::new();
+    if file.read_to_end(&mut data).is_err() {
+        return Vec::new();
+    }
+
+    let stored = count.min(CAPACITY);
+    let oldest = if count > CAPACITY { count % CAPACITY } else { 0 };
+
+    (0..stored)
+        .filter_map(|i| {
+            let offset = (((oldest + i) % CAPACITY) * RECORD_SIZE) as usize;
+            let record = data.get(offset..offset + RECORD_SIZE as usize)?;
+            Some(Sample {
+                time: i64::from_le_bytes(record[..8].try_into().ok()?),
+                power_mw: i32::from_le_bytes(record[8..12].try_into().ok()?),
+            })
+        })
+        .filter(|sample| sample.time >= since)
+        .collect()
+}
+
+/// Records a settings change to annotate the chart with.
+pub fn record_event(label: &str) {
+    let cutoff = unix_now() - EVENT_RETENTION_SECS;
+    let mut events: Vec<Event> = load_events(cutoff);
+    events.push(Event {
+        time: unix_now(),
+        label: label.replace('\n', " "),
+    });
+
+    let content: String = events
+        .iter()
+        .map(|event| format!("{}\t{}\n", event.time, event.label))
+        .collect();
+    let _ = fs::create_dir_all(state_dir());
+    let _ = fs::write(events_path(), content);
+}
+
+/// Events newer than `since`, oldest first.
+pub fn load_events(since: i64) -> Vec<Event> {
+    fs::read_to_string(events_path())
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let (time, label) = line.split_once('\t')?;
+            Some(Event {
+                time: time.parse().ok()?,
+                label: label.to_string(),
+            })
+        })
+        .filter(|event| event.time >= since)
+        .collect()
+}
+
+/// Average discharge power in milliwatts per profile, attributing each
+/// discharging sample to the profile switched to most recently before it.
+/// Profiles with too little data are left out.
+pub fn average_draw_by_profile(samples: &[Sample], events: &[Event]) -> HashMap<String, f64> {
+    let switches: Vec<(i64, &str)> = events
+        .iter()
+        .filter_map(|event| {
+            event
+                .label
+                .strip_prefix(PROFILE_EVENT_PREFIX)
+                .map(|name| (event.time, name))
+        })
+        .collect();
+
+    let mut totals: HashMap<String, (i64, usize)> = HashMap::new();
+    for sample in samples.iter().filter(|s| s.power_mw > 0) {
+        let active = switches
+            .iter()
+            .rev()
+            .find(|(time, _)| *time <= sample.time)
+            .map(|(_, name)| *name);
+
+        if let Some(name) = active {
+            let entry = totals.entry(name.to_string()).or_default();
+            entry.0 += sample.power_mw as i64;
+            entry.1 += 1;
+        }
+    }
+
+    totals
+        .into_iter()
+        .filter(|(_, (_, count))| *count >= MIN_PROFILE_SAMPLES)
+        .map(|(name, (sum, count))| (name, sum as f64 / count as f64))
+        .collect()
+}
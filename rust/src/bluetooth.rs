@@ -0,0 +1,92 @@
+use crate::probe;
+use crate::remote;
+use gtk4::prelude::*;
+use gtk4::{gio, glib};
+
+const BUS_NAME: &str = "org.bluez";
+const ADAPTER_INTERFACE: &str = "org.bluez.Adapter1";
+
+fn probe_timeout_ms() -> i32 {
+    probe::timeout().as_millis().min(i32::MAX as u128) as i32
+}
+
+fn call(
+    object_path: &str,
+    interface: &str,
+    method: &str,
+    args: Option<&glib::Variant>,
+    reply: &str,
+) -> Result<glib::Variant, String> {
+    let connection = gio::bus_get_sync(gio::BusType::System, None::<&gio::Cancellable>)
+        .map_err(|e| e.to_string())?;
+    connection
+        .call_sync(
+            Some(BUS_NAME),
+            object_path,
+            interface,
+            method,
+            args,
+            Some(glib::VariantTy::new(reply).map_err(|e| e.to_string())?),
+            gio::DBusCallFlags::NO_AUTO_START,
+            probe_timeout_ms(),
+            None::<&gio::Cancellable>,
+        )
+        .map_err(|e| e.message().to_string())
+}
+
+/// Object path of the first BlueZ adapter, e.g. `/org/bluez/hci0`. BlueZ
+/// is reached over this machine's system bus, so there's none in remote
+/// mode.
+fn adapter_path() -> Option<String> {
+    if remote::host().is_some() {
+        return None;
+    }
+
+    let reply = call("/", "org.freedesktop.DBus.ObjectManager", "GetManagedObjects", None, "(a{oa{sa{sv}}})").ok()?;
+    let objects = reply.child_value(0);
+    let mut adapters: Vec<String> = objects
+        .iter()
+        .filter_map(|entry| {
+            let path = entry.child_value(0).str()?.to_string();
+            let interfaces = entry.child_value(1);
+            interfaces
+                .iter()
+                .any(|interface| interface.child_value(0).str() == Some(ADAPTER_INTERFACE))
+                .then_some(path)
+        })
+        .collect();
+    adapters.sort();
+    adapters.into_iter().next()
+}
+
+/// Whether the Bluetooth adapter is powered, or `None` without one (or
+/// without bluetoothd).
+pub fn powered() -> Option<bool> {
+    let path = adapter_path()?;
+    let reply = call(
+        &path,
+        "org.freedesktop.DBus.Properties",
+        "Get",
+        Some(&(ADAPTER_INTERFACE, "Powered").to_variant()),
+        "(v)",
+    )
+    .ok()?;
+    reply.child_value(0).as_variant()?.get::<bool>()
+}
+
+/// Powers the adapter on or off. BlueZ lets the session user do this
+/// without the helper. Succeeds quietly when there's no adapter, since
+/// profiles treat Bluetooth as optional hardware.
+pub fn set_powered(on: bool) -> Result<(), String> {
+    let Some(path) = adapter_path() else {
+        return Ok(());
+    };
+    call(
+        &path,
+        "org.freedesktop.DBus.Properties",
+        "Set",
+        Some(&(ADAPTER_INTERFACE, "Powered", on.to_variant()).to_variant()),
+        "()",
+    )
+    .map(|_| ())
+}
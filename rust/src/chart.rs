@@ -0,0 +1,122 @@
+use gtk4::cairo::Context;
+
+const ACCENT: (f64, f64, f64) = (0.914, 0.271, 0.376);
+const LABEL_WIDTH: f64 = 40.0;
+const PADDING: f64 = 8.0;
+
+/// A line chart of `points` (unix time, value) over `[start, end]`, with a
+/// vertical marker for each event. Gaps longer than `max_gap` seconds
+/// (e.g. while the app wasn't running) break the line.
+pub struct TimeSeries<'a> {
+    pub start: i64,
+    pub end: i64,
+    pub points: &'a [(i64, f64)],
+    pub events: &'a [(i64, String)],
+    pub unit: &'a str,
+    pub max_gap: i64,
+}
+
+pub fn draw_time_series(cr: &Context, width: f64, height: f64, series: &TimeSeries) {
+    let TimeSeries {
+        start,
+        end,
+        points,
+        events,
+        unit,
+        max_gap,
+    } = *series;
+
+    let plot_x = LABEL_WIDTH;
+    let plot_w = (width - LABEL_WIDTH - PADDING).max(1.0);
+    let plot_y = PADDING + 12.0;
+    let plot_h = (height - plot_y - PADDING - 12.0).max(1.0);
+    let span = (end - start).max(1) as f64;
+
+    let max_value = points
+        .iter()
+        .map(|&(_, v)| v)
+        .fold(0.0f64, f64::max)
+        .max(1.0)
+        * 1.1;
+
+    let x_for = |t: i64| plot_x + (t - start) as f64 / span * plot_w;
+    let y_for = |v: f64| plot_y + plot_h - (v.max(0.0) / max_value) * plot_h;
+
+    cr.set_font_size(10.0);
+
+    // Grid and value labels.
+    cr.set_line_width(1.0);
+    for fraction in [0.0, 0.5, 1.0] {
+        let value = max_value * fraction;
+        let y = y_for(value);
+        cr.set_source_rgba(1.0, 1.0, 1.0, 0.12);
+        cr.move_to(plot_x, y);
+        cr.line_to(plot_x + plot_w, y);
+        let _ = cr.stroke();
+
+        cr.set_source_rgba(1.0, 1.0, 1.0, 0.6);
+        cr.move_to(2.0, y + 3.0);
+        let _ = cr.show_text(&format!("{:.0}{}", value, unit));
+    }
+
+    // Time axis labels.
+    let hours = (end - start) / 3600;
+    cr.move_to(plot_x, height - 2.0);
+    let _ = cr.show_text(&format!("-{}h", hours));
+    cr.move_to(plot_x + plot_w - 24.0, height - 2.0);
+    let _ = cr.show_text("now");
+
+    // Event markers.
+    cr.set_dash(&[3.0, 3.0], 0.0);
+    for (time, label) in events.iter().filter(|(t, _)| *t >= start && *t <= end) {
+        let x = x_for(*time);
+        cr.set_source_rgba(1.0, 1.0, 1.0, 0.35);
+        cr.move_to(x, plot_y);
+        cr.line_to(x, plot_y + plot_h);
+        let _ = cr.stroke();
+
+        cr.set_source_rgba(1.0, 1.0, 1.0, 0.7);
+        cr.move_to(x + 2.0, plot_y - 2.0);
+        let _ = cr.show_text(label);
+    }
+    cr.set_dash(&[], 0.0);
+
+    // Data line.
+    cr.set_source_rgb(ACCENT.0, ACCENT.1, ACCENT.2);
+    cr.set_line_width(2.0);
+    let mut previous: Option<i64> = None;
+    for &(time, value) in points.iter().filter(|(t, _)| *t >= start) {
+        let (x, y) = (x_for(time), y_for(value));
+        match previous {
+            Some(prev) if time - prev <= max_gap => cr.line_to(x, y),
+            _ => cr.move_to(x, y),
+        }
+        previous = Some(time);
+    }
+    let _ = cr.stroke();
+}
+
+/// A tiny axis-less trend line of `values`, scaled to their own range.
+pub fn draw_sparkline(cr: &Context, width: f64, height: f64, values: &[f64]) {
+    if values.len() < 2 {
+        return;
+    }
+
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let range = (max - min).max(1.0);
+    let step = width / (values.len() - 1) as f64;
+
+    cr.set_source_rgba(ACCENT.0, ACCENT.1, ACCENT.2, 0.9);
+    cr.set_line_width(1.5);
+    for (i, value) in values.iter().enumerate() {
+        let x = i as f64 * step;
+        let y = height - 1.0 - (value - min) / range * (height - 2.0);
+        if i == 0 {
+            cr.move_to(x, y);
+        } else {
+            cr.line_to(x, y);
+        }
+    }
+    let _ = cr.stroke();
+}
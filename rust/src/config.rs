@@ -0,0 +1,338 @@
+use crate::backlight::{self, DimStep};
+use crate::gpufan::FanCurve;
+use crate::hooks::HooksConfig;
+use crate::lighting::LightingLevel;
+use crate::metrics::MetricsConfig;
+use crate::mqtt::MqttConfig;
+use crate::privacy::PrivacyDevice;
+use crate::profiles::Profile;
+use crate::rules::Rule;
+use crate::ryzenadj::TdpLimits;
+use crate::schedule::SleepAction;
+use crate::script::ScriptRule;
+use gtk4::prelude::*;
+use gtk4::{gio, glib};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+
+const CONFIG_FILE: &str = "config.toml";
+/// Editors save in bursts (truncate, write, rename); wait for it to settle.
+const RELOAD_DELAY_MS: u64 = 300;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Keyed by EDID identity (see `MonitorInfo::identity`).
+    pub monitors: BTreeMap<String, MonitorPreference>,
+    pub night_light: NightLightConfig,
+    pub adaptive_cores: AdaptiveCoresConfig,
+    pub automation: AutomationConfig,
+    pub confirm: ConfirmConfig,
+    pub battery: BatteryConfig,
+    pub power_schedule: PowerScheduleConfig,
+    /// User-defined profiles, shown after the built-in ones.
+    pub profiles: Vec<Profile>,
+    pub active_profile: Option<String>,
+    /// Last RGB lighting level applied, if any.
+    pub lighting: Option<LightingLevel>,
+    /// Last AMD APU power limits applied; ryzenadj can't read them back
+    /// without root.
+    pub tdp: Option<TdpLimits>,
+    /// GPU fan curve in force; `None` leaves the fan to the card.
+    pub gpu_fan_curve: Option<FanCurve>,
+    /// Cameras and microphones TuxTuner de-authorized; they can't be
+    /// recognized again until unblocked.
+    pub privacy_blocked: Vec<PrivacyDevice>,
+    /// Presets ticked in the launch options builder (see `launch::PRESETS`).
+    pub launch_presets: Vec<String>,
+    pub hooks: HooksConfig,
+    pub metrics: MetricsConfig,
+    pub mqtt: MqttConfig,
+    /// Apply spinners shortly after they change instead of waiting for
+    /// their Apply button.
+    pub apply_on_change: bool,
+    /// Screen blanking chosen by the last profile or by hand; see
+    /// `Profile::idle_timeout_secs`. `None` leaves it to the session.
+    pub idle_timeout_secs: Option<u32>,
+    /// Serve the JSON control API on a Unix socket (see `api::start`).
+    pub control_socket: bool,
+    /// Count how long each profile is active (see `usage`). Kept in the
+    /// state directory and never sent anywhere.
+    pub usage_stats: bool,
+    /// `[user@]host` to tune over SSH instead of this machine; read at
+    /// startup (see `remote::host`).
+    pub remote_host: Option<String>,
+    /// Seconds before a hung external tool (hyprctl, supergfxctl) is
+    /// abandoned; defaults to `probe::DEFAULT_TIMEOUT_SECS`.
+    pub probe_timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonitorPreference {
+    pub refresh_hz: Option<u32>,
+    pub vrr: Option<bool>,
+    pub scale: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NightLightConfig {
+    pub enabled: bool,
+    pub temperature: u32,
+    /// When set, the filter is only active between `start` and `end`.
+    pub scheduled: bool,
+    pub start: String,
+    pub end: String,
+}
+
+impl Default for NightLightConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            temperature: 4500,
+            scheduled: false,
+            start: "20:00".to_string(),
+            end: "07:00".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdaptiveCoresConfig {
+    pub enabled: bool,
+    pub min_threads: u32,
+    /// Upper bound; 0 means all threads.
+    pub max_threads: u32,
+}
+
+impl Default for AdaptiveCoresConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_threads: 4,
+            max_threads: 0,
+        }
+    }
+}
+
+/// Changes that ask before applying. Graphics mode switches always ask,
+/// since they end the session.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfirmConfig {
+    pub refresh_rate: bool,
+    /// Lowering the CPU thread limit.
+    pub cpu_offline: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutomationConfig {
+    /// Drop to `battery_refresh_hz` on battery unless a game or video runs.
+    pub battery_refresh: bool,
+    pub battery_refresh_hz: u32,
+    /// Window class fragments identifying games.
+    pub game_classes: Vec<String>,
+    /// Window class fragments identifying video players.
+    pub video_classes: Vec<String>,
+    /// Custom rules, evaluated before the built-in ones.
+    pub rules: Vec<Rule>,
+    /// Rules with expression conditions, evaluated on a timer.
+    pub scripts: Vec<ScriptRule>,
+}
+
+impl Default for AutomationConfig {
+    fn default() -> Self {
+        Self {
+            battery_refresh: false,
+            battery_refresh_hz: 60,
+            game_classes: ["steam_app_", "gamescope", "lutris", "heroic", "minecraft"]
+                .map(String::from)
+                .to_vec(),
+            video_classes: ["mpv", "vlc", "celluloid", "totem", "haruna"]
+                .map(String::from)
+                .to_vec(),
+            rules: Vec::new(),
+            scripts: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BatteryConfig {
+    /// `None` until the user picks a limit; the firmware's threshold is
+    /// left alone until then.
+    pub charge_limit: Option<u32>,
+    /// Unix time until which charging to 100% is allowed ("travel mode").
+    pub full_charge_until: Option<i64>,
+    /// Per-battery overrides of `charge_limit`, by name (`BAT1`), for
+    /// laptops with two batteries.
+    pub battery_limits: BTreeMap<String, u32>,
+    /// Dim the screen step by step as the battery runs down.
+    pub auto_dim: bool,
+    pub dim_steps: Vec<DimStep>,
+    /// Switch to the most frugal built-in profile once the battery falls
+    /// to `emergency_percent`.
+    pub emergency: bool,
+    pub emergency_percent: u32,
+    /// Hibernate as well, unless the charger is plugged in meanwhile.
+    pub emergency_hibernate: bool,
+}
+
+impl Default for BatteryConfig {
+    fn default() -> Self {
+        Self {
+            charge_limit: None,
+            full_charge_until: None,
+            battery_limits: BTreeMap::new(),
+            auto_dim: false,
+            dim_steps: backlight::default_dim_steps(),
+            emergency: false,
+            emergency_percent: 7,
+            emergency_hibernate: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PowerScheduleConfig {
+    pub enabled: bool,
+    pub action: SleepAction,
+    /// `HH:MM` at which to run `action`.
+    pub sleep_at: String,
+    /// `HH:MM` at which the RTC alarm wakes the machine again.
+    pub wake_at: String,
+}
+
+impl Default for PowerScheduleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            action: SleepAction::default(),
+            sleep_at: "01:00".to_string(),
+            wake_at: "07:00".to_string(),
+        }
+    }
+}
+
+/// `$XDG_CONFIG_HOME/tuxtuner`, falling back to `~/.config/tuxtuner`.
+pub fn config_dir() -> PathBuf {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_else(|| PathBuf::from("/tmp"));
+    base.join("tuxtuner")
+}
+
+/// `$XDG_STATE_HOME/tuxtuner`, falling back to `~/.local/state/tuxtuner`.
+pub fn state_dir() -> PathBuf {
+    let base = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))
+        .unwrap_or_else(|| PathBuf::from("/tmp"));
+    base.join("tuxtuner")
+}
+
+impl Config {
+    pub fn path() -> PathBuf {
+        config_dir().join(CONFIG_FILE)
+    }
+
+    /// Loads the user config, returning defaults if it is missing or invalid.
+    pub fn load() -> Self {
+        fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|content| toml::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Parses the config file, reporting why it is invalid rather than
+    /// falling back to defaults like `load`. A missing file is fine.
+    pub fn check() -> Result<(), String> {
+        match fs::read_to_string(Self::path()) {
+            Ok(content) => toml::from_str::<Config>(&content)
+                .map(|_| ())
+                .map_err(|e| e.message().to_string()),
+            Err(_) => Ok(()),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let content = toml::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::create_dir_all(config_dir()).map_err(|e| e.to_string())?;
+
+        // Write through a temp file so a crash never leaves a truncated config.
+        let path = Self::path();
+        let tmp_path = path.with_extension("toml.tmp");
+        fs::write(&tmp_path, content).map_err(|e| e.to_string())?;
+        fs::rename(&tmp_path, &path).map_err(|e| e.to_string())
+    }
+
+    /// Looks up a monitor by identity, falling back to entries saved
+    /// under the connector name by older versions.
+    pub fn monitor(&self, identity: &str, name: &str) -> Option<&MonitorPreference> {
+        self.monitors.get(identity).or_else(|| self.monitors.get(name))
+    }
+
+    pub fn monitor_mut(&mut self, identity: &str) -> &mut MonitorPreference {
+        self.monitors.entry(identity.to_string()).or_default()
+    }
+}
+
+/// Calls `callback` on the main loop shortly after a `*.toml` file in the
+/// config directory changes, whether by hand or through `save`. Keep the
+/// returned monitor alive for as long as the watch should last.
+pub fn watch<F: Fn() + 'static>(callback: F) -> Option<gio::FileMonitor> {
+    let _ = fs::create_dir_all(config_dir());
+    let monitor = gio::File::for_path(config_dir())
+        .monitor_directory(gio::FileMonitorFlags::WATCH_MOVES, None::<&gio::Cancellable>)
+        .ok()?;
+
+    let callback = Rc::new(callback);
+    let pending: Rc<Cell<Option<glib::SourceId>>> = Rc::new(Cell::new(None));
+    monitor.connect_changed(move |_, file, other, event| {
+        let is_toml = |file: Option<&gio::File>| {
+            file.and_then(|f| f.path())
+                .is_some_and(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        };
+        let relevant = match event {
+            gio::FileMonitorEvent::ChangesDoneHint
+            | gio::FileMonitorEvent::Created
+            | gio::FileMonitorEvent::Deleted
+            | gio::FileMonitorEvent::MovedIn
+            | gio::FileMonitorEvent::MovedOut => is_toml(Some(file)),
+            // `save` and many editors write a temp file and rename it over.
+            gio::FileMonitorEvent::Renamed => is_toml(other),
+            _ => false,
+        };
+        if !relevant {
+            return;
+        }
+
+        if let Some(source) = pending.take() {
+            source.remove();
+        }
+        let callback = callback.clone();
+        let fired = pending.clone();
+        pending.set(Some(glib::timeout_add_local_once(
+            Duration::from_millis(RELOAD_DELAY_MS),
+            move || {
+                fired.set(None);
+                callback();
+            },
+        )));
+    });
+
+    Some(monitor)
+}
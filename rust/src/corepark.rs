@@ -0,0 +1,180 @@
+use crate::remote::{self, HelperFailure};
+use std::fs;
+use std::time::{Duration, Instant};
+
+pub const SAMPLE_INTERVAL_SECS: u32 = 3;
+
+/// Add cores when the online ones are busier than this.
+const HIGH_LOAD: f64 = 0.75;
+/// Consider parking cores only when load stays below this.
+const LOW_LOAD: f64 = 0.30;
+/// Utilization the controller aims for after resizing.
+const TARGET_LOAD: f64 = 0.50;
+/// Consecutive calm samples required before parking cores.
+const CALM_SAMPLES: u32 = 5;
+/// Minimum time between two changes, so short bursts don't cause flapping
+/// (and repeated authentication prompts).
+const MIN_CHANGE_INTERVAL: Duration = Duration::from_secs(20);
+/// Never park more than this many cores in one step.
+const MAX_PARK_STEP: u32 = 2;
+/// Pause after authentication is dismissed or refused, doubled on each
+/// further refusal, so a background loop doesn't keep prompting.
+pub const AUTH_BACKOFF: Duration = Duration::from_secs(60);
+const MAX_AUTH_BACKOFF: Duration = Duration::from_secs(60 * 60);
+
+/// Computes CPU utilization from the aggregate line of `/proc/stat`.
+#[derive(Debug, Default)]
+pub struct CpuSampler {
+    prev: Option<(u64, u64)>,
+}
+
+impl CpuSampler {
+    fn read_times() -> Option<(u64, u64)> {
+        let stat = fs::read_to_string("/proc/stat").ok()?;
+        let line = stat.lines().find(|l| l.starts_with("cpu "))?;
+        let values: Vec<u64> = line
+            .split_whitespace()
+            .skip(1)
+            .filter_map(|v| v.parse().ok())
+            .collect();
+        if values.len() < 5 {
+            return None;
+        }
+
+        // idle + iowait count as idle time.
+        let idle = values[3] + values[4];
+        let total = values.iter().sum();
+        Some((idle, total))
+    }
+
+    /// Returns the busy fraction (0.0 to 1.0) of online CPUs since the
+    /// previous call, or `None` on the first call.
+    pub fn sample(&mut self) -> Option<f64> {
+        let (idle, total) = Self::read_times()?;
+        let prev = self.prev.replace((idle, total));
+        let (prev_idle, prev_total) = prev?;
+
+        let total_delta = total.saturating_sub(prev_total);
+        if total_delta == 0 {
+            return None;
+        }
+        let idle_delta = idle.saturating_sub(prev_idle);
+        Some(1.0 - idle_delta as f64 / total_delta as f64)
+    }
+}
+
+/// Decides how many threads should be online based on recent load.
+#[derive(Debug)]
+pub struct AdaptiveController {
+    min: u32,
+    max: u32,
+    calm_samples: u32,
+    last_change: Option<Instant>,
+    /// Consecutive requests that weren't authorized.
+    auth_failures: u32,
+    paused_until: Option<Instant>,
+}
+
+impl AdaptiveController {
+    pub fn new(min: u32, max: u32) -> Self {
+        let min = min.max(1);
+        Self {
+            min,
+            max: max.max(min),
+            calm_samples: 0,
+            last_change: None,
+            auth_failures: 0,
+            paused_until: None,
+        }
+    }
+
+    /// Records that applying a target was refused before anything ran,
+    /// and returns how long the controller now waits.
+    pub fn auth_failed(&mut self) -> Duration {
+        let backoff = AUTH_BACKOFF
+            .saturating_mul(1 << self.auth_failures.min(6))
+            .min(MAX_AUTH_BACKOFF);
+        self.auth_failures += 1;
+        self.paused_until = Some(Instant::now() + backoff);
+        backoff
+    }
+
+    pub fn applied(&mut self) {
+        self.auth_failures = 0;
+        self.paused_until = None;
+    }
+
+    /// Returns a new thread count if `current` should change given the
+    /// measured `usage` of the online threads.
+    pub fn next_target(&mut self, current: u32, usage: f64) -> Option<u32> {
+        if self.paused_until.is_some_and(|until| Instant::now() < until) {
+            return None;
+        }
+
+        let busy_threads = usage * current as f64;
+        let ideal = ((busy_threads / TARGET_LOAD).ceil() as u32).clamp(self.min, self.max);
+
+        // Out-of-bounds counts (e.g. after a manual change) are fixed at once.
+        let target = if current < self.min || current > self.max {
+            current.clamp(self.min, self.max)
+        } else if usage >= HIGH_LOAD {
+            self.calm_samples = 0;
+            ideal.max(current)
+        } else if usage <= LOW_LOAD {
+            self.calm_samples += 1;
+            if self.calm_samples < CALM_SAMPLES {
+                return None;
+            }
+            ideal.max(current.saturating_sub(MAX_PARK_STEP))
+        } else {
+            self.calm_samples = 0;
+            return None;
+        };
+
+        if target == current {
+            return None;
+        }
+
+        let scaling_up = target > current;
+        let throttled = self
+            .last_change
+            .is_some_and(|t| t.elapsed() < MIN_CHANGE_INTERVAL);
+        // Scaling up under load is never delayed; parking is.
+        if throttled && !scaling_up {
+            return None;
+        }
+
+        self.calm_samples = 0;
+        self.last_change = Some(Instant::now());
+        Some(target)
+    }
+}
+
+/// Brings `target` threads online in one helper request, whose failure
+/// says whether authentication was refused. Blocking.
+pub fn apply(target: u32) -> Result<(), HelperFailure> {
+    remote::run_helper_transaction(&[vec!["cpu", &target.to_string()]], |_, _| {})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backs_off_after_refused_authentication() {
+        let mut controller = AdaptiveController::new(2, 16);
+        assert_eq!(controller.auth_failed(), AUTH_BACKOFF);
+        assert_eq!(controller.auth_failed(), AUTH_BACKOFF * 2);
+        // Even an out-of-bounds count waits out the pause.
+        assert_eq!(controller.next_target(1, 0.9), None);
+
+        for _ in 0..10 {
+            controller.auth_failed();
+        }
+        assert_eq!(controller.auth_failed(), MAX_AUTH_BACKOFF);
+
+        controller.applied();
+        assert_eq!(controller.next_target(1, 0.9), Some(2));
+        assert_eq!(controller.auth_failed(), AUTH_BACKOFF);
+    }
+}
@@ -0,0 +1,124 @@
+use crate::remote;
+use std::fmt;
+use std::str::FromStr;
+
+/// cpufreq of the first CPU; the helper sets every CPU alike.
+const CPUFREQ_PATH: &str = "/sys/devices/system/cpu/cpu0/cpufreq";
+/// intel_pstate's turbo switch; 1 means boost is off.
+const NO_TURBO_PATH: &str = "/sys/devices/system/cpu/intel_pstate/no_turbo";
+/// The generic boost switch of acpi-cpufreq and amd-pstate.
+const BOOST_PATH: &str = "/sys/devices/system/cpu/cpufreq/boost";
+
+/// Energy-performance preferences the helper's `epp` command accepts;
+/// keep in sync with its `EPP_VALUES`.
+pub const EPP_VALUES: &[&str] = &["default", "performance", "balance_performance", "balance_power", "power"];
+
+/// cpufreq governors TuxTuner switches between; keep in sync with the
+/// helper's `CPU_GOVERNORS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Governor {
+    Performance,
+    Powersave,
+    Schedutil,
+    Ondemand,
+    Conservative,
+    Userspace,
+}
+
+impl Governor {
+    pub const ALL: [Governor; 6] = [
+        Governor::Performance,
+        Governor::Powersave,
+        Governor::Schedutil,
+        Governor::Ondemand,
+        Governor::Conservative,
+        Governor::Userspace,
+    ];
+
+    /// The kernel's name, which the helper's `governor` command takes.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Governor::Performance => "performance",
+            Governor::Powersave => "powersave",
+            Governor::Schedutil => "schedutil",
+            Governor::Ondemand => "ondemand",
+            Governor::Conservative => "conservative",
+            Governor::Userspace => "userspace",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Governor::Performance => "Performance",
+            Governor::Powersave => "Power Saver",
+            Governor::Schedutil => "Scheduler-Driven",
+            Governor::Ondemand => "On Demand",
+            Governor::Conservative => "Conservative",
+            Governor::Userspace => "Userspace",
+        }
+    }
+
+    /// Reads a governor as sysfs or a config file spells it.
+    pub fn parse(name: &str) -> Option<Self> {
+        Governor::ALL.into_iter().find(|governor| governor.as_str() == name.trim())
+    }
+}
+
+impl fmt::Display for Governor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Governor {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Governor::parse(name).ok_or_else(|| format!("Invalid CPU governor: {}", name))
+    }
+}
+
+/// The governor in charge; `None` without cpufreq, e.g. in most VMs.
+pub fn governor() -> Option<Governor> {
+    Governor::parse(&remote::read_to_string(format!("{}/scaling_governor", CPUFREQ_PATH)).ok()?)
+}
+
+/// Governors the cpufreq driver offers, in the kernel's order. Drivers
+/// like `intel_pstate` in active mode offer only performance and
+/// powersave.
+pub fn available_governors() -> Vec<Governor> {
+    remote::read_to_string(format!("{}/scaling_available_governors", CPUFREQ_PATH))
+        .map(|names| names.split_whitespace().filter_map(Governor::parse).collect())
+        .unwrap_or_default()
+}
+
+/// Sets every CPU's governor until reboot. Blocking.
+pub fn set_governor(governor: Governor) -> Result<(), String> {
+    remote::run_helper(&["governor", governor.as_str()])
+}
+
+/// The energy-performance preference hint of intel_pstate and amd-pstate,
+/// e.g. `balance_performance`; `None` with other drivers.
+pub fn energy_preference() -> Option<String> {
+    let value = remote::read_to_string(format!("{}/energy_performance_preference", CPUFREQ_PATH)).ok()?;
+    Some(value.trim().to_string()).filter(|value| EPP_VALUES.contains(&value.as_str()))
+}
+
+/// Sets every CPU's preference until reboot. Blocking.
+pub fn set_energy_preference(value: &str) -> Result<(), String> {
+    remote::run_helper(&["epp", value])
+}
+
+/// Whether the CPU may run above its base clock; `None` when the driver
+/// has no switch for it.
+pub fn boost() -> Option<bool> {
+    if let Ok(value) = remote::read_to_string(NO_TURBO_PATH) {
+        return Some(value.trim() == "0");
+    }
+    Some(remote::read_to_string(BOOST_PATH).ok()?.trim() == "1")
+}
+
+/// Lasts until reboot. Blocking.
+pub fn set_boost(enabled: bool) -> Result<(), String> {
+    remote::run_helper(&["boost", if enabled { "on" } else { "off" }])
+}
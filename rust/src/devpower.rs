@@ -0,0 +1,166 @@
+use crate::immutable;
+use crate::remote;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+
+const USB_DEVICES_PATH: &str = "/sys/bus/usb/devices";
+const PCI_DEVICES_PATH: &str = "/sys/bus/pci/devices";
+/// udev rules written by the helper's `device-power` command.
+const POWER_RULES_PATH: &str = "/etc/udev/rules.d/90-tuxtuner-power.rules";
+
+static USB_RULE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"ATTR\{idVendor\}=="([0-9a-f]{4})", ATTR\{idProduct\}=="([0-9a-f]{4})".*ATTR\{power/control\}="(\w+)""#)
+        .unwrap()
+});
+static PCI_RULE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"KERNEL=="([0-9a-f:.]+)".*ATTR\{power/control\}="(\w+)""#).unwrap()
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceKind {
+    Usb,
+    Pci,
+}
+
+impl DeviceKind {
+    fn helper_arg(self) -> &'static str {
+        match self {
+            DeviceKind::Usb => "usb",
+            DeviceKind::Pci => "pci",
+        }
+    }
+}
+
+/// A device with a runtime power management switch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PowerDevice {
+    pub kind: DeviceKind,
+    /// USB port (e.g. `1-2`) or PCI address (e.g. `0000:03:00.0`).
+    pub id: String,
+    pub description: String,
+    /// `power/control` is `auto` (autosuspend / runtime PM) rather than `on`.
+    pub auto: bool,
+}
+
+/// A persistent power setting in TuxTuner's udev rules file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PowerRule {
+    pub kind: DeviceKind,
+    /// `vendor:product` for USB, the PCI address otherwise.
+    pub key: String,
+    pub auto: bool,
+}
+
+fn read_attr(dir: &Path, attr: &str) -> Option<String> {
+    fs::read_to_string(dir.join(attr))
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn list_devices<F>(root: &str, kind: DeviceKind, describe: F) -> Vec<PowerDevice>
+where
+    F: Fn(&str, &Path) -> Option<String>,
+{
+    let mut devices: Vec<PowerDevice> = fs::read_dir(root)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let id = entry.file_name().to_string_lossy().to_string();
+            let path = entry.path();
+            let control = read_attr(&path, "power/control")?;
+            Some(PowerDevice {
+                kind,
+                description: describe(&id, &path)?,
+                id,
+                auto: control == "auto",
+            })
+        })
+        .collect();
+    devices.sort_by(|a, b| a.id.cmp(&b.id));
+    devices
+}
+
+/// USB devices, leaving out root hubs and interfaces.
+pub fn usb_devices() -> Vec<PowerDevice> {
+    list_devices(USB_DEVICES_PATH, DeviceKind::Usb, |id, path| {
+        if !id.contains('-') || id.contains(':') {
+            return None;
+        }
+        Some(match (read_attr(path, "manufacturer"), read_attr(path, "product")) {
+            (Some(vendor), Some(product)) => format!("{} {}", vendor, product),
+            (None, Some(product)) => product,
+            _ => format!(
+                "USB device {}:{}",
+                read_attr(path, "idVendor").unwrap_or_default(),
+                read_attr(path, "idProduct").unwrap_or_default()
+            ),
+        })
+    })
+}
+
+fn pci_class_name(class: &str) -> &'static str {
+    match class.trim_start_matches("0x").get(..2).unwrap_or("") {
+        "01" => "Storage controller",
+        "02" => "Network controller",
+        "03" => "Display controller",
+        "04" => "Multimedia controller",
+        "06" => "Bridge",
+        "0c" => "Serial bus controller",
+        "0d" => "Wireless controller",
+        _ => "PCI device",
+    }
+}
+
+pub fn pci_devices() -> Vec<PowerDevice> {
+    list_devices(PCI_DEVICES_PATH, DeviceKind::Pci, |_, path| {
+        let class = read_attr(path, "class").unwrap_or_default();
+        let vendor = read_attr(path, "vendor").unwrap_or_default();
+        let device = read_attr(path, "device").unwrap_or_default();
+        Some(format!(
+            "{} ({}:{})",
+            pci_class_name(&class),
+            vendor.trim_start_matches("0x"),
+            device.trim_start_matches("0x")
+        ))
+    })
+}
+
+/// Rules TuxTuner has installed, in file order.
+pub fn owned_rules() -> Vec<PowerRule> {
+    fs::read_to_string(immutable::persisted_path(POWER_RULES_PATH))
+        .unwrap_or_default()
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| {
+            if let Some(caps) = USB_RULE.captures(line) {
+                return Some(PowerRule {
+                    kind: DeviceKind::Usb,
+                    key: format!("{}:{}", &caps[1], &caps[2]),
+                    auto: &caps[3] == "auto",
+                });
+            }
+            PCI_RULE.captures(line).map(|caps| PowerRule {
+                kind: DeviceKind::Pci,
+                key: caps[1].to_string(),
+                auto: &caps[2] == "auto",
+            })
+        })
+        .collect()
+}
+
+/// Applies the setting now and installs a udev rule so it survives
+/// reboots and replugs.
+pub fn apply_device_power(device: &PowerDevice, auto: bool) -> Result<(), String> {
+    let state = if auto { "auto" } else { "on" };
+    remote::run_helper(&["device-power", device.kind.helper_arg(), &device.id, state])
+}
+
+/// Deletes a persistent rule; the device keeps its current setting until
+/// the next reboot or replug.
+pub fn remove_rule(rule: &PowerRule) -> Result<(), String> {
+    remote::run_helper(&["device-power-remove", rule.kind.helper_arg(), &rule.key])
+}
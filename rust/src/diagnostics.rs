@@ -0,0 +1,217 @@
+use crate::immutable::{self, Immutable};
+use crate::mac;
+use crate::privileges::{self, POLKIT_POLICY};
+use crate::probe::{self, ProbeError};
+use crate::session;
+use crate::system_info::{command_exists, HELPER_PATH};
+use crate::virt;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+/// Outcome of one self-check, with a suggested fix when it fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub passed: bool,
+    pub detail: String,
+    pub fix: Option<&'static str>,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            passed: true,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, fix: &'static str) -> Self {
+        Self {
+            name,
+            passed: false,
+            detail: detail.into(),
+            fix: Some(fix),
+        }
+    }
+}
+
+fn check_helper() -> Check {
+    const NAME: &str = "Privileged helper";
+    // /usr is read-only on immutable systems, so reinstalling the usual
+    // way doesn't work there.
+    let fix = immutable::current()
+        .map(Immutable::helper_install)
+        .unwrap_or("Reinstall TuxTuner to restore the helper");
+
+    match fs::metadata(HELPER_PATH) {
+        Ok(meta) if meta.is_file() && meta.permissions().mode() & 0o111 != 0 => {
+            Check::pass(NAME, HELPER_PATH)
+        }
+        Ok(_) => Check::fail(NAME, format!("{} is not executable", HELPER_PATH), fix),
+        Err(_) => Check::fail(NAME, format!("{} not found", HELPER_PATH), fix),
+    }
+}
+
+fn check_helper_version() -> Check {
+    const NAME: &str = "Helper version";
+
+    match privileges::helper_mismatch() {
+        None => Check::pass(NAME, env!("CARGO_PKG_VERSION")),
+        Some(mismatch) => Check::fail(NAME, mismatch, "Reinstall TuxTuner so the app and helper match"),
+    }
+}
+
+fn check_polkit() -> Check {
+    const NAME: &str = "Polkit policy";
+
+    if !command_exists("pkexec") {
+        return Check::fail(NAME, "pkexec not found", "Install polkit");
+    }
+    if Path::new(POLKIT_POLICY).is_file() {
+        Check::pass(NAME, POLKIT_POLICY)
+    } else {
+        Check::fail(
+            NAME,
+            format!("{} not found", POLKIT_POLICY),
+            "Reinstall TuxTuner to restore the polkit policy",
+        )
+    }
+}
+
+fn check_access_control() -> Check {
+    const NAME: &str = "Access control policy";
+
+    match mac::active() {
+        Some(framework) => Check::pass(
+            NAME,
+            format!("{} is enforcing; changes it refuses are reported as such", framework.label()),
+        ),
+        None => Check::pass(NAME, "No SELinux or AppArmor policy enforced"),
+    }
+}
+
+fn check_immutable() -> Check {
+    const NAME: &str = "System layout";
+
+    match immutable::current() {
+        Some(Immutable::Ostree) => Check::pass(NAME, "ostree: /usr is read-only, /etc is writable"),
+        Some(Immutable::NixOS) => Check::pass(
+            NAME,
+            "NixOS: udev rules, module options and boot services belong in your configuration; \
+             the snippet below carries TuxTuner's",
+        ),
+        None => Check::pass(NAME, "Conventional, writable /usr and /etc"),
+    }
+}
+
+fn check_virtualization() -> Check {
+    const NAME: &str = "Virtualization";
+
+    match virt::current() {
+        Some(virtualization) => Check::pass(
+            NAME,
+            format!("Running in a {}; hardware-specific settings are disabled", virtualization.label()),
+        ),
+        None => Check::pass(NAME, "Bare metal"),
+    }
+}
+
+fn check_supergfxd() -> Check {
+    const NAME: &str = "supergfxd daemon";
+
+    if !command_exists("supergfxctl") {
+        return Check::pass(NAME, "Not installed; GPU mode switching is unavailable");
+    }
+    match probe::run("supergfxctl", &["-g"]) {
+        Ok(output) if output.status.success() => Check::pass(NAME, "Running"),
+        Err(ProbeError::TimedOut) => Check::fail(
+            NAME,
+            "supergfxctl did not respond",
+            "Run: systemctl restart supergfxd",
+        ),
+        _ => Check::fail(
+            NAME,
+            "supergfxctl cannot reach the daemon",
+            "Run: systemctl enable --now supergfxd",
+        ),
+    }
+}
+
+fn check_hyprctl() -> Check {
+    const NAME: &str = "Hyprland IPC";
+
+    if !command_exists("hyprctl") {
+        return Check::fail(NAME, "hyprctl not found", "Refresh rate control needs Hyprland");
+    }
+    let session = session::current();
+    if !session.may_be_hyprland() {
+        return Check::fail(
+            NAME,
+            format!("Running inside {}, not Hyprland", session.label()),
+            "Start TuxTuner from your Hyprland session",
+        );
+    }
+    match probe::run("hyprctl", &["version"]) {
+        Ok(output) if output.status.success() => Check::pass(NAME, "Reachable"),
+        Err(ProbeError::TimedOut) => Check::fail(
+            NAME,
+            "hyprctl did not respond",
+            "Hyprland may be stuck; restart it or log in again",
+        ),
+        _ => Check::fail(NAME, "hyprctl cannot reach Hyprland", "Restart Hyprland or log in again"),
+    }
+}
+
+fn check_sysfs() -> Check {
+    const NAME: &str = "Writable sysfs";
+
+    let read_write = fs::read_to_string("/proc/mounts")
+        .unwrap_or_default()
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .find(|fields| fields.get(1) == Some(&"/sys"))
+        .and_then(|fields| fields.get(3).map(|opts| opts.split(',').any(|opt| opt == "rw")));
+
+    match read_write {
+        Some(true) => Check::pass(NAME, "/sys is mounted read-write"),
+        Some(false) => Check::fail(
+            NAME,
+            "/sys is mounted read-only",
+            "Containers and sandboxes can't change hardware settings; run TuxTuner on the host",
+        ),
+        None => Check::fail(NAME, "/sys is not mounted", "Mount sysfs at /sys"),
+    }
+}
+
+fn check_cpu_hotplug() -> Check {
+    const NAME: &str = "CPU hotplug";
+
+    if Path::new("/sys/devices/system/cpu/cpu1/online").exists() {
+        Check::pass(NAME, "Supported")
+    } else {
+        Check::fail(
+            NAME,
+            "No hot-pluggable CPUs found",
+            "Thread control needs a kernel with CONFIG_HOTPLUG_CPU",
+        )
+    }
+}
+
+/// Runs every self-check. Blocking; run it off the main thread.
+pub fn run_checks() -> Vec<Check> {
+    vec![
+        check_helper(),
+        check_helper_version(),
+        check_polkit(),
+        check_access_control(),
+        check_immutable(),
+        check_virtualization(),
+        check_supergfxd(),
+        check_hyprctl(),
+        check_sysfs(),
+        check_cpu_hotplug(),
+    ]
+}
@@ -0,0 +1,56 @@
+use crate::hyprland;
+use crate::session::{self, Desktop};
+use gtk4::gio;
+use gtk4::prelude::*;
+
+const GNOME_INTERFACE_SCHEMA: &str = "org.gnome.desktop.interface";
+
+/// Compositor whose animations and blur ("eye candy") can be switched off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Hyprland,
+    Gnome,
+}
+
+fn gnome_settings() -> Option<gio::Settings> {
+    let source = gio::SettingsSchemaSource::default()?;
+    source.lookup(GNOME_INTERFACE_SCHEMA, true)?;
+    Some(gio::Settings::new(GNOME_INTERFACE_SCHEMA))
+}
+
+/// Asks hyprctl first, which also covers Hyprland on a remote host. GNOME
+/// settings live in this session's dconf, so only count locally.
+pub fn detect() -> Option<Backend> {
+    if hyprland::option_bool("animations:enabled").is_some() {
+        return Some(Backend::Hyprland);
+    }
+    let gnome = session::current().desktop == Desktop::Gnome;
+    (gnome && gnome_settings().is_some()).then_some(Backend::Gnome)
+}
+
+/// Whether animations are on.
+pub fn enabled(backend: Backend) -> Option<bool> {
+    match backend {
+        Backend::Hyprland => hyprland::option_bool("animations:enabled"),
+        Backend::Gnome => gnome_settings().map(|settings| settings.boolean("enable-animations")),
+    }
+}
+
+/// Turns animations (and blur, on Hyprland) on or off. Hyprland forgets
+/// this when its config is reloaded; GNOME keeps it.
+pub fn set_enabled(backend: Backend, on: bool) -> Result<(), String> {
+    match backend {
+        Backend::Hyprland => {
+            let value = if on { "1" } else { "0" };
+            hyprland::set_keywords(&[("animations:enabled", value), ("decoration:blur:enabled", value)])
+        }
+        Backend::Gnome => {
+            let settings = gnome_settings().ok_or("GNOME interface settings are missing")?;
+            settings
+                .set_boolean("enable-animations", on)
+                .map_err(|e| e.to_string())?;
+            gio::Settings::sync();
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,330 @@
+use crate::battery;
+use crate::config::Config;
+use crate::immutable;
+use crate::profiles::{self, Profile};
+use crate::remote;
+use crate::system76;
+use crate::system_info::{SystemInfo, HELPER_PATH};
+use crate::tunables;
+use crate::validate;
+
+/// Files the helper persists udev rules and module options to.
+const UDEV_RULES: [&str; 2] = [
+    "/etc/udev/rules.d/90-tuxtuner-power.rules",
+    "/etc/udev/rules.d/90-tuxtuner-nic.rules",
+];
+const MODPROBE_CONF: &str = "/etc/modprobe.d/90-tuxtuner.conf";
+
+/// Boot services the helper enables, keyed by the config file whose
+/// presence means one is in use; keep in sync with data/*.service.
+const BOOT_SERVICES: &[BootService] = &[
+    BootService {
+        name: "tuxtuner-wakeup",
+        description: "Restore TuxTuner wakeup source settings",
+        config: "/etc/tuxtuner/wakeup.conf",
+        service_type: "oneshot",
+        remain_after_exit: false,
+        start: "wakeup-restore",
+        stop: None,
+    },
+    BootService {
+        name: "tuxtuner-undervolt",
+        description: "Restore TuxTuner undervolt settings",
+        config: "/etc/tuxtuner/undervolt.conf",
+        service_type: "oneshot",
+        remain_after_exit: true,
+        start: "undervolt-restore",
+        stop: Some("undervolt-shutdown"),
+    },
+    BootService {
+        name: "tuxtuner-gpu-fan",
+        description: "TuxTuner GPU fan curve",
+        config: "/etc/tuxtuner/gpu-fan.conf",
+        service_type: "simple",
+        remain_after_exit: false,
+        start: "gpu-fan-daemon",
+        stop: None,
+    },
+];
+
+#[derive(Debug)]
+struct BootService {
+    name: &'static str,
+    description: &'static str,
+    config: &'static str,
+    service_type: &'static str,
+    remain_after_exit: bool,
+    start: &'static str,
+    stop: Option<&'static str>,
+}
+
+/// Configuration management formats the setup can be rendered as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    NixOS,
+    Ansible,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 2] = [ExportFormat::NixOS, ExportFormat::Ansible];
+
+    /// Parses the command-line spelling, `nixos` or `ansible`.
+    pub fn from_arg(arg: &str) -> Option<Self> {
+        match arg.to_ascii_lowercase().as_str() {
+            "nixos" | "nix" => Some(ExportFormat::NixOS),
+            "ansible" => Some(ExportFormat::Ansible),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::NixOS => "NixOS Module",
+            ExportFormat::Ansible => "Ansible Tasks",
+        }
+    }
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            ExportFormat::NixOS => "tuxtuner.nix",
+            ExportFormat::Ansible => "tuxtuner.yml",
+        }
+    }
+}
+
+/// What TuxTuner set that a configuration manager can reproduce.
+#[derive(Debug, Default)]
+pub struct Setup {
+    /// Profile the settings come from, for the header comment.
+    profile: Option<String>,
+    /// sysfs values the kernel forgets on reboot, as (path, value).
+    sysfs: Vec<(String, String)>,
+    /// sysctl keys and values.
+    sysctls: Vec<(&'static str, &'static str)>,
+    udev_rules: String,
+    modprobe: String,
+    /// Boot services in use, with their config file's contents.
+    services: Vec<(&'static BootService, String)>,
+}
+
+impl Setup {
+    /// What the helper persisted on disk: udev rules, module options and
+    /// boot services. Blocking.
+    pub fn persisted() -> Self {
+        let read = |path: &str| remote::read_to_string(immutable::persisted_path(path)).unwrap_or_default();
+        let udev_rules = UDEV_RULES.iter().map(|path| read(path)).collect::<Vec<_>>().join("\n");
+        let services = BOOT_SERVICES
+            .iter()
+            .filter_map(|service| Some((service, remote::read_to_string(service.config).ok()?)))
+            .collect();
+
+        Self {
+            udev_rules,
+            modprobe: read(MODPROBE_CONF),
+            services,
+            ..Self::default()
+        }
+    }
+
+    /// The persisted setup plus the active profile's kernel settings, the
+    /// charge limits and the sysctl tunables in force. Blocking.
+    pub fn current() -> Self {
+        let mut setup = Self::persisted();
+        let config = Config::load();
+        let profile = config
+            .active_profile
+            .as_deref()
+            .and_then(|name| profiles::find_profile(&config, name).ok());
+        if let Some(profile) = &profile {
+            setup.add_profile(profile);
+        }
+
+        for (battery, percent) in battery::charge_limits() {
+            setup.sysfs.push((
+                format!("/sys/class/power_supply/{}/charge_control_end_threshold", battery),
+                percent.to_string(),
+            ));
+        }
+        setup.sysctls = tunables::audit()
+            .into_iter()
+            .filter(|state| state.good)
+            .filter_map(|state| state.tunable.sysctl())
+            .collect();
+        setup
+    }
+
+    fn add_profile(&mut self, profile: &Profile) {
+        self.profile = Some(profile.name.clone());
+
+        // system76-power keeps the platform profile itself.
+        if let Some(platform) = &profile.platform_profile {
+            if !system76::installed() && !profiles::platform_profile_choices().is_empty() {
+                self.sysfs.push(("/sys/firmware/acpi/platform_profile".to_string(), platform.clone()));
+            }
+        }
+
+        if let Some(threads) = profile.cpu_threads {
+            let (total, _) = SystemInfo::fetch_cpu_info();
+            let threads = validate::cpu_threads(threads, total);
+            for cpu in threads..total {
+                self.sysfs.push((format!("/sys/devices/system/cpu/cpu{}/online", cpu), "0".to_string()));
+            }
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.sysfs.is_empty()
+            && self.sysctls.is_empty()
+            && self.udev_rules.trim().is_empty()
+            && self.modprobe.trim().is_empty()
+            && self.services.is_empty()
+    }
+
+    fn header(&self, format: ExportFormat) -> String {
+        let target = match format {
+            ExportFormat::NixOS => "a NixOS module",
+            ExportFormat::Ansible => "Ansible tasks",
+        };
+        let mut header = match &self.profile {
+            Some(profile) => format!("# TuxTuner setup ({} profile) as {}\n", profile, target),
+            None => format!("# TuxTuner setup as {}\n", target),
+        };
+        if self.is_empty() {
+            header.push_str("# TuxTuner hasn't set anything that needs reapplying.\n");
+        }
+        header
+    }
+
+    /// tmpfiles.d lines writing the sysfs values at boot.
+    fn tmpfiles_lines(&self) -> Vec<String> {
+        self.sysfs
+            .iter()
+            .map(|(path, value)| format!("w {} - - - - {}", path, value))
+            .collect()
+    }
+
+    pub fn render(&self, format: ExportFormat) -> String {
+        match format {
+            ExportFormat::NixOS => self.render_nixos(),
+            ExportFormat::Ansible => self.render_ansible(),
+        }
+    }
+
+    fn render_nixos(&self) -> String {
+        let mut out = self.header(ExportFormat::NixOS);
+        out.push_str("{\n");
+
+        let tmpfiles = self.tmpfiles_lines();
+        if !tmpfiles.is_empty() {
+            out.push_str("  systemd.tmpfiles.rules = [\n");
+            for line in tmpfiles {
+                out.push_str(&format!("    \"{}\"\n", line));
+            }
+            out.push_str("  ];\n");
+        }
+        if !self.sysctls.is_empty() {
+            out.push_str("  boot.kernel.sysctl = {\n");
+            for (key, value) in &self.sysctls {
+                out.push_str(&format!("    \"{}\" = {};\n", key, value));
+            }
+            out.push_str("  };\n");
+        }
+        if !self.udev_rules.trim().is_empty() {
+            out.push_str(&format!("  services.udev.extraRules = ''\n{}  '';\n", indent(&self.udev_rules, 4)));
+        }
+        if !self.modprobe.trim().is_empty() {
+            out.push_str(&format!("  boot.extraModprobeConfig = ''\n{}  '';\n", indent(&self.modprobe, 4)));
+        }
+        // The helper keeps updating the services' config files, so they
+        // stay out of environment.etc.
+        for (service, _) in &self.services {
+            out.push_str(&format!("  systemd.services.{} = {{\n", service.name));
+            out.push_str(&format!("    description = \"{}\";\n", service.description));
+            out.push_str("    wantedBy = [ \"multi-user.target\" ];\n");
+            out.push_str("    serviceConfig = {\n");
+            out.push_str(&format!("      Type = \"{}\";\n", service.service_type));
+            if service.remain_after_exit {
+                out.push_str("      RemainAfterExit = true;\n");
+            }
+            out.push_str(&format!("      ExecStart = \"{} {}\";\n", HELPER_PATH, service.start));
+            if let Some(stop) = service.stop {
+                out.push_str(&format!("      ExecStop = \"{} {}\";\n", HELPER_PATH, stop));
+            }
+            out.push_str("    };\n  };\n");
+        }
+
+        out.push_str("}\n");
+        out
+    }
+
+    fn render_ansible(&self) -> String {
+        let mut out = self.header(ExportFormat::Ansible);
+        out.push_str("# Boot services need TuxTuner installed on the target.\n");
+
+        let tmpfiles = self.tmpfiles_lines();
+        if !tmpfiles.is_empty() {
+            out.push_str(&copy_task(
+                "Write TuxTuner sysfs settings at boot",
+                "/etc/tmpfiles.d/tuxtuner.conf",
+                &tmpfiles.join("\n"),
+            ));
+            out.push_str(
+                "- name: Apply TuxTuner sysfs settings now\n  ansible.builtin.command: \
+                 systemd-tmpfiles --create /etc/tmpfiles.d/tuxtuner.conf\n",
+            );
+        }
+        for (key, value) in &self.sysctls {
+            out.push_str(&format!(
+                "- name: Set {key}\n  ansible.posix.sysctl:\n    name: {key}\n    value: \"{value}\"\n    \
+                 sysctl_file: /etc/sysctl.d/90-tuxtuner.conf\n",
+                key = key,
+                value = value,
+            ));
+        }
+        if !self.udev_rules.trim().is_empty() {
+            out.push_str(&copy_task(
+                "Install TuxTuner udev rules",
+                "/etc/udev/rules.d/90-tuxtuner.rules",
+                &self.udev_rules,
+            ));
+            out.push_str("- name: Reload udev rules\n  ansible.builtin.command: udevadm control --reload\n");
+        }
+        if !self.modprobe.trim().is_empty() {
+            out.push_str(&copy_task(
+                "Install TuxTuner module options",
+                "/etc/modprobe.d/90-tuxtuner.conf",
+                &self.modprobe,
+            ));
+        }
+        for (service, config) in &self.services {
+            out.push_str(&copy_task(
+                &format!("Install {} settings", service.name),
+                service.config,
+                config,
+            ));
+            out.push_str(&format!(
+                "- name: Enable {name}\n  ansible.builtin.systemd:\n    name: {name}.service\n    enabled: true\n",
+                name = service.name,
+            ));
+        }
+        out
+    }
+}
+
+/// Indents the non-empty lines of `text` by `width` spaces.
+fn indent(text: &str, width: usize) -> String {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| format!("{}{}\n", " ".repeat(width), line))
+        .collect()
+}
+
+/// An Ansible task writing `content` to `dest`.
+fn copy_task(name: &str, dest: &str, content: &str) -> String {
+    format!(
+        "- name: {}\n  ansible.builtin.copy:\n    dest: {}\n    mode: \"0644\"\n    content: |\n{}",
+        name,
+        dest,
+        indent(content, 6)
+    )
+}
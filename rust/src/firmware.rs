@@ -0,0 +1,116 @@
+use crate::remote;
+use std::fs;
+use std::path::Path;
+
+const FIRMWARE_ATTRIBUTES_PATH: &str = "/sys/class/firmware-attributes";
+
+/// Drivers with their own controls elsewhere in the app.
+const SKIPPED_DRIVERS: [&str; 1] = ["asus-armoury"];
+
+/// Dell and Framework firmware expose hundreds of BIOS settings; only
+/// thermal and battery ones belong in a power tool.
+const RELEVANT_KEYWORDS: [&str; 4] = ["thermal", "batt", "charge", "fan"];
+
+/// Value constraints read from the kernel's firmware-attributes interface.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeKind {
+    Enumeration(Vec<String>),
+    Integer { min: i64, max: i64, step: i64 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FirmwareAttribute {
+    pub driver: String,
+    pub name: String,
+    pub display_name: String,
+    pub kind: AttributeKind,
+    pub current: String,
+}
+
+fn read_value(dir: &Path, file: &str) -> Option<String> {
+    fs::read_to_string(dir.join(file))
+        .ok()
+        .map(|value| value.trim().to_string())
+}
+
+fn read_attribute(driver: &str, dir: &Path) -> Option<FirmwareAttribute> {
+    let name = dir.file_name()?.to_string_lossy().to_string();
+    let kind = match read_value(dir, "type")?.as_str() {
+        "enumeration" => AttributeKind::Enumeration(
+            read_value(dir, "possible_values")?
+                .split(';')
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+                .collect(),
+        ),
+        "integer" => AttributeKind::Integer {
+            min: read_value(dir, "min_value")?.parse().ok()?,
+            max: read_value(dir, "max_value")?.parse().ok()?,
+            step: read_value(dir, "scalar_increment")
+                .and_then(|step| step.parse().ok())
+                .filter(|step| *step > 0)
+                .unwrap_or(1),
+        },
+        // Strings and passwords have no constraints worth offering.
+        _ => return None,
+    };
+
+    Some(FirmwareAttribute {
+        driver: driver.to_string(),
+        display_name: read_value(dir, "display_name")
+            .filter(|label| !label.is_empty())
+            .unwrap_or_else(|| name.clone()),
+        name,
+        kind,
+        current: read_value(dir, "current_value")?,
+    })
+}
+
+/// Thermal and battery attributes of every firmware-attributes driver,
+/// e.g. dell-wmi-sysman.
+pub fn attributes() -> Vec<FirmwareAttribute> {
+    let mut attributes: Vec<FirmwareAttribute> = fs::read_dir(FIRMWARE_ATTRIBUTES_PATH)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|driver| {
+            let name = driver.file_name().to_string_lossy().to_string();
+            (!SKIPPED_DRIVERS.contains(&name.as_str())).then_some((name, driver.path()))
+        })
+        .flat_map(|(driver, path)| {
+            fs::read_dir(path.join("attributes"))
+                .into_iter()
+                .flatten()
+                .flatten()
+                .filter(|entry| {
+                    let name = entry.file_name().to_string_lossy().to_lowercase();
+                    RELEVANT_KEYWORDS.iter().any(|keyword| name.contains(keyword))
+                })
+                .filter_map(move |entry| read_attribute(&driver, &entry.path()))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    attributes.sort_by(|a, b| a.display_name.cmp(&b.display_name));
+    attributes
+}
+
+/// Writes `value` through the helper, which re-checks it against the
+/// kernel's constraints.
+pub fn apply_attribute(attribute: &FirmwareAttribute, value: &str) -> Result<(), String> {
+    let valid = match &attribute.kind {
+        AttributeKind::Enumeration(values) => values.iter().any(|v| v == value),
+        AttributeKind::Integer { min, max, .. } => value
+            .parse::<i64>()
+            .is_ok_and(|v| (*min..=*max).contains(&v)),
+    };
+    if !valid {
+        return Err(format!("Invalid value for {}: {}", attribute.display_name, value));
+    }
+
+    remote::run_helper(&[
+        "firmware-attribute",
+        &attribute.driver,
+        &attribute.name,
+        value,
+    ])
+}
@@ -0,0 +1,144 @@
+use crate::probe;
+use crate::remote;
+use gtk4::prelude::*;
+use gtk4::{gio, glib};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const BUS_NAME: &str = "org.freedesktop.fwupd";
+const OBJECT_PATH: &str = "/";
+const INTERFACE: &str = "org.freedesktop.fwupd";
+/// `FWUPD_DEVICE_FLAG_UPDATABLE`.
+const FLAG_UPDATABLE: u64 = 1 << 1;
+/// How long `recent_updates` reuses an answer; fwupd refreshes its
+/// metadata at most daily.
+const RECHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+static LAST_CHECK: Mutex<Option<(Instant, Vec<FirmwareUpdate>)>> = Mutex::new(None);
+
+/// Firmware that shapes power and thermal behavior.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FirmwareKind {
+    /// The UEFI/BIOS image, which on many laptops carries the embedded
+    /// controller's firmware and fan tables too.
+    System,
+    /// The embedded controller, which runs the fans, charging and
+    /// thermal limits.
+    EmbeddedController,
+    Battery,
+}
+
+impl FirmwareKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            FirmwareKind::System => "System Firmware",
+            FirmwareKind::EmbeddedController => "Embedded Controller",
+            FirmwareKind::Battery => "Battery",
+        }
+    }
+
+    fn classify(name: &str, plugin: &str) -> Option<Self> {
+        let lower = name.to_lowercase();
+        if lower.contains("embedded controller") || name.split_whitespace().any(|word| word == "EC") {
+            Some(FirmwareKind::EmbeddedController)
+        } else if lower.contains("battery") {
+            Some(FirmwareKind::Battery)
+        } else if matches!(plugin, "uefi-capsule" | "flashrom")
+            || lower.contains("system firmware")
+            || lower.contains("bios")
+        {
+            Some(FirmwareKind::System)
+        } else {
+            None
+        }
+    }
+}
+
+/// A firmware update fwupd offers for one device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FirmwareUpdate {
+    /// The device as fwupd names it, e.g. `ThinkPad X1 Embedded Controller`.
+    pub device: String,
+    pub kind: FirmwareKind,
+    pub current: String,
+    pub available: String,
+    /// One line about the release, when the vendor wrote one.
+    pub summary: Option<String>,
+}
+
+fn call(method: &str, args: Option<&glib::Variant>) -> Result<glib::Variant, String> {
+    let connection = gio::bus_get_sync(gio::BusType::System, None::<&gio::Cancellable>)
+        .map_err(|e| e.to_string())?;
+    // fwupd is usually bus-activated, and scans devices when it starts.
+    connection
+        .call_sync(
+            Some(BUS_NAME),
+            OBJECT_PATH,
+            INTERFACE,
+            method,
+            args,
+            Some(glib::VariantTy::new("(aa{sv})").map_err(|e| e.to_string())?),
+            gio::DBusCallFlags::NONE,
+            probe::timeout().as_millis().min(i32::MAX as u128) as i32,
+            None::<&gio::Cancellable>,
+        )
+        .map_err(|e| e.message().to_string())
+}
+
+/// Each `a{sv}` of an `(aa{sv})` reply.
+fn entries(reply: &glib::Variant) -> Vec<glib::VariantDict> {
+    reply
+        .child_value(0)
+        .iter()
+        .map(|entry| glib::VariantDict::new(Some(&entry)))
+        .collect()
+}
+
+fn lookup<T: glib::variant::FromVariant>(entry: &glib::VariantDict, key: &str) -> Option<T> {
+    entry.lookup::<T>(key).ok().flatten()
+}
+
+/// Pending updates to system, EC and battery firmware, as of the metadata
+/// fwupd last downloaded. Errors when fwupd isn't reachable, which it
+/// never is in remote mode since it's on this machine's system bus.
+/// Blocking.
+pub fn pending_updates() -> Result<Vec<FirmwareUpdate>, String> {
+    if remote::host().is_some() {
+        return Err("fwupd is only reachable on this machine".to_string());
+    }
+
+    let devices = call("GetDevices", None)?;
+    Ok(entries(&devices)
+        .iter()
+        .filter(|device| lookup::<u64>(device, "Flags").is_some_and(|flags| flags & FLAG_UPDATABLE != 0))
+        .filter_map(|device| {
+            let name = lookup::<String>(device, "Name")?;
+            let kind = FirmwareKind::classify(&name, &lookup::<String>(device, "Plugin").unwrap_or_default())?;
+            let id = lookup::<String>(device, "DeviceId")?;
+            // Fails with "nothing to do" when the device is up to date.
+            let releases = call("GetUpgrades", Some(&(id,).to_variant())).ok()?;
+            let newest = entries(&releases).into_iter().next()?;
+            Some(FirmwareUpdate {
+                device: name,
+                kind,
+                current: lookup(device, "Version").unwrap_or_default(),
+                available: lookup(&newest, "Version")?,
+                summary: lookup::<String>(&newest, "Summary").filter(|summary| !summary.is_empty()),
+            })
+        })
+        .collect())
+}
+
+/// `pending_updates`, asked at most every few hours and empty when fwupd
+/// can't be reached, for checks that run all the time. Blocking.
+pub fn recent_updates() -> Vec<FirmwareUpdate> {
+    let mut last = LAST_CHECK.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((checked, updates)) = last.as_ref() {
+        if checked.elapsed() < RECHECK_INTERVAL {
+            return updates.clone();
+        }
+    }
+    let updates = pending_updates().unwrap_or_default();
+    *last = Some((Instant::now(), updates.clone()));
+    updates
+}
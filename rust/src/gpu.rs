@@ -0,0 +1,649 @@
+use crate::probe::{self, Capability};
+use crate::remote;
+use crate::sysfs::{LocalSysfs, SysfsProvider};
+use crate::system76;
+use crate::system_info::{command_exists, SESSION_ID_PATTERN};
+use gtk4::{gio, glib};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::sync::Arc;
+
+const SUPERGFX_INTERFACE: &str = "org.supergfxctl.Daemon";
+const SUPERGFX_PATH: &str = "/org/supergfxctl/Gfx";
+/// How often backends without change signals are re-queried.
+const MODE_POLL_SECONDS: u32 = 10;
+
+/// Firmware knobs exposing the ASUS GPU MUX (0 = dGPU direct, 1 = Optimus).
+const GPU_MUX_PATHS: [&str; 2] = [
+    "/sys/class/firmware-attributes/asus-armoury/attributes/gpu_mux_mode/current_value",
+    "/sys/devices/platform/asus-nb-wmi/gpu_mux_mode",
+];
+
+/// Graphics modes TuxTuner knows how to switch between. Backends
+/// translate their own vocabulary into these; `as_str` gives the names
+/// the helper's `gpu` command accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GpuMode {
+    Integrated,
+    Hybrid,
+    Dedicated,
+    Compute,
+    #[serde(rename = "VFIO")]
+    Vfio,
+    /// ASUS hardware MUX routing the panel straight to the dGPU.
+    AsusMuxDgpu,
+}
+
+impl GpuMode {
+    pub const ALL: [GpuMode; 6] = [
+        GpuMode::Integrated,
+        GpuMode::Hybrid,
+        GpuMode::Dedicated,
+        GpuMode::Compute,
+        GpuMode::Vfio,
+        GpuMode::AsusMuxDgpu,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GpuMode::Integrated => "Integrated",
+            GpuMode::Hybrid => "Hybrid",
+            GpuMode::Dedicated => "Dedicated",
+            GpuMode::Compute => "Compute",
+            GpuMode::Vfio => "VFIO",
+            GpuMode::AsusMuxDgpu => "AsusMuxDgpu",
+        }
+    }
+
+    /// Human-readable label.
+    pub fn label(self) -> &'static str {
+        match self {
+            GpuMode::AsusMuxDgpu => "MUX: dGPU direct",
+            mode => mode.as_str(),
+        }
+    }
+
+    /// Reads a mode as any supergfxctl release spells it: `Hybrid` on
+    /// 5.x, `hybrid` on 4.x, `dedicated`/`nvidia` before that. `None`,
+    /// `AsusEgpu` and names from newer releases aren't modes TuxTuner can
+    /// switch to and give `None`.
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.trim().trim_matches(|c: char| c == '"' || c == '\'' || c == '[' || c == ']');
+        match name.to_ascii_lowercase().as_str() {
+            "integrated" | "igpu" => Some(GpuMode::Integrated),
+            // NvidiaNoModeset is Hybrid with the NVIDIA driver's modeset off.
+            "hybrid" | "nvidianomodeset" => Some(GpuMode::Hybrid),
+            "dedicated" | "nvidia" | "dgpu" => Some(GpuMode::Dedicated),
+            "compute" => Some(GpuMode::Compute),
+            "vfio" => Some(GpuMode::Vfio),
+            "asusmuxdgpu" | "asusmuxdiscreet" => Some(GpuMode::AsusMuxDgpu),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for GpuMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for GpuMode {
+    type Err = String;
+
+    /// Accepts exactly the names `as_str` gives; see `parse` for tool
+    /// output.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        GpuMode::ALL
+            .into_iter()
+            .find(|mode| mode.as_str() == name)
+            .ok_or_else(|| format!("Invalid GPU mode: {}", name))
+    }
+}
+
+/// Parses `supergfxctl -s`. Releases print `[Integrated, Hybrid]`, a
+/// `Supported modes:` prefix, one mode per line, or quoted names;
+/// anything unrecognised is skipped rather than shown as a bogus mode.
+fn parse_supergfx_modes(output: &str) -> Vec<GpuMode> {
+    let list = output
+        .rsplit_once(':')
+        .filter(|(prefix, _)| !prefix.contains('['))
+        .map_or(output, |(_, list)| list);
+    let mut modes = Vec::new();
+    for mode in list.split([',', '\n', ' ']).filter_map(GpuMode::parse) {
+        if !modes.contains(&mode) {
+            modes.push(mode);
+        }
+    }
+    modes
+}
+
+/// Parses `supergfxctl -g`, which prints the bare mode on current releases
+/// and `Current graphics mode: hybrid` on older ones.
+fn parse_supergfx_mode(output: &str) -> Option<GpuMode> {
+    let line = output.lines().map(str::trim).find(|line| !line.is_empty())?;
+    GpuMode::parse(line.rsplit_once(':').map_or(line, |(_, mode)| mode))
+}
+
+/// What a backend reported about the graphics setup.
+#[derive(Debug, Clone, Default)]
+pub struct GpuStatus {
+    /// `None` when the mode can't be told.
+    pub mode: Option<GpuMode>,
+    /// Modes the backend can switch to; empty when switching isn't possible.
+    pub modes: Vec<GpuMode>,
+    pub mux: bool,
+    pub capability: Capability,
+}
+
+/// A tool that can report, and usually switch, the graphics mode.
+pub trait GpuBackend: Send + Sync {
+    /// Command-line tool behind the backend, named in status messages.
+    fn tool(&self) -> &'static str;
+
+    /// Blocking; run it off the main thread.
+    fn status(&self) -> GpuStatus;
+
+    /// Whether switching from `current` to `target` takes a reboot rather
+    /// than a logout.
+    fn requires_reboot(&self, current: Option<GpuMode>, target: GpuMode) -> bool;
+
+    /// Switches to `mode`, then reboots or ends the session. Blocking.
+    fn apply(&self, mode: GpuMode, reboot: bool) -> Result<(), String>;
+
+    /// Switches to VFIO mode and starts the libvirt `domain` on the freed
+    /// GPU, switching back to Hybrid once it shuts down. Blocking.
+    fn apply_for_vm(&self, _domain: &str) -> Result<(), String> {
+        Err(format!("{} can't hand the GPU to a VM", self.tool()))
+    }
+
+    /// Whether the mode can change at all, and so is worth watching.
+    fn can_switch(&self) -> bool {
+        true
+    }
+}
+
+/// Picks the switching backend for this machine, falling back to a
+/// read-only one that only reports whether graphics are hybrid.
+pub fn detect() -> Box<dyn GpuBackend> {
+    detect_with(Arc::new(LocalSysfs))
+}
+
+/// `detect`, reading the firmware MUX knob through `sysfs`.
+pub fn detect_with(sysfs: Arc<dyn SysfsProvider>) -> Box<dyn GpuBackend> {
+    if command_exists("supergfxctl") || read_gpu_mux(sysfs.as_ref()).is_some() {
+        Box::new(Supergfx { sysfs })
+    } else if system76::installed() {
+        Box::new(System76)
+    } else if command_exists("envycontrol") {
+        Box::new(Envycontrol)
+    } else {
+        Box::new(ReadOnly)
+    }
+}
+
+/// Calls `callback` on the main loop with a fresh status from `backend`
+/// whenever the graphics mode may have been changed outside TuxTuner,
+/// e.g. by `supergfxctl -m` in a terminal. supergfxd announces switches
+/// with its `NotifyGfx` signal; other backends are polled.
+pub fn watch_mode<F: Fn(GpuStatus) + 'static>(backend: Arc<dyn GpuBackend>, callback: F) {
+    let callback = Rc::new(callback);
+    let polled = backend.clone();
+    let refresh = move || {
+        let callback = callback.clone();
+        let backend = polled.clone();
+        glib::spawn_future_local(async move {
+            if let Ok(status) = gio::spawn_blocking(move || backend.status()).await {
+                callback(status);
+            }
+        });
+    };
+
+    if backend.tool() == Supergfx::TOOL {
+        if let Ok(connection) = gio::bus_get_sync(gio::BusType::System, None::<&gio::Cancellable>) {
+            connection.signal_subscribe(
+                None,
+                Some(SUPERGFX_INTERFACE),
+                Some("NotifyGfx"),
+                Some(SUPERGFX_PATH),
+                None,
+                gio::DBusSignalFlags::NONE,
+                move |_, _, _, _, _, _| refresh(),
+            );
+            return;
+        }
+    }
+
+    if !backend.can_switch() {
+        return;
+    }
+    glib::timeout_add_seconds_local(MODE_POLL_SECONDS, move || {
+        refresh();
+        glib::ControlFlow::Continue
+    });
+}
+
+/// Returns `Some(true)` when the MUX routes the panel to the dGPU,
+/// `Some(false)` in Optimus mode and `None` when no MUX is exposed.
+fn read_gpu_mux(sysfs: &dyn SysfsProvider) -> Option<bool> {
+    GPU_MUX_PATHS.iter().find_map(|path| {
+        sysfs
+            .read_to_string(Path::new(path))
+            .ok()
+            .and_then(|content| content.trim().parse::<u8>().ok())
+            .map(|value| value == 0)
+    })
+}
+
+/// Switches through the helper's `gpu` subcommand, which drives whichever
+/// switching tool is installed, and then starts `vm` when given.
+fn apply_with_helper(mode: GpuMode, reboot: bool, vm: Option<&str>) -> Result<(), String> {
+    let mut args = vec!["gpu", mode.as_str()];
+    if let Some(vm) = vm {
+        args.push("--start-vm");
+        args.push(vm);
+    }
+
+    let session_id = std::env::var("XDG_SESSION_ID").ok();
+    if reboot {
+        args.push("--reboot");
+    } else if let Some(ref sid) = session_id {
+        if SESSION_ID_PATTERN.is_match(sid) {
+            args.push("--logout");
+            args.push(sid);
+        }
+    }
+
+    remote::run_helper(&args)
+}
+
+/// asusd's supergfxctl, plus the ASUS firmware MUX knob read through
+/// `sysfs`.
+struct Supergfx {
+    sysfs: Arc<dyn SysfsProvider>,
+}
+
+impl Supergfx {
+    const TOOL: &'static str = "supergfxctl";
+}
+
+impl GpuBackend for Supergfx {
+    fn tool(&self) -> &'static str {
+        Self::TOOL
+    }
+
+    fn status(&self) -> GpuStatus {
+        let mut mode = Some(GpuMode::Integrated);
+        let mut modes = Vec::new();
+        let mut capability = Capability::Available;
+
+        match probe::run("supergfxctl", &["-s"]) {
+            Ok(output) if output.status.success() => {
+                modes = parse_supergfx_modes(&String::from_utf8_lossy(&output.stdout));
+            }
+            Ok(_) => capability = Capability::Missing,
+            Err(e) => capability = Capability::from_error(&e),
+        }
+
+        // Don't wait on a hung daemon twice.
+        if capability != Capability::TimedOut {
+            if let Ok(output) = probe::run("supergfxctl", &["-g"]) {
+                if let Some(current) = output
+                    .status
+                    .success()
+                    .then(|| parse_supergfx_mode(&String::from_utf8_lossy(&output.stdout)))
+                    .flatten()
+                {
+                    mode = Some(current);
+                }
+            }
+        }
+
+        // A hardware MUX is reported either by supergfxctl itself or by the
+        // firmware attribute, which also tells us if dGPU direct is active.
+        let mux_state = read_gpu_mux(self.sysfs.as_ref());
+        let mux = mux_state.is_some() || modes.contains(&GpuMode::AsusMuxDgpu);
+
+        if mux && !modes.contains(&GpuMode::AsusMuxDgpu) {
+            modes.push(GpuMode::AsusMuxDgpu);
+        }
+
+        if mux_state == Some(true) {
+            mode = Some(GpuMode::AsusMuxDgpu);
+        }
+
+        if modes.is_empty() {
+            mode = None;
+        } else {
+            // The firmware MUX alone still makes the mode switchable.
+            capability = Capability::Available;
+        }
+
+        GpuStatus {
+            mode,
+            modes,
+            mux,
+            capability,
+        }
+    }
+
+    /// Entering or leaving the hardware MUX mode reroutes the panel and
+    /// only takes effect after a full reboot, unlike the software modes.
+    fn requires_reboot(&self, current: Option<GpuMode>, target: GpuMode) -> bool {
+        current == Some(GpuMode::AsusMuxDgpu) || target == GpuMode::AsusMuxDgpu
+    }
+
+    fn apply(&self, mode: GpuMode, reboot: bool) -> Result<(), String> {
+        apply_with_helper(mode, reboot, None)
+    }
+
+    fn apply_for_vm(&self, domain: &str) -> Result<(), String> {
+        apply_with_helper(GpuMode::Vfio, false, Some(domain))
+    }
+}
+
+/// Pop!_OS's system76-power daemon, driven over D-Bus.
+struct System76;
+
+fn from_system76(mode: &str) -> Option<GpuMode> {
+    match mode {
+        "integrated" => Some(GpuMode::Integrated),
+        "hybrid" => Some(GpuMode::Hybrid),
+        "nvidia" => Some(GpuMode::Dedicated),
+        "compute" => Some(GpuMode::Compute),
+        _ => None,
+    }
+}
+
+fn to_system76(mode: GpuMode) -> Option<&'static str> {
+    match mode {
+        GpuMode::Integrated => Some("integrated"),
+        GpuMode::Hybrid => Some("hybrid"),
+        GpuMode::Dedicated => Some("nvidia"),
+        GpuMode::Compute => Some("compute"),
+        GpuMode::Vfio | GpuMode::AsusMuxDgpu => None,
+    }
+}
+
+impl GpuBackend for System76 {
+    fn tool(&self) -> &'static str {
+        "system76-power"
+    }
+
+    fn status(&self) -> GpuStatus {
+        let mode = match system76::graphics() {
+            Ok(mode) => mode,
+            Err(_) => {
+                return GpuStatus::default();
+            }
+        };
+        let modes = if system76::switchable() {
+            vec![GpuMode::Integrated, GpuMode::Hybrid, GpuMode::Dedicated, GpuMode::Compute]
+        } else {
+            Vec::new()
+        };
+
+        let capability = if modes.is_empty() {
+            Capability::Missing
+        } else {
+            Capability::Available
+        };
+
+        GpuStatus {
+            mode: from_system76(&mode),
+            modes,
+            mux: false,
+            capability,
+        }
+    }
+
+    /// system76-power rewrites the driver configuration, which only takes
+    /// effect after a reboot.
+    fn requires_reboot(&self, _current: Option<GpuMode>, _target: GpuMode) -> bool {
+        true
+    }
+
+    fn apply(&self, mode: GpuMode, _reboot: bool) -> Result<(), String> {
+        let target = to_system76(mode).ok_or_else(|| format!("system76-power has no {} mode", mode))?;
+        system76::set_graphics(target)?;
+
+        let output = Command::new("systemctl")
+            .arg("reboot")
+            .output()
+            .map_err(|e| e.to_string())?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(String::from_utf8_lossy(&output.stderr).to_string())
+        }
+    }
+}
+
+/// envycontrol, common on non-ASUS Optimus laptops. Switching needs root,
+/// so it goes through the helper.
+struct Envycontrol;
+
+/// Parses `envycontrol --query`, which prints just the mode on current
+/// releases and `Current graphics mode is: <mode>` on older ones.
+fn parse_envycontrol_query(output: &str) -> Option<GpuMode> {
+    match output.split_whitespace().last()?.to_lowercase().as_str() {
+        "integrated" => Some(GpuMode::Integrated),
+        "hybrid" => Some(GpuMode::Hybrid),
+        "nvidia" => Some(GpuMode::Dedicated),
+        _ => None,
+    }
+}
+
+impl GpuBackend for Envycontrol {
+    fn tool(&self) -> &'static str {
+        "envycontrol"
+    }
+
+    fn status(&self) -> GpuStatus {
+        let (mode, capability) = match probe::run("envycontrol", &["--query"]) {
+            Ok(output) if output.status.success() => (
+                parse_envycontrol_query(&String::from_utf8_lossy(&output.stdout)),
+                Capability::Available,
+            ),
+            Ok(_) => (None, Capability::Missing),
+            Err(e) => (None, Capability::from_error(&e)),
+        };
+
+        match mode {
+            Some(mode) => GpuStatus {
+                mode: Some(mode),
+                modes: vec![GpuMode::Integrated, GpuMode::Hybrid, GpuMode::Dedicated],
+                mux: false,
+                capability,
+            },
+            None => GpuStatus {
+                capability: if capability == Capability::Available {
+                    Capability::Missing
+                } else {
+                    capability
+                },
+                ..Default::default()
+            },
+        }
+    }
+
+    /// envycontrol swaps driver and Xorg configuration, which only takes
+    /// effect after a reboot.
+    fn requires_reboot(&self, _current: Option<GpuMode>, _target: GpuMode) -> bool {
+        true
+    }
+
+    fn apply(&self, mode: GpuMode, _reboot: bool) -> Result<(), String> {
+        apply_with_helper(mode, true, None)
+    }
+}
+
+/// No switching tool installed: reports hybrid graphics found by lspci so
+/// the user at least knows what they have.
+struct ReadOnly;
+
+impl GpuBackend for ReadOnly {
+    fn tool(&self) -> &'static str {
+        "lspci"
+    }
+
+    fn status(&self) -> GpuStatus {
+        let (gpus, capability) = match probe::run("lspci", &[]) {
+            Ok(output) => {
+                let count = String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .filter(|line| {
+                        line.contains("VGA compatible controller")
+                            || line.contains("3D controller")
+                            || line.contains("Display controller")
+                    })
+                    .count();
+                (count, Capability::Missing)
+            }
+            Err(e) => (0, Capability::from_error(&e)),
+        };
+
+        GpuStatus {
+            mode: (gpus > 1).then_some(GpuMode::Hybrid),
+            modes: Vec::new(),
+            mux: false,
+            capability,
+        }
+    }
+
+    fn requires_reboot(&self, _current: Option<GpuMode>, _target: GpuMode) -> bool {
+        false
+    }
+
+    fn apply(&self, _mode: GpuMode, _reboot: bool) -> Result<(), String> {
+        Err("No GPU switching tool is installed".to_string())
+    }
+
+    fn can_switch(&self) -> bool {
+        false
+    }
+}
+
+/// No switching tool at all, for tests.
+#[cfg(test)]
+pub struct NoGpu;
+
+#[cfg(test)]
+impl GpuBackend for NoGpu {
+    fn tool(&self) -> &'static str {
+        "none"
+    }
+
+    fn status(&self) -> GpuStatus {
+        GpuStatus {
+            capability: Capability::Missing,
+            ..Default::default()
+        }
+    }
+
+    fn requires_reboot(&self, _current: Option<GpuMode>, _target: GpuMode) -> bool {
+        false
+    }
+
+    fn apply(&self, _mode: GpuMode, _reboot: bool) -> Result<(), String> {
+        Err("no GPU switching".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::FakeSysfs;
+
+    #[test]
+    fn parses_bracketed_mode_list() {
+        assert_eq!(
+            parse_supergfx_modes("[Integrated, Hybrid]\n"),
+            vec![GpuMode::Integrated, GpuMode::Hybrid]
+        );
+    }
+
+    #[test]
+    fn parses_supported_modes_prefix() {
+        assert_eq!(
+            parse_supergfx_modes("Supported modes: [Integrated, Hybrid, AsusMuxDgpu]"),
+            vec![GpuMode::Integrated, GpuMode::Hybrid, GpuMode::AsusMuxDgpu]
+        );
+        assert_eq!(
+            parse_supergfx_modes("Supported modes: integrated, hybrid, vfio"),
+            vec![GpuMode::Integrated, GpuMode::Hybrid, GpuMode::Vfio]
+        );
+    }
+
+    #[test]
+    fn parses_one_mode_per_line() {
+        assert_eq!(
+            parse_supergfx_modes("Integrated\nHybrid\nVfio\n"),
+            vec![GpuMode::Integrated, GpuMode::Hybrid, GpuMode::Vfio]
+        );
+    }
+
+    #[test]
+    fn parses_lowercase_and_legacy_names() {
+        assert_eq!(
+            parse_supergfx_modes("[\"integrated\", \"hybrid\", \"compute\", \"nvidia\"]"),
+            vec![GpuMode::Integrated, GpuMode::Hybrid, GpuMode::Compute, GpuMode::Dedicated]
+        );
+        assert_eq!(GpuMode::parse("dedicated"), Some(GpuMode::Dedicated));
+        assert_eq!(GpuMode::parse("NvidiaNoModeset"), Some(GpuMode::Hybrid));
+    }
+
+    #[test]
+    fn skips_modes_tuxtuner_cannot_switch_to() {
+        assert_eq!(GpuMode::parse("AsusMuxDgpu"), Some(GpuMode::AsusMuxDgpu));
+        assert_eq!(GpuMode::parse("AsusEgpu"), None);
+        assert_eq!(GpuMode::parse("None"), None);
+        assert_eq!(
+            parse_supergfx_modes("[Integrated, Hybrid, AsusEgpu, None]"),
+            vec![GpuMode::Integrated, GpuMode::Hybrid]
+        );
+    }
+
+    #[test]
+    fn ignores_unknown_and_duplicate_names() {
+        assert_eq!(GpuMode::parse("Turbo"), None);
+        assert_eq!(GpuMode::parse(""), None);
+        assert!(parse_supergfx_modes("Error: supergfxd is not running").is_empty());
+        assert_eq!(parse_supergfx_modes("Hybrid\nhybrid\n"), vec![GpuMode::Hybrid]);
+    }
+
+    #[test]
+    fn parses_current_mode_in_both_formats() {
+        assert_eq!(parse_supergfx_mode("Hybrid\n"), Some(GpuMode::Hybrid));
+        assert_eq!(
+            parse_supergfx_mode("Current graphics mode: integrated"),
+            Some(GpuMode::Integrated)
+        );
+        assert_eq!(parse_supergfx_mode("\nAsusMuxDgpu\n"), Some(GpuMode::AsusMuxDgpu));
+        assert_eq!(parse_supergfx_mode("AsusEgpu"), None);
+        assert_eq!(parse_supergfx_mode(""), None);
+    }
+
+    #[test]
+    fn from_str_accepts_only_helper_names() {
+        for mode in GpuMode::ALL {
+            assert_eq!(mode.as_str().parse::<GpuMode>(), Ok(mode));
+        }
+        assert!("hybrid".parse::<GpuMode>().is_err());
+    }
+
+    #[test]
+    fn reads_mux_from_either_firmware_attribute() {
+        let armoury = FakeSysfs::default().with(GPU_MUX_PATHS[0], "0\n");
+        assert_eq!(read_gpu_mux(&armoury), Some(true));
+
+        let wmi = FakeSysfs::default().with(GPU_MUX_PATHS[1], "1\n");
+        assert_eq!(read_gpu_mux(&wmi), Some(false));
+
+        assert_eq!(read_gpu_mux(&FakeSysfs::default()), None);
+    }
+}
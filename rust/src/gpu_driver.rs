@@ -0,0 +1,119 @@
+use crate::remote;
+use std::path::Path;
+
+const DRM_PATH: &str = "/sys/class/drm";
+/// Where the Vulkan loader finds installable client drivers; the last is
+/// NixOS's.
+const VULKAN_ICD_DIRS: [&str; 3] = [
+    "/usr/share/vulkan/icd.d",
+    "/etc/vulkan/icd.d",
+    "/run/opengl-driver/share/vulkan/icd.d",
+];
+
+/// The kernel driver behind one GPU.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GpuDriver {
+    /// PCI `vendor:device`, e.g. `10DE:28E0`.
+    pub pci_id: String,
+    /// Kernel module name, e.g. `nvidia`, `nouveau`, `amdgpu`, `i915`.
+    pub driver: String,
+    /// The module's own version for out-of-tree drivers, otherwise the
+    /// kernel release it shipped with.
+    pub version: String,
+    /// Loaded from the NVIDIA open kernel modules rather than the
+    /// closed ones.
+    pub nvidia_open: bool,
+    /// Vulkan driver manifest installed for this driver, e.g.
+    /// `radeon_icd.x86_64.json`.
+    pub vulkan_icd: Option<String>,
+}
+
+impl GpuDriver {
+    /// Names the driver the way bug trackers do.
+    pub fn label(&self) -> String {
+        match self.driver.as_str() {
+            "nvidia" if self.nvidia_open => "NVIDIA (open kernel modules)".to_string(),
+            "nvidia" => "NVIDIA (proprietary)".to_string(),
+            "nouveau" => "Nouveau (open source NVIDIA)".to_string(),
+            "amdgpu" => "AMDGPU".to_string(),
+            "radeon" => "Radeon (legacy AMD)".to_string(),
+            "i915" => "Intel i915".to_string(),
+            "xe" => "Intel Xe".to_string(),
+            other => other.to_string(),
+        }
+    }
+
+    /// ICD manifest name prefixes of the Vulkan drivers for this kernel
+    /// driver.
+    fn icd_prefixes(&self) -> &'static [&'static str] {
+        match self.driver.as_str() {
+            "nvidia" => &["nvidia_icd"],
+            "nouveau" => &["nouveau_icd"],
+            "amdgpu" => &["radeon_icd", "amd_icd"],
+            "i915" | "xe" => &["intel_icd", "intel_hasvk_icd"],
+            _ => &[],
+        }
+    }
+}
+
+fn read_trimmed(path: impl AsRef<Path>) -> Option<String> {
+    remote::read_to_string(path)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn vulkan_icds() -> Vec<String> {
+    let mut icds: Vec<String> = VULKAN_ICD_DIRS
+        .iter()
+        .flat_map(|dir| remote::read_dir(dir).unwrap_or_default())
+        .filter_map(|path| Some(path.file_name()?.to_string_lossy().to_string()))
+        .filter(|name| name.ends_with(".json"))
+        .collect();
+    icds.sort();
+    icds
+}
+
+/// The driver of every DRM card, sorted by PCI ID. Reads sysfs only, so
+/// it doesn't wake a sleeping discrete GPU the way querying Vulkan would.
+pub fn drivers() -> Vec<GpuDriver> {
+    let kernel = read_trimmed("/proc/sys/kernel/osrelease").unwrap_or_default();
+    let icds = vulkan_icds();
+
+    let mut drivers: Vec<GpuDriver> = remote::read_dir(DRM_PATH)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|card| {
+            card.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .is_some_and(|name| name.starts_with("card") && !name.contains('-'))
+        })
+        .filter_map(|card| {
+            let uevent = remote::read_to_string(card.join("device/uevent")).ok()?;
+            let field = |key: &str| {
+                uevent
+                    .lines()
+                    .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+                    .map(str::to_string)
+            };
+            let driver = field("DRIVER")?;
+            let mut gpu = GpuDriver {
+                pci_id: field("PCI_ID").unwrap_or_default(),
+                version: read_trimmed(format!("/sys/module/{}/version", driver))
+                    .unwrap_or_else(|| format!("in-kernel, Linux {}", kernel)),
+                nvidia_open: driver == "nvidia"
+                    && read_trimmed("/proc/driver/nvidia/version").is_some_and(|version| version.contains("Open Kernel Module")),
+                vulkan_icd: None,
+                driver,
+            };
+            gpu.vulkan_icd = icds
+                .iter()
+                .find(|icd| gpu.icd_prefixes().iter().any(|prefix| icd.starts_with(prefix)))
+                .cloned();
+            Some(gpu)
+        })
+        .collect();
+    drivers.sort_by(|a, b| a.pci_id.cmp(&b.pci_id));
+    drivers.dedup();
+    drivers
+}
@@ -0,0 +1,65 @@
+use crate::probe;
+use crate::remote;
+use crate::system_info::command_exists;
+use std::fs;
+use std::path::PathBuf;
+
+/// Compositors that ask for a high-priority GPU context
+/// (`EGL_IMG_context_priority`) and get one when they hold CAP_SYS_NICE.
+const COMPOSITORS: &[&str] = &["Hyprland", "kwin_wayland", "sway", "gamescope"];
+/// Kernel drivers that honor context priorities.
+const DRIVERS: &[&str] = &["amdgpu", "i915", "xe"];
+const DRM_PATH: &str = "/sys/class/drm";
+
+/// The running compositor whose GPU priority can be raised.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Compositor {
+    pub name: String,
+    pub exe: PathBuf,
+}
+
+fn gpu_driver_supported() -> bool {
+    fs::read_dir(DRM_PATH)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| fs::read_link(entry.path().join("device/driver")).ok())
+        .any(|driver| {
+            driver
+                .file_name()
+                .is_some_and(|name| DRIVERS.iter().any(|known| name == *known))
+        })
+}
+
+/// The compositor of this session, when both it and the GPU driver
+/// support priorities. Not available in remote mode.
+pub fn detect() -> Option<Compositor> {
+    if remote::host().is_some() || !command_exists("getcap") || !gpu_driver_supported() {
+        return None;
+    }
+
+    fs::read_dir("/proc").ok()?.flatten().find_map(|entry| {
+        entry.file_name().to_str()?.parse::<u32>().ok()?;
+        let name = fs::read_to_string(entry.path().join("comm")).ok()?.trim().to_string();
+        if !COMPOSITORS.contains(&name.as_str()) {
+            return None;
+        }
+        let exe = fs::read_link(entry.path().join("exe")).ok()?;
+        Some(Compositor { name, exe })
+    })
+}
+
+/// Whether the compositor binary carries CAP_SYS_NICE.
+pub fn enabled(compositor: &Compositor) -> bool {
+    probe::run("getcap", &[&compositor.exe.to_string_lossy()])
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains("cap_sys_nice"))
+}
+
+/// Grants or drops CAP_SYS_NICE on the compositor binary, leaving its
+/// other capabilities alone. Only a grant TuxTuner made can be dropped.
+/// The compositor picks it up when it next starts, and package updates
+/// clear it.
+pub fn set_enabled(compositor: &Compositor, on: bool) -> Result<(), String> {
+    let exe = compositor.exe.to_string_lossy();
+    remote::run_helper(&["compositor-priority", &exe, if on { "on" } else { "off" }])
+}
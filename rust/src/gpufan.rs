@@ -0,0 +1,276 @@
+use crate::probe;
+use crate::remote;
+use crate::system_info::command_exists;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const DRM_PATH: &str = "/sys/class/drm";
+
+/// At or above this GPU temperature the fan always runs flat out,
+/// whatever the curve says. The helper enforces the same floor.
+pub const SAFETY_TEMP_C: u32 = 90;
+pub const MAX_CURVE_TEMP_C: u32 = 110;
+
+/// One point of a fan curve: at `temp_c`, run the fan at `percent`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FanPoint {
+    pub temp_c: u32,
+    pub percent: u32,
+}
+
+/// Temperature to fan speed mapping, linear between points.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FanCurve {
+    pub points: Vec<FanPoint>,
+}
+
+impl Default for FanCurve {
+    fn default() -> Self {
+        Self {
+            points: [(40, 25), (60, 40), (75, 70), (85, 100)]
+                .map(|(temp_c, percent)| FanPoint { temp_c, percent })
+                .to_vec(),
+        }
+    }
+}
+
+impl FanCurve {
+    /// Parses `TEMP:PERCENT` pairs, e.g. `40:25 60:40 85:100`. Temperatures
+    /// must increase and speeds must not drop as it gets hotter.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let points = text
+            .split_whitespace()
+            .map(|pair| {
+                let (temp, percent) = pair
+                    .split_once(':')
+                    .ok_or_else(|| format!("Expected TEMP:PERCENT, got {}", pair))?;
+                let point = FanPoint {
+                    temp_c: temp.parse().map_err(|_| format!("Invalid temperature: {}", temp))?,
+                    percent: percent.parse().map_err(|_| format!("Invalid speed: {}", percent))?,
+                };
+                if point.temp_c > MAX_CURVE_TEMP_C || point.percent > 100 {
+                    return Err(format!("Out of range: {}", pair));
+                }
+                Ok(point)
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        if points.is_empty() {
+            return Err("A fan curve needs at least one point".to_string());
+        }
+        if points
+            .windows(2)
+            .any(|pair| pair[1].temp_c <= pair[0].temp_c || pair[1].percent < pair[0].percent)
+        {
+            return Err("Temperatures must rise and speeds must not fall".to_string());
+        }
+        Ok(Self { points })
+    }
+
+    pub fn to_text(&self) -> String {
+        self.points
+            .iter()
+            .map(|point| format!("{}:{}", point.temp_c, point.percent))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Fan speed for `temp_c`, in percent.
+    pub fn speed_for(&self, temp_c: u32) -> u32 {
+        if temp_c >= SAFETY_TEMP_C {
+            return 100;
+        }
+        let Some(first) = self.points.first() else {
+            return 100;
+        };
+        if temp_c <= first.temp_c {
+            return first.percent;
+        }
+        for pair in self.points.windows(2) {
+            let (low, high) = (pair[0], pair[1]);
+            if temp_c <= high.temp_c {
+                let span = high.temp_c - low.temp_c;
+                return low.percent + (high.percent - low.percent) * (temp_c - low.temp_c) / span;
+            }
+        }
+        self.points.last().map_or(100, |last| last.percent)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GpuFan {
+    /// amdgpu hwmon with a writable PWM; the curve runs in a root service.
+    Amd(PathBuf),
+    /// NVIDIA through nvidia-settings, which needs Coolbits and an X
+    /// display; the curve runs while TuxTuner is open.
+    Nvidia,
+}
+
+fn read_u32(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+fn amdgpu_hwmon() -> Option<PathBuf> {
+    fs::read_dir(DRM_PATH)
+        .ok()?
+        .flatten()
+        .filter(|card| {
+            let name = card.file_name().to_string_lossy().to_string();
+            name.starts_with("card") && !name.contains('-')
+        })
+        .flat_map(|card| {
+            fs::read_dir(card.path().join("device/hwmon"))
+                .into_iter()
+                .flatten()
+                .flatten()
+        })
+        .map(|hwmon| hwmon.path())
+        .find(|hwmon| {
+            fs::read_to_string(hwmon.join("name")).is_ok_and(|name| name.trim() == "amdgpu")
+                && hwmon.join("pwm1").exists()
+        })
+}
+
+/// The first controllable GPU fan, AMD preferred.
+pub fn detect() -> Option<GpuFan> {
+    if let Some(hwmon) = amdgpu_hwmon() {
+        return Some(GpuFan::Amd(hwmon));
+    }
+    if command_exists("nvidia-settings") && command_exists("nvidia-smi") {
+        return Some(GpuFan::Nvidia);
+    }
+    None
+}
+
+fn nvidia_query(attribute: &str) -> Option<u32> {
+    let output = probe::run("nvidia-settings", &["-t", "-q", attribute]).ok()?;
+    String::from_utf8_lossy(&output.stdout).lines().next()?.trim().parse().ok()
+}
+
+impl GpuFan {
+    /// GPU temperature in °C.
+    pub fn temperature(&self) -> Option<u32> {
+        match self {
+            GpuFan::Amd(hwmon) => read_u32(&hwmon.join("temp1_input")).map(|m| m / 1000),
+            GpuFan::Nvidia => nvidia_query("[gpu:0]/GPUCoreTemp"),
+        }
+    }
+
+    /// Current fan speed in percent.
+    pub fn speed_percent(&self) -> Option<u32> {
+        match self {
+            GpuFan::Amd(hwmon) => {
+                let pwm = read_u32(&hwmon.join("pwm1"))?;
+                let max = read_u32(&hwmon.join("pwm1_max")).unwrap_or(255).max(1);
+                Some(pwm * 100 / max)
+            }
+            GpuFan::Nvidia => nvidia_query("[fan:0]/GPUCurrentFanSpeed"),
+        }
+    }
+
+    /// Starts following `curve`. Blocking.
+    pub fn apply_curve(&self, curve: &FanCurve) -> Result<(), String> {
+        match self {
+            GpuFan::Amd(_) => remote::run_helper(&["gpu-fan-curve", &curve.to_text()]),
+            GpuFan::Nvidia => {
+                let temp = self.temperature().ok_or("Cannot read the GPU temperature")?;
+                set_nvidia_speed(Some(curve.speed_for(temp)))
+            }
+        }
+    }
+
+    /// Hands the fan back to the card's own control. Blocking.
+    pub fn reset(&self) -> Result<(), String> {
+        match self {
+            GpuFan::Amd(_) => remote::run_helper(&["gpu-fan-reset"]),
+            GpuFan::Nvidia => set_nvidia_speed(None),
+        }
+    }
+}
+
+/// Board power limit of a discrete GPU, in watts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PowerLimit {
+    pub current_w: u32,
+    pub min_w: u32,
+    pub max_w: u32,
+}
+
+fn parse_nvidia_power_limit(output: &str) -> Option<PowerLimit> {
+    let mut values = output
+        .lines()
+        .next()?
+        .split(',')
+        .map(|value| value.trim().parse::<f64>().ok().map(|watts| watts.round() as u32));
+    Some(PowerLimit {
+        current_w: values.next()??,
+        min_w: values.next()??,
+        max_w: values.next()??,
+    })
+}
+
+impl GpuFan {
+    /// The card's power limit, if the driver lets it be changed.
+    pub fn power_limit(&self) -> Option<PowerLimit> {
+        let limit = match self {
+            GpuFan::Amd(hwmon) => {
+                let watts = |file: &str| read_u32(&hwmon.join(file)).map(|uw| uw / 1_000_000);
+                PowerLimit {
+                    current_w: watts("power1_cap")?,
+                    min_w: watts("power1_cap_min")?,
+                    max_w: watts("power1_cap_max")?,
+                }
+            }
+            GpuFan::Nvidia => {
+                let output = probe::run(
+                    "nvidia-smi",
+                    &[
+                        "-i",
+                        "0",
+                        "--query-gpu=power.limit,power.min_limit,power.max_limit",
+                        "--format=csv,noheader,nounits",
+                    ],
+                )
+                .ok()?;
+                parse_nvidia_power_limit(&String::from_utf8_lossy(&output.stdout))?
+            }
+        };
+        (limit.max_w > limit.min_w).then_some(limit)
+    }
+
+    /// Sets the power limit until the next reboot. Blocking.
+    pub fn apply_power_limit(&self, watts: u32) -> Result<(), String> {
+        if let Some(limit) = self.power_limit() {
+            if !(limit.min_w..=limit.max_w).contains(&watts) {
+                return Err(format!(
+                    "Power limit must be between {} and {} W",
+                    limit.min_w, limit.max_w
+                ));
+            }
+        }
+        remote::run_helper(&["gpu-power-limit", &watts.to_string()])
+    }
+}
+
+/// `None` returns the fan to the driver's automatic control.
+fn set_nvidia_speed(percent: Option<u32>) -> Result<(), String> {
+    let mut args = vec!["-a".to_string()];
+    match percent {
+        Some(percent) => {
+            args.push("[gpu:0]/GPUFanControlState=1".to_string());
+            args.push("-a".to_string());
+            args.push(format!("[fan:0]/GPUTargetFanSpeed={}", percent.min(100)));
+        }
+        None => args.push("[gpu:0]/GPUFanControlState=0".to_string()),
+    }
+    let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+    let output = probe::run("nvidia-settings", &args).map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).to_string())
+    }
+}
+
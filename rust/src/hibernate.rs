@@ -0,0 +1,147 @@
+use crate::remote;
+
+/// The kernel needs room for a compressed memory image; requiring swap of
+/// at least RAM size leaves a comfortable margin.
+const MIN_SWAP_RATIO: f64 = 1.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwapArea {
+    pub path: String,
+    pub size_bytes: u64,
+}
+
+/// Everything hibernation depends on, as found on this system.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HibernateStatus {
+    /// Largest disk-backed swap area; zram can't hold a hibernation image.
+    pub swap: Option<SwapArea>,
+    pub ram_bytes: u64,
+    /// `resume=` is on the kernel command line.
+    pub resume_configured: bool,
+    /// Active kernel lockdown mode other than `none`, which blocks
+    /// hibernation (usually enabled by Secure Boot).
+    pub lockdown: Option<String>,
+    /// The kernel offers the `disk` sleep state.
+    pub kernel_support: bool,
+    /// The kernel can suspend after writing the image (hybrid sleep).
+    pub hybrid_sleep: bool,
+}
+
+fn active_swaps() -> Vec<SwapArea> {
+    remote::read_to_string("/proc/swaps")
+        .unwrap_or_default()
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let path = fields.first()?.to_string();
+            let size_kib: u64 = fields.get(2)?.parse().ok()?;
+            Some(SwapArea {
+                path,
+                size_bytes: size_kib * 1024,
+            })
+        })
+        .filter(|swap| !swap.path.starts_with("/dev/zram"))
+        .collect()
+}
+
+fn ram_bytes() -> u64 {
+    remote::read_to_string("/proc/meminfo")
+        .unwrap_or_default()
+        .lines()
+        .find_map(|line| line.strip_prefix("MemTotal:"))
+        .and_then(|rest| rest.split_whitespace().next()?.parse::<u64>().ok())
+        .map(|kib| kib * 1024)
+        .unwrap_or(0)
+}
+
+/// The bracketed entry of `/sys/kernel/security/lockdown`, unless `none`.
+fn lockdown_mode() -> Option<String> {
+    let content = remote::read_to_string("/sys/kernel/security/lockdown").ok()?;
+    let start = content.find('[')?;
+    let end = content[start..].find(']')? + start;
+    let mode = &content[start + 1..end];
+    (mode != "none").then(|| mode.to_string())
+}
+
+impl HibernateStatus {
+    pub fn detect() -> Self {
+        let swap = active_swaps().into_iter().max_by_key(|swap| swap.size_bytes);
+        let cmdline = remote::read_to_string("/proc/cmdline").unwrap_or_default();
+        let read_words = |path: &str| -> Vec<String> {
+            remote::read_to_string(path)
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(|word| word.trim_matches(|c| c == '[' || c == ']').to_string())
+                .collect()
+        };
+
+        Self {
+            swap,
+            ram_bytes: ram_bytes(),
+            resume_configured: cmdline.split_whitespace().any(|arg| arg.starts_with("resume=")),
+            lockdown: lockdown_mode(),
+            kernel_support: read_words("/sys/power/state").iter().any(|s| s == "disk"),
+            hybrid_sleep: read_words("/sys/power/disk").iter().any(|s| s == "suspend"),
+        }
+    }
+
+    pub fn swap_sufficient(&self) -> bool {
+        self.swap
+            .as_ref()
+            .is_some_and(|swap| swap.size_bytes as f64 >= self.ram_bytes as f64 * MIN_SWAP_RATIO)
+    }
+
+    /// Problems TuxTuner can't fix itself, in the order to address them.
+    pub fn blockers(&self) -> Vec<String> {
+        let mut blockers = Vec::new();
+        if !self.kernel_support {
+            blockers.push("The kernel does not support hibernation".to_string());
+        }
+        if let Some(mode) = &self.lockdown {
+            blockers.push(format!(
+                "Kernel lockdown ({}) blocks hibernation; it is usually enabled by Secure Boot",
+                mode
+            ));
+        }
+        if !self.swap_sufficient() {
+            blockers.push(format!(
+                "Needs a disk swap area of at least {:.1} GiB",
+                gib(self.ram_bytes)
+            ));
+        }
+        blockers
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.blockers().is_empty() && self.resume_configured
+    }
+
+    pub fn can_configure(&self) -> bool {
+        self.blockers().is_empty() && !self.resume_configured
+    }
+}
+
+pub fn gib(bytes: u64) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0 * 1024.0)
+}
+
+/// Points the kernel at `swap` for resuming and rebuilds the boot files.
+/// Blocking and slow (regenerates the initramfs); takes effect on reboot.
+pub fn configure(swap: &SwapArea) -> Result<(), String> {
+    remote::run_helper(&["hibernate-setup", &swap.path])
+}
+
+/// Hibernates now through logind, which allows the session's user to.
+/// Blocking.
+pub fn hibernate_now() -> Result<(), String> {
+    let output = remote::command("systemctl", &["hibernate"])
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).to_string())
+    }
+}
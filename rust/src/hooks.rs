@@ -0,0 +1,99 @@
+use crate::battery_history;
+use crate::config::Config;
+use crate::probe::{self, ProbeError};
+use gtk4::glib;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use std::time::Duration;
+
+pub const DEFAULT_TIMEOUT_SECS: u64 = 10;
+/// Longest hook output kept in the history, which labels a chart.
+const SUMMARY_CHARS: usize = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    PreProfileApply,
+    PostProfileApply,
+    PreGpuSwitch,
+    PostGpuSwitch,
+}
+
+impl HookEvent {
+    /// Key of the hook in the `[hooks]` config table.
+    pub fn name(self) -> &'static str {
+        match self {
+            HookEvent::PreProfileApply => "pre_profile_apply",
+            HookEvent::PostProfileApply => "post_profile_apply",
+            HookEvent::PreGpuSwitch => "pre_gpu_switch",
+            HookEvent::PostGpuSwitch => "post_gpu_switch",
+        }
+    }
+}
+
+/// Shell commands run around settings changes, e.g.
+/// `pre_profile_apply = "systemctl --user stop syncthing"`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HooksConfig {
+    pub pre_profile_apply: Option<String>,
+    pub post_profile_apply: Option<String>,
+    pub pre_gpu_switch: Option<String>,
+    pub post_gpu_switch: Option<String>,
+    /// Seconds before a hook is killed; defaults to `DEFAULT_TIMEOUT_SECS`.
+    pub timeout_secs: Option<u64>,
+}
+
+impl HooksConfig {
+    fn command(&self, event: HookEvent) -> Option<&str> {
+        let command = match event {
+            HookEvent::PreProfileApply => &self.pre_profile_apply,
+            HookEvent::PostProfileApply => &self.post_profile_apply,
+            HookEvent::PreGpuSwitch => &self.pre_gpu_switch,
+            HookEvent::PostGpuSwitch => &self.post_gpu_switch,
+        };
+        command.as_deref().map(str::trim).filter(|c| !c.is_empty())
+    }
+}
+
+fn summarize(output: &[u8]) -> String {
+    let text = String::from_utf8_lossy(output);
+    let line = text.lines().map(str::trim).find(|l| !l.is_empty()).unwrap_or("");
+    line.chars().take(SUMMARY_CHARS).collect()
+}
+
+/// Runs the hook configured for `event`, if any, through `sh -c` with
+/// `$TUXTUNER_EVENT` and `vars` set. The outcome is logged to the history;
+/// a failing hook never stops the change it is attached to. Blocking.
+pub fn run(event: HookEvent, vars: &[(&str, &str)]) {
+    let config = Config::load().hooks;
+    let Some(script) = config.command(event) else {
+        return;
+    };
+    let timeout = Duration::from_secs(config.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS).max(1));
+
+    let mut command = Command::new("sh");
+    command
+        .args(["-c", script])
+        .env("TUXTUNER_EVENT", event.name())
+        .envs(vars.iter().copied());
+
+    let outcome = match probe::run_command(command, timeout) {
+        Ok(output) if output.status.success() => {
+            let summary = summarize(&output.stdout);
+            if summary.is_empty() {
+                "ok".to_string()
+            } else {
+                summary
+            }
+        }
+        Ok(output) => match summarize(&output.stderr) {
+            reason if reason.is_empty() => format!("failed ({})", output.status),
+            reason => format!("failed: {}", reason),
+        },
+        Err(ProbeError::TimedOut) => format!("killed after {}s", timeout.as_secs()),
+        Err(ProbeError::Spawn(e)) => format!("failed: {}", e),
+    };
+
+    glib::g_debug!(crate::LOG_DOMAIN, "Hook {} ({}): {}", event.name(), script, outcome);
+    battery_history::record_event(&format!("Hook {}: {}", event.name(), outcome.replace('\t', " ")));
+}
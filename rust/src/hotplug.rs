@@ -0,0 +1,180 @@
+use crate::config::Config;
+use crate::hyprland;
+use crate::sysfs::SysfsProvider;
+use crate::system_info::{self, DisplayBackend, MonitorInfo};
+use gtk4::glib;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::Arc;
+
+const DRM_PATH: &str = "/sys/class/drm";
+const DRM_POLL_SECONDS: u32 = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MonitorEvent {
+    Added(String),
+    Removed(String),
+}
+
+fn parse_hyprland_event(event: &str, data: &str) -> Option<MonitorEvent> {
+    match event {
+        "monitoradded" => Some(MonitorEvent::Added(data.to_string())),
+        "monitorremoved" => Some(MonitorEvent::Removed(data.to_string())),
+        _ => None,
+    }
+}
+
+/// Connectors (e.g. `card1-HDMI-A-1`) that currently report a display.
+fn connected_drm_outputs(sysfs: &dyn SysfsProvider) -> BTreeSet<String> {
+    let mut outputs = BTreeSet::new();
+
+    if let Ok(entries) = sysfs.read_dir(Path::new(DRM_PATH)) {
+        for entry in entries {
+            let Some(name) = entry.file_name().map(|name| name.to_string_lossy().to_string()) else {
+                continue;
+            };
+            if !name.starts_with("card") || !name.contains('-') {
+                continue;
+            }
+
+            if let Ok(status) = sysfs.read_to_string(&entry.join("status")) {
+                if status.trim() == "connected" {
+                    outputs.insert(name);
+                }
+            }
+        }
+    }
+
+    outputs
+}
+
+/// Strips the `cardN-` prefix so DRM names match compositor output names.
+fn drm_output_name(connector: &str) -> String {
+    connector
+        .split_once('-')
+        .map(|(_, output)| output.to_string())
+        .unwrap_or_else(|| connector.to_string())
+}
+
+/// Invokes `callback` on the main loop whenever a monitor is connected or
+/// disconnected. Uses the Hyprland event socket when available and falls
+/// back to polling DRM connector status in `sysfs` elsewhere.
+pub fn watch_monitors<F: Fn(MonitorEvent) + 'static>(sysfs: Arc<dyn SysfsProvider>, callback: F) {
+    let callback = Rc::new(callback);
+
+    let on_event = callback.clone();
+    let listening = hyprland::listen_events(
+        move |event, data| {
+            if let Some(event) = parse_hyprland_event(event, data) {
+                on_event(event);
+            }
+        },
+        {
+            let callback = callback.clone();
+            let sysfs = sysfs.clone();
+            move || watch_drm(sysfs, callback)
+        },
+    );
+
+    if !listening {
+        watch_drm(sysfs, callback);
+    }
+}
+
+fn watch_drm(sysfs: Arc<dyn SysfsProvider>, callback: Rc<dyn Fn(MonitorEvent)>) {
+    let mut known = connected_drm_outputs(sysfs.as_ref());
+
+    glib::timeout_add_seconds_local(DRM_POLL_SECONDS, move || {
+        let current = connected_drm_outputs(sysfs.as_ref());
+
+        for added in current.difference(&known) {
+            callback(MonitorEvent::Added(drm_output_name(added)));
+        }
+        for removed in known.difference(&current) {
+            callback(MonitorEvent::Removed(drm_output_name(removed)));
+        }
+
+        known = current;
+        glib::ControlFlow::Continue
+    });
+}
+
+/// Re-applies the saved refresh rate, VRR and scale for a monitor that
+/// just reappeared, through `display`. Returns the applied rate, or
+/// `None` if nothing needed to change.
+pub fn restore_monitor_preference(display: &dyn DisplayBackend, name: &str) -> Option<Result<u32, String>> {
+    let mut monitor = display
+        .monitors()
+        .unwrap_or_default()
+        .into_iter()
+        .find(|mon| mon.name == name)?;
+    let pref = Config::load().monitor(&monitor.identity, &monitor.name)?.clone();
+
+    let hz = pref
+        .refresh_hz
+        .filter(|hz| monitor.available_hz.contains(hz))
+        .unwrap_or(monitor.refresh_hz);
+    let scale = pref.scale.filter(|&s| s > 0.0).unwrap_or(monitor.scale);
+    let vrr = pref.vrr.filter(|&v| v != monitor.vrr);
+
+    if hz == monitor.refresh_hz && (scale - monitor.scale).abs() < 0.01 && vrr.is_none() {
+        return None;
+    }
+
+    monitor.scale = scale;
+    Some(system_info::apply_monitor_mode_with(display, &monitor, hz, vrr).map(|()| hz))
+}
+
+/// Records the current settings of `monitor` as its preferred ones.
+pub fn save_monitor_preference(monitor: &MonitorInfo, hz: u32, vrr: bool) -> Result<(), String> {
+    let mut config = Config::load();
+    let pref = config.monitor_mut(&monitor.identity);
+    pref.refresh_hz = Some(hz);
+    pref.vrr = Some(vrr);
+    pref.scale = Some(monitor.scale);
+    config.save()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::FakeSysfs;
+
+    #[test]
+    fn lists_connected_connectors_only() {
+        let sysfs = FakeSysfs::default()
+            .with("/sys/class/drm/card1-eDP-1/status", "connected\n")
+            .with("/sys/class/drm/card1-HDMI-A-1/status", "disconnected\n")
+            .with("/sys/class/drm/card1-DP-2/status", "connected\n")
+            .with("/sys/class/drm/card1/dev", "226:1\n")
+            .with("/sys/class/drm/renderD128/dev", "226:128\n");
+
+        let outputs: Vec<_> = connected_drm_outputs(&sysfs).into_iter().collect();
+        assert_eq!(outputs, ["card1-DP-2", "card1-eDP-1"]);
+    }
+
+    #[test]
+    fn no_drm_means_no_outputs() {
+        assert!(connected_drm_outputs(&FakeSysfs::default()).is_empty());
+    }
+
+    #[test]
+    fn strips_card_prefix() {
+        assert_eq!(drm_output_name("card1-HDMI-A-1"), "HDMI-A-1");
+        assert_eq!(drm_output_name("card0"), "card0");
+    }
+
+    #[test]
+    fn parses_monitor_events() {
+        assert_eq!(
+            parse_hyprland_event("monitoradded", "DP-2"),
+            Some(MonitorEvent::Added("DP-2".to_string()))
+        );
+        assert_eq!(
+            parse_hyprland_event("monitorremoved", "DP-2"),
+            Some(MonitorEvent::Removed("DP-2".to_string()))
+        );
+        assert_eq!(parse_hyprland_event("workspace", "2"), None);
+    }
+}
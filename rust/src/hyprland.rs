@@ -0,0 +1,104 @@
+use crate::probe;
+use crate::session;
+use gtk4::gio;
+use gtk4::glib;
+use gtk4::prelude::*;
+use std::path::PathBuf;
+
+/// Path of Hyprland's event socket (`.socket2.sock`) for this session.
+pub fn event_socket() -> Option<PathBuf> {
+    let signature = std::env::var("HYPRLAND_INSTANCE_SIGNATURE").ok()?;
+    let runtime_dir = std::env::var("XDG_RUNTIME_DIR").ok()?;
+
+    // Hyprland moved its sockets from /tmp to the runtime dir in 0.40.
+    [
+        PathBuf::from(runtime_dir).join("hypr").join(&signature),
+        PathBuf::from("/tmp/hypr").join(&signature),
+    ]
+    .into_iter()
+    .map(|dir| dir.join(".socket2.sock"))
+    .find(|path| path.exists())
+}
+
+/// Splits an event line such as `monitoradded>>DP-1` into name and data.
+pub fn parse_event(line: &str) -> Option<(&str, &str)> {
+    line.split_once(">>")
+}
+
+/// Streams Hyprland events to `on_event(name, data)` on the main loop.
+/// Returns `false` without calling anything if Hyprland isn't running;
+/// `on_disconnect` runs if the socket cannot be read.
+pub fn listen_events<F, D>(on_event: F, on_disconnect: D) -> bool
+where
+    F: Fn(&str, &str) + 'static,
+    D: FnOnce() + 'static,
+{
+    let Some(path) = event_socket() else {
+        return false;
+    };
+
+    glib::spawn_future_local(async move {
+        let address = gio::UnixSocketAddress::new(&path);
+        let connection = match gio::SocketClient::new().connect_future(&address).await {
+            Ok(connection) => connection,
+            Err(_) => {
+                on_disconnect();
+                return;
+            }
+        };
+
+        let stream = gio::DataInputStream::new(&connection.input_stream());
+        while let Ok(Some(line)) = stream.read_line_utf8_future(glib::Priority::DEFAULT).await {
+            if let Some((event, data)) = parse_event(&line) {
+                on_event(event, data);
+            }
+        }
+        on_disconnect();
+    });
+
+    true
+}
+
+/// A boolean (integer) option of the running Hyprland, e.g.
+/// `animations:enabled`, or `None` without Hyprland.
+pub fn option_bool(name: &str) -> Option<bool> {
+    if !session::current().may_be_hyprland() {
+        return None;
+    }
+    let output = probe::run("hyprctl", &["getoption", name, "-j"]).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
+    value.get("int")?.as_i64().map(|int| int != 0)
+}
+
+/// Sets options at runtime in one batch. Hyprland forgets them when its
+/// config is reloaded.
+pub fn set_keywords(keywords: &[(&str, &str)]) -> Result<(), String> {
+    if !session::current().may_be_hyprland() {
+        return Err(format!("Not available in {}", session::current().label()));
+    }
+    let batch: Vec<String> = keywords
+        .iter()
+        .map(|(name, value)| format!("keyword {} {}", name, value))
+        .collect();
+    let output = probe::run("hyprctl", &["--batch", &batch.join(" ; ")]).map_err(|e| e.to_string())?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if output.status.success() && !stdout.contains("error") {
+        Ok(())
+    } else {
+        Err(stdout.trim().to_string())
+    }
+}
+
+/// Whether Hyprland skips frames while nothing on screen changes
+/// (`misc:vfr`). It has no per-monitor render rate cap, so this is the
+/// way to save GPU power without changing the display mode.
+pub fn skip_idle_frames() -> Option<bool> {
+    option_bool("misc:vfr")
+}
+
+pub fn set_skip_idle_frames(on: bool) -> Result<(), String> {
+    set_keywords(&[("misc:vfr", if on { "1" } else { "0" })])
+}
@@ -0,0 +1,136 @@
+use crate::config::state_dir;
+use crate::session::{self, Desktop};
+use crate::system_info::command_exists;
+use std::fs;
+use std::process::{Child, Command, Stdio};
+
+const HYPRIDLE_CONFIG: &str = "hypridle.conf";
+const DPMS_OFF: &str = "hyprctl dispatch dpms off";
+const DPMS_ON: &str = "hyprctl dispatch dpms on";
+
+/// Screen blanking choices offered in the UI, in seconds; 0 keeps the
+/// screen on.
+pub const TIMEOUT_CHOICES: [u32; 6] = [0, 60, 120, 300, 600, 900];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Hypridle,
+    Swayidle,
+}
+
+impl Backend {
+    fn binary(self) -> &'static str {
+        match self {
+            Backend::Hypridle => "hypridle",
+            Backend::Swayidle => "swayidle",
+        }
+    }
+
+    fn command(self, timeout: u32) -> Result<Command, String> {
+        let mut command = Command::new(self.binary());
+        match self {
+            Backend::Hypridle => {
+                // A config of its own, so the user's hypridle.conf (and its
+                // lock screen) is left alone.
+                let path = state_dir().join(HYPRIDLE_CONFIG);
+                let config = format!(
+                    "listener {{\n    timeout = {}\n    on-timeout = {}\n    on-resume = {}\n}}\n",
+                    timeout, DPMS_OFF, DPMS_ON
+                );
+                fs::create_dir_all(state_dir()).map_err(|e| e.to_string())?;
+                fs::write(&path, config).map_err(|e| e.to_string())?;
+                command.arg("--config").arg(path);
+            }
+            Backend::Swayidle => {
+                command.args(["-w", "timeout", &timeout.to_string(), DPMS_OFF, "resume", DPMS_ON]);
+            }
+        }
+        Ok(command)
+    }
+}
+
+/// Owns the process behind the profile's screen blanking: an idle daemon
+/// that turns the screen off after the timeout, or an idle inhibitor that
+/// keeps the user's own daemon from blanking it. Stopped on drop, so the
+/// session's usual idle behaviour returns when TuxTuner quits.
+#[derive(Debug)]
+pub struct IdleControl {
+    backend: Option<Backend>,
+    child: Option<Child>,
+    timeout: Option<u32>,
+}
+
+impl IdleControl {
+    /// hypridle is preferred under Hyprland; swayidle works with any
+    /// compositor that has the idle protocol.
+    pub fn detect() -> Self {
+        let hyprland = session::current().desktop == Desktop::Hyprland;
+        let mut candidates = vec![Backend::Swayidle];
+        if hyprland {
+            candidates.insert(0, Backend::Hypridle);
+        }
+
+        Self {
+            backend: candidates.into_iter().find(|b| command_exists(b.binary())),
+            child: None,
+            timeout: None,
+        }
+    }
+
+    pub fn backend(&self) -> Option<Backend> {
+        self.backend
+    }
+
+    /// Blanks the screen after `timeout` seconds, keeps it on for 0, or
+    /// leaves idle handling to the session for `None`.
+    pub fn set(&mut self, timeout: Option<u32>) -> Result<(), String> {
+        if timeout == self.timeout {
+            return Ok(());
+        }
+
+        self.stop();
+
+        let Some(timeout) = timeout else {
+            return Ok(());
+        };
+        let mut command = if timeout == 0 {
+            if !command_exists("systemd-inhibit") {
+                return Err("systemd-inhibit not found".to_string());
+            }
+            // `cat` holds the inhibitor until its stdin, our end of the
+            // pipe, closes; killing systemd-inhibit alone would orphan it.
+            let mut command = Command::new("systemd-inhibit");
+            command
+                .args(["--what=idle", "--who=TuxTuner", "--why=Screen blanking is off for this profile", "cat"])
+                .stdin(Stdio::piped());
+            command
+        } else {
+            self.backend.ok_or("No idle daemon found")?.command(timeout)?
+        };
+
+        let child = command
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| e.to_string())?;
+
+        self.child = Some(child);
+        self.timeout = Some(timeout);
+        Ok(())
+    }
+
+    fn stop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            drop(child.stdin.take());
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        self.timeout = None;
+    }
+}
+
+impl Drop for IdleControl {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
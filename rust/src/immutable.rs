@@ -0,0 +1,61 @@
+use crate::remote;
+use once_cell::sync::Lazy;
+use std::path::{Path, PathBuf};
+
+/// Where the helper keeps udev rules and module options on NixOS; keep in
+/// sync with the helper's `PERSIST_UDEV_DIR`.
+const NIXOS_PERSIST_DIR: &str = "/etc/tuxtuner/nixos";
+
+/// A system whose /usr (and on NixOS most of /etc) is read-only, so the
+/// usual install locations don't work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Immutable {
+    /// Fedora Silverblue/Kinoite and other rpm-ostree systems: /etc stays
+    /// writable, /usr is extended with systemd-sysext.
+    Ostree,
+    /// /etc/udev, /etc/systemd and the module options are built from the
+    /// system configuration.
+    NixOS,
+}
+
+static SYSTEM: Lazy<Option<Immutable>> = Lazy::new(|| {
+    if remote::exists("/etc/NIXOS") {
+        Some(Immutable::NixOS)
+    } else if remote::exists("/run/ostree-booted") {
+        Some(Immutable::Ostree)
+    } else {
+        None
+    }
+});
+
+/// The tuned machine's kind of immutable system, if it is one.
+pub fn current() -> Option<Immutable> {
+    *SYSTEM
+}
+
+impl Immutable {
+    pub fn label(self) -> &'static str {
+        match self {
+            Immutable::Ostree => "ostree",
+            Immutable::NixOS => "NixOS",
+        }
+    }
+
+    /// How the helper gets installed here.
+    pub fn helper_install(self) -> &'static str {
+        match self {
+            Immutable::Ostree => "install.sh adds the helper and its polkit policy as a systemd-sysext extension",
+            Immutable::NixOS => "Add the helper and its polkit policy to your NixOS configuration",
+        }
+    }
+}
+
+/// The file the helper persists to in place of `path`: on NixOS, udev
+/// rules and modprobe options go under /etc/tuxtuner instead.
+pub fn persisted_path(path: &str) -> PathBuf {
+    let path = Path::new(path);
+    match (current(), path.file_name()) {
+        (Some(Immutable::NixOS), Some(name)) => Path::new(NIXOS_PERSIST_DIR).join(name),
+        _ => path.to_path_buf(),
+    }
+}
@@ -0,0 +1,27 @@
+use crate::remote;
+
+/// Whether the scheduler starts busy threads on the cores firmware ranks
+/// fastest (Intel Turbo Boost Max 3.0). On hybrid CPUs the ranking puts
+/// the P-cores first.
+const ITMT_PATH: &str = "/proc/sys/kernel/sched_itmt_enabled";
+/// CPU list of the efficiency cores on Intel hybrid CPUs.
+const EFFICIENCY_CORES_PATH: &str = "/sys/devices/cpu_atom/cpus";
+
+/// Whether ITMT is on, on hybrid Intel CPUs; `None` elsewhere or when
+/// the kernel doesn't rank the cores.
+pub fn enabled() -> Option<bool> {
+    let hybrid = remote::read_to_string(EFFICIENCY_CORES_PATH).is_ok_and(|cpus| !cpus.trim().is_empty());
+    if !hybrid {
+        return None;
+    }
+    match remote::read_to_string(ITMT_PATH).ok()?.trim() {
+        "1" => Some(true),
+        "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Lasts until reboot. Blocking.
+pub fn set_enabled(enabled: bool) -> Result<(), String> {
+    remote::run_helper(&["itmt", if enabled { "on" } else { "off" }])
+}
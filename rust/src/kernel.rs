@@ -0,0 +1,222 @@
+use crate::probe;
+use crate::remote;
+
+/// Timer frequency below which games and audio notice coarse scheduling.
+const LOW_LATENCY_HZ: u32 = 1000;
+/// Where kernel packages install their modules, one directory per
+/// release; the last is NixOS's, for the generation booted next.
+const MODULE_DIRS: [&str; 3] = [
+    "/lib/modules",
+    "/usr/lib/modules",
+    "/run/current-system/kernel-modules/lib/modules",
+];
+
+/// How readily the kernel interrupts running tasks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preemption {
+    None,
+    Voluntary,
+    Full,
+    Lazy,
+    Realtime,
+}
+
+impl Preemption {
+    pub fn label(self) -> &'static str {
+        match self {
+            Preemption::None => "None (server)",
+            Preemption::Voluntary => "Voluntary (desktop)",
+            Preemption::Full => "Full (low latency)",
+            Preemption::Lazy => "Lazy",
+            Preemption::Realtime => "Realtime",
+        }
+    }
+
+    fn from_mode(mode: &str) -> Option<Self> {
+        match mode {
+            "none" => Some(Preemption::None),
+            "voluntary" => Some(Preemption::Voluntary),
+            "full" => Some(Preemption::Full),
+            "lazy" => Some(Preemption::Lazy),
+            _ => None,
+        }
+    }
+}
+
+/// Build-time scheduling settings of the running kernel, which runtime
+/// tuning can't change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KernelInfo {
+    pub release: String,
+    /// Tuned kernel family, e.g. `Zen`, from the release suffix.
+    pub flavor: Option<&'static str>,
+    /// `CONFIG_HZ`, when the kernel config is readable.
+    pub hz: Option<u32>,
+    pub preemption: Option<Preemption>,
+    /// Built with `PREEMPT_DYNAMIC`, so `preempt=` on the command line
+    /// picks the model at boot.
+    pub dynamic: bool,
+    /// A newer installed kernel of the same kind, which the next boot
+    /// would run.
+    pub newer_installed: Option<String>,
+}
+
+/// The kernel config from `/proc/config.gz`, or the distribution's copy
+/// in `/boot`.
+fn kernel_config(release: &str) -> Option<String> {
+    if let Ok(output) = probe::run("zcat", &["/proc/config.gz"]) {
+        if output.status.success() {
+            return Some(String::from_utf8_lossy(&output.stdout).to_string());
+        }
+    }
+    remote::read_to_string(format!("/boot/config-{}", release)).ok()
+}
+
+fn config_enabled(config: &str, option: &str) -> bool {
+    config.lines().any(|line| line.strip_prefix(option) == Some("=y"))
+}
+
+fn flavor(release: &str, realtime: bool) -> Option<&'static str> {
+    const FLAVORS: &[(&str, &str)] = &[
+        ("-zen", "Zen"),
+        ("-lqx", "Liquorix"),
+        ("-xanmod", "XanMod"),
+        ("-cachyos", "CachyOS"),
+        ("-tkg", "TKG"),
+    ];
+    FLAVORS
+        .iter()
+        .find(|(suffix, _)| release.contains(suffix))
+        .map(|(_, name)| *name)
+        .or_else(|| realtime.then_some("Realtime"))
+}
+
+/// The numbers in a release, in order, e.g. `[6, 9, 3, 1, 1]` for
+/// `6.9.3-arch1-1`.
+fn version_key(release: &str) -> Vec<u64> {
+    release
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|part| part.parse().ok())
+        .collect()
+}
+
+/// What separates kernels installed side by side, e.g. linux-zen from
+/// linux, or Ubuntu's `-generic` from `-lowlatency`.
+fn family(release: &str) -> (Option<&'static str>, Option<&str>) {
+    let suffix = release
+        .rsplit_once('-')
+        .map(|(_, suffix)| suffix)
+        .filter(|suffix| suffix.chars().all(|c| c.is_ascii_alphabetic()));
+    (flavor(release, false), suffix)
+}
+
+/// The newest installed kernel of `release`'s family, if it's newer.
+/// Directories without `modules.builtin` are leftovers of removed
+/// kernels, e.g. from DKMS.
+fn newer_installed(release: &str) -> Option<String> {
+    let mut installed: Vec<String> = MODULE_DIRS
+        .iter()
+        .flat_map(|dir| remote::read_dir(dir).unwrap_or_default())
+        .filter(|dir| remote::exists(dir.join("modules.builtin")))
+        .filter_map(|dir| Some(dir.file_name()?.to_string_lossy().to_string()))
+        .filter(|installed| family(installed) == family(release))
+        .collect();
+    installed.sort_by_key(|installed| version_key(installed));
+    installed
+        .pop()
+        .filter(|newest| version_key(newest) > version_key(release))
+}
+
+pub fn info() -> KernelInfo {
+    let release = remote::read_to_string("/proc/sys/kernel/osrelease")
+        .map(|release| release.trim().to_string())
+        .unwrap_or_default();
+    // e.g. `#1 SMP PREEMPT_DYNAMIC Sat, 01 Jan 2026 ...`
+    let version = remote::read_to_string("/proc/version").unwrap_or_default();
+    let config = kernel_config(&release);
+
+    let hz = config.as_deref().and_then(|config| {
+        config
+            .lines()
+            .find_map(|line| line.strip_prefix("CONFIG_HZ="))
+            .and_then(|hz| hz.trim().parse().ok())
+    });
+
+    let has = |option: &str, word: &str| match &config {
+        Some(config) => config_enabled(config, option),
+        None => version.split_whitespace().any(|token| token == word),
+    };
+    let realtime = has("CONFIG_PREEMPT_RT", "PREEMPT_RT");
+    let dynamic = has("CONFIG_PREEMPT_DYNAMIC", "PREEMPT_DYNAMIC");
+
+    // A dynamic kernel runs whatever `preempt=` asked for, else its default.
+    let boot_mode = remote::read_to_string("/proc/cmdline")
+        .ok()
+        .and_then(|cmdline| {
+            cmdline
+                .split_whitespace()
+                .find_map(|arg| arg.strip_prefix("preempt="))
+                .and_then(Preemption::from_mode)
+        })
+        .filter(|_| dynamic);
+    let preemption = if realtime {
+        Some(Preemption::Realtime)
+    } else if boot_mode.is_some() {
+        boot_mode
+    } else if let Some(config) = &config {
+        [
+            ("CONFIG_PREEMPT_LAZY", Preemption::Lazy),
+            ("CONFIG_PREEMPT", Preemption::Full),
+            ("CONFIG_PREEMPT_VOLUNTARY", Preemption::Voluntary),
+            ("CONFIG_PREEMPT_NONE", Preemption::None),
+        ]
+        .into_iter()
+        .find(|(option, _)| config_enabled(config, option))
+        .map(|(_, model)| model)
+    } else if version.split_whitespace().any(|token| token == "PREEMPT") {
+        Some(Preemption::Full)
+    } else {
+        None
+    };
+
+    KernelInfo {
+        flavor: flavor(&release, realtime),
+        newer_installed: newer_installed(&release),
+        release,
+        hz,
+        preemption,
+        dynamic,
+    }
+}
+
+impl KernelInfo {
+    /// What a different kernel or boot option would improve; empty when
+    /// this one is already set up for low latency.
+    pub fn recommendations(&self) -> Vec<String> {
+        let mut tips = Vec::new();
+
+        if let Some(hz) = self.hz.filter(|&hz| hz < LOW_LATENCY_HZ) {
+            tips.push(format!(
+                "The timer ticks at {} Hz. Kernels built for {} Hz, such as linux-zen, schedule games and audio more finely.",
+                hz, LOW_LATENCY_HZ
+            ));
+        }
+
+        match self.preemption {
+            Some(Preemption::None | Preemption::Voluntary) if self.dynamic => tips.push(
+                "Add preempt=full to the kernel command line to cut input and audio latency.".to_string(),
+            ),
+            Some(Preemption::None | Preemption::Voluntary) => tips.push(
+                "This kernel favors throughput over latency. A kernel with full preemption, such as linux-zen, feels snappier under load."
+                    .to_string(),
+            ),
+            Some(Preemption::Realtime) => tips.push(
+                "Realtime kernels trade throughput and battery life for predictable latency. They help audio work, rarely games."
+                    .to_string(),
+            ),
+            _ => {}
+        }
+
+        tips
+    }
+}
@@ -0,0 +1,57 @@
+use crate::remote;
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, Stdio};
+
+const CPU_DMA_LATENCY_PATH: &str = "/dev/cpu_dma_latency";
+
+/// Whether the kernel takes CPU latency requests.
+pub fn supported() -> bool {
+    remote::exists(CPU_DMA_LATENCY_PATH)
+}
+
+/// A request keeping CPUs out of deep idle states. The helper holds
+/// `/dev/cpu_dma_latency` open while its stdin is; the kernel drops the
+/// request once the file is closed, so it also ends when TuxTuner exits
+/// or crashes.
+pub struct LatencyHold {
+    child: Child,
+}
+
+/// Starts a zero-latency request; returns once the helper holds it.
+pub fn hold() -> Result<LatencyHold, String> {
+    let mut child = remote::helper(&["latency-hold"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    let mut line = String::new();
+    if let Some(stdout) = child.stdout.take() {
+        let _ = BufReader::new(stdout).read_line(&mut line);
+    }
+    if line.trim() == "ready" {
+        return Ok(LatencyHold { child });
+    }
+
+    // The helper failed (or authentication was dismissed) before holding.
+    drop(child.stdin.take());
+    let mut stderr = String::new();
+    if let Some(mut pipe) = child.stderr.take() {
+        let _ = pipe.read_to_string(&mut stderr);
+    }
+    let _ = child.wait();
+    Err(if stderr.trim().is_empty() {
+        "Latency request was not granted".to_string()
+    } else {
+        stderr.trim().to_string()
+    })
+}
+
+impl LatencyHold {
+    /// Ends the request and lets CPUs idle deeply again.
+    pub fn release(mut self) {
+        drop(self.child.stdin.take());
+        let _ = self.child.wait();
+    }
+}
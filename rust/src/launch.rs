@@ -0,0 +1,101 @@
+use crate::system_info::command_exists;
+
+/// A tweak for Steam's per-game launch options: environment variables,
+/// or a wrapper command that runs the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Preset {
+    /// Stable key kept in the config.
+    pub id: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    pub env: &'static [(&'static str, &'static str)],
+    /// Wrapper command; the preset is hidden when it isn't installed.
+    pub wrapper: Option<&'static str>,
+}
+
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        id: "dxvk-async",
+        title: "DXVK Async",
+        description: "Compile shaders in the background (dxvk-async and GPL-async builds)",
+        env: &[("DXVK_ASYNC", "1")],
+        wrapper: None,
+    },
+    Preset {
+        id: "shader-cache",
+        title: "Large Shader Cache",
+        description: "Keep up to 10 GB of compiled shaders on Mesa and NVIDIA",
+        env: &[
+            ("MESA_SHADER_CACHE_MAX_SIZE", "10G"),
+            ("__GL_SHADER_DISK_CACHE_SIZE", "10737418240"),
+        ],
+        wrapper: None,
+    },
+    Preset {
+        id: "mesa-glthread",
+        title: "Threaded OpenGL",
+        description: "Offload OpenGL driver work to a second thread on Mesa",
+        env: &[("mesa_glthread", "true")],
+        wrapper: None,
+    },
+    Preset {
+        id: "vkd3d-dxr",
+        title: "DirectX 12 Ray Tracing",
+        description: "Expose DXR in VKD3D-Proton",
+        env: &[("VKD3D_CONFIG", "dxr11")],
+        wrapper: None,
+    },
+    Preset {
+        id: "nvapi",
+        title: "NVAPI",
+        description: "Enable DLSS and Reflex in Proton on NVIDIA cards",
+        env: &[("PROTON_ENABLE_NVAPI", "1")],
+        wrapper: None,
+    },
+    Preset {
+        id: "gamemode",
+        title: "GameMode",
+        description: "Let gamemoded raise priorities and the CPU governor",
+        env: &[],
+        wrapper: Some("gamemoderun"),
+    },
+    Preset {
+        id: "prime-run",
+        title: "Discrete GPU",
+        description: "Render on the NVIDIA card in hybrid mode",
+        env: &[],
+        wrapper: Some("prime-run"),
+    },
+    Preset {
+        id: "mangohud",
+        title: "MangoHud",
+        description: "Show the performance overlay",
+        env: &[],
+        wrapper: Some("mangohud"),
+    },
+];
+
+/// Presets usable on this machine.
+pub fn available_presets() -> Vec<&'static Preset> {
+    PRESETS
+        .iter()
+        .filter(|preset| preset.wrapper.is_none_or(command_exists))
+        .collect()
+}
+
+/// Steam launch options for the presets with these ids, e.g.
+/// `DXVK_ASYNC=1 gamemoderun %command%`.
+pub fn launch_options(ids: &[String]) -> String {
+    let selected: Vec<&Preset> = PRESETS
+        .iter()
+        .filter(|preset| ids.iter().any(|id| id == preset.id))
+        .collect();
+
+    let mut parts: Vec<String> = selected
+        .iter()
+        .flat_map(|preset| preset.env.iter().map(|(name, value)| format!("{}={}", name, value)))
+        .collect();
+    parts.extend(selected.iter().filter_map(|preset| preset.wrapper.map(String::from)));
+    parts.push("%command%".to_string());
+    parts.join(" ")
+}
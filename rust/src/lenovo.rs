@@ -0,0 +1,60 @@
+use crate::remote;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Kernel modules for Lenovo laptop extras: mainline ideapad_laptop and
+/// the out-of-tree legion_laptop.
+const MODULES: [&str; 2] = ["/sys/module/ideapad_laptop", "/sys/module/legion_laptop"];
+
+/// Platform drivers whose device directories hold the knobs.
+const DRIVER_DIRS: [&str; 2] = [
+    "/sys/bus/platform/drivers/ideapad_acpi",
+    "/sys/bus/platform/drivers/legion",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LenovoFeature {
+    /// Holds the battery around 60% to extend its lifespan.
+    ConservationMode,
+    FnLock,
+    /// Faster charging, Legion only.
+    RapidCharge,
+}
+
+impl LenovoFeature {
+    fn attribute(self) -> &'static str {
+        match self {
+            LenovoFeature::ConservationMode => "conservation_mode",
+            LenovoFeature::FnLock => "fn_lock",
+            LenovoFeature::RapidCharge => "rapidcharge",
+        }
+    }
+}
+
+/// Whether either Lenovo platform module is loaded.
+pub fn loaded() -> bool {
+    MODULES.iter().any(|module| Path::new(module).exists())
+}
+
+fn attribute_path(feature: LenovoFeature) -> Option<PathBuf> {
+    DRIVER_DIRS
+        .iter()
+        .flat_map(|dir| fs::read_dir(dir).into_iter().flatten().flatten())
+        .map(|entry| entry.path().join(feature.attribute()))
+        .find(|path| path.is_file())
+}
+
+/// Current state of `feature`, or `None` when the loaded driver doesn't
+/// expose it.
+pub fn feature(feature: LenovoFeature) -> Option<bool> {
+    let path = attribute_path(feature)?;
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|value| value.trim().parse::<u8>().ok())
+        .map(|value| value != 0)
+}
+
+pub fn apply_feature(feature: LenovoFeature, enabled: bool) -> Result<(), String> {
+    let value = if enabled { "1" } else { "0" };
+    remote::run_helper(&["lenovo", feature.attribute(), value])
+}
@@ -0,0 +1,83 @@
+use crate::probe;
+use crate::remote;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// The system libvirt instance, which owns VMs with host devices; keep in
+/// sync with the helper's `LIBVIRT_URI`.
+const CONNECT_URI: &str = "qemu:///system";
+const PCI_DEVICES_PATH: &str = "/sys/bus/pci/devices";
+
+/// The host side of a PCI `<hostdev>`, i.e. its `<source>` address.
+static HOSTDEV_SOURCE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?s)<hostdev[^>]*type=['"]pci['"][^>]*>.*?<source[^>]*>\s*<address([^>]*)/>"#).unwrap()
+});
+static ADDRESS_PART: Lazy<Regex> = Lazy::new(|| Regex::new(r#"(\w+)=['"]0x([0-9a-fA-F]+)['"]"#).unwrap());
+
+/// Whether `name` is a domain name the helper accepts.
+fn valid_domain(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphanumeric())
+        && chars.all(|c| c.is_ascii_alphanumeric() || "_.+-".contains(c))
+}
+
+/// The discrete GPUs' slots as `domain:bus:slot` (e.g. `0000:01:00`), so
+/// the card's audio function matches too. The boot display is the iGPU.
+fn gpu_slots() -> Vec<String> {
+    remote::read_dir(PCI_DEVICES_PATH)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|dir| {
+            let read = |attr: &str| remote::read_to_string(dir.join(attr)).unwrap_or_default();
+            read("class").trim().starts_with("0x03") && read("boot_vga").trim() != "1"
+        })
+        .filter_map(|dir| {
+            let address = dir.file_name()?.to_string_lossy().to_string();
+            Some(address.rsplit_once('.').map_or(address.clone(), |(slot, _)| slot.to_string()))
+        })
+        .collect()
+}
+
+/// Slots of the PCI devices a domain's XML passes through.
+fn hostdev_slots(xml: &str) -> Vec<String> {
+    HOSTDEV_SOURCE
+        .captures_iter(xml)
+        .filter_map(|source| {
+            let part = |name: &str| {
+                ADDRESS_PART
+                    .captures_iter(&source[1])
+                    .find(|part| &part[1] == name)
+                    .and_then(|part| u32::from_str_radix(&part[2], 16).ok())
+            };
+            Some(format!("{:04x}:{:02x}:{:02x}", part("domain")?, part("bus")?, part("slot")?))
+        })
+        .collect()
+}
+
+/// Defined VMs that pass the discrete GPU through, in libvirt's order.
+/// Empty without libvirt or a discrete GPU. Blocking.
+pub fn gpu_domains() -> Vec<String> {
+    let slots = gpu_slots();
+    if slots.is_empty() {
+        return Vec::new();
+    }
+    let virsh = |args: &[&str]| {
+        let args = [&["--connect", CONNECT_URI], args].concat();
+        probe::run("virsh", &args)
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
+    };
+
+    let names = virsh(&["list", "--all", "--name"]).unwrap_or_default();
+    names
+        .lines()
+        .map(str::trim)
+        .filter(|name| valid_domain(name))
+        .filter(|name| {
+            virsh(&["dumpxml", name])
+                .is_some_and(|xml| hostdev_slots(&xml).iter().any(|slot| slots.contains(slot)))
+        })
+        .map(str::to_string)
+        .collect()
+}
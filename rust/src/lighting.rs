@@ -0,0 +1,93 @@
+use crate::probe;
+use crate::system_info::command_exists;
+use serde::{Deserialize, Serialize};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+/// Default address of the OpenRGB SDK server.
+const OPENRGB_SERVER: &str = "127.0.0.1:6742";
+/// Brightness used for `Dim` on OpenRGB devices, in percent.
+const OPENRGB_DIM_BRIGHTNESS: u32 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// A running OpenRGB SDK server, driven through the `openrgb` client.
+    OpenRgb,
+    /// ASUS keyboard backlight through asusd.
+    Asusctl,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LightingLevel {
+    Off,
+    Dim,
+    #[default]
+    On,
+}
+
+impl LightingLevel {
+    pub const ALL: [LightingLevel; 3] = [LightingLevel::Off, LightingLevel::Dim, LightingLevel::On];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LightingLevel::Off => "Off",
+            LightingLevel::Dim => "Dim",
+            LightingLevel::On => "On",
+        }
+    }
+}
+
+fn openrgb_server_running() -> bool {
+    OPENRGB_SERVER
+        .parse::<SocketAddr>()
+        .is_ok_and(|addr| TcpStream::connect_timeout(&addr, Duration::from_millis(200)).is_ok())
+}
+
+/// Picks the lighting controller to use, preferring OpenRGB since it
+/// covers every device it manages, keyboards included.
+pub fn detect() -> Option<Backend> {
+    if command_exists("openrgb") && openrgb_server_running() {
+        Some(Backend::OpenRgb)
+    } else if command_exists("asusctl") {
+        Some(Backend::Asusctl)
+    } else {
+        None
+    }
+}
+
+fn run(program: &str, args: &[&str]) -> Result<(), String> {
+    let output = probe::run(program, args).map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).to_string())
+    }
+}
+
+/// Sets every RGB device handled by `backend` to `level`. Blocking.
+pub fn apply(backend: Backend, level: LightingLevel) -> Result<(), String> {
+    match backend {
+        Backend::OpenRgb => {
+            let brightness = match level {
+                LightingLevel::Off => 0,
+                LightingLevel::Dim => OPENRGB_DIM_BRIGHTNESS,
+                LightingLevel::On => 100,
+            }
+            .to_string();
+            run(
+                "openrgb",
+                &["--client", OPENRGB_SERVER, "--noautoconnect", "--brightness", &brightness],
+            )
+        }
+        Backend::Asusctl => {
+            let brightness = match level {
+                LightingLevel::Off => "off",
+                LightingLevel::Dim => "low",
+                LightingLevel::On => "high",
+            };
+            run("asusctl", &["--kbd-bright", brightness])
+        }
+    }
+}
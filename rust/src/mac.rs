@@ -0,0 +1,112 @@
+use crate::remote;
+use crate::system_info::HELPER_PATH;
+
+/// Where AppArmor expects the helper's profile, named after its path.
+pub const APPARMOR_PROFILE_FILE: &str = "usr.lib.tuxtuner.tuxtuner-helper";
+
+/// A mandatory access control framework, which can refuse the helper's
+/// writes even though it runs as root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mac {
+    /// SELinux in enforcing mode; permissive mode only logs.
+    SELinux,
+    AppArmor,
+}
+
+impl Mac {
+    pub fn label(self) -> &'static str {
+        match self {
+            Mac::SELinux => "SELinux (enforcing)",
+            Mac::AppArmor => "AppArmor",
+        }
+    }
+
+    fn remedy(self) -> String {
+        match self {
+            Mac::SELinux => "SELinux policy blocked this change. `sudo ausearch -m avc -ts recent` shows the \
+                 denial; `audit2allow` can turn it into a local policy module."
+                .to_string(),
+            Mac::AppArmor => format!(
+                "AppArmor policy blocked this change. `sudo journalctl -k -g apparmor=\"DENIED\"` shows the \
+                 denial; the helper profile from Diagnostics allows what {} needs.",
+                HELPER_PATH
+            ),
+        }
+    }
+}
+
+/// The framework enforcing policy on the tuned machine, if any.
+pub fn active() -> Option<Mac> {
+    let read = |path: &str| remote::read_to_string(path).map(|value| value.trim().to_string());
+    if read("/sys/fs/selinux/enforce").is_ok_and(|value| value == "1") {
+        return Some(Mac::SELinux);
+    }
+    if read("/sys/module/apparmor/parameters/enabled").is_ok_and(|value| value == "Y") {
+        return Some(Mac::AppArmor);
+    }
+    None
+}
+
+/// A remediation hint for a helper `error` that looks like a policy
+/// denial. The helper runs as root, so plain permissions don't refuse
+/// it; while a framework enforces policy, a refusal is most likely its.
+pub fn explain(error: &str) -> Option<String> {
+    if !error.contains("Permission denied") && !error.contains("Operation not permitted") {
+        return None;
+    }
+    active().map(Mac::remedy)
+}
+
+/// An AppArmor profile allowing what the helper does: writing sysfs,
+/// procfs and device knobs, its own files under /etc and /var/lib, and
+/// running the system tools it drives under their own profiles.
+pub fn apparmor_profile() -> String {
+    format!(
+        r#"# AppArmor profile for the TuxTuner helper, generated by TuxTuner.
+#
+# Install with:
+#   sudo cp {file} /etc/apparmor.d/{file}
+#   sudo apparmor_parser -r /etc/apparmor.d/{file}
+
+abi <abi/3.0>,
+
+include <tunables/global>
+
+profile tuxtuner-helper {helper} {{
+  include <abstractions/base>
+  include <abstractions/bash>
+  include <abstractions/consoles>
+
+  capability dac_override,
+  capability dac_read_search,
+  capability net_admin,
+  capability sys_admin,
+  capability sys_module,
+  capability sys_nice,
+  capability sys_rawio,
+  capability sys_resource,
+
+  {helper} r,
+  /{{usr/,}}bin/{{bash,cat,basename,dirname,getconf,grep,id,mkdir,mktemp,mv,readlink,rm,sleep,touch}} ix,
+  /{{usr/,}}{{bin,sbin}}/* PUx,
+
+  /sys/** rw,
+  /proc/** r,
+  /proc/sys/** rw,
+  /proc/*/oom_score_adj rw,
+  /dev/cpu_dma_latency w,
+  /dev/cpu/*/msr rw,
+
+  /etc/** r,
+  /etc/tuxtuner/** rw,
+  /etc/modprobe.d/90-tuxtuner.conf rw,
+  /etc/udev/rules.d/90-tuxtuner-*.rules rw,
+  /etc/udev/rules.d/90-tuxtuner-*.rules.tmp rw,
+  /var/lib/tuxtuner/** rw,
+  /tmp/** rw,
+}}
+"#,
+        file = APPARMOR_PROFILE_FILE,
+        helper = HELPER_PATH,
+    )
+}
@@ -1,25 +1,202 @@
+mod advice;
+mod affinity;
+mod api;
+mod asus;
+mod audit;
+mod autostart;
+mod backends;
+mod backlight;
+mod battery;
+mod battery_history;
+mod bluetooth;
+mod chart;
+mod config;
+mod corepark;
+mod cpufreq;
+mod devpower;
+mod diagnostics;
+mod effects;
+mod export;
+mod firmware;
+mod fwupd;
+mod gpu;
+mod gpu_driver;
+mod gpu_priority;
+mod gpufan;
+mod hibernate;
+mod hooks;
+mod hotplug;
+mod hyprland;
+mod idle;
+mod immutable;
+mod itmt;
+mod kernel;
+mod latency;
+mod launch;
+mod lenovo;
+mod libvirt;
+mod lighting;
+mod mac;
+mod mangohud;
+mod metrics;
+mod modparams;
+mod mqtt;
+mod nic;
+mod nightlight;
+mod platform;
+mod power;
+mod privacy;
+mod privileges;
+mod probe;
+mod processes;
+mod profiles;
+mod remote;
+mod report;
+mod rfkill;
+mod rules;
+mod ryzenadj;
+mod schedule;
+mod script;
+mod session;
+mod snapshot;
+mod storage;
+mod sysfs;
+mod system76;
 mod system_info;
+mod thermal;
+mod thinkpad;
+mod tunables;
 mod ui;
+mod undervolt;
+mod updater;
+mod ups;
+mod usage;
+mod validate;
+mod virt;
+mod vmhost;
+mod wakeup;
+mod window_watch;
 
+use gtk4::{gio, glib};
 use gtk4::prelude::*;
 use libadwaita as adw;
 
 const APP_ID: &str = "com.github.xavrir.TuxTuner";
+/// Debug output is shown with `G_MESSAGES_DEBUG=tuxtuner`.
+const LOG_DOMAIN: &str = "tuxtuner";
 
 fn main() -> gtk4::glib::ExitCode {
+    // Later launches hand their command line to the running instance,
+    // so `tuxtuner profile Performance` drives the open window.
     let app = adw::Application::builder()
         .application_id(APP_ID)
+        .flags(gio::ApplicationFlags::HANDLES_COMMAND_LINE)
         .build();
+    app.add_main_option(
+        autostart::BACKGROUND_FLAG,
+        glib::Char::from(b'b'),
+        glib::OptionFlags::NONE,
+        glib::OptionArg::None,
+        "Start hidden, running automation rules only",
+        None,
+    );
 
     app.connect_startup(|app| {
         ui::load_css();
         ui::setup_actions(app);
+        if let Err(e) = metrics::start_if_enabled() {
+            gtk4::glib::g_debug!(LOG_DOMAIN, "Metrics exporter failed to start: {}", e);
+        }
     });
 
-    app.connect_activate(|app| {
-        let window = ui::TuxTunerWindow::new(app);
-        window.present();
-    });
+    app.connect_activate(|app| show_window(app, false));
+    app.connect_command_line(handle_command_line);
 
     app.run()
 }
+
+/// Presents the window, creating it first if needed. A window created
+/// `hidden` stays in the background and only hides when closed, so its
+/// automation keeps running until Quit.
+fn show_window(app: &adw::Application, hidden: bool) {
+    if let Some(window) = app.windows().first() {
+        if !hidden {
+            window.present();
+        }
+        return;
+    }
+    let window = ui::TuxTunerWindow::new(app);
+    if hidden {
+        window.set_hide_on_close(true);
+    } else {
+        window.present();
+    }
+}
+
+/// Runs in the primary instance for its own launch and for every later
+/// one. `cmdline.arguments()` still holds the options, so they're skipped.
+fn handle_command_line(app: &adw::Application, cmdline: &gio::ApplicationCommandLine) -> i32 {
+    let args: Vec<String> = cmdline
+        .arguments()
+        .iter()
+        .skip(1)
+        .map(|arg| arg.to_string_lossy().to_string())
+        .filter(|arg| !arg.starts_with('-'))
+        .collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match args.as_slice() {
+        [] => {
+            show_window(app, cmdline.options_dict().contains(autostart::BACKGROUND_FLAG));
+            0
+        }
+        ["profile", name] => run_profile_command(app, cmdline, name),
+        ["export", format] => run_export_command(cmdline, format),
+        _ => {
+            cmdline.printerr_literal(
+                "Usage: tuxtuner [--background]\n       tuxtuner profile <name>\n       \
+                 tuxtuner export <nixos|ansible>\n",
+            );
+            1
+        }
+    }
+}
+
+/// Hands `name` to the open window, or applies it directly when
+/// TuxTuner wasn't running. Messages go to the terminal that ran the
+/// command, which for a forwarded launch isn't this process's.
+fn run_profile_command(app: &adw::Application, cmdline: &gio::ApplicationCommandLine, name: &str) -> i32 {
+    if let Some(window) = app.windows().first().and_then(|w| w.clone().downcast::<ui::TuxTunerWindow>().ok()) {
+        if window.request_profile(name) {
+            return 0;
+        }
+        cmdline.printerr_literal(&format!("No profile named {}\n", name));
+        return 1;
+    }
+
+    match rules::apply_action(&rules::Action::Profile(name.to_string())) {
+        Ok(()) => {
+            cmdline.print_literal(&format!("{} profile applied\n", name));
+            0
+        }
+        Err(e) => {
+            cmdline.printerr_literal(&format!("Profile failed: {}\n", e));
+            1
+        }
+    }
+}
+
+/// Prints the current setup as a NixOS module or Ansible tasks to the
+/// caller's stdout, so redirecting it works while a window is open.
+fn run_export_command(cmdline: &gio::ApplicationCommandLine, format: &str) -> i32 {
+    match export::ExportFormat::from_arg(format) {
+        Some(format) => {
+            cmdline.print_literal(&export::Setup::current().render(format));
+            0
+        }
+        None => {
+            cmdline.printerr_literal(&format!("Unknown export format: {} (use nixos or ansible)\n", format));
+            1
+        }
+    }
+}
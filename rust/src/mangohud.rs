@@ -0,0 +1,134 @@
+use crate::remote;
+use crate::system_info::command_exists;
+use std::fs;
+use std::path::PathBuf;
+
+pub const MAX_FPS_LIMIT: u32 = 500;
+
+/// `$XDG_CONFIG_HOME/MangoHud/MangoHud.conf`, MangoHud's global config.
+fn config_path() -> PathBuf {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_else(|| PathBuf::from("/tmp"));
+    base.join("MangoHud").join("MangoHud.conf")
+}
+
+/// Whether MangoHud is installed here. Its config lives in this user's
+/// home, so remote hosts don't count.
+pub fn installed() -> bool {
+    remote::host().is_none() && command_exists("mangohud")
+}
+
+/// The options TuxTuner manages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Settings {
+    /// Overlay visible at start (no `no_display`).
+    pub overlay: bool,
+    /// 0 means unlimited.
+    pub fps_limit: u32,
+    pub frame_timing: bool,
+}
+
+/// MangoHud.conf as lines of `key=value` or bare `key` flags. Comments
+/// and options TuxTuner doesn't know are kept as they are.
+struct ConfigFile {
+    lines: Vec<String>,
+}
+
+impl ConfigFile {
+    fn load() -> Self {
+        Self {
+            lines: fs::read_to_string(config_path())
+                .unwrap_or_default()
+                .lines()
+                .map(String::from)
+                .collect(),
+        }
+    }
+
+    fn key_of(line: &str) -> Option<&str> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        Some(line.split_once('=').map_or(line, |(key, _)| key).trim())
+    }
+
+    /// `Some(None)` for a bare flag, `Some(Some(value))` for `key=value`.
+    fn get(&self, key: &str) -> Option<Option<String>> {
+        self.lines
+            .iter()
+            .rfind(|line| Self::key_of(line) == Some(key))
+            .map(|line| line.split_once('=').map(|(_, value)| value.trim().to_string()))
+    }
+
+    /// Replaces every occurrence of `key` with one line, or removes it for
+    /// `None`.
+    fn set(&mut self, key: &str, line: Option<String>) {
+        let position = self.lines.iter().position(|l| Self::key_of(l) == Some(key));
+        self.lines.retain(|l| Self::key_of(l) != Some(key));
+        if let Some(line) = line {
+            let index = position.unwrap_or(self.lines.len()).min(self.lines.len());
+            self.lines.insert(index, line);
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let path = config_path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let mut content = self.lines.join("\n");
+        content.push('\n');
+        fs::write(path, content).map_err(|e| e.to_string())
+    }
+}
+
+pub fn settings() -> Settings {
+    let config = ConfigFile::load();
+    let flag = |key: &str, default: bool| match config.get(key) {
+        Some(Some(value)) => value != "0",
+        Some(None) => true,
+        None => default,
+    };
+    Settings {
+        overlay: !flag("no_display", false),
+        fps_limit: config
+            .get("fps_limit")
+            .flatten()
+            // Several limits may be listed to cycle through; the first applies.
+            .and_then(|value| value.split(',').next()?.trim().parse().ok())
+            .unwrap_or(0),
+        // MangoHud draws the frame time graph unless told otherwise.
+        frame_timing: flag("frame_timing", true),
+    }
+}
+
+/// Writes `settings`; running games pick the change up on their own.
+pub fn apply(settings: Settings) -> Result<(), String> {
+    if settings.fps_limit > MAX_FPS_LIMIT {
+        return Err(format!("FPS limit must be at most {}", MAX_FPS_LIMIT));
+    }
+
+    let mut config = ConfigFile::load();
+    config.set("no_display", (!settings.overlay).then(|| "no_display".to_string()));
+    config.set(
+        "fps_limit",
+        (settings.fps_limit > 0).then(|| format!("fps_limit={}", settings.fps_limit)),
+    );
+    config.set(
+        "frame_timing",
+        Some(format!("frame_timing={}", settings.frame_timing as u8)),
+    );
+    config.save()
+}
+
+/// Changes only the FPS limit, as profiles do.
+pub fn apply_fps_limit(fps_limit: u32) -> Result<(), String> {
+    apply(Settings {
+        fps_limit,
+        ..settings()
+    })
+}
@@ -0,0 +1,201 @@
+use crate::battery;
+use crate::config::Config;
+use crate::corepark::CpuSampler;
+use crate::gpufan;
+use crate::profiles;
+use crate::system_info::SystemInfo;
+use crate::thermal;
+use gtk4::glib;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+pub const DEFAULT_PORT: u16 = 9847;
+/// Slow or stuck scrapers must not hold up the next one.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Prometheus exporter, off by default. Bound to localhost unless
+/// `address` says otherwise, e.g. `0.0.0.0` for a homelab Prometheus.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    pub address: String,
+    pub port: u16,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: "127.0.0.1".to_string(),
+            port: DEFAULT_PORT,
+        }
+    }
+}
+
+impl MetricsConfig {
+    pub fn url(&self) -> String {
+        format!("http://{}:{}/metrics", self.address, self.port)
+    }
+}
+
+struct Server {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+}
+
+static SERVER: Mutex<Option<Server>> = Mutex::new(None);
+
+fn gauge(out: &mut String, name: &str, help: &str, value: Option<f64>) {
+    let Some(value) = value else {
+        return;
+    };
+    let _ = writeln!(out, "# HELP tuxtuner_{} {}", name, help);
+    let _ = writeln!(out, "# TYPE tuxtuner_{} gauge", name);
+    let _ = writeln!(out, "tuxtuner_{} {}", name, value);
+}
+
+/// Current readings in the Prometheus text format. Missing hardware
+/// (no battery, no GPU fan) simply leaves its metrics out.
+fn render(sampler: &mut CpuSampler) -> String {
+    let mut out = String::new();
+    let (total_cpus, online_cpus) = SystemInfo::fetch_cpu_info();
+
+    gauge(&mut out, "cpu_threads_total", "CPU threads present.", Some(total_cpus as f64));
+    gauge(&mut out, "cpu_threads_online", "CPU threads online.", Some(online_cpus as f64));
+    gauge(
+        &mut out,
+        "cpu_load_ratio",
+        "Busy fraction of online CPUs since the previous scrape.",
+        sampler.sample(),
+    );
+    gauge(
+        &mut out,
+        "cpu_temperature_celsius",
+        "CPU package temperature.",
+        thermal::cpu_temperature(),
+    );
+    gauge(
+        &mut out,
+        "battery_capacity_percent",
+        "Battery charge.",
+        battery::capacity_percent().map(f64::from),
+    );
+    gauge(
+        &mut out,
+        "battery_power_watts",
+        "Battery power draw; negative while charging.",
+        battery::power_draw_mw().map(|mw| mw as f64 / 1000.0),
+    );
+    gauge(
+        &mut out,
+        "battery_charge_limit_percent",
+        "Charge limit set in the firmware.",
+        battery::charge_limit().map(f64::from),
+    );
+
+    if let Some(fan) = gpufan::detect() {
+        gauge(
+            &mut out,
+            "gpu_temperature_celsius",
+            "Discrete GPU temperature.",
+            fan.temperature().map(f64::from),
+        );
+        gauge(
+            &mut out,
+            "gpu_fan_percent",
+            "Discrete GPU fan speed.",
+            fan.speed_percent().map(f64::from),
+        );
+    }
+
+    if let Some(profile) = profiles::platform_profile() {
+        let _ = writeln!(out, "# HELP tuxtuner_platform_profile Active ACPI platform profile.");
+        let _ = writeln!(out, "# TYPE tuxtuner_platform_profile gauge");
+        let _ = writeln!(out, "tuxtuner_platform_profile{{profile=\"{}\"}} 1", profile.replace('"', ""));
+    }
+
+    out
+}
+
+fn serve(mut stream: TcpStream, sampler: &mut CpuSampler) {
+    let _ = stream.set_read_timeout(Some(CLIENT_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(CLIENT_TIMEOUT));
+
+    let mut request_line = String::new();
+    if BufReader::new(&stream).read_line(&mut request_line).is_err() {
+        return;
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let response = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => {
+            let body = render(sampler);
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+        }
+        _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+    };
+    let _ = stream.write_all(response.as_bytes());
+}
+
+/// Starts serving `/metrics` on a background thread, replacing any
+/// exporter already running.
+pub fn start(config: &MetricsConfig) -> Result<(), String> {
+    stop();
+
+    let listener = TcpListener::bind((config.address.as_str(), config.port)).map_err(|e| e.to_string())?;
+    let addr = listener.local_addr().map_err(|e| e.to_string())?;
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let stopped = stop.clone();
+    thread::spawn(move || {
+        let mut sampler = CpuSampler::default();
+        sampler.sample();
+        // One scraper at a time is plenty, and keeps the CPU load delta
+        // meaningful between scrapes.
+        for stream in listener.incoming() {
+            if stopped.load(Ordering::Relaxed) {
+                break;
+            }
+            if let Ok(stream) = stream {
+                serve(stream, &mut sampler);
+            }
+        }
+    });
+
+    glib::g_debug!(crate::LOG_DOMAIN, "Metrics exporter listening on {}", addr);
+    if let Ok(mut server) = SERVER.lock() {
+        *server = Some(Server { addr, stop });
+    }
+    Ok(())
+}
+
+/// Stops the exporter, if running.
+pub fn stop() {
+    let Some(server) = SERVER.lock().ok().and_then(|mut server| server.take()) else {
+        return;
+    };
+    server.stop.store(true, Ordering::Relaxed);
+    // Wake the accept loop so it notices.
+    let _ = TcpStream::connect_timeout(&server.addr, CLIENT_TIMEOUT);
+}
+
+/// Starts the exporter if the config enables it.
+pub fn start_if_enabled() -> Result<(), String> {
+    let config = Config::load().metrics;
+    if config.enabled {
+        start(&config)
+    } else {
+        Ok(())
+    }
+}
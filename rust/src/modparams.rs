@@ -0,0 +1,163 @@
+use crate::immutable;
+use crate::remote;
+
+/// modprobe options written by the helper's `module-param` command.
+const MODPROBE_CONF_PATH: &str = "/etc/modprobe.d/90-tuxtuner.conf";
+
+/// A power-relevant kernel module parameter offered in the editor. Each
+/// `choices` value is spelled the way sysfs prints it back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModuleParam {
+    pub module: &'static str,
+    pub name: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    pub choices: &'static [(&'static str, &'static str)],
+    /// Built into the kernel, so modprobe.d never reaches it and a value
+    /// lasts until reboot. The first choice is the kernel default.
+    pub built_in: bool,
+}
+
+/// Keep in sync with the helper's `MODULE_PARAMS` and
+/// `BUILTIN_MODULE_PARAMS`.
+pub const PARAMS: &[ModuleParam] = &[
+    ModuleParam {
+        module: "iwlwifi",
+        name: "power_save",
+        title: "Intel Wi-Fi Power Saving",
+        description: "Lets the card doze between beacons; can add latency on some access points",
+        choices: &[("N", "Off"), ("Y", "On")],
+        built_in: false,
+    },
+    ModuleParam {
+        module: "iwlmvm",
+        name: "power_scheme",
+        title: "Intel Wi-Fi Power Scheme",
+        description: "How aggressively the firmware saves power while connected",
+        choices: &[("1", "Active"), ("2", "Balanced"), ("3", "Low Power")],
+        built_in: false,
+    },
+    ModuleParam {
+        module: "nvme_core",
+        name: "default_ps_max_latency_us",
+        title: "NVMe Power State Latency",
+        description: "Deepest SSD sleep state allowed; 0 works around drives that vanish after idling",
+        choices: &[("0", "Never Sleep"), ("5500", "5.5 ms"), ("25000", "25 ms"), ("100000", "100 ms")],
+        built_in: false,
+    },
+    ModuleParam {
+        module: "i915",
+        name: "enable_psr",
+        title: "Intel Panel Self Refresh",
+        description: "The panel refreshes from its own memory while the image is static",
+        choices: &[("-1", "Automatic"), ("0", "Off"), ("1", "PSR1"), ("2", "PSR2")],
+        built_in: false,
+    },
+    ModuleParam {
+        module: "amdgpu",
+        name: "dcdebugmask",
+        title: "AMD Panel Self Refresh",
+        description: "Display core debug flags; 16 turns Panel Self Refresh off",
+        choices: &[("0", "Automatic"), ("16", "Off")],
+        built_in: false,
+    },
+    ModuleParam {
+        module: "i915",
+        name: "enable_fbc",
+        title: "Intel Framebuffer Compression",
+        description: "Compresses the framebuffer to cut memory traffic",
+        choices: &[("-1", "Automatic"), ("0", "Off"), ("1", "On")],
+        built_in: false,
+    },
+    ModuleParam {
+        module: "snd_hda_intel",
+        name: "power_save",
+        title: "HD Audio Power Saving",
+        description: "Seconds of silence before the codec powers down; some speakers pop when it wakes",
+        choices: &[("0", "Off"), ("1", "1 s"), ("10", "10 s")],
+        built_in: false,
+    },
+    ModuleParam {
+        module: "pcie_aspm",
+        name: "policy",
+        title: "PCIe Active State Power Management",
+        description: "Link power saving for every PCIe device",
+        choices: &[
+            ("default", "Firmware Default"),
+            ("performance", "Performance"),
+            ("powersave", "Power Save"),
+            ("powersupersave", "Power Super Save"),
+        ],
+        built_in: true,
+    },
+];
+
+impl ModuleParam {
+    /// Value in force now, or `None` when the module isn't loaded.
+    pub fn current(&self) -> Option<String> {
+        let value = remote::read_to_string(format!("/sys/module/{}/parameters/{}", self.module, self.name)).ok()?;
+        let value = value.trim();
+        // Choice parameters print every option with the active one in brackets.
+        match value.split_once('[').and_then(|(_, rest)| rest.split_once(']')) {
+            Some((active, _)) => Some(active.to_string()),
+            None => Some(value.to_string()),
+        }
+    }
+
+    /// Label of `value`, or the raw value if it isn't one of `choices`.
+    pub fn label_for(&self, value: &str) -> String {
+        self.choices
+            .iter()
+            .find(|(choice, _)| *choice == value)
+            .map(|(_, label)| label.to_string())
+            .unwrap_or_else(|| value.to_string())
+    }
+}
+
+/// The Panel Self Refresh parameter of the loaded display driver and the
+/// value that turns PSR off. Turning it off is the usual fix for a
+/// flickering or stuttering panel.
+pub fn psr_control() -> Option<(ModuleParam, &'static str)> {
+    [("i915", "enable_psr", "0"), ("amdgpu", "dcdebugmask", "16")]
+        .into_iter()
+        .find_map(|(module, name, off)| {
+            let param = PARAMS.iter().find(|p| p.module == module && p.name == name)?;
+            param.current().map(|_| (*param, off))
+        })
+}
+
+/// The value saved for `param` in TuxTuner's modprobe.d file, if any.
+/// Built-in parameters can't be saved, so theirs is the value in force.
+pub fn saved(param: &ModuleParam) -> Option<String> {
+    if param.built_in {
+        return param.current();
+    }
+    remote::read_to_string(immutable::persisted_path(MODPROBE_CONF_PATH))
+        .unwrap_or_default()
+        .lines()
+        .find_map(|line| {
+            let mut words = line.split_whitespace();
+            if words.next() != Some("options") || words.next() != Some(param.module) {
+                return None;
+            }
+            let (name, value) = words.next()?.split_once('=')?;
+            (name == param.name).then(|| value.to_string())
+        })
+}
+
+/// Saves `value` for the next module load, or forgets the saved value
+/// for `None`. Returns whether the running kernel took the value too;
+/// many parameters are only read when the module loads. Built-in
+/// parameters are set until reboot, `None` putting back the kernel
+/// default. Blocking.
+pub fn set(param: &ModuleParam, value: Option<&str>) -> Result<bool, String> {
+    let value = match param.choices.first() {
+        Some((default, _)) if param.built_in => Some(value.unwrap_or(default)),
+        _ => value,
+    };
+    match value {
+        Some(value) => remote::run_helper(&["module-param", param.module, param.name, value])?,
+        None => remote::run_helper(&["module-param-reset", param.module, param.name])?,
+    }
+    Ok(value.is_some() && param.current().as_deref() == value)
+}
@@ -0,0 +1,374 @@
+use crate::battery;
+use crate::config::Config;
+use crate::corepark::CpuSampler;
+use crate::power::{self, PowerSource};
+use crate::profiles;
+use crate::thermal;
+use gtk4::glib;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Seconds the broker waits without hearing from us before it drops us
+/// and publishes the offline will.
+const KEEP_ALIVE_SECS: u16 = 60;
+const STATE_INTERVAL: Duration = Duration::from_secs(15);
+const RECONNECT_DELAY: Duration = Duration::from_secs(30);
+/// Read timeout, i.e. how often the loop wakes up to publish.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Largest packet accepted from the broker. Profile commands are a few
+/// bytes; MQTT allows 256 MiB, which a bad broker shouldn't make us
+/// allocate.
+const MAX_PACKET_BYTES: usize = 64 * 1024;
+
+/// Home Assistant integration over MQTT, off by default. Plain TCP only;
+/// keep the broker on a trusted network.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MqttConfig {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub discovery_prefix: String,
+    /// Identifies this machine in topics; defaults to the hostname.
+    pub node_id: Option<String>,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "localhost".to_string(),
+            port: 1883,
+            username: None,
+            password: None,
+            discovery_prefix: "homeassistant".to_string(),
+            node_id: None,
+        }
+    }
+}
+
+fn hostname() -> String {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|name| name.trim().to_string())
+        .unwrap_or_default()
+}
+
+impl MqttConfig {
+    fn node_id(&self) -> String {
+        let raw = self.node_id.clone().unwrap_or_else(hostname);
+        let id: String = raw
+            .to_lowercase()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        if id.is_empty() {
+            "tuxtuner".to_string()
+        } else {
+            id
+        }
+    }
+}
+
+/// Topics of one machine.
+struct Topics {
+    base: String,
+    discovery_prefix: String,
+    node: String,
+}
+
+impl Topics {
+    fn state(&self) -> String {
+        format!("{}/state", self.base)
+    }
+
+    fn availability(&self) -> String {
+        format!("{}/availability", self.base)
+    }
+
+    fn profile_command(&self) -> String {
+        format!("{}/profile/set", self.base)
+    }
+
+    fn discovery(&self, component: &str, key: &str) -> String {
+        format!("{}/{}/{}/{}/config", self.discovery_prefix, component, self.node, key)
+    }
+}
+
+fn encode_length(mut length: usize, out: &mut Vec<u8>) {
+    loop {
+        let mut byte = (length % 128) as u8;
+        length /= 128;
+        if length > 0 {
+            byte |= 0x80;
+        }
+        out.push(byte);
+        if length == 0 {
+            break;
+        }
+    }
+}
+
+fn encode_str(value: &str, out: &mut Vec<u8>) {
+    out.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    out.extend_from_slice(value.as_bytes());
+}
+
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut out = vec![header];
+    encode_length(body.len(), &mut out);
+    out.extend_from_slice(body);
+    out
+}
+
+/// Minimal MQTT 3.1.1 client: QoS 0 only, which is all state updates and
+/// profile commands need.
+struct Client {
+    stream: TcpStream,
+}
+
+impl Client {
+    fn connect(config: &MqttConfig, client_id: &str, will_topic: &str) -> io::Result<Self> {
+        let mut stream = TcpStream::connect((config.host.as_str(), config.port))?;
+
+        // Clean session, plus a retained "offline" will.
+        let mut flags = 0x02 | 0x04 | 0x20;
+        if config.username.is_some() {
+            flags |= 0x80;
+        }
+        if config.password.is_some() {
+            flags |= 0x40;
+        }
+
+        let mut body = Vec::new();
+        encode_str("MQTT", &mut body);
+        body.push(4);
+        body.push(flags);
+        body.extend_from_slice(&KEEP_ALIVE_SECS.to_be_bytes());
+        encode_str(client_id, &mut body);
+        encode_str(will_topic, &mut body);
+        encode_str("offline", &mut body);
+        if let Some(username) = &config.username {
+            encode_str(username, &mut body);
+        }
+        if let Some(password) = &config.password {
+            encode_str(password, &mut body);
+        }
+        stream.write_all(&packet(0x10, &body))?;
+
+        let mut connack = [0u8; 4];
+        stream.read_exact(&mut connack)?;
+        if connack[0] != 0x20 || connack[3] != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                format!("broker refused the connection (code {})", connack[3]),
+            ));
+        }
+
+        stream.set_read_timeout(Some(POLL_INTERVAL))?;
+        Ok(Self { stream })
+    }
+
+    fn publish(&mut self, topic: &str, payload: &str, retain: bool) -> io::Result<()> {
+        let mut body = Vec::new();
+        encode_str(topic, &mut body);
+        body.extend_from_slice(payload.as_bytes());
+        self.stream.write_all(&packet(0x30 | retain as u8, &body))
+    }
+
+    fn subscribe(&mut self, topic: &str) -> io::Result<()> {
+        let mut body = vec![0, 1];
+        encode_str(topic, &mut body);
+        body.push(0);
+        self.stream.write_all(&packet(0x82, &body))
+    }
+
+    fn ping(&mut self) -> io::Result<()> {
+        self.stream.write_all(&[0xc0, 0])
+    }
+
+    /// Waits up to `POLL_INTERVAL` for a packet and returns the topic and
+    /// payload of incoming publishes.
+    fn poll(&mut self) -> io::Result<Option<(String, String)>> {
+        let mut header = [0u8; 1];
+        match self.stream.read(&mut header) {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(_) => {}
+            Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {
+                return Ok(None)
+            }
+            Err(e) => return Err(e),
+        }
+
+        let mut length = 0usize;
+        for shift in (0..4).map(|i| i * 7) {
+            let mut byte = [0u8; 1];
+            self.stream.read_exact(&mut byte)?;
+            length |= ((byte[0] & 0x7f) as usize) << shift;
+            if byte[0] & 0x80 == 0 {
+                break;
+            }
+        }
+        if length > MAX_PACKET_BYTES {
+            // The rest of the packet is still unread, so the stream can't
+            // be resynchronized; reconnect instead.
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("MQTT packet of {} bytes exceeds {}", length, MAX_PACKET_BYTES),
+            ));
+        }
+        let mut body = vec![0u8; length];
+        self.stream.read_exact(&mut body)?;
+
+        // SUBACK, PINGRESP and the like need no handling.
+        if header[0] >> 4 != 3 || body.len() < 2 {
+            return Ok(None);
+        }
+        let topic_len = u16::from_be_bytes([body[0], body[1]]) as usize;
+        let Some(topic) = body.get(2..2 + topic_len) else {
+            return Ok(None);
+        };
+        // QoS 1/2 publishes carry a packet ID we don't acknowledge; we only
+        // subscribe with QoS 0, so the broker never sends them.
+        let payload = &body[2 + topic_len..];
+        Ok(Some((
+            String::from_utf8_lossy(topic).to_string(),
+            String::from_utf8_lossy(payload).trim().to_string(),
+        )))
+    }
+}
+
+fn state_payload(sampler: &mut CpuSampler) -> String {
+    let power_source = match power::power_source() {
+        PowerSource::Ac => "ac",
+        PowerSource::Battery => "battery",
+        PowerSource::Unknown => "unknown",
+    };
+    json!({
+        "cpu_temp": thermal::cpu_temperature().map(|t| (t * 10.0).round() / 10.0),
+        "cpu_load": sampler.sample().map(|load| (load * 1000.0).round() / 10.0),
+        "battery": battery::capacity_percent(),
+        "power_w": battery::power_draw_mw().map(|mw| mw as f64 / 1000.0),
+        "power_source": power_source,
+        "profile": Config::load().active_profile,
+    })
+    .to_string()
+}
+
+/// Announces every entity to Home Assistant's MQTT discovery.
+fn publish_discovery(client: &mut Client, topics: &Topics) -> io::Result<()> {
+    let device = json!({
+        "identifiers": [format!("tuxtuner_{}", topics.node)],
+        "name": format!("TuxTuner {}", topics.node),
+        "manufacturer": "TuxTuner",
+    });
+    let sensors = [
+        ("cpu_temp", "CPU Temperature", Some("°C"), Some("temperature")),
+        ("cpu_load", "CPU Load", Some("%"), None),
+        ("battery", "Battery", Some("%"), Some("battery")),
+        ("power_w", "Power Draw", Some("W"), Some("power")),
+        ("power_source", "Power Source", None, None),
+    ];
+
+    for (key, name, unit, class) in sensors {
+        let mut config = json!({
+            "name": name,
+            "unique_id": format!("tuxtuner_{}_{}", topics.node, key),
+            "state_topic": topics.state(),
+            "value_template": format!("{{{{ value_json.{} }}}}", key),
+            "availability_topic": topics.availability(),
+            "device": device,
+        });
+        // Home Assistant rejects explicit nulls here.
+        if let Some(unit) = unit {
+            config["unit_of_measurement"] = unit.into();
+            config["state_class"] = "measurement".into();
+        }
+        if let Some(class) = class {
+            config["device_class"] = class.into();
+        }
+        client.publish(&topics.discovery("sensor", key), &config.to_string(), true)?;
+    }
+
+    let options: Vec<String> = profiles::all_profiles(&Config::load())
+        .into_iter()
+        .map(|profile| profile.name)
+        .collect();
+    let select = json!({
+        "name": "Profile",
+        "unique_id": format!("tuxtuner_{}_profile", topics.node),
+        "state_topic": topics.state(),
+        "value_template": "{{ value_json.profile }}",
+        "command_topic": topics.profile_command(),
+        "options": options,
+        "availability_topic": topics.availability(),
+        "device": device,
+    });
+    client.publish(&topics.discovery("select", "profile"), &select.to_string(), true)
+}
+
+fn run_session(config: &MqttConfig, topics: &Topics, commands: &Sender<String>) -> io::Result<()> {
+    let mut client = Client::connect(config, &format!("tuxtuner-{}", topics.node), &topics.availability())?;
+    glib::g_debug!(crate::LOG_DOMAIN, "Connected to MQTT broker {}:{}", config.host, config.port);
+
+    publish_discovery(&mut client, topics)?;
+    client.publish(&topics.availability(), "online", true)?;
+    client.subscribe(&topics.profile_command())?;
+
+    let mut sampler = CpuSampler::default();
+    sampler.sample();
+    let mut last_state: Option<Instant> = None;
+    let mut last_sent = Instant::now();
+
+    loop {
+        if last_state.is_none_or(|at| at.elapsed() >= STATE_INTERVAL) {
+            client.publish(&topics.state(), &state_payload(&mut sampler), false)?;
+            last_state = Some(Instant::now());
+            last_sent = Instant::now();
+        }
+        if last_sent.elapsed() >= Duration::from_secs(KEEP_ALIVE_SECS as u64 / 2) {
+            client.ping()?;
+            last_sent = Instant::now();
+        }
+
+        if let Some((topic, payload)) = client.poll()? {
+            if topic == topics.profile_command() && commands.send(payload).is_err() {
+                // The app is gone.
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Connects to the broker on a background thread, reconnecting whenever
+/// the connection drops. Profile names requested from Home Assistant
+/// arrive on the returned channel.
+pub fn start(config: MqttConfig) -> Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let node = config.node_id();
+        let topics = Topics {
+            base: format!("tuxtuner/{}", node),
+            discovery_prefix: config.discovery_prefix.clone(),
+            node,
+        };
+
+        loop {
+            match run_session(&config, &topics, &sender) {
+                Ok(()) => break,
+                Err(e) => glib::g_debug!(crate::LOG_DOMAIN, "MQTT connection lost: {}", e),
+            }
+            thread::sleep(RECONNECT_DELAY);
+        }
+    });
+
+    receiver
+}
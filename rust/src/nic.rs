@@ -0,0 +1,118 @@
+use crate::probe;
+use crate::remote;
+use std::path::Path;
+
+const NET_PATH: &str = "/sys/class/net";
+
+/// A wired network interface and the settings that affect standby power.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetInterface {
+    pub name: String,
+    /// Negotiated speed in Mb/s; `None` without a link.
+    pub speed_mbps: Option<u32>,
+    /// Whether the card can wake the machine on a magic packet.
+    pub wol_supported: bool,
+    pub wol_enabled: bool,
+    /// Energy-Efficient Ethernet state; `None` if the card or driver
+    /// doesn't support it, or ethtool is missing.
+    pub eee: Option<bool>,
+}
+
+impl NetInterface {
+    pub fn link_label(&self) -> String {
+        match self.speed_mbps {
+            Some(mbps) if mbps >= 1000 && mbps % 1000 == 0 => format!("{} Gb/s", mbps / 1000),
+            Some(mbps) if mbps >= 1000 => format!("{:.1} Gb/s", mbps as f64 / 1000.0),
+            Some(mbps) => format!("{} Mb/s", mbps),
+            None => "No link".to_string(),
+        }
+    }
+}
+
+fn read_attr(dir: &Path, attr: &str) -> Option<String> {
+    remote::read_to_string(dir.join(attr))
+        .ok()
+        .map(|value| value.trim().to_string())
+}
+
+/// Parses `ethtool <iface>` for the supported and active Wake-on modes,
+/// e.g. `Supports Wake-on: pumbg` and `Wake-on: d`. Magic packet is `g`.
+fn wake_on_lan(name: &str) -> (bool, bool) {
+    let Ok(output) = probe::run("ethtool", &[name]) else {
+        return (false, false);
+    };
+    let text = String::from_utf8_lossy(&output.stdout);
+
+    let mut supported = false;
+    let mut enabled = false;
+    for line in text.lines().map(str::trim) {
+        if let Some(modes) = line.strip_prefix("Supports Wake-on:") {
+            supported = modes.contains('g');
+        } else if let Some(modes) = line.strip_prefix("Wake-on:") {
+            enabled = modes.contains('g');
+        }
+    }
+    (supported, enabled)
+}
+
+/// Parses `ethtool --show-eee`, e.g. `EEE status: enabled - active`.
+fn eee(name: &str) -> Option<bool> {
+    let output = probe::run("ethtool", &["--show-eee", name]).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    let status = text
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("EEE status:"))?
+        .trim();
+
+    if status.starts_with("enabled") {
+        Some(true)
+    } else if status.starts_with("disabled") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Physical wired interfaces, sorted by name. Virtual interfaces (no
+/// `device` link) and Wi-Fi cards are left out.
+pub fn interfaces() -> Vec<NetInterface> {
+    let mut dirs: Vec<_> = remote::read_dir(NET_PATH)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|dir| remote::exists(dir.join("device")) && !remote::exists(dir.join("wireless")))
+        .collect();
+    dirs.sort();
+
+    dirs.into_iter()
+        .filter_map(|dir| {
+            let name = dir.file_name()?.to_string_lossy().to_string();
+            // speed reads -1 (or fails) while the link is down.
+            let speed_mbps = (read_attr(&dir, "operstate").as_deref() == Some("up"))
+                .then(|| read_attr(&dir, "speed")?.parse::<i64>().ok())
+                .flatten()
+                .filter(|&mbps| mbps > 0)
+                .map(|mbps| mbps as u32);
+            let (wol_supported, wol_enabled) = wake_on_lan(&name);
+            Some(NetInterface {
+                eee: eee(&name),
+                name,
+                speed_mbps,
+                wol_supported,
+                wol_enabled,
+            })
+        })
+        .collect()
+}
+
+/// Turns wake on magic packet on or off; the helper keeps the choice
+/// across reboots.
+pub fn apply_wol(name: &str, enabled: bool) -> Result<(), String> {
+    remote::run_helper(&["nic-wol", name, if enabled { "g" } else { "d" }])
+}
+
+pub fn apply_eee(name: &str, enabled: bool) -> Result<(), String> {
+    remote::run_helper(&["nic-eee", name, if enabled { "on" } else { "off" }])
+}
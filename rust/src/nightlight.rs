@@ -0,0 +1,130 @@
+use crate::session::{self, Desktop};
+use crate::system_info::command_exists;
+use std::process::{Child, Command, Stdio};
+
+pub const MIN_TEMPERATURE: u32 = 1000;
+pub const MAX_TEMPERATURE: u32 = 6500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Hyprsunset,
+    Wlsunset,
+    Gammastep,
+}
+
+impl Backend {
+    fn binary(self) -> &'static str {
+        match self {
+            Backend::Hyprsunset => "hyprsunset",
+            Backend::Wlsunset => "wlsunset",
+            Backend::Gammastep => "gammastep",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        self.binary()
+    }
+
+    fn args(self, temperature: u32) -> Vec<String> {
+        match self {
+            Backend::Hyprsunset => vec!["-t".into(), temperature.to_string()],
+            // wlsunset insists on its own day/night cycle; a night from
+            // 00:00 to 23:59 with an instant transition keeps it constant.
+            // Scheduling is handled by TuxTuner instead.
+            Backend::Wlsunset => vec![
+                "-t".into(),
+                temperature.to_string(),
+                "-T".into(),
+                (temperature + 1).to_string(),
+                "-s".into(),
+                "00:00".into(),
+                "-S".into(),
+                "23:59".into(),
+                "-d".into(),
+                "1".into(),
+            ],
+            Backend::Gammastep => vec!["-P".into(), "-O".into(), temperature.to_string()],
+        }
+    }
+}
+
+/// Owns the blue-light filter process. Only one filter runs at a time;
+/// the window stops it when the application shuts down. Instances it
+/// didn't start, e.g. from the user's autostart, are left running.
+#[derive(Debug)]
+pub struct NightLight {
+    backend: Option<Backend>,
+    child: Option<Child>,
+    temperature: Option<u32>,
+    /// The temperature the filter last failed to start at; not retried
+    /// until a different one is asked for.
+    failed: Option<u32>,
+}
+
+impl NightLight {
+    /// Picks the first available filter, preferring the compositor-native one.
+    pub fn detect() -> Self {
+        let hyprland = session::current().desktop == Desktop::Hyprland;
+        let mut candidates = vec![Backend::Wlsunset, Backend::Gammastep];
+        if hyprland {
+            candidates.insert(0, Backend::Hyprsunset);
+        }
+
+        Self {
+            backend: candidates.into_iter().find(|b| command_exists(b.binary())),
+            child: None,
+            temperature: None,
+            failed: None,
+        }
+    }
+
+    pub fn backend(&self) -> Option<Backend> {
+        self.backend
+    }
+
+    /// Starts the filter at `temperature` (Kelvin), or stops it on `None`.
+    /// A start that failed before is only reported once.
+    pub fn set(&mut self, temperature: Option<u32>) -> Result<(), String> {
+        let temperature = temperature.map(|t| t.clamp(MIN_TEMPERATURE, MAX_TEMPERATURE));
+        if temperature == self.temperature || (temperature.is_some() && temperature == self.failed) {
+            return Ok(());
+        }
+
+        self.stop();
+        self.failed = None;
+
+        let Some(temperature) = temperature else {
+            return Ok(());
+        };
+        let backend = self.backend.ok_or("No night light tool found")?;
+
+        let child = Command::new(backend.binary())
+            .args(backend.args(temperature))
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| {
+                self.failed = Some(temperature);
+                e.to_string()
+            })?;
+
+        self.child = Some(child);
+        self.temperature = Some(temperature);
+        Ok(())
+    }
+
+    /// Ends the filter this controller started.
+    pub fn stop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        self.temperature = None;
+    }
+}
+
+impl Drop for NightLight {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,54 @@
+use once_cell::sync::Lazy;
+use std::fs;
+
+const CHASSIS_TYPE_PATH: &str = "/sys/class/dmi/id/chassis_type";
+const POWER_SUPPLY_PATH: &str = "/sys/class/power_supply";
+
+/// SMBIOS chassis types of machines without a battery: desktops, towers,
+/// all-in-ones, mini PCs and servers.
+const DESKTOP_CHASSIS: [u32; 14] = [3, 4, 5, 6, 7, 13, 15, 16, 17, 23, 24, 28, 35, 36];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormFactor {
+    Laptop,
+    Desktop,
+}
+
+impl FormFactor {
+    pub fn is_desktop(self) -> bool {
+        self == FormFactor::Desktop
+    }
+}
+
+fn has_battery() -> bool {
+    fs::read_dir(POWER_SUPPLY_PATH)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .any(|supply| {
+            fs::read_to_string(supply.path().join("type")).is_ok_and(|kind| kind.trim() == "Battery")
+        })
+}
+
+fn detect() -> FormFactor {
+    let chassis = fs::read_to_string(CHASSIS_TYPE_PATH)
+        .ok()
+        .and_then(|chassis| chassis.trim().parse::<u32>().ok());
+
+    match chassis {
+        Some(chassis) if DESKTOP_CHASSIS.contains(&chassis) => FormFactor::Desktop,
+        Some(_) => FormFactor::Laptop,
+        // No DMI (some ARM boards, VMs): a battery is the next best hint.
+        None if has_battery() => FormFactor::Laptop,
+        None => FormFactor::Desktop,
+    }
+}
+
+static FORM_FACTOR: Lazy<FormFactor> = Lazy::new(detect);
+
+/// The machine's form factor, from the DMI chassis type. Laptop-only
+/// features (battery, lid) are hidden on desktops, which get discrete GPU
+/// controls instead.
+pub fn form_factor() -> FormFactor {
+    *FORM_FACTOR
+}
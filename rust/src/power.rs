@@ -0,0 +1,150 @@
+use gtk4::glib;
+use crate::battery;
+use crate::remote;
+use crate::ups;
+use std::path::Path;
+
+const POWER_SUPPLY_PATH: &str = "/sys/class/power_supply";
+const POLL_SECONDS: u32 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PowerSource {
+    Ac,
+    Battery,
+    #[default]
+    Unknown,
+}
+
+fn read_attr(dir: &Path, attr: &str) -> Option<String> {
+    remote::read_to_string(dir.join(attr))
+        .ok()
+        .map(|value| value.trim().to_string())
+}
+
+/// Reports whether the machine currently runs from mains or battery,
+/// counting a UPS on battery as battery power.
+pub fn power_source() -> PowerSource {
+    let Ok(entries) = remote::read_dir(POWER_SUPPLY_PATH) else {
+        return PowerSource::Unknown;
+    };
+
+    let mut has_battery = false;
+    for path in entries {
+        match read_attr(&path, "type").as_deref() {
+            Some("Mains") | Some("USB") if read_attr(&path, "online").as_deref() == Some("1") => {
+                return PowerSource::Ac;
+            }
+            // Peripheral batteries (mice, headsets) report scope=Device.
+            Some("Battery") if read_attr(&path, "scope").as_deref() != Some("Device") => {
+                has_battery = true;
+            }
+            _ => {}
+        }
+    }
+
+    if has_battery {
+        return PowerSource::Battery;
+    }
+    // Desktops have no battery of their own but may sit on a UPS.
+    match ups::on_battery() {
+        Some(true) => PowerSource::Battery,
+        Some(false) => PowerSource::Ac,
+        None => PowerSource::Unknown,
+    }
+}
+
+/// Calls `callback` with the current power source, then again on the main
+/// loop whenever it changes.
+pub fn watch_power_source<F: Fn(PowerSource) + 'static>(callback: F) {
+    let mut last = power_source();
+    callback(last);
+
+    glib::timeout_add_seconds_local(POLL_SECONDS, move || {
+        let current = power_source();
+        if current != last {
+            last = current;
+            callback(current);
+        }
+        glib::ControlFlow::Continue
+    });
+}
+
+/// Calls `callback` with the battery level the first time it drops to
+/// `threshold()` percent while on battery. It fires again only after the
+/// machine has been plugged in or the battery has recovered; `threshold`
+/// returns `None` while the check is switched off.
+pub fn watch_critical_battery<T, F>(threshold: T, callback: F)
+where
+    T: Fn() -> Option<u32> + 'static,
+    F: Fn(u32) + 'static,
+{
+    let mut fired = false;
+    glib::timeout_add_seconds_local(POLL_SECONDS, move || {
+        let critical = match (threshold(), power_source(), battery::capacity_percent()) {
+            (Some(threshold), PowerSource::Battery, Some(capacity)) if capacity <= threshold => Some(capacity),
+            _ => None,
+        };
+        match critical {
+            Some(capacity) if !fired => {
+                fired = true;
+                callback(capacity);
+            }
+            Some(_) => {}
+            None => fired = false,
+        }
+        glib::ControlFlow::Continue
+    });
+}
+
+/// Below this a USB-C charger can't feed a laptop running flat out.
+const PERFORMANCE_MIN_WATTS: f64 = 60.0;
+/// Battery drain, in milliwatts, that counts as discharging on AC rather
+/// than measurement noise.
+const DRAIN_THRESHOLD_MW: i32 = 1000;
+
+/// A connected charger and what it negotiated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Charger {
+    /// Whether the link uses USB Power Delivery.
+    pub usb_pd: bool,
+    pub volts: Option<f64>,
+    pub watts: Option<f64>,
+}
+
+impl Charger {
+    /// Whether this charger can't keep up with Performance mode: the
+    /// battery drains while plugged in, or the negotiated power is too low.
+    pub fn underpowered(&self) -> bool {
+        battery::power_draw_mw().is_some_and(|mw| mw > DRAIN_THRESHOLD_MW)
+            || self.watts.is_some_and(|watts| watts < PERFORMANCE_MIN_WATTS)
+    }
+}
+
+/// The online charger, preferring one that reports its power. USB-C
+/// ports report the negotiated contract through UCSI as `voltage_max`
+/// and `current_max` (µV, µA); some also give live readings.
+pub fn charger() -> Option<Charger> {
+    let read_number = |dir: &Path, attr: &str| -> Option<f64> {
+        read_attr(dir, attr)?.parse::<f64>().ok().filter(|value| *value > 0.0)
+    };
+
+    remote::read_dir(POWER_SUPPLY_PATH)
+        .ok()?
+        .into_iter()
+        .filter(|path| {
+            matches!(read_attr(path, "type").as_deref(), Some("Mains") | Some("USB"))
+                && read_attr(path, "online").as_deref() == Some("1")
+        })
+        .map(|path| {
+            let volts = read_number(&path, "voltage_now").or_else(|| read_number(&path, "voltage_max"));
+            let amps = read_number(&path, "current_now").or_else(|| read_number(&path, "current_max"));
+            // The active entry of usb_type is in brackets, e.g. "[PD] PD_PPS".
+            let usb_pd = read_attr(&path, "usb_type").is_some_and(|types| types.contains("[PD"));
+            Charger {
+                usb_pd,
+                volts: volts.map(|uv| uv / 1e6),
+                watts: volts.zip(amps).map(|(uv, ua)| uv * ua / 1e12),
+            }
+        })
+        .max_by_key(|charger| charger.watts.is_some())
+}
@@ -0,0 +1,152 @@
+use crate::config::Config;
+use crate::remote;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+const USB_DEVICES_PATH: &str = "/sys/bus/usb/devices";
+/// USB interface classes worth a privacy switch.
+const CLASS_AUDIO: &str = "01";
+const CLASS_VIDEO: &str = "0e";
+
+/// A USB camera or microphone that can be cut off by de-authorizing it,
+/// which unbinds its drivers until it's authorized again.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrivacyDevice {
+    /// USB port, e.g. `3-4`.
+    pub id: String,
+    pub description: String,
+    pub camera: bool,
+    pub microphone: bool,
+    #[serde(skip)]
+    pub blocked: bool,
+}
+
+impl PrivacyDevice {
+    pub fn kind_label(&self) -> &'static str {
+        match (self.camera, self.microphone) {
+            (true, true) => "Camera and microphone",
+            (true, false) => "Camera",
+            _ => "Microphone",
+        }
+    }
+}
+
+fn read_attr(dir: &Path, attr: &str) -> Option<String> {
+    fs::read_to_string(dir.join(attr))
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn describe(path: &Path) -> String {
+    match (read_attr(path, "manufacturer"), read_attr(path, "product")) {
+        (Some(vendor), Some(product)) => format!("{} {}", vendor, product),
+        (None, Some(product)) => product,
+        _ => format!(
+            "USB device {}:{}",
+            read_attr(path, "idVendor").unwrap_or_default(),
+            read_attr(path, "idProduct").unwrap_or_default()
+        ),
+    }
+}
+
+/// Interface classes of an authorized USB device, from its `1-2:1.0`
+/// style interface directories.
+fn interface_classes(id: &str) -> Vec<String> {
+    fs::read_dir(USB_DEVICES_PATH)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .strip_prefix(id)
+                .is_some_and(|rest| rest.starts_with(':'))
+        })
+        .filter_map(|entry| read_attr(&entry.path(), "bInterfaceClass"))
+        .collect()
+}
+
+/// Cameras and microphones on USB, plus the ones TuxTuner blocked: those
+/// lose their interfaces while blocked, so they're remembered in the
+/// config. Built-in microphones on the audio codec aren't USB and can't
+/// be listed. Empty in remote mode.
+pub fn devices() -> Vec<PrivacyDevice> {
+    if remote::host().is_some() {
+        return Vec::new();
+    }
+
+    let mut devices: Vec<PrivacyDevice> = fs::read_dir(USB_DEVICES_PATH)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let id = entry.file_name().to_string_lossy().to_string();
+            // Leave out root hubs and interfaces.
+            if !id.contains('-') || id.contains(':') {
+                return None;
+            }
+            let path = entry.path();
+            if read_attr(&path, "authorized").as_deref() != Some("1") {
+                return None;
+            }
+            let classes = interface_classes(&id);
+            let camera = classes.iter().any(|class| class == CLASS_VIDEO);
+            let microphone = classes.iter().any(|class| class == CLASS_AUDIO);
+            (camera || microphone).then(|| PrivacyDevice {
+                description: describe(&path),
+                id,
+                camera,
+                microphone,
+                blocked: false,
+            })
+        })
+        .collect();
+
+    for remembered in Config::load().privacy_blocked {
+        let path = Path::new(USB_DEVICES_PATH).join(&remembered.id);
+        if read_attr(&path, "authorized").as_deref() == Some("0") {
+            devices.push(PrivacyDevice {
+                blocked: true,
+                ..remembered
+            });
+        }
+    }
+
+    devices.sort_by(|a, b| a.id.cmp(&b.id));
+    devices
+}
+
+/// The helper command blocking or unblocking `device`.
+pub fn helper_args(device: &PrivacyDevice, blocked: bool) -> Vec<&str> {
+    vec!["usb-authorize", &device.id, if blocked { "0" } else { "1" }]
+}
+
+/// Remembers `device` while it's blocked, so it stays listed.
+pub fn remember(device: &PrivacyDevice, blocked: bool) -> Result<(), String> {
+    let mut config = Config::load();
+    config.privacy_blocked.retain(|remembered| remembered.id != device.id);
+    if blocked {
+        config.privacy_blocked.push(device.clone());
+    }
+    config.save()
+}
+
+/// Blocks or unblocks one device and remembers it while blocked.
+pub fn set_blocked(device: &PrivacyDevice, blocked: bool) -> Result<(), String> {
+    remote::run_helper(&helper_args(device, blocked))?;
+    remember(device, blocked)
+}
+
+/// The cameras and microphones that blocking (or unblocking) them all
+/// would change. Devices blocked by other tools (e.g. USBGuard) are left
+/// alone.
+pub fn pending_changes(blocked: bool) -> Vec<PrivacyDevice> {
+    devices()
+        .into_iter()
+        .filter(|device| device.blocked != blocked)
+        .collect()
+}
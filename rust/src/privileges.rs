@@ -0,0 +1,130 @@
+use crate::probe;
+use crate::remote;
+use crate::system_info::{command_exists, HELPER_PATH};
+use gtk4::{gio, glib};
+use gtk4::prelude::*;
+use std::collections::HashMap;
+use std::path::Path;
+
+pub const POLKIT_POLICY: &str = "/usr/share/polkit-1/actions/com.github.xavrir.tuxtuner.policy";
+const POLKIT_ACTION: &str = "com.github.xavrir.tuxtuner.helper";
+/// CheckAuthorization flag letting polkit show its password prompt.
+const ALLOW_USER_INTERACTION: u32 = 1;
+/// How long to wait for the user to answer the prompt.
+const PROMPT_TIMEOUT_MS: i32 = 5 * 60 * 1000;
+
+/// Version reported by the installed helper. Helpers predating the
+/// handshake don't understand `--version` and yield `None`.
+pub fn helper_version() -> Option<String> {
+    let output = probe::run(HELPER_PATH, &["--version"]).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!version.is_empty()).then_some(version)
+}
+
+/// Describes a helper left over from another release, which would reject
+/// or misinterpret subcommands this build relies on.
+pub fn helper_mismatch() -> Option<String> {
+    let expected = env!("CARGO_PKG_VERSION");
+    match helper_version() {
+        Some(version) if version == expected => None,
+        Some(version) => Some(format!(
+            "The installed helper is version {} but TuxTuner is {}; reinstall TuxTuner",
+            version, expected
+        )),
+        None => Some(format!(
+            "The installed helper is older than TuxTuner {}; reinstall TuxTuner",
+            expected
+        )),
+    }
+}
+
+/// Explains why privileged changes can't be made in this session, or
+/// returns `None` if pkexec should be able to authorize them.
+pub fn read_only_reason() -> Option<String> {
+    if let Some(host) = remote::host() {
+        return remote::read_only_reason(host).or_else(helper_mismatch);
+    }
+
+    if std::env::var_os("SSH_CONNECTION").is_some() || std::env::var_os("SSH_TTY").is_some() {
+        return Some("Remote sessions cannot authorize system changes".to_string());
+    }
+
+    if !command_exists("pkexec") {
+        return Some("pkexec is not installed".to_string());
+    }
+
+    if !Path::new(HELPER_PATH).is_file() {
+        return Some(format!("The TuxTuner helper is missing from {}", HELPER_PATH));
+    }
+
+    if let Some(mismatch) = helper_mismatch() {
+        return Some(mismatch);
+    }
+
+    if !Path::new(POLKIT_POLICY).is_file() {
+        return Some("The TuxTuner polkit policy is not installed".to_string());
+    }
+
+    // Without a graphical session there is no polkit agent to ask.
+    if std::env::var_os("WAYLAND_DISPLAY").is_none() && std::env::var_os("DISPLAY").is_none() {
+        return Some("No graphical session to show authentication prompts".to_string());
+    }
+
+    None
+}
+
+/// Start time of this process in clock ticks since boot, which polkit
+/// uses together with the pid to identify it.
+fn own_start_time() -> Option<u64> {
+    let stat = std::fs::read_to_string("/proc/self/stat").ok()?;
+    // Fields after the parenthesised command name start at field 3.
+    let (_, rest) = stat.rsplit_once(')')?;
+    rest.split_whitespace().nth(19)?.parse().ok()
+}
+
+/// Asks polkit once for the helper action on behalf of this process.
+/// The policy retains a granted authorization (`auth_admin_keep`), and
+/// pkexec checks the process that launched it, so the helper calls of a
+/// profile that follow run without further prompts.
+///
+/// Only a refusal is an error. Without polkit or an agent the helper
+/// calls go ahead and pkexec asks as before. Blocking; run it off the
+/// main thread.
+pub fn preauthorize() -> Result<(), String> {
+    if remote::host().is_some() {
+        return Ok(());
+    }
+    let Ok(connection) = gio::bus_get_sync(gio::BusType::System, None::<&gio::Cancellable>) else {
+        return Ok(());
+    };
+
+    let mut subject_details: HashMap<String, glib::Variant> = HashMap::new();
+    subject_details.insert("pid".to_string(), std::process::id().to_variant());
+    subject_details.insert("start-time".to_string(), own_start_time().unwrap_or(0).to_variant());
+    let subject = ("unix-process".to_string(), subject_details);
+    let details: HashMap<String, String> = HashMap::new();
+    let params = (subject, POLKIT_ACTION, details, ALLOW_USER_INTERACTION, "").to_variant();
+
+    let Ok(reply) = connection.call_sync(
+        Some("org.freedesktop.PolicyKit1"),
+        "/org/freedesktop/PolicyKit1/Authority",
+        "org.freedesktop.PolicyKit1.Authority",
+        "CheckAuthorization",
+        Some(&params),
+        Some(glib::VariantTy::new("((bba{ss}))").expect("valid type string")),
+        gio::DBusCallFlags::NONE,
+        PROMPT_TIMEOUT_MS,
+        None::<&gio::Cancellable>,
+    ) else {
+        return Ok(());
+    };
+
+    // A challenge means no agent could ask; leave that to pkexec.
+    match reply.get::<((bool, bool, HashMap<String, String>),)>() {
+        Some(((false, false, _),)) => Err("Authorization was denied".to_string()),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,102 @@
+use crate::config::Config;
+use crate::remote;
+use std::io::Read;
+use std::process::{Command, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_TIMEOUT_SECS: u64 = 3;
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProbeError {
+    /// The command couldn't be started, usually because it isn't installed.
+    Spawn(String),
+    /// The command didn't exit in time and was killed.
+    TimedOut,
+}
+
+impl std::fmt::Display for ProbeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProbeError::Spawn(e) => write!(f, "{}", e),
+            ProbeError::TimedOut => write!(f, "timed out after {}s", timeout().as_secs()),
+        }
+    }
+}
+
+/// How a feature backed by an external tool fared during detection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Capability {
+    Available,
+    #[default]
+    Missing,
+    /// The tool is installed but hung, e.g. a stuck daemon.
+    TimedOut,
+}
+
+impl Capability {
+    pub fn from_error(error: &ProbeError) -> Self {
+        match error {
+            ProbeError::Spawn(_) => Capability::Missing,
+            ProbeError::TimedOut => Capability::TimedOut,
+        }
+    }
+}
+
+pub fn timeout() -> Duration {
+    let secs = Config::load().probe_timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS);
+    Duration::from_secs(secs.max(1))
+}
+
+fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+/// Runs a non-interactive command on the tuned machine, killing it if it
+/// outlives the probe timeout. Never use this for pkexec, which waits on
+/// the user.
+pub fn run(program: &str, args: &[&str]) -> Result<Output, ProbeError> {
+    run_command(remote::command(program, args), timeout())
+}
+
+/// Like `run`, for a prepared command and a timeout of the caller's choosing.
+pub fn run_command(mut command: Command, timeout: Duration) -> Result<Output, ProbeError> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| ProbeError::Spawn(e.to_string()))?;
+
+    // Read both pipes concurrently so a chatty command can't fill one and
+    // block before exiting.
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(ProbeError::TimedOut);
+            }
+            Ok(None) => thread::sleep(POLL_INTERVAL),
+            Err(e) => return Err(ProbeError::Spawn(e.to_string())),
+        }
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
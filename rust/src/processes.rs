@@ -0,0 +1,127 @@
+use crate::remote;
+use std::collections::HashMap;
+use std::fs;
+
+pub const SAMPLE_INTERVAL_SECS: u32 = 3;
+/// Processes listed in the Monitoring group.
+pub const TOP_COUNT: usize = 5;
+
+pub const MIN_NICE: i32 = -20;
+pub const MAX_NICE: i32 = 19;
+/// Nice change applied by each priority button.
+pub const NICE_STEP: i32 = 5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessUsage {
+    pub pid: u32,
+    pub name: String,
+    pub nice: i32,
+    /// Share of total CPU time since the previous sample, 0 to 100.
+    pub cpu_percent: f64,
+}
+
+/// Tracks per-process CPU time from `/proc/<pid>/stat` between samples.
+#[derive(Debug, Default)]
+pub struct ProcessSampler {
+    prev_total: u64,
+    prev: HashMap<u32, u64>,
+}
+
+struct ProcStat {
+    name: String,
+    nice: i32,
+    ticks: u64,
+}
+
+fn read_total_ticks() -> Option<u64> {
+    let stat = fs::read_to_string("/proc/stat").ok()?;
+    let line = stat.lines().find(|l| l.starts_with("cpu "))?;
+    Some(
+        line.split_whitespace()
+            .skip(1)
+            .filter_map(|v| v.parse::<u64>().ok())
+            .sum(),
+    )
+}
+
+fn read_proc_stat(pid: u32) -> Option<ProcStat> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+
+    // The command name is parenthesized and may itself contain spaces or
+    // parentheses, so split at the last closing one.
+    let open = stat.find('(')?;
+    let close = stat.rfind(')')?;
+    let name = stat.get(open + 1..close)?.to_string();
+    let fields: Vec<&str> = stat.get(close + 1..)?.split_whitespace().collect();
+
+    // Fields after the name start at `state`: utime is 11, stime 12, nice 16.
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    let nice: i32 = fields.get(16)?.parse().ok()?;
+
+    Some(ProcStat {
+        name,
+        nice,
+        ticks: utime + stime,
+    })
+}
+
+impl ProcessSampler {
+    /// Returns the `limit` busiest processes since the previous call,
+    /// busiest first. The first call only records a baseline.
+    pub fn sample(&mut self, limit: usize) -> Vec<ProcessUsage> {
+        let Some(total) = read_total_ticks() else {
+            return Vec::new();
+        };
+        let total_delta = total.saturating_sub(self.prev_total);
+        let first = self.prev_total == 0;
+        self.prev_total = total;
+
+        let pids = fs::read_dir("/proc")
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok());
+
+        let mut current = HashMap::new();
+        let mut usage = Vec::new();
+        for pid in pids {
+            let Some(stat) = read_proc_stat(pid) else {
+                continue;
+            };
+            current.insert(pid, stat.ticks);
+
+            let Some(&prev_ticks) = self.prev.get(&pid) else {
+                continue;
+            };
+            let delta = stat.ticks.saturating_sub(prev_ticks);
+            if delta == 0 || total_delta == 0 {
+                continue;
+            }
+
+            usage.push(ProcessUsage {
+                pid,
+                name: stat.name,
+                nice: stat.nice,
+                cpu_percent: delta as f64 / total_delta as f64 * 100.0,
+            });
+        }
+        self.prev = current;
+
+        if first {
+            return Vec::new();
+        }
+
+        usage.sort_by(|a, b| b.cpu_percent.total_cmp(&a.cpu_percent));
+        usage.truncate(limit);
+        usage
+    }
+}
+
+pub fn apply_nice(pid: u32, nice: i32) -> Result<(), String> {
+    if !(MIN_NICE..=MAX_NICE).contains(&nice) {
+        return Err("Nice value out of valid range".to_string());
+    }
+
+    remote::run_helper(&["renice", &pid.to_string(), &nice.to_string()])
+}
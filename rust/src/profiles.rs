@@ -0,0 +1,571 @@
+use crate::bluetooth;
+use crate::config::Config;
+use crate::cpufreq;
+use crate::effects;
+use crate::hooks::{self, HookEvent};
+use crate::hyprland;
+use crate::lighting::{self, LightingLevel};
+use crate::mangohud;
+use crate::platform;
+use crate::privacy;
+use crate::remote;
+use crate::rfkill;
+use crate::rules::{self, Action};
+use crate::system76;
+use crate::system_info;
+use crate::validate;
+use serde::{Deserialize, Serialize};
+
+const PLATFORM_PROFILE_PATH: &str = "/sys/firmware/acpi/platform_profile";
+
+/// Prefix of history events marking a profile switch.
+pub const PROFILE_EVENT_PREFIX: &str = "Profile: ";
+
+/// A named set of settings applied together. `None` leaves a setting as is.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub name: String,
+    /// Name of a profile whose settings fill in the ones left unset here,
+    /// e.g. a quiet variant of "Performance".
+    pub extends: Option<String>,
+    /// Online threads; 0 means all.
+    pub cpu_threads: Option<u32>,
+    /// Primary monitor refresh rate; 0 means native.
+    pub refresh_hz: Option<u32>,
+    /// ACPI platform profile, e.g. `low-power` or `performance`.
+    pub platform_profile: Option<String>,
+    /// cpufreq governor, e.g. `schedutil`, where the driver offers it.
+    pub governor: Option<String>,
+    /// RGB lighting level, when a lighting controller is present.
+    pub lighting: Option<LightingLevel>,
+    /// Bluetooth adapter power, when an adapter is present.
+    pub bluetooth: Option<bool>,
+    /// Block (`true`) or unblock every radio, e.g. for a "Flight" profile.
+    pub airplane_mode: Option<bool>,
+    /// MangoHud frame rate cap; 0 means unlimited.
+    pub fps_limit: Option<u32>,
+    /// Compositor animations and blur; off is a common gaming tweak.
+    pub effects: Option<bool>,
+    /// Hyprland renders only when the screen changes.
+    pub skip_idle_frames: Option<bool>,
+    /// Seconds before the screen blanks while TuxTuner runs; 0 keeps it
+    /// on, e.g. for a game profile (see `idle::IdleControl`).
+    pub idle_timeout_secs: Option<u32>,
+    /// Dim the screen as the battery runs down (see `BatteryConfig::dim_steps`).
+    pub auto_dim: Option<bool>,
+    /// Block USB cameras and microphones (`true`), or unblock the ones
+    /// TuxTuner blocked.
+    pub privacy: Option<bool>,
+}
+
+impl Profile {
+    /// Takes each setting left as `None` from `base`.
+    fn inherit(&mut self, base: &Profile) {
+        self.cpu_threads = self.cpu_threads.or(base.cpu_threads);
+        self.refresh_hz = self.refresh_hz.or(base.refresh_hz);
+        self.platform_profile = self.platform_profile.take().or_else(|| base.platform_profile.clone());
+        self.governor = self.governor.take().or_else(|| base.governor.clone());
+        self.lighting = self.lighting.or(base.lighting);
+        self.bluetooth = self.bluetooth.or(base.bluetooth);
+        self.airplane_mode = self.airplane_mode.or(base.airplane_mode);
+        self.fps_limit = self.fps_limit.or(base.fps_limit);
+        self.effects = self.effects.or(base.effects);
+        self.skip_idle_frames = self.skip_idle_frames.or(base.skip_idle_frames);
+        self.idle_timeout_secs = self.idle_timeout_secs.or(base.idle_timeout_secs);
+        self.auto_dim = self.auto_dim.or(base.auto_dim);
+        self.privacy = self.privacy.or(base.privacy);
+    }
+}
+
+/// Presets for this machine. Desktops have no battery to save, so their
+/// low-power preset only quietens fans and keeps every core and the full
+/// refresh rate.
+pub fn builtin_profiles() -> Vec<Profile> {
+    let low_power = if platform::form_factor().is_desktop() {
+        Profile {
+            name: "Quiet".to_string(),
+            extends: None,
+            cpu_threads: Some(0),
+            refresh_hz: None,
+            platform_profile: Some("low-power".to_string()),
+            governor: None,
+            lighting: Some(LightingLevel::Off),
+            bluetooth: None,
+            airplane_mode: None,
+            fps_limit: None,
+            effects: None,
+            skip_idle_frames: None,
+            idle_timeout_secs: None,
+            auto_dim: None,
+            privacy: None,
+        }
+    } else {
+        Profile {
+            name: "Battery Saver".to_string(),
+            extends: None,
+            cpu_threads: Some(4),
+            refresh_hz: Some(60),
+            platform_profile: Some("low-power".to_string()),
+            governor: None,
+            lighting: Some(LightingLevel::Off),
+            bluetooth: Some(false),
+            airplane_mode: None,
+            fps_limit: None,
+            effects: None,
+            skip_idle_frames: Some(true),
+            idle_timeout_secs: Some(120),
+            auto_dim: Some(true),
+            privacy: None,
+        }
+    };
+
+    vec![
+        low_power,
+        Profile {
+            name: "Balanced".to_string(),
+            extends: None,
+            cpu_threads: Some(0),
+            refresh_hz: None,
+            platform_profile: Some("balanced".to_string()),
+            governor: None,
+            lighting: None,
+            bluetooth: None,
+            airplane_mode: None,
+            fps_limit: None,
+            effects: None,
+            skip_idle_frames: None,
+            idle_timeout_secs: None,
+            auto_dim: None,
+            privacy: None,
+        },
+        Profile {
+            name: "Performance".to_string(),
+            extends: None,
+            cpu_threads: Some(0),
+            refresh_hz: Some(0),
+            platform_profile: Some("performance".to_string()),
+            governor: None,
+            lighting: Some(LightingLevel::On),
+            bluetooth: None,
+            airplane_mode: None,
+            fps_limit: None,
+            effects: None,
+            skip_idle_frames: None,
+            idle_timeout_secs: None,
+            auto_dim: None,
+            privacy: None,
+        },
+    ]
+}
+
+/// Name of the most frugal built-in profile, which the critical battery
+/// check switches to.
+pub fn power_saver_name() -> String {
+    builtin_profiles().remove(0).name
+}
+
+/// Built-in profiles followed by user ones; a user profile with a
+/// built-in name replaces it. Inheritance is resolved, so every profile
+/// carries the settings it gets from its bases.
+pub fn all_profiles(config: &Config) -> Vec<Profile> {
+    let mut profiles = builtin_profiles();
+    for user in &config.profiles {
+        match profiles.iter_mut().find(|p| p.name == user.name) {
+            Some(existing) => *existing = user.clone(),
+            None => profiles.push(user.clone()),
+        }
+    }
+
+    let declared = profiles.clone();
+    for profile in &mut profiles {
+        resolve_bases(profile, &declared);
+    }
+    profiles
+}
+
+/// The profile called `name`, ignoring case, for requests from the
+/// command line, automation rules and the control API.
+pub fn find_profile(config: &Config, name: &str) -> Result<Profile, String> {
+    all_profiles(config)
+        .into_iter()
+        .find(|profile| profile.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("No profile named {}", name))
+}
+
+/// Walks up `profile`'s `extends` chain, nearest base first, stopping at
+/// a missing base or a cycle.
+fn resolve_bases(profile: &mut Profile, declared: &[Profile]) {
+    let mut seen = vec![profile.name.clone()];
+    let mut next = profile.extends.clone();
+    while let Some(name) = next {
+        if seen.contains(&name) {
+            break;
+        }
+        let Some(base) = declared.iter().find(|p| p.name == name) else {
+            break;
+        };
+        profile.inherit(base);
+        next = base.extends.clone();
+        seen.push(name);
+    }
+}
+
+/// Platform profiles the firmware accepts, if any.
+pub fn platform_profile_choices() -> Vec<String> {
+    remote::read_to_string(format!("{}_choices", PLATFORM_PROFILE_PATH))
+        .map(|choices| choices.split_whitespace().map(String::from).collect())
+        .unwrap_or_default()
+}
+
+/// The active platform profile, if the firmware exposes one.
+pub fn platform_profile() -> Option<String> {
+    remote::read_to_string(PLATFORM_PROFILE_PATH)
+        .ok()
+        .map(|profile| profile.trim().to_string())
+}
+
+pub fn apply_platform_profile(profile: &str) -> Result<(), String> {
+    if !platform_profile_choices().iter().any(|c| c == profile) {
+        return Err(format!("Unsupported platform profile: {}", profile));
+    }
+
+    remote::run_helper(&["platform-profile", profile])
+}
+
+/// The settings of `profile` that need root, sent as one atomic helper
+/// request: a single password prompt, and when a step fails the earlier
+/// ones are undone and the error names the setting. Blocking.
+fn apply_privileged(profile: &Profile, total_cpus: u32) -> Result<(), String> {
+    let mut labels = Vec::new();
+    let mut commands = Vec::new();
+
+    // Not every laptop exposes platform profiles; skip silently there.
+    let choices = platform_profile_choices();
+    let platform = profile
+        .platform_profile
+        .as_deref()
+        .filter(|_| !system76::installed() && !choices.is_empty());
+    if let Some(platform) = platform {
+        if !choices.iter().any(|c| c == platform) {
+            return Err(format!("Unsupported platform profile: {}", platform));
+        }
+        labels.push("Platform profile");
+        commands.push(vec!["platform-profile", platform]);
+    }
+
+    let governor = profile.governor.as_deref().map(validate::governor).transpose()?;
+    if let Some(governor) = governor {
+        if !cpufreq::available_governors().contains(&governor) {
+            return Err(format!("Unsupported CPU governor: {}", governor));
+        }
+        labels.push("CPU governor");
+        commands.push(vec!["governor", governor.as_str()]);
+    }
+
+    let threads = profile
+        .cpu_threads
+        .map(|threads| validate::cpu_threads(threads, total_cpus).to_string());
+    if let Some(threads) = &threads {
+        labels.push("CPU threads");
+        commands.push(vec!["cpu", threads.as_str()]);
+    }
+
+    let blocked = profile.privacy.unwrap_or_default();
+    let devices = match profile.privacy {
+        Some(blocked) => privacy::pending_changes(blocked),
+        None => Vec::new(),
+    };
+    for device in &devices {
+        labels.push("Camera and microphone");
+        commands.push(privacy::helper_args(device, blocked));
+    }
+
+    if commands.is_empty() {
+        return Ok(());
+    }
+    remote::run_helper_transaction(&commands, |_, _| {}).map_err(|failure| {
+        let Some(label) = failure.failed.and_then(|index| labels.get(index)) else {
+            return failure.error;
+        };
+        let undone = if failure.failed == Some(0) {
+            ""
+        } else if failure.rolled_back {
+            " (earlier settings were restored)"
+        } else {
+            " (earlier settings could not all be restored)"
+        };
+        format!("{}: {}{}", label, failure.error.trim(), undone)
+    })?;
+
+    for device in &devices {
+        privacy::remember(device, blocked)?;
+    }
+    Ok(())
+}
+
+/// Settings of `profile` the machine no longer matches, e.g. after a
+/// manual change, named for display. Blocking; run it off the main thread.
+pub fn divergences(profile: &Profile) -> Vec<&'static str> {
+    let mut changed = Vec::new();
+
+    if let Some(threads) = profile.cpu_threads {
+        let (total, online) = system_info::SystemInfo::fetch_cpu_info();
+        if online != validate::cpu_threads(threads, total) {
+            changed.push("CPU threads");
+        }
+    }
+
+    if let Some(hz) = profile.refresh_hz {
+        if let Some(monitor) = system_info::fetch_monitors().into_iter().next() {
+            // Profiles land on the closest rate the monitor offers.
+            let expected = if hz == 0 {
+                monitor.available_hz.last().copied()
+            } else {
+                monitor
+                    .available_hz
+                    .iter()
+                    .copied()
+                    .min_by_key(|&available| available.abs_diff(hz))
+            };
+            if expected.is_some_and(|expected| expected != monitor.refresh_hz) {
+                changed.push("refresh rate");
+            }
+        }
+    }
+
+    if let Some(expected) = &profile.platform_profile {
+        if platform_profile().is_some_and(|current| &current != expected) {
+            changed.push("platform profile");
+        }
+    }
+
+    if let Some(expected) = profile.governor.as_deref().and_then(cpufreq::Governor::parse) {
+        if cpufreq::governor().is_some_and(|current| current != expected) {
+            changed.push("CPU governor");
+        }
+    }
+
+    if let Some(expected) = profile.effects {
+        if effects::detect().and_then(effects::enabled).is_some_and(|on| on != expected) {
+            changed.push("effects");
+        }
+    }
+
+    if let Some(expected) = profile.skip_idle_frames {
+        if hyprland::skip_idle_frames().is_some_and(|on| on != expected) {
+            changed.push("idle frames");
+        }
+    }
+
+    if profile.idle_timeout_secs.is_some() && Config::load().idle_timeout_secs != profile.idle_timeout_secs {
+        changed.push("screen blanking");
+    }
+
+    changed
+}
+
+/// Applies every setting of `profile`. Blocking; run it off the main thread.
+pub fn apply_profile(profile: &Profile, total_cpus: u32) -> Result<(), String> {
+    let vars = [("TUXTUNER_PROFILE", profile.name.as_str())];
+    hooks::run(HookEvent::PreProfileApply, &vars);
+
+    // system76-power owns the platform profile where it runs, so go
+    // through it rather than fighting it over sysfs.
+    if let Some(platform) = &profile.platform_profile {
+        if system76::installed() {
+            system76::set_profile(platform)?;
+        }
+    }
+    apply_privileged(profile, total_cpus)?;
+
+    if let Some(hz) = profile.refresh_hz {
+        let action = if hz == 0 {
+            Action::NativeRefreshRate
+        } else {
+            Action::RefreshRate(hz)
+        };
+        rules::apply_action(&action)?;
+    }
+
+    if let Some(level) = profile.lighting {
+        // Like platform profiles, lighting is optional hardware.
+        if let Some(backend) = lighting::detect() {
+            lighting::apply(backend, level)?;
+        }
+    }
+
+    if let Some(fps_limit) = profile.fps_limit {
+        if mangohud::installed() {
+            mangohud::apply_fps_limit(fps_limit)?;
+        }
+    }
+
+    if let Some(on) = profile.effects {
+        // Optional like lighting: not every session has a compositor we know.
+        if let Some(backend) = effects::detect() {
+            effects::set_enabled(backend, on)?;
+        }
+    }
+
+    if let Some(on) = profile.skip_idle_frames {
+        if hyprland::skip_idle_frames().is_some() {
+            hyprland::set_skip_idle_frames(on)?;
+        }
+    }
+
+    // Before Bluetooth: BlueZ can't power an adapter that rfkill blocks.
+    if let Some(on) = profile.airplane_mode {
+        rfkill::set_airplane_mode(on)?;
+    }
+
+    if let Some(on) = profile.bluetooth {
+        bluetooth::set_powered(on)?;
+    }
+
+    // The idle daemon and auto-dimming belong to the window; they follow
+    // the config.
+    if profile.idle_timeout_secs.is_some() || profile.auto_dim.is_some() {
+        let mut config = Config::load();
+        config.idle_timeout_secs = profile.idle_timeout_secs.or(config.idle_timeout_secs);
+        config.battery.auto_dim = profile.auto_dim.unwrap_or(config.battery.auto_dim);
+        config.save()?;
+    }
+
+    hooks::run(HookEvent::PostProfileApply, &vars);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str, extends: Option<&str>) -> Profile {
+        Profile {
+            name: name.to_string(),
+            extends: extends.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn resolved(name: &str, declared: &[Profile]) -> Profile {
+        let mut profile = declared.iter().find(|p| p.name == name).unwrap().clone();
+        resolve_bases(&mut profile, declared);
+        profile
+    }
+
+    #[test]
+    fn inherits_through_a_chain_nearest_base_first() {
+        let declared = vec![
+            Profile {
+                cpu_threads: Some(16),
+                refresh_hz: Some(144),
+                governor: Some("performance".to_string()),
+                ..profile("Performance", None)
+            },
+            Profile {
+                refresh_hz: Some(60),
+                ..profile("Quiet Performance", Some("Performance"))
+            },
+            Profile {
+                fps_limit: Some(30),
+                ..profile("Travel", Some("Quiet Performance"))
+            },
+        ];
+
+        let travel = resolved("Travel", &declared);
+        assert_eq!(travel.fps_limit, Some(30));
+        assert_eq!(travel.refresh_hz, Some(60));
+        assert_eq!(travel.cpu_threads, Some(16));
+        assert_eq!(travel.governor.as_deref(), Some("performance"));
+        // The chain stays recorded; only settings are filled in.
+        assert_eq!(travel.extends.as_deref(), Some("Quiet Performance"));
+    }
+
+    #[test]
+    fn own_settings_win_over_bases() {
+        let declared = vec![
+            Profile {
+                cpu_threads: Some(16),
+                ..profile("Base", None)
+            },
+            Profile {
+                cpu_threads: Some(0),
+                ..profile("Child", Some("Base"))
+            },
+        ];
+        assert_eq!(resolved("Child", &declared).cpu_threads, Some(0));
+    }
+
+    #[test]
+    fn stops_at_a_cycle() {
+        let declared = vec![
+            Profile {
+                cpu_threads: Some(4),
+                ..profile("A", Some("B"))
+            },
+            Profile {
+                refresh_hz: Some(60),
+                ..profile("B", Some("C"))
+            },
+            Profile {
+                fps_limit: Some(30),
+                ..profile("C", Some("A"))
+            },
+            Profile {
+                effects: Some(false),
+                ..profile("Self", Some("Self"))
+            },
+        ];
+
+        let a = resolved("A", &declared);
+        assert_eq!((a.cpu_threads, a.refresh_hz, a.fps_limit), (Some(4), Some(60), Some(30)));
+        assert_eq!(resolved("Self", &declared).effects, Some(false));
+    }
+
+    #[test]
+    fn stops_at_a_missing_base() {
+        let declared = vec![
+            Profile {
+                refresh_hz: Some(60),
+                ..profile("Base", Some("Deleted"))
+            },
+            profile("Child", Some("Base")),
+            Profile {
+                cpu_threads: Some(2),
+                ..profile("Orphan", Some("Deleted"))
+            },
+        ];
+
+        assert_eq!(resolved("Child", &declared).refresh_hz, Some(60));
+        let orphan = resolved("Orphan", &declared);
+        assert_eq!(orphan, Profile { cpu_threads: Some(2), ..profile("Orphan", Some("Deleted")) });
+    }
+
+    #[test]
+    fn user_profiles_extend_and_replace_built_ins() {
+        let builtin = power_saver_name();
+        let config = Config {
+            profiles: vec![
+                Profile {
+                    bluetooth: Some(false),
+                    ..profile("Offline", Some(&builtin))
+                },
+                Profile {
+                    cpu_threads: Some(1),
+                    ..profile(&builtin, None)
+                },
+            ],
+            ..Default::default()
+        };
+
+        let profiles = all_profiles(&config);
+        assert_eq!(profiles.iter().filter(|p| p.name == builtin).count(), 1);
+        // Bases resolve against the replacement, not the built-in.
+        let offline = profiles.iter().find(|p| p.name == "Offline").unwrap();
+        assert_eq!(offline.bluetooth, Some(false));
+        assert_eq!(offline.cpu_threads, Some(1));
+        assert_eq!(offline.platform_profile, None);
+        assert_eq!(find_profile(&config, "offline").unwrap(), *offline);
+    }
+}
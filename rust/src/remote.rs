@@ -0,0 +1,303 @@
+use crate::config::Config;
+use crate::mac;
+use crate::probe;
+use crate::system_info::{self, HELPER_PATH};
+use crate::validate;
+use gtk4::glib;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Version of the JSON requests `tuxtuner-helper --json` reads; keep in
+/// sync with the helper.
+const HELPER_PROTOCOL: u32 = 1;
+
+/// The machine being tuned, read once at startup so a session never mixes
+/// readings from two hosts. `None` means this machine.
+static HOST: Lazy<Option<String>> = Lazy::new(|| {
+    Config::load()
+        .remote_host
+        .map(|host| host.trim().to_string())
+        .filter(|host| !host.is_empty())
+        .filter(|host| match validate::remote_host(host) {
+            Ok(()) => true,
+            Err(e) => {
+                glib::g_warning!(crate::LOG_DOMAIN, "{}; tuning this machine instead", e);
+                false
+            }
+        })
+});
+
+/// The `[user@]host` probes and applies run on, when in remote mode.
+pub fn host() -> Option<&'static str> {
+    HOST.as_deref()
+}
+
+/// Quotes `arg` for the remote login shell.
+fn quote(arg: &str) -> String {
+    if !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:@%+,".contains(c))
+    {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+fn ssh(host: &str, remote_args: &[&str]) -> Command {
+    let mut command = Command::new("ssh");
+    // Never prompt; keys or an agent must be set up. One shared connection
+    // keeps the many small reads per refresh cheap.
+    command.args(["-o", "BatchMode=yes", "-o", "ControlMaster=auto", "-o", "ControlPersist=60"]);
+    if let Some(runtime_dir) = std::env::var_os("XDG_RUNTIME_DIR") {
+        let path = PathBuf::from(runtime_dir).join("tuxtuner-ssh-%C");
+        command.arg("-o").arg(format!("ControlPath={}", path.display()));
+    }
+    let line: Vec<String> = remote_args.iter().map(|arg| quote(arg)).collect();
+    command.args([host, "--"]).arg(line.join(" "));
+    command
+}
+
+/// `program args` on the tuned machine.
+pub fn command(program: &str, args: &[&str]) -> Command {
+    match host() {
+        Some(host) => {
+            let mut remote_args = vec![program];
+            remote_args.extend_from_slice(args);
+            ssh(host, &remote_args)
+        }
+        None => {
+            let mut command = Command::new(program);
+            command.args(args);
+            command
+        }
+    }
+}
+
+/// The privileged helper with `args`: through pkexec locally, through
+/// passwordless sudo on a remote host since there's no polkit agent there.
+pub fn helper(args: &[&str]) -> Command {
+    match host() {
+        Some(host) => {
+            let mut remote_args = vec!["sudo", "-n", HELPER_PATH];
+            remote_args.extend_from_slice(args);
+            ssh(host, &remote_args)
+        }
+        None => {
+            let mut command = Command::new("pkexec");
+            command.arg(HELPER_PATH).args(args);
+            command
+        }
+    }
+}
+
+/// One line of the helper's JSON reply: a result or rollback report for
+/// the command at `index`, or the final status when there's no index.
+#[derive(Debug, Deserialize)]
+struct HelperReply {
+    index: Option<usize>,
+    #[serde(default)]
+    ok: bool,
+    #[serde(default)]
+    output: String,
+    error: Option<String>,
+    rolled_back: Option<bool>,
+    failed: Option<usize>,
+}
+
+/// Why a helper request failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HelperFailure {
+    /// Index of the command that failed; `None` when the request was
+    /// refused or authentication failed before anything ran.
+    pub failed: Option<usize>,
+    pub error: String,
+    /// In an atomic request, whether every command before the failed one
+    /// was undone.
+    pub rolled_back: bool,
+    /// pkexec didn't start the helper because authentication was
+    /// dismissed or refused, as opposed to any other failure.
+    pub not_authorized: bool,
+}
+
+/// Runs one helper command. Blocking.
+pub fn run_helper(args: &[&str]) -> Result<(), String> {
+    run_helper_batch(&[args.to_vec()], |_, _| {})
+}
+
+/// Runs helper commands in order under a single authentication, stopping
+/// at the first that fails. `progress` gets each command's index and
+/// output as it completes. Blocking.
+pub fn run_helper_batch(
+    commands: &[Vec<&str>],
+    progress: impl FnMut(usize, &str),
+) -> Result<(), String> {
+    send_helper_request(commands, false, progress).map_err(|failure| failure.error)
+}
+
+/// Like `run_helper_batch`, but all or nothing: when a command fails the
+/// helper puts back what the earlier ones changed. Only commands the
+/// helper can undo are accepted. Blocking.
+pub fn run_helper_transaction(
+    commands: &[Vec<&str>],
+    progress: impl FnMut(usize, &str),
+) -> Result<(), HelperFailure> {
+    send_helper_request(commands, true, progress)
+}
+
+fn send_helper_request(
+    commands: &[Vec<&str>],
+    atomic: bool,
+    mut progress: impl FnMut(usize, &str),
+) -> Result<(), HelperFailure> {
+    let failure = |error: String| HelperFailure {
+        failed: None,
+        error,
+        rolled_back: false,
+        not_authorized: false,
+    };
+    let request = serde_json::json!({
+        "version": HELPER_PROTOCOL,
+        "atomic": atomic,
+        "commands": commands,
+    });
+    let mut child = helper(&["--json"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| failure(e.to_string()))?;
+
+    // Dismissing authentication closes the pipe before the request is
+    // read; the error then comes from pkexec on stderr.
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(request.to_string().as_bytes());
+    }
+
+    let mut status = None;
+    let mut undone = true;
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            let Ok(reply) = serde_json::from_str::<HelperReply>(&line) else {
+                continue;
+            };
+            match (reply.index, reply.rolled_back) {
+                (Some(index), Some(rolled_back)) => {
+                    if !rolled_back {
+                        glib::g_debug!(
+                            crate::LOG_DOMAIN,
+                            "Couldn't undo helper command {}: {}",
+                            index,
+                            reply.error.as_deref().unwrap_or_default()
+                        );
+                    }
+                    undone &= rolled_back;
+                }
+                (Some(index), None) if reply.ok => progress(index, reply.output.trim()),
+                (Some(_), None) => {}
+                (None, _) => status = Some(reply),
+            }
+        }
+    }
+
+    let output = child.wait_with_output().map_err(|e| failure(e.to_string()))?;
+    let mut result = match status {
+        Some(reply) if reply.ok => return Ok(()),
+        Some(reply) => HelperFailure {
+            failed: reply.failed,
+            error: reply.error.unwrap_or_else(|| "Helper failed".to_string()),
+            rolled_back: atomic && undone,
+            not_authorized: false,
+        },
+        None => {
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            // pkexec exits 126 when the prompt is dismissed and 127 when
+            // authorization is refused, but also 127 when it can't run the
+            // helper at all; only its own refusal message tells them apart.
+            let not_authorized = host().is_none()
+                && match output.status.code() {
+                    Some(126) => true,
+                    Some(127) => stderr.contains("Not authorized"),
+                    _ => false,
+                };
+            HelperFailure {
+                not_authorized,
+                ..failure(stderr)
+            }
+        }
+    };
+    if let Some(remedy) = mac::explain(&result.error) {
+        result.error = format!("{}\n{}", result.error.trim(), remedy);
+    }
+    Err(result)
+}
+
+fn remote_output(host: &str, args: &[&str]) -> io::Result<String> {
+    let output = probe::run_command(ssh(host, args), probe::timeout())
+        .map_err(|e| io::Error::other(e.to_string()))?;
+    if !output.status.success() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Reads a sysfs or procfs file of the tuned machine.
+pub fn read_to_string(path: impl AsRef<Path>) -> io::Result<String> {
+    let path = path.as_ref();
+    match host() {
+        Some(host) => remote_output(host, &["cat", &path.to_string_lossy()]),
+        None => std::fs::read_to_string(path),
+    }
+}
+
+/// Entries of a directory on the tuned machine.
+pub fn read_dir(path: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+    let path = path.as_ref();
+    match host() {
+        Some(host) => Ok(remote_output(host, &["ls", "-1", &path.to_string_lossy()])?
+            .lines()
+            .filter(|name| !name.is_empty())
+            .map(|name| path.join(name))
+            .collect()),
+        None => Ok(std::fs::read_dir(path)?.flatten().map(|entry| entry.path()).collect()),
+    }
+}
+
+/// Whether `program` is on the tuned machine's `PATH`.
+pub fn command_exists(program: &str) -> bool {
+    match host() {
+        Some(host) => remote_output(host, &["command", "-v", program]).is_ok(),
+        None => system_info::command_exists(program),
+    }
+}
+
+/// Whether `path` exists on the tuned machine.
+pub fn exists(path: impl AsRef<Path>) -> bool {
+    let path = path.as_ref();
+    match host() {
+        Some(host) => remote_output(host, &["test", "-e", &path.to_string_lossy()]).is_ok(),
+        None => path.exists(),
+    }
+}
+
+/// Explains why the remote host can't accept changes, mirroring
+/// `privileges::read_only_reason` for local sessions.
+pub fn read_only_reason(host: &str) -> Option<String> {
+    if remote_output(host, &["true"]).is_err() {
+        return Some(format!("Cannot reach {} over SSH without a password", host));
+    }
+    if remote_output(host, &["test", "-x", HELPER_PATH]).is_err() {
+        return Some(format!("The TuxTuner helper is missing from {} on {}", HELPER_PATH, host));
+    }
+    if remote_output(host, &["sudo", "-n", "-l", HELPER_PATH]).is_err() {
+        return Some(format!("{} needs a passwordless sudo rule for {}", host, HELPER_PATH));
+    }
+    None
+}
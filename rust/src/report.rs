@@ -0,0 +1,114 @@
+use crate::gpu_driver;
+use crate::immutable;
+use crate::probe;
+use crate::session;
+use crate::system_info::{command_exists, HELPER_PATH};
+use crate::virt;
+use std::fs;
+use std::path::Path;
+
+fn read_trimmed(path: &str) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn command_version(program: &str, args: &[&str]) -> Option<String> {
+    if !command_exists(program) {
+        return None;
+    }
+    let output = probe::run(program, args).ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+}
+
+fn distro() -> Option<String> {
+    fs::read_to_string("/etc/os-release")
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+        .map(|name| name.trim_matches('"').to_string())
+}
+
+fn cpu_model() -> Option<String> {
+    fs::read_to_string("/proc/cpuinfo")
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("model name"))
+        .and_then(|rest| rest.split_once(':'))
+        .map(|(_, model)| model.trim().to_string())
+}
+
+/// `vendor:device driver version` for every DRM card, with its Vulkan
+/// driver, e.g. `1002:1681 amdgpu in-kernel, Linux 6.9.3 (Vulkan: radeon_icd.x86_64.json)`.
+fn gpu_drivers() -> Vec<String> {
+    gpu_driver::drivers()
+        .into_iter()
+        .map(|gpu| {
+            format!(
+                "{} {} {} (Vulkan: {})",
+                gpu.pci_id,
+                gpu.driver,
+                gpu.version,
+                gpu.vulkan_icd.as_deref().unwrap_or("none")
+            )
+        })
+        .collect()
+}
+
+fn compositor() -> String {
+    let session = session::current();
+    let init = if session.systemd { "systemd" } else { "no systemd" };
+    let mut parts = vec![session.label(), init.to_string()];
+    if let Some(system) = immutable::current() {
+        parts.push(system.label().to_string());
+    }
+    if let Some(virtualization) = virt::current() {
+        parts.push(virtualization.label());
+    }
+    parts.join(", ")
+}
+
+/// Plain-text summary of the machine for bug reports. Leaves out anything
+/// identifying: hostnames, user names, serial numbers, MAC addresses.
+pub fn hardware_report() -> String {
+    let unknown = || "unknown".to_string();
+    let model = match (
+        read_trimmed("/sys/class/dmi/id/sys_vendor"),
+        read_trimmed("/sys/class/dmi/id/product_name"),
+    ) {
+        (Some(vendor), Some(product)) => format!("{} {}", vendor, product),
+        (vendor, product) => vendor.or(product).unwrap_or_else(unknown),
+    };
+    let gpus = gpu_drivers();
+
+    let lines = [
+        format!("TuxTuner: {}", env!("CARGO_PKG_VERSION")),
+        format!("Distribution: {}", distro().unwrap_or_else(unknown)),
+        format!("Kernel: {}", read_trimmed("/proc/sys/kernel/osrelease").unwrap_or_else(unknown)),
+        format!("Model: {}", model),
+        format!("CPU: {}", cpu_model().unwrap_or_else(unknown)),
+        format!(
+            "GPU drivers: {}",
+            if gpus.is_empty() { unknown() } else { gpus.join(", ") }
+        ),
+        format!(
+            "supergfxctl: {}",
+            command_version("supergfxctl", &["--version"]).unwrap_or_else(|| "not installed".to_string())
+        ),
+        format!(
+            "Hyprland: {}",
+            command_version("hyprctl", &["version"]).unwrap_or_else(|| "not installed".to_string())
+        ),
+        format!("Compositor: {}", compositor()),
+        format!(
+            "Helper: {}",
+            if Path::new(HELPER_PATH).is_file() { "installed" } else { "missing" }
+        ),
+    ];
+    lines.join("\n")
+}
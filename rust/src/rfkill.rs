@@ -0,0 +1,107 @@
+use crate::remote;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+const RFKILL_CLASS_PATH: &str = "/sys/class/rfkill";
+/// logind grants the seat user write access, so no helper is needed.
+const RFKILL_DEVICE: &str = "/dev/rfkill";
+
+const RFKILL_TYPE_ALL: u8 = 0;
+const RFKILL_OP_CHANGE: u8 = 2;
+const RFKILL_OP_CHANGE_ALL: u8 = 3;
+
+/// A radio known to the kernel's rfkill subsystem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Radio {
+    pub index: u32,
+    /// rfkill type, e.g. `wlan`, `bluetooth` or `wwan`.
+    pub kind: String,
+    /// Driver name, e.g. `phy0` or `hci0`.
+    pub name: String,
+    pub soft_blocked: bool,
+    /// Blocked by a hardware switch; software can't undo it.
+    pub hard_blocked: bool,
+}
+
+impl Radio {
+    pub fn label(&self) -> String {
+        match self.kind.as_str() {
+            "wlan" => "Wi-Fi",
+            "bluetooth" => "Bluetooth",
+            "wwan" => "Mobile Broadband",
+            "gps" => "GPS",
+            "nfc" => "NFC",
+            "fm" => "FM Radio",
+            "uwb" => "Ultra-Wideband",
+            _ => "Radio",
+        }
+        .to_string()
+    }
+}
+
+fn read_attr(dir: &Path, attr: &str) -> Option<String> {
+    fs::read_to_string(dir.join(attr))
+        .ok()
+        .map(|value| value.trim().to_string())
+}
+
+/// Radios of this machine, by rfkill index. Empty in remote mode, since
+/// `/dev/rfkill` can't be reached over SSH without the helper.
+pub fn radios() -> Vec<Radio> {
+    if remote::host().is_some() {
+        return Vec::new();
+    }
+
+    let mut radios: Vec<Radio> = fs::read_dir(RFKILL_CLASS_PATH)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let dir = entry.path();
+            Some(Radio {
+                index: read_attr(&dir, "index")?.parse().ok()?,
+                kind: read_attr(&dir, "type")?,
+                name: read_attr(&dir, "name").unwrap_or_default(),
+                soft_blocked: read_attr(&dir, "soft").as_deref() == Some("1"),
+                hard_blocked: read_attr(&dir, "hard").as_deref() == Some("1"),
+            })
+        })
+        .collect();
+    radios.sort_by_key(|radio| radio.index);
+    radios
+}
+
+/// Whether every radio is blocked, i.e. airplane mode is on.
+pub fn airplane_mode() -> bool {
+    let radios = radios();
+    !radios.is_empty() && radios.iter().all(|radio| radio.soft_blocked || radio.hard_blocked)
+}
+
+/// Writes a `struct rfkill_event`: index, type, op, soft, hard.
+fn write_event(index: u32, op: u8, blocked: bool) -> Result<(), String> {
+    let mut event = [0u8; 8];
+    event[..4].copy_from_slice(&index.to_ne_bytes());
+    event[4] = RFKILL_TYPE_ALL;
+    event[5] = op;
+    event[6] = blocked as u8;
+
+    OpenOptions::new()
+        .write(true)
+        .open(RFKILL_DEVICE)
+        .and_then(|mut device| device.write_all(&event))
+        .map_err(|e| format!("{}: {}", RFKILL_DEVICE, e))
+}
+
+pub fn set_blocked(radio: &Radio, blocked: bool) -> Result<(), String> {
+    write_event(radio.index, RFKILL_OP_CHANGE, blocked)
+}
+
+/// Blocks or unblocks every radio at once. Succeeds quietly without any,
+/// since profiles treat radios as optional hardware.
+pub fn set_airplane_mode(on: bool) -> Result<(), String> {
+    if radios().is_empty() {
+        return Ok(());
+    }
+    write_event(0, RFKILL_OP_CHANGE_ALL, on)
+}
@@ -0,0 +1,147 @@
+use crate::battery;
+use crate::config::{AutomationConfig, Config};
+use crate::power::PowerSource;
+use crate::profiles;
+use crate::system_info::{self, SystemInfo};
+use crate::updater;
+use crate::window_watch::ActiveWindow;
+use serde::{Deserialize, Serialize};
+
+/// Everything rules can currently react to.
+#[derive(Debug, Clone, Default)]
+pub struct Context {
+    pub power: PowerSource,
+    pub window: ActiveWindow,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    OnBattery,
+    OnAc,
+    Fullscreen,
+    /// Focused window class contains one of these (case-insensitive).
+    AppClass(Vec<String>),
+    All(Vec<Condition>),
+    Any(Vec<Condition>),
+    Not(Box<Condition>),
+}
+
+impl Condition {
+    pub fn matches(&self, ctx: &Context) -> bool {
+        match self {
+            Condition::OnBattery => ctx.power == PowerSource::Battery,
+            Condition::OnAc => ctx.power == PowerSource::Ac,
+            Condition::Fullscreen => ctx.window.fullscreen,
+            Condition::AppClass(classes) => {
+                let class = ctx.window.class.to_lowercase();
+                !class.is_empty() && classes.iter().any(|c| class.contains(&c.to_lowercase()))
+            }
+            Condition::All(conditions) => conditions.iter().all(|c| c.matches(ctx)),
+            Condition::Any(conditions) => conditions.iter().any(|c| c.matches(ctx)),
+            Condition::Not(condition) => !condition.matches(ctx),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// Switch the primary monitor to its highest refresh rate.
+    NativeRefreshRate,
+    /// Switch the primary monitor to the closest available rate.
+    RefreshRate(u32),
+    /// Apply the built-in or user profile with this name.
+    Profile(String),
+    /// Stop charging at this percentage and keep it as the configured limit.
+    ChargeLimit(u32),
+    /// Open the desktop's app for installing firmware updates.
+    OpenFirmwareUpdater,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    pub name: String,
+    pub when: Condition,
+    pub then: Action,
+}
+
+/// Returns the first rule whose condition holds.
+pub fn evaluate<'a>(rules: &'a [Rule], ctx: &Context) -> Option<&'a Rule> {
+    rules.iter().find(|rule| rule.when.matches(ctx))
+}
+
+/// User rules first, followed by the enabled built-in automations.
+pub fn active_rules(config: &AutomationConfig) -> Vec<Rule> {
+    let mut rules = config.rules.clone();
+
+    if config.battery_refresh {
+        rules.push(Rule {
+            name: "Native rate on AC".to_string(),
+            when: Condition::OnAc,
+            then: Action::NativeRefreshRate,
+        });
+        rules.push(Rule {
+            name: "Native rate for games".to_string(),
+            when: Condition::AppClass(config.game_classes.clone()),
+            then: Action::NativeRefreshRate,
+        });
+        rules.push(Rule {
+            name: "Low rate on battery".to_string(),
+            when: Condition::All(vec![
+                Condition::OnBattery,
+                Condition::Not(Box::new(Condition::Fullscreen)),
+                Condition::Not(Box::new(Condition::AppClass(config.video_classes.clone()))),
+            ]),
+            then: Action::RefreshRate(config.battery_refresh_hz),
+        });
+    }
+
+    rules
+}
+
+/// Executes `action`. Blocking; run it off the main thread.
+pub fn apply_action(action: &Action) -> Result<(), String> {
+    match action {
+        Action::NativeRefreshRate => apply_refresh_rate(None),
+        Action::RefreshRate(hz) => apply_refresh_rate(Some(*hz)),
+        Action::Profile(name) => {
+            let profile = profiles::find_profile(&Config::load(), name)?;
+            let (total_cpus, _) = SystemInfo::fetch_cpu_info();
+            profiles::apply_profile(&profile, total_cpus)
+        }
+        Action::ChargeLimit(percent) => {
+            battery::apply_charge_limit(*percent)?;
+            let mut config = Config::load();
+            config.battery.charge_limit = Some(*percent);
+            config.save()
+        }
+        Action::OpenFirmwareUpdater => updater::find(true)
+            .ok_or("No firmware updater installed; run fwupdmgr update in a terminal")?
+            .launch(),
+    }
+}
+
+/// Switches the primary monitor to the rate closest to `hz`, or to its
+/// highest one for `None`.
+fn apply_refresh_rate(hz: Option<u32>) -> Result<(), String> {
+    let monitor = system_info::fetch_monitors()
+        .into_iter()
+        .next()
+        .ok_or("No monitor found")?;
+
+    let hz = match hz {
+        None => monitor.available_hz.last().copied(),
+        Some(hz) => monitor
+            .available_hz
+            .iter()
+            .copied()
+            .min_by_key(|&available| available.abs_diff(hz)),
+    }
+    .ok_or("No refresh rates available")?;
+
+    if hz == monitor.refresh_hz {
+        return Ok(());
+    }
+    system_info::apply_monitor_mode(&monitor, hz, None)
+}
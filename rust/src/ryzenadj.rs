@@ -0,0 +1,67 @@
+use crate::remote;
+use serde::{Deserialize, Serialize};
+
+/// Lowest limit accepted; below this APUs stutter or fail to boost at all.
+pub const MIN_TDP_W: u32 = 5;
+/// Safety ceiling, enforced again by the helper. Mobile APUs beyond this
+/// are rare and the VRMs of thin laptops aren't built for more.
+pub const MAX_TDP_W: u32 = 54;
+
+/// AMD APU power limits in watts, as passed to ryzenadj.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TdpLimits {
+    /// Sustained limit (STAPM).
+    pub stapm_w: u32,
+    /// Short boost limit (PPT fast).
+    pub fast_w: u32,
+    /// Longer boost limit (PPT slow).
+    pub slow_w: u32,
+}
+
+impl Default for TdpLimits {
+    fn default() -> Self {
+        Self {
+            stapm_w: 15,
+            fast_w: 25,
+            slow_w: 20,
+        }
+    }
+}
+
+impl TdpLimits {
+    /// Boost limits below the sustained one are meaningless and confuse
+    /// the SMU, so require `fast >= slow >= stapm`.
+    pub fn validate(&self) -> Result<(), String> {
+        for watts in [self.stapm_w, self.fast_w, self.slow_w] {
+            if !(MIN_TDP_W..=MAX_TDP_W).contains(&watts) {
+                return Err(format!("Limits must be between {} and {} W", MIN_TDP_W, MAX_TDP_W));
+            }
+        }
+        if self.fast_w < self.slow_w || self.slow_w < self.stapm_w {
+            return Err("Fast limit must be at least the slow limit, and slow at least STAPM".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn is_amd_cpu() -> bool {
+    remote::read_to_string("/proc/cpuinfo")
+        .map(|info| info.lines().any(|line| line.starts_with("vendor_id") && line.contains("AuthenticAMD")))
+        .unwrap_or(false)
+}
+
+/// Whether TDP limits can be set: an AMD CPU with ryzenadj installed.
+pub fn available() -> bool {
+    is_amd_cpu() && remote::command_exists("ryzenadj")
+}
+
+pub fn apply_limits(limits: TdpLimits) -> Result<(), String> {
+    limits.validate()?;
+
+    remote::run_helper(&[
+        "tdp",
+        &limits.stapm_w.to_string(),
+        &limits.fast_w.to_string(),
+        &limits.slow_w.to_string(),
+    ])
+}
@@ -0,0 +1,105 @@
+use crate::remote;
+use gtk4::glib;
+use serde::{Deserialize, Serialize};
+
+/// Poll often enough that no wall-clock minute is skipped.
+const POLL_SECONDS: u32 = 15;
+
+/// Parses `HH:MM` into minutes since midnight.
+pub fn parse_time(value: &str) -> Option<u32> {
+    let (hours, minutes) = value.trim().split_once(':')?;
+    let hours: u32 = hours.parse().ok()?;
+    let minutes: u32 = minutes.parse().ok()?;
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+/// Whether `now` falls inside the `[start, end)` window, which may wrap
+/// past midnight (e.g. 20:00 to 07:00).
+pub fn in_schedule(now: u32, start: u32, end: u32) -> bool {
+    if start <= end {
+        now >= start && now < end
+    } else {
+        now >= start || now < end
+    }
+}
+
+/// Local minutes since midnight.
+pub fn minutes_now() -> u32 {
+    glib::DateTime::now_local()
+        .map(|dt| (dt.hour() * 60 + dt.minute()) as u32)
+        .unwrap_or(0)
+}
+
+/// Unix time of the next local occurrence of `minutes` past midnight.
+pub fn next_occurrence(minutes: u32) -> Option<i64> {
+    let now = glib::DateTime::now_local().ok()?;
+    let today = glib::DateTime::from_local(
+        now.year(),
+        now.month(),
+        now.day_of_month(),
+        (minutes / 60) as i32,
+        (minutes % 60) as i32,
+        0.0,
+    )
+    .ok()?;
+
+    let target = if today.to_unix() <= now.to_unix() {
+        today.add_days(1).ok()?
+    } else {
+        today
+    };
+    Some(target.to_unix())
+}
+
+/// Calls `callback` on the main loop with the local time in minutes since
+/// midnight, once at the start of every minute.
+pub fn watch_minutes<F: Fn(u32) + 'static>(callback: F) {
+    let mut last = minutes_now();
+
+    glib::timeout_add_seconds_local(POLL_SECONDS, move || {
+        let now = minutes_now();
+        if now != last {
+            last = now;
+            callback(now);
+        }
+        glib::ControlFlow::Continue
+    });
+}
+
+/// What a scheduled power event does before the RTC alarm wakes the
+/// machine again.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SleepAction {
+    #[default]
+    Suspend,
+    Hibernate,
+    Shutdown,
+}
+
+impl SleepAction {
+    pub const ALL: [SleepAction; 3] = [SleepAction::Suspend, SleepAction::Hibernate, SleepAction::Shutdown];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SleepAction::Suspend => "Suspend",
+            SleepAction::Hibernate => "Hibernate",
+            SleepAction::Shutdown => "Shut Down",
+        }
+    }
+
+    /// The matching `rtcwake -m` mode.
+    fn rtcwake_mode(self) -> &'static str {
+        match self {
+            SleepAction::Suspend => "mem",
+            SleepAction::Hibernate => "disk",
+            SleepAction::Shutdown => "off",
+        }
+    }
+}
+
+/// Arms the RTC alarm for `wake_at` (unix time) and runs `action`.
+/// Blocks until the machine resumes when suspending.
+pub fn sleep_until(action: SleepAction, wake_at: i64) -> Result<(), String> {
+    remote::run_helper(&["rtcwake", action.rtcwake_mode(), &wake_at.to_string()])
+}
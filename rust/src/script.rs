@@ -0,0 +1,319 @@
+use crate::rules::Action;
+use rhai::{Engine, EvalAltResult, ParseErrorType, Scope, AST};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+pub const EVAL_INTERVAL_SECS: u32 = 5;
+/// Deepest nesting of parentheses and operators a script may use, so a
+/// pathological one can't exhaust the stack.
+const MAX_EXPR_DEPTH: usize = 32;
+/// Evaluation steps allowed per run; expressions can't loop, this only
+/// bounds a very long one.
+const MAX_OPERATIONS: u64 = 10_000;
+const MAX_STRING_LEN: usize = 256;
+
+/// Names scripts can read; see `Readings`.
+pub const VARIABLES: [&str; 7] = [
+    "cpu_temp",
+    "cpu_load",
+    "battery",
+    "power_w",
+    "on_battery",
+    "on_ac",
+    "fullscreen",
+];
+
+/// A rule whose condition is a Rhai expression over live readings, for
+/// cases the built-in triggers can't express, e.g. `when = "cpu_temp > 85"`,
+/// `for_secs = 60`, `then = { profile = "Quiet" }`. Expressions can only
+/// read `Readings` and only run an `Action`, so a script can't touch files,
+/// run commands or loop forever.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScriptRule {
+    pub name: String,
+    pub when: String,
+    /// How long `when` must hold before acting.
+    #[serde(default)]
+    pub for_secs: u64,
+    pub then: Action,
+}
+
+/// Values scripts can read. `None` when the hardware doesn't report it;
+/// an expression using a missing value is false.
+#[derive(Debug, Clone, Default)]
+pub struct Readings {
+    /// °C.
+    pub cpu_temp: Option<f64>,
+    /// Percent of online CPUs busy.
+    pub cpu_load: Option<f64>,
+    /// Battery charge in percent.
+    pub battery: Option<f64>,
+    /// Battery discharge in watts; 0 while charging.
+    pub power_w: Option<f64>,
+    pub on_battery: bool,
+    pub on_ac: bool,
+    pub fullscreen: bool,
+}
+
+impl Readings {
+    /// The readings as script variables. Missing ones are left out, so an
+    /// expression using one fails and counts as false. Expressions can't
+    /// assign, so scripts still only read them.
+    fn scope(&self) -> Scope<'static> {
+        let mut scope = Scope::new();
+        for (name, value) in [
+            ("cpu_temp", self.cpu_temp),
+            ("cpu_load", self.cpu_load),
+            ("battery", self.battery),
+            ("power_w", self.power_w),
+        ] {
+            if let Some(value) = value {
+                scope.push(name, value);
+            }
+        }
+        // Not constants: the optimizer would bake the values of the scope
+        // a script is compiled against into it.
+        scope.push("on_battery", self.on_battery);
+        scope.push("on_ac", self.on_ac);
+        scope.push("fullscreen", self.fullscreen);
+        scope
+    }
+
+    /// Every reading present, for checking scripts before they run.
+    fn sample(level: f64, flag: bool) -> Self {
+        Readings {
+            cpu_temp: Some(level),
+            cpu_load: Some(level),
+            battery: Some(level),
+            power_w: Some(level),
+            on_battery: flag,
+            on_ac: flag,
+            fullscreen: flag,
+        }
+    }
+}
+
+/// A Rhai engine that only evaluates expressions: no functions beyond the
+/// operators, no printing, no modules, and bounded nesting and work.
+fn engine() -> Engine {
+    let mut engine = Engine::new_raw();
+    engine.set_strict_variables(true);
+    engine.set_max_expr_depths(MAX_EXPR_DEPTH);
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_string_size(MAX_STRING_LEN);
+    engine
+}
+
+fn run(engine: &Engine, ast: &AST, readings: &Readings) -> Result<bool, Box<EvalAltResult>> {
+    engine.eval_ast_with_scope::<bool>(&mut readings.scope(), ast)
+}
+
+/// Compiles `source` as a single expression over `VARIABLES` and checks
+/// that it yields true/false. Rhai is dynamically typed, so the check runs
+/// it against sample readings taking both sides of every flag.
+fn compile(engine: &Engine, source: &str) -> Result<AST, String> {
+    let ast = engine
+        .compile_expression_with_scope(&Readings::sample(0.0, false).scope(), source)
+        .map_err(|e| match &*e.0 {
+            ParseErrorType::VariableUndefined(name) => {
+                format!("Unknown variable: {} (available: {})", name, VARIABLES.join(", "))
+            }
+            _ => e.to_string(),
+        })?;
+    for readings in [Readings::sample(0.0, false), Readings::sample(100.0, true)] {
+        run(engine, &ast, &readings).map_err(|e| e.to_string())?;
+    }
+    Ok(ast)
+}
+/// Tracks how long each script's condition has held.
+#[derive(Debug)]
+pub struct ScriptRunner {
+    engine: Engine,
+    /// Compiled conditions by source, so unchanged scripts aren't parsed
+    /// every tick.
+    compiled: HashMap<String, Result<AST, String>>,
+    held_since: HashMap<String, Instant>,
+    /// Scripts that acted during the current stretch of their condition.
+    fired: HashSet<String>,
+    /// Errors already reported, so a broken script isn't reported every tick.
+    reported: HashSet<String>,
+}
+
+impl Default for ScriptRunner {
+    fn default() -> Self {
+        Self {
+            engine: engine(),
+            compiled: HashMap::new(),
+            held_since: HashMap::new(),
+            fired: HashSet::new(),
+            reported: HashSet::new(),
+        }
+    }
+}
+
+impl ScriptRunner {
+    /// Returns scripts whose condition has now held for `for_secs`; each
+    /// acts once until its condition turns false again. `Err` carries a
+    /// newly broken script.
+    pub fn tick(&mut self, scripts: &[ScriptRule], readings: &Readings, now: Instant) -> Vec<Result<ScriptRule, String>> {
+        let mut results = Vec::new();
+        self.compiled.retain(|source, _| scripts.iter().any(|script| &script.when == source));
+
+        for script in scripts {
+            let engine = &self.engine;
+            let compiled = self
+                .compiled
+                .entry(script.when.clone())
+                .or_insert_with(|| compile(engine, &script.when));
+            let error = match compiled {
+                Ok(ast) => match run(engine, ast, readings) {
+                    Ok(holds) => {
+                        self.track(script, holds, now, &mut results);
+                        continue;
+                    }
+                    // A reading the hardware doesn't report right now.
+                    Err(e) if matches!(*e, EvalAltResult::ErrorVariableNotFound(..)) => {
+                        self.track(script, false, now, &mut results);
+                        continue;
+                    }
+                    Err(e) => e.to_string(),
+                },
+                Err(e) => e.clone(),
+            };
+
+            let message = format!("Script \"{}\": {}", script.name, error);
+            if self.reported.insert(message.clone()) {
+                results.push(Err(message));
+            }
+            self.track(script, false, now, &mut results);
+        }
+
+        results
+    }
+
+    fn track(&mut self, script: &ScriptRule, holds: bool, now: Instant, results: &mut Vec<Result<ScriptRule, String>>) {
+        if !holds {
+            self.held_since.remove(&script.name);
+            self.fired.remove(&script.name);
+            return;
+        }
+
+        let since = *self.held_since.entry(script.name.clone()).or_insert(now);
+        if now.duration_since(since) >= Duration::from_secs(script.for_secs)
+            && self.fired.insert(script.name.clone())
+        {
+            results.push(Ok(script.clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(source: &str, readings: &Readings) -> Result<bool, String> {
+        let engine = engine();
+        let ast = compile(&engine, source)?;
+        run(&engine, &ast, readings).map_err(|e| e.to_string())
+    }
+
+    fn script(name: &str, when: &str, for_secs: u64) -> ScriptRule {
+        ScriptRule {
+            name: name.to_string(),
+            when: when.to_string(),
+            for_secs,
+            then: Action::Profile("Quiet".to_string()),
+        }
+    }
+
+    #[test]
+    fn follows_operator_precedence() {
+        let readings = Readings::default();
+        assert_eq!(check("1 + 2 * 3 == 7", &readings), Ok(true));
+        assert_eq!(check("(1 + 2) * 3 == 9", &readings), Ok(true));
+        assert_eq!(check("true || false && false", &readings), Ok(true));
+        assert_eq!(check("!false == true", &readings), Ok(true));
+    }
+
+    #[test]
+    fn compares_readings_with_whole_numbers() {
+        let readings = Readings {
+            cpu_temp: Some(90.5),
+            on_battery: true,
+            ..Default::default()
+        };
+        assert_eq!(check("cpu_temp > 85", &readings), Ok(true));
+        assert_eq!(check("cpu_temp - 5 <= 85.5 && on_battery", &readings), Ok(true));
+        assert_eq!(check("cpu_temp < 85 || on_ac", &readings), Ok(false));
+    }
+
+    #[test]
+    fn missing_readings_are_false() {
+        let mut runner = ScriptRunner::default();
+        let readings = Readings::default();
+        let scripts = [script("Hot", "cpu_temp > 85", 0), script("Not hot", "!(cpu_temp > 85)", 0)];
+        assert!(runner.tick(&scripts, &readings, Instant::now()).is_empty());
+        // Short-circuiting skips the missing battery.
+        assert_eq!(check("on_battery && battery < 20", &readings), Ok(false));
+    }
+
+    #[test]
+    fn rejects_broken_scripts_before_running() {
+        let cases = [
+            ("cpu_tmp > 85", "Unknown variable: cpu_tmp"),
+            ("cpu_temp + 1", "bool"),
+            ("cpu_temp >", "incomplete"),
+            ("let hot = cpu_temp > 85", ""),
+            ("while true {}", ""),
+            ("print(\"hi\")", "print"),
+            // Only reached when on battery, which the check also tries.
+            ("on_battery && cpu_temp + true", "+"),
+        ];
+        for (source, error) in cases {
+            match compile(&engine(), source) {
+                Ok(_) => panic!("{} compiled", source),
+                Err(e) => assert!(e.contains(error), "{}: {}", source, e),
+            }
+        }
+    }
+
+    #[test]
+    fn limits_nesting_depth() {
+        let deep = format!("{}true{}", "(".repeat(10_000), ")".repeat(10_000));
+        assert!(compile(&engine(), &deep).is_err());
+        let negated = format!("{}true", "!".repeat(10_000));
+        assert!(compile(&engine(), &negated).is_err());
+    }
+
+    #[test]
+    fn acts_once_after_the_condition_holds_long_enough() {
+        let mut runner = ScriptRunner::default();
+        let scripts = [script("Hot", "cpu_temp > 85", 60)];
+        let hot = Readings {
+            cpu_temp: Some(90.0),
+            ..Default::default()
+        };
+        let start = Instant::now();
+
+        assert!(runner.tick(&scripts, &hot, start).is_empty());
+        assert!(runner.tick(&scripts, &hot, start + Duration::from_secs(30)).is_empty());
+        let fired = runner.tick(&scripts, &hot, start + Duration::from_secs(60));
+        assert_eq!(fired, vec![Ok(scripts[0].clone())]);
+        assert!(runner.tick(&scripts, &hot, start + Duration::from_secs(65)).is_empty());
+
+        // Cooling down starts a new stretch.
+        runner.tick(&scripts, &Readings::default(), start + Duration::from_secs(70));
+        assert!(runner.tick(&scripts, &hot, start + Duration::from_secs(75)).is_empty());
+        assert_eq!(runner.tick(&scripts, &hot, start + Duration::from_secs(135)).len(), 1);
+    }
+
+    #[test]
+    fn reports_a_broken_script_once() {
+        let mut runner = ScriptRunner::default();
+        let scripts = [script("Typo", "cpu_tmp > 85", 0)];
+        let errors = runner.tick(&scripts, &Readings::default(), Instant::now());
+        assert!(matches!(errors.as_slice(), [Err(e)] if e.starts_with("Script \"Typo\": Unknown variable")));
+        assert!(runner.tick(&scripts, &Readings::default(), Instant::now()).is_empty());
+    }
+}
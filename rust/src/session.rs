@@ -0,0 +1,117 @@
+use crate::remote;
+use once_cell::sync::Lazy;
+use std::env;
+
+/// The desktop or compositor running the graphical session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Desktop {
+    Hyprland,
+    Sway,
+    Gnome,
+    Kde,
+    /// Some other desktop that named itself in `XDG_CURRENT_DESKTOP`.
+    Other,
+    /// Nothing to go by, e.g. when tuning a remote host over SSH.
+    #[default]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DisplayServer {
+    Wayland,
+    X11,
+    #[default]
+    Unknown,
+}
+
+/// What kind of session TuxTuner runs in, which decides the backends it
+/// can use for refresh rates, night light, screen blanking and effects.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Session {
+    pub desktop: Desktop,
+    /// `XDG_CURRENT_DESKTOP` as given, for reports.
+    pub desktop_name: String,
+    pub display_server: DisplayServer,
+    /// Whether the tuned machine booted with systemd as init.
+    pub systemd: bool,
+}
+
+static SESSION: Lazy<Session> = Lazy::new(Session::detect);
+
+/// The session detected at startup.
+pub fn current() -> &'static Session {
+    &SESSION
+}
+
+impl Session {
+    /// Reads the session from the environment. Over SSH the desktop is the
+    /// remote one, which the local environment says nothing about.
+    pub fn detect() -> Self {
+        let systemd = remote::exists("/run/systemd/system");
+        if remote::host().is_some() {
+            return Self {
+                systemd,
+                ..Self::default()
+            };
+        }
+
+        let var = |name: &str| env::var(name).unwrap_or_default();
+        let desktop_name = var("XDG_CURRENT_DESKTOP");
+        let names: Vec<String> = desktop_name.split(':').map(|name| name.to_ascii_lowercase()).collect();
+        let named = |wanted: &str| names.iter().any(|name| name == wanted);
+
+        // The compositors' own sockets are more reliable than the desktop
+        // name, which display managers don't always set.
+        let desktop = if env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() || named("hyprland") {
+            Desktop::Hyprland
+        } else if env::var_os("SWAYSOCK").is_some() || named("sway") {
+            Desktop::Sway
+        } else if named("gnome") {
+            Desktop::Gnome
+        } else if named("kde") {
+            Desktop::Kde
+        } else if !desktop_name.is_empty() {
+            Desktop::Other
+        } else {
+            Desktop::Unknown
+        };
+
+        let display_server = match var("XDG_SESSION_TYPE").as_str() {
+            "wayland" => DisplayServer::Wayland,
+            "x11" => DisplayServer::X11,
+            _ if env::var_os("WAYLAND_DISPLAY").is_some() => DisplayServer::Wayland,
+            _ if env::var_os("DISPLAY").is_some() => DisplayServer::X11,
+            _ => DisplayServer::Unknown,
+        };
+
+        Self {
+            desktop,
+            desktop_name,
+            display_server,
+            systemd,
+        }
+    }
+
+    /// Whether hyprctl may work here: in a Hyprland session, or on a
+    /// remote host whose session can't be seen from here.
+    pub fn may_be_hyprland(&self) -> bool {
+        self.desktop == Desktop::Hyprland || remote::host().is_some()
+    }
+
+    /// Short description such as "GNOME (Wayland)".
+    pub fn label(&self) -> String {
+        let desktop = match self.desktop {
+            Desktop::Hyprland => "Hyprland",
+            Desktop::Sway => "Sway",
+            Desktop::Gnome => "GNOME",
+            Desktop::Kde => "KDE Plasma",
+            Desktop::Other => self.desktop_name.as_str(),
+            Desktop::Unknown => "Unknown desktop",
+        };
+        match self.display_server {
+            DisplayServer::Wayland => format!("{} (Wayland)", desktop),
+            DisplayServer::X11 => format!("{} (X11)", desktop),
+            DisplayServer::Unknown => desktop.to_string(),
+        }
+    }
+}
@@ -0,0 +1,336 @@
+use crate::battery;
+use crate::battery_history::unix_now;
+use crate::config::{state_dir, Config, NightLightConfig};
+use crate::cpufreq::{self, Governor};
+use crate::effects;
+use crate::hyprland;
+use crate::itmt;
+use crate::lenovo::{self, LenovoFeature};
+use crate::modparams;
+use crate::nic;
+use crate::privileges;
+use crate::profiles;
+use crate::remote;
+use crate::storage::Writeback;
+use crate::system76;
+use crate::system_info::{self, SystemInfo};
+use crate::thinkpad;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const SNAPSHOT_FILE: &str = "snapshot.json";
+
+/// Refresh rate and VRR of one monitor, keyed like monitor preferences.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonitorMode {
+    pub identity: String,
+    pub name: String,
+    pub refresh_hz: u32,
+    pub vrr: bool,
+}
+
+/// Standby power settings of one wired interface.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NicPower {
+    pub name: String,
+    pub wol: Option<bool>,
+    pub eee: Option<bool>,
+}
+
+/// The value saved for a module parameter in TuxTuner's modprobe.d
+/// file; `None` when none was, so the module's own default applies.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedModuleParam {
+    pub module: String,
+    pub name: String,
+    pub value: Option<String>,
+}
+
+/// Every knob TuxTuner can both read and set, as found at `taken_at`.
+/// `None` means the machine didn't expose it, and restoring leaves it be.
+///
+/// Restore is partial: the GPU mode, which needs a new session, huge
+/// pages and what TuxTuner installs rather than sets (undervolt, fan
+/// curves, wakeup and device power rules, boot options) are left as they
+/// are. `factory_reset` removes the installed parts.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Snapshot {
+    pub taken_at: i64,
+    /// Taken before TuxTuner ever ran, so it holds the hardware's own
+    /// defaults. Defaults recorded on the first run after an upgrade may
+    /// already be tuned and aren't offered as factory settings.
+    pub factory: bool,
+    /// The remote host it was taken on; `None` for this machine.
+    pub host: Option<String>,
+    pub cpu_threads: Option<u32>,
+    pub platform_profile: Option<String>,
+    pub governor: Option<String>,
+    /// Energy-performance preference, e.g. `balance_power`.
+    pub epp: Option<String>,
+    pub boost: Option<bool>,
+    pub itmt: Option<bool>,
+    pub writeback: Option<Writeback>,
+    pub charge_limit: Option<u32>,
+    pub charge_start: Option<u32>,
+    pub conservation_mode: Option<bool>,
+    pub fn_lock: Option<bool>,
+    pub rapid_charge: Option<bool>,
+    pub effects: Option<bool>,
+    pub skip_idle_frames: Option<bool>,
+    pub monitors: Vec<MonitorMode>,
+    pub nics: Vec<NicPower>,
+    /// Parameters of the modules loaded at `taken_at`.
+    pub module_params: Vec<SavedModuleParam>,
+    /// Night light runs in this session rather than on the tuned host.
+    pub night_light: Option<NightLightConfig>,
+}
+
+fn path() -> PathBuf {
+    state_dir().join(SNAPSHOT_FILE)
+}
+
+/// Where the state found on first run is kept, one file per tuned host.
+fn defaults_path() -> PathBuf {
+    match remote::host() {
+        Some(host) => state_dir().join(format!("defaults-{}.json", host)),
+        None => state_dir().join("defaults.json"),
+    }
+}
+
+/// Reads the current state. Blocking; run it off the main thread.
+pub fn capture() -> Snapshot {
+    let (_, online_cpus) = SystemInfo::fetch_cpu_info();
+    let lenovo_loaded = lenovo::loaded();
+    let lenovo_feature = |feature| lenovo_loaded.then(|| lenovo::feature(feature)).flatten();
+
+    Snapshot {
+        taken_at: unix_now(),
+        factory: false,
+        host: remote::host().map(String::from),
+        cpu_threads: Some(online_cpus),
+        platform_profile: profiles::platform_profile(),
+        governor: cpufreq::governor().map(|governor| governor.as_str().to_string()),
+        epp: cpufreq::energy_preference(),
+        boost: cpufreq::boost(),
+        itmt: itmt::enabled(),
+        writeback: Writeback::read(),
+        charge_limit: battery::charge_limit(),
+        charge_start: thinkpad::present().then(thinkpad::start_threshold).flatten(),
+        conservation_mode: lenovo_feature(LenovoFeature::ConservationMode),
+        fn_lock: lenovo_feature(LenovoFeature::FnLock),
+        rapid_charge: lenovo_feature(LenovoFeature::RapidCharge),
+        effects: effects::detect().and_then(effects::enabled),
+        skip_idle_frames: hyprland::skip_idle_frames(),
+        monitors: system_info::fetch_monitors()
+            .into_iter()
+            .map(|monitor| MonitorMode {
+                identity: monitor.identity,
+                name: monitor.name,
+                refresh_hz: monitor.refresh_hz,
+                vrr: monitor.vrr,
+            })
+            .collect(),
+        nics: nic::interfaces()
+            .into_iter()
+            .map(|nic| NicPower {
+                wol: nic.wol_supported.then_some(nic.wol_enabled),
+                eee: nic.eee,
+                name: nic.name,
+            })
+            .collect(),
+        module_params: modparams::PARAMS
+            .iter()
+            .filter(|param| param.current().is_some())
+            .map(|param| SavedModuleParam {
+                module: param.module.to_string(),
+                name: param.name.to_string(),
+                value: modparams::saved(param),
+            })
+            .collect(),
+        night_light: Some(Config::load().night_light),
+    }
+}
+
+fn write(path: &Path, snapshot: &Snapshot) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(snapshot).map_err(|e| e.to_string())?;
+    fs::create_dir_all(state_dir()).map_err(|e| e.to_string())?;
+    fs::write(path, content).map_err(|e| e.to_string())
+}
+
+fn read(path: &Path) -> Option<Snapshot> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+}
+
+pub fn save(snapshot: &Snapshot) -> Result<(), String> {
+    write(&path(), snapshot)
+}
+
+/// The saved snapshot, if one was taken and still parses.
+pub fn load() -> Option<Snapshot> {
+    read(&path())
+}
+
+/// Whether TuxTuner has never run as this user: there is no config or
+/// state yet. Ask before anything writes either.
+pub fn first_run() -> bool {
+    !Config::path().exists() && !state_dir().exists()
+}
+
+/// Captures the state the first time TuxTuner runs against a host. Only
+/// a `first_run` on this machine counts as factory state; a remote host
+/// may have been tuned from elsewhere. Blocking; run it off the main
+/// thread.
+pub fn record_defaults(first_run: bool) {
+    let path = defaults_path();
+    if !path.exists() {
+        let defaults = Snapshot {
+            factory: first_run && remote::host().is_none(),
+            ..capture()
+        };
+        let _ = write(&path, &defaults);
+    }
+}
+
+/// The state recorded on first run, if it predates any tuning.
+pub fn factory_defaults() -> Option<Snapshot> {
+    read(&defaults_path()).filter(|defaults| defaults.factory)
+}
+
+/// Puts back the factory state, when one was recorded, and removes the
+/// boot-time services, udev rules and configs the helper installed.
+/// Blocking; run it off the main thread.
+pub fn factory_reset() -> Result<(), String> {
+    let restored = match factory_defaults() {
+        Some(defaults) => restore(&defaults),
+        None => Ok(()),
+    };
+    remote::run_helper(&["factory-reset"])?;
+    restored
+}
+
+/// Sets every knob recorded in `snapshot`. Keeps going past failures so
+/// one missing driver doesn't leave the rest unrestored, and reports them
+/// together. Blocking; run it off the main thread.
+pub fn restore(snapshot: &Snapshot) -> Result<(), String> {
+    if snapshot.host.as_deref() != remote::host() {
+        return Err(match &snapshot.host {
+            Some(host) => format!("The snapshot was taken on {}", host),
+            None => "The snapshot was taken on this machine, not the remote host".to_string(),
+        });
+    }
+
+    privileges::preauthorize()?;
+
+    let mut errors = Vec::new();
+    let mut check = |what: &str, result: Result<(), String>| {
+        if let Err(e) = result {
+            errors.push(format!("{}: {}", what, e.trim()));
+        }
+    };
+
+    if let Some(profile) = &snapshot.platform_profile {
+        let result = if system76::installed() {
+            system76::set_profile(profile)
+        } else {
+            profiles::apply_platform_profile(profile)
+        };
+        check("Platform profile", result);
+    }
+    if let Some(threads) = snapshot.cpu_threads {
+        check("CPU threads", system_info::apply_cpu_threads(threads.max(1)));
+    }
+    // After the thread count, so CPUs brought back online get them too.
+    if let Some(name) = &snapshot.governor {
+        let result = name.parse::<Governor>().and_then(cpufreq::set_governor);
+        check("CPU governor", result);
+    }
+    if let Some(value) = &snapshot.epp {
+        check("Energy preference", cpufreq::set_energy_preference(value));
+    }
+    if let Some(on) = snapshot.boost {
+        check("CPU boost", cpufreq::set_boost(on));
+    }
+    if let Some(on) = snapshot.itmt {
+        check("Core ranking", itmt::set_enabled(on));
+    }
+    if let Some(writeback) = snapshot.writeback {
+        check("Disk writeback", writeback.apply());
+    }
+    if let Some(percent) = snapshot.charge_limit {
+        check("Charge limit", battery::apply_charge_limit(percent));
+    }
+    if let Some(percent) = snapshot.charge_start {
+        check("Charge start", thinkpad::apply_start_threshold(percent));
+    }
+    for (what, feature, value) in [
+        ("Conservation mode", LenovoFeature::ConservationMode, snapshot.conservation_mode),
+        ("Fn Lock", LenovoFeature::FnLock, snapshot.fn_lock),
+        ("Rapid charge", LenovoFeature::RapidCharge, snapshot.rapid_charge),
+    ] {
+        if let Some(on) = value {
+            check(what, lenovo::apply_feature(feature, on));
+        }
+    }
+    if let (Some(on), Some(backend)) = (snapshot.effects, effects::detect()) {
+        check("Effects", effects::set_enabled(backend, on));
+    }
+    if let Some(on) = snapshot.skip_idle_frames {
+        check("Idle frames", hyprland::set_skip_idle_frames(on));
+    }
+
+    for saved in &snapshot.nics {
+        if let Some(on) = saved.wol {
+            check(&saved.name, nic::apply_wol(&saved.name, on));
+        }
+        if let Some(on) = saved.eee {
+            check(&saved.name, nic::apply_eee(&saved.name, on));
+        }
+    }
+    for saved in &snapshot.module_params {
+        let Some(param) = modparams::PARAMS
+            .iter()
+            .find(|param| param.module == saved.module && param.name == saved.name)
+        else {
+            continue;
+        };
+        if modparams::saved(param) != saved.value {
+            check(param.title, modparams::set(param, saved.value.as_deref()).map(|_| ()));
+        }
+    }
+    if let Some(night_light) = &snapshot.night_light {
+        let mut config = Config::load();
+        config.night_light = night_light.clone();
+        check("Night light", config.save());
+    }
+
+    // Monitors may have moved ports since; match them by EDID identity.
+    let current = system_info::fetch_monitors();
+    for saved in &snapshot.monitors {
+        let Some(monitor) = current
+            .iter()
+            .find(|monitor| !saved.identity.is_empty() && monitor.identity == saved.identity)
+            .or_else(|| current.iter().find(|monitor| monitor.name == saved.name))
+        else {
+            continue;
+        };
+        if monitor.refresh_hz != saved.refresh_hz || monitor.vrr != saved.vrr {
+            check(
+                &monitor.name,
+                system_info::apply_monitor_mode(monitor, saved.refresh_hz, Some(saved.vrr)),
+            );
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("\n"))
+    }
+}
@@ -0,0 +1,79 @@
+use crate::probe;
+use crate::remote;
+use serde::{Deserialize, Serialize};
+
+const DIRTY_RATIO_PATH: &str = "/proc/sys/vm/dirty_ratio";
+const WRITEBACK_PATH: &str = "/proc/sys/vm/dirty_writeback_centisecs";
+/// util-linux's weekly trim of mounted filesystems.
+const TRIM_TIMER: &str = "fstrim.timer";
+
+/// Bounds the helper accepts; keep in sync with its `MIN_DIRTY_RATIO` and
+/// friends.
+pub const DIRTY_RATIO_RANGE: (u32, u32) = (5, 60);
+pub const WRITEBACK_SECS_RANGE: (u32, u32) = (1, 60);
+
+/// How the kernel batches writes to disk. A higher dirty ratio and a
+/// longer interval let the disk sleep longer, at the risk of losing more
+/// unsaved data on a crash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Writeback {
+    /// Percent of memory dirty pages may fill before writers have to
+    /// wait for the disk.
+    pub dirty_ratio: u32,
+    /// Seconds between flusher wake-ups.
+    pub interval_secs: u32,
+}
+
+fn read_value(path: &str) -> Option<u32> {
+    remote::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+impl Writeback {
+    pub fn read() -> Option<Self> {
+        Some(Self {
+            dirty_ratio: read_value(DIRTY_RATIO_PATH)?,
+            interval_secs: read_value(WRITEBACK_PATH)? / 100,
+        })
+    }
+
+    /// Sets both until reboot. Blocking.
+    pub fn apply(self) -> Result<(), String> {
+        remote::run_helper(&["writeback", &self.dirty_ratio.to_string(), &self.interval_secs.to_string()])
+    }
+}
+
+/// State of the weekly trim timer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrimTimer {
+    pub enabled: bool,
+    /// When it last fired, as systemd prints it.
+    pub last_run: Option<String>,
+}
+
+/// `None` when util-linux's timer isn't installed. Blocking.
+pub fn trim_timer() -> Option<TrimTimer> {
+    let output = probe::run("systemctl", &["is-enabled", TRIM_TIMER]).ok()?;
+    let enabled = match String::from_utf8_lossy(&output.stdout).trim() {
+        "enabled" => true,
+        "disabled" => false,
+        _ => return None,
+    };
+    let last_run = probe::run("systemctl", &["show", TRIM_TIMER, "--property=LastTriggerUSec", "--value"])
+        .ok()
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|last| !last.is_empty() && last != "n/a");
+    Some(TrimTimer { enabled, last_run })
+}
+
+/// Blocking.
+pub fn set_trim_timer(enabled: bool) -> Result<(), String> {
+    remote::run_helper(&["fstrim-timer", if enabled { "on" } else { "off" }])
+}
+
+/// Trims every mounted filesystem now, returning the helper's summary.
+/// Blocking; can take a while on large disks.
+pub fn trim_now() -> Result<String, String> {
+    let mut summary = String::new();
+    remote::run_helper_batch(&[vec!["fstrim"]], |_, output| summary = output.to_string())?;
+    Ok(summary)
+}
@@ -0,0 +1,67 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Reads kernel attributes on this machine. Code that parses sysfs takes
+/// one so it can be tested against a made-up tree.
+pub trait SysfsProvider: Send + Sync {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    /// Entries of `dir`, as full paths.
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>>;
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        String::from_utf8(self.read(path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// The real `/sys`.
+pub struct LocalSysfs;
+
+impl SysfsProvider for LocalSysfs {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        Ok(fs::read_dir(dir)?.flatten().map(|entry| entry.path()).collect())
+    }
+}
+
+/// A sysfs tree held in memory, for tests.
+#[cfg(test)]
+#[derive(Default)]
+pub struct FakeSysfs {
+    files: std::collections::BTreeMap<PathBuf, Vec<u8>>,
+}
+
+#[cfg(test)]
+impl FakeSysfs {
+    pub fn with(mut self, path: &str, content: impl AsRef<[u8]>) -> Self {
+        self.files.insert(PathBuf::from(path), content.as_ref().to_vec());
+        self
+    }
+}
+
+#[cfg(test)]
+impl SysfsProvider for FakeSysfs {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.files.get(path).cloned().ok_or_else(|| io::ErrorKind::NotFound.into())
+    }
+
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut entries: Vec<PathBuf> = self
+            .files
+            .keys()
+            .filter_map(|path| {
+                let rest = path.strip_prefix(dir).ok()?;
+                Some(dir.join(rest.components().next()?))
+            })
+            .collect();
+        entries.dedup();
+        if entries.is_empty() {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+        Ok(entries)
+    }
+}
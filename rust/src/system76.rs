@@ -0,0 +1,81 @@
+use crate::probe;
+use crate::system_info::command_exists;
+use gtk4::prelude::*;
+use gtk4::{gio, glib};
+
+const BUS_NAME: &str = "com.system76.PowerDaemon";
+const OBJECT_PATH: &str = "/com/system76/PowerDaemon";
+const INTERFACE: &str = "com.system76.PowerDaemon";
+
+/// `SetGraphics` rebuilds the initramfs, which easily outlasts a probe.
+const NO_TIMEOUT: i32 = i32::MAX;
+
+fn call(
+    method: &str,
+    args: Option<&glib::Variant>,
+    reply: &str,
+    timeout_ms: i32,
+) -> Result<glib::Variant, String> {
+    let connection = gio::bus_get_sync(gio::BusType::System, None::<&gio::Cancellable>)
+        .map_err(|e| e.to_string())?;
+    connection
+        .call_sync(
+            Some(BUS_NAME),
+            OBJECT_PATH,
+            INTERFACE,
+            method,
+            args,
+            Some(glib::VariantTy::new(reply).map_err(|e| e.to_string())?),
+            gio::DBusCallFlags::NO_AUTO_START,
+            timeout_ms,
+            None::<&gio::Cancellable>,
+        )
+        .map_err(|e| e.message().to_string())
+}
+
+/// Whether system76-power is installed. Cheap; doesn't touch the bus.
+pub fn installed() -> bool {
+    command_exists("system76-power")
+}
+
+fn probe_timeout_ms() -> i32 {
+    probe::timeout().as_millis().min(i32::MAX as u128) as i32
+}
+
+fn get_string(method: &str) -> Result<String, String> {
+    call(method, None, "(s)", probe_timeout_ms())?
+        .get::<(String,)>()
+        .map(|(value,)| value)
+        .ok_or_else(|| format!("Unexpected reply to {}", method))
+}
+
+/// Current graphics mode: `integrated`, `hybrid`, `nvidia` or `compute`.
+pub fn graphics() -> Result<String, String> {
+    get_string("GetGraphics")
+}
+
+/// Whether the machine has switchable graphics at all.
+pub fn switchable() -> bool {
+    call("GetSwitchable", None, "(b)", probe_timeout_ms())
+        .ok()
+        .and_then(|reply| reply.get::<(bool,)>())
+        .is_some_and(|(switchable,)| switchable)
+}
+
+/// Blocking, and slow: the daemon regenerates the initramfs. It asks
+/// polkit for authorization itself.
+pub fn set_graphics(mode: &str) -> Result<(), String> {
+    call("SetGraphics", Some(&(mode,).to_variant()), "()", NO_TIMEOUT).map(|_| ())
+}
+
+/// Switches the daemon's power profile, mapping ACPI platform profile
+/// names onto its `Battery`, `Balanced` and `Performance` methods.
+pub fn set_profile(platform_profile: &str) -> Result<(), String> {
+    let method = match platform_profile {
+        "low-power" | "quiet" | "cool" => "Battery",
+        "balanced" => "Balanced",
+        "performance" => "Performance",
+        other => return Err(format!("Unsupported platform profile: {}", other)),
+    };
+    call(method, None, "()", probe_timeout_ms()).map(|_| ())
+}
@@ -1,29 +1,38 @@
+use crate::config::state_dir;
+use crate::gpu::{self, GpuMode};
+use crate::probe::{self, Capability, ProbeError};
+use crate::remote;
+use crate::session::{self, Session};
+use crate::sysfs::{LocalSysfs, SysfsProvider};
+use crate::validate;
+use gtk4::glib;
 use once_cell::sync::Lazy;
 use regex::Regex;
-use serde::Deserialize;
-use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
 use std::fs;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
 
 pub const HELPER_PATH: &str = "/usr/lib/tuxtuner/tuxtuner-helper";
 
-pub static VALID_GPU_MODES: Lazy<HashSet<&'static str>> = Lazy::new(|| {
-    ["Integrated", "Hybrid", "Dedicated", "Compute", "VFIO"]
-        .into_iter()
-        .collect()
-});
-
-pub static MONITOR_NAME_PATTERN: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"^[A-Za-z0-9_-]+$").unwrap());
+/// Last successful snapshot, shown while fresh data loads on startup.
+const CACHE_FILE: &str = "system-info.json";
+const DRM_PATH: &str = "/sys/class/drm";
 
 pub static SESSION_ID_PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[0-9]+$").unwrap());
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SystemInfo {
     pub total_cpus: u32,
     pub online_cpus: u32,
-    pub gpu_mode: String,
-    pub supported_gpu_modes: Vec<String>,
+    /// Tool behind the GPU backend, named in status messages.
+    pub gpu_tool: String,
+    pub gpu_mode: Option<GpuMode>,
+    pub supported_gpu_modes: Vec<GpuMode>,
+    pub gpu_mux: bool,
     pub refresh_rates: Vec<String>,
     pub current_hz: String,
     pub native_hz: String,
@@ -33,6 +42,15 @@ pub struct SystemInfo {
     pub monitor_x: i32,
     pub monitor_y: i32,
     pub monitor_scale: f64,
+    pub monitor_identity: String,
+    pub monitor_vrr: bool,
+    /// Detected per run rather than cached.
+    #[serde(skip)]
+    pub session: Session,
+    #[serde(skip)]
+    pub gpu_capability: Capability,
+    #[serde(skip)]
+    pub display_capability: Capability,
 }
 
 #[derive(Debug, Deserialize)]
@@ -52,58 +70,354 @@ struct HyprMonitor {
     y: i32,
     #[serde(default = "default_scale")]
     scale: f64,
+    #[serde(default)]
+    make: String,
+    #[serde(default)]
+    model: String,
+    #[serde(default)]
+    serial: String,
+    #[serde(default)]
+    vrr: bool,
 }
 
 fn default_scale() -> f64 {
     1.0
 }
 
+/// Geometry and mode list of a single connected monitor.
+#[derive(Debug, Clone, Default, glib::Boxed)]
+#[boxed_type(name = "TuxTunerMonitorInfo")]
+pub struct MonitorInfo {
+    pub name: String,
+    pub refresh_hz: u32,
+    pub available_hz: Vec<u32>,
+    pub width: u32,
+    pub height: u32,
+    pub x: i32,
+    pub y: i32,
+    pub scale: f64,
+    pub vrr: bool,
+    /// Stable identity derived from the EDID, so preferences follow the
+    /// physical monitor regardless of which port it is plugged into.
+    pub identity: String,
+}
+
+/// Builds a `MFG-PRODUCT-SERIAL` identity from a raw EDID blob.
+fn edid_identity(edid: &[u8]) -> Option<String> {
+    const HEADER: [u8; 8] = [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00];
+    if edid.len() < 128 || edid[..8] != HEADER {
+        return None;
+    }
+
+    let packed = u16::from_be_bytes([edid[8], edid[9]]);
+    let manufacturer: String = [10u16, 5, 0]
+        .iter()
+        .map(|shift| (b'@' + ((packed >> shift) & 0x1f) as u8) as char)
+        .collect();
+    let product = u16::from_le_bytes([edid[10], edid[11]]);
+
+    // Prefer the serial string descriptor (tag 0xff) over the numeric
+    // serial, which many vendors leave zeroed.
+    let serial_text = [54usize, 72, 90, 108].iter().find_map(|&offset| {
+        let desc = &edid[offset..offset + 18];
+        if desc[..3] == [0, 0, 0] && desc[3] == 0xff {
+            let text: String = desc[5..]
+                .iter()
+                .take_while(|&&b| b != 0x0a)
+                .map(|&b| b as char)
+                .collect();
+            Some(text.trim().to_string()).filter(|t| !t.is_empty())
+        } else {
+            None
+        }
+    });
+    let serial = serial_text.unwrap_or_else(|| {
+        u32::from_le_bytes([edid[12], edid[13], edid[14], edid[15]]).to_string()
+    });
+
+    Some(format!("{}-{:04X}-{}", manufacturer, product, serial))
+}
+
+fn read_edid_identity(sysfs: &dyn SysfsProvider, connector: &str) -> Option<String> {
+    let entries = sysfs.read_dir(Path::new(DRM_PATH)).ok()?;
+    entries.into_iter().find_map(|entry| {
+        let name = entry.file_name()?.to_string_lossy().to_string();
+        let matches = name
+            .split_once('-')
+            .is_some_and(|(card, output)| card.starts_with("card") && output == connector);
+        if !matches {
+            return None;
+        }
+        sysfs
+            .read(&entry.join("edid"))
+            .ok()
+            .and_then(|edid| edid_identity(&edid))
+    })
+}
+
+fn monitor_identity(sysfs: &dyn SysfsProvider, mon: &HyprMonitor) -> String {
+    if let Some(identity) = read_edid_identity(sysfs, &mon.name) {
+        return identity;
+    }
+
+    let parts: Vec<&str> = [mon.make.as_str(), mon.model.as_str(), mon.serial.as_str()]
+        .into_iter()
+        .filter(|p| !p.is_empty())
+        .collect();
+    if parts.is_empty() {
+        mon.name.clone()
+    } else {
+        parts.join("-")
+    }
+}
+
+/// Extracts the distinct refresh rates from Hyprland mode strings
+/// such as `2560x1600@165.00Hz`, sorted ascending.
+fn parse_mode_rates(modes: &[String]) -> Vec<f64> {
+    let mut hz_values: Vec<f64> = Vec::new();
+    for mode in modes {
+        if let Some(hz_part) = mode.split('@').nth(1) {
+            let hz_str = hz_part.replace("Hz", "");
+            if let Ok(hz_val) = hz_str.parse::<f64>() {
+                if !hz_values.iter().any(|&v| (v - hz_val).abs() < 0.5) {
+                    hz_values.push(hz_val);
+                }
+            }
+        }
+    }
+
+    hz_values.sort_by(f64::total_cmp);
+    hz_values
+}
+
+fn query_hypr_monitors() -> Result<Vec<HyprMonitor>, ProbeError> {
+    if !session::current().may_be_hyprland() {
+        return Ok(Vec::new());
+    }
+    let output = probe::run("hyprctl", &["monitors", "-j"])?;
+    if !output.status.success() {
+        return Ok(Vec::new());
+    }
+    Ok(serde_json::from_slice(&output.stdout).unwrap_or_default())
+}
+
+/// Reads and sets monitor modes. Display code takes one so it can be
+/// exercised without a compositor.
+pub trait DisplayBackend: Send + Sync {
+    /// Every monitor the compositor drives; empty under one it doesn't
+    /// understand.
+    fn monitors(&self) -> Result<Vec<MonitorInfo>, ProbeError>;
+
+    /// Sets `mon` to `hz`, keeping its resolution, position and scale.
+    /// Callers go through `apply_monitor_mode_with`, which validates.
+    /// Blocking.
+    fn set_mode(&self, mon: &MonitorInfo, hz: u32, vrr: Option<bool>) -> Result<(), String>;
+}
+
+/// Hyprland, through `hyprctl`. Monitors are identified by the EDID
+/// read through `sysfs`.
+pub struct Hyprctl {
+    sysfs: Arc<dyn SysfsProvider>,
+}
+
+impl Hyprctl {
+    pub fn new(sysfs: Arc<dyn SysfsProvider>) -> Self {
+        Self { sysfs }
+    }
+
+    /// Reads this machine's `/sys`.
+    pub fn local() -> Self {
+        Self::new(Arc::new(LocalSysfs))
+    }
+}
+
+impl DisplayBackend for Hyprctl {
+    fn monitors(&self) -> Result<Vec<MonitorInfo>, ProbeError> {
+        Ok(query_hypr_monitors()?
+            .into_iter()
+            .map(|mon| monitor_info(self.sysfs.as_ref(), mon))
+            .collect())
+    }
+
+    fn set_mode(&self, mon: &MonitorInfo, hz: u32, vrr: Option<bool>) -> Result<(), String> {
+        // Use explicit resolution and position to preserve the current monitor
+        // layout. Using "preferred" or "auto" can cause Hyprland to reposition
+        // monitors, which destroys layer surfaces (e.g. Waybar).
+        let scale = if mon.scale > 0.0 { mon.scale } else { 1.0 };
+        let mut monitor_arg = format!(
+            "{},{}x{}@{},{}x{},{}",
+            mon.name, mon.width, mon.height, hz, mon.x, mon.y, scale
+        );
+        if let Some(vrr) = vrr {
+            monitor_arg.push_str(if vrr { ",vrr,1" } else { ",vrr,0" });
+        }
+
+        let output = probe::run("hyprctl", &["keyword", "monitor", &monitor_arg])
+            .map_err(|e| e.to_string())?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(String::from_utf8_lossy(&output.stderr).to_string())
+        }
+    }
+}
+
+/// Lists every monitor Hyprland currently drives; none outside Hyprland.
+pub fn fetch_monitors() -> Vec<MonitorInfo> {
+    Hyprctl::local().monitors().unwrap_or_default()
+}
+
+fn monitor_info(sysfs: &dyn SysfsProvider, mon: HyprMonitor) -> MonitorInfo {
+    MonitorInfo {
+        identity: monitor_identity(sysfs, &mon),
+        vrr: mon.vrr,
+        refresh_hz: mon.refresh_rate.round() as u32,
+        // A garbled mode shouldn't become a rate to switch to.
+        available_hz: parse_mode_rates(&mon.available_modes)
+            .into_iter()
+            .filter_map(|hz| validate::refresh_rate(hz).ok())
+            .collect(),
+        width: mon.width,
+        height: mon.height,
+        x: mon.x,
+        y: mon.y,
+        scale: if mon.scale > 0.0 { mon.scale } else { 1.0 },
+        name: mon.name,
+    }
+}
+
+/// How a rate is shown and stored in `AppState`, e.g. `120Hz`.
+pub fn rate_label(hz: u32) -> String {
+    format!("{}Hz", hz)
+}
+
+/// The rate of a refresh rate choice such as `165Hz (Native)`.
+pub fn rate_from_label(label: &str) -> Option<u32> {
+    label.trim_end_matches(" (Native)").strip_suffix("Hz")?.parse().ok()
+}
+
+/// Runs `probe`, logging how long it took.
+fn timed<T>(name: &str, probe: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let result = probe();
+    glib::g_debug!(crate::LOG_DOMAIN, "{} probe took {:?}", name, started.elapsed());
+    result
+}
+
 impl SystemInfo {
+    /// Probes everything concurrently, so a slow tool (supergfxctl waking
+    /// the dGPU, a busy compositor) only delays startup by its own latency.
     pub fn fetch() -> Self {
-        let (total_cpus, online_cpus) = Self::fetch_cpu_info();
-        let (gpu_mode, supported_gpu_modes) = Self::fetch_gpu_info();
-        let display = Self::fetch_display_info();
+        Self::fetch_with(gpu::detect().as_ref(), &Hyprctl::local())
+    }
+
+    /// `fetch` through the given backends.
+    pub fn fetch_with(gpu: &dyn gpu::GpuBackend, display: &dyn DisplayBackend) -> Self {
+        let started = Instant::now();
+        let ((total_cpus, online_cpus), gpu, display) = thread::scope(|scope| {
+            let cpu = scope.spawn(|| timed("CPU", Self::fetch_cpu_info));
+            let gpu = scope.spawn(|| timed("GPU", || (gpu.tool(), gpu.status())));
+            let display = timed("Display", || Self::fetch_display_info(display));
+            (
+                cpu.join().unwrap_or_default(),
+                gpu.join().unwrap_or_default(),
+                display,
+            )
+        });
+        glib::g_debug!(crate::LOG_DOMAIN, "Hardware probing took {:?}", started.elapsed());
+
+        let (gpu_tool, gpu) = gpu;
+        let (refresh_rates, current_hz, native_hz, monitor, display_capability) = display;
 
         Self {
             total_cpus,
             online_cpus,
-            gpu_mode,
-            supported_gpu_modes,
-            refresh_rates: display.0,
-            current_hz: display.1,
-            native_hz: display.2,
-            monitor_name: display.3,
-            monitor_width: display.4,
-            monitor_height: display.5,
-            monitor_x: display.6,
-            monitor_y: display.7,
-            monitor_scale: display.8,
+            gpu_tool: gpu_tool.to_string(),
+            gpu_mode: gpu.mode,
+            supported_gpu_modes: gpu.modes,
+            gpu_mux: gpu.mux,
+            refresh_rates,
+            current_hz,
+            native_hz,
+            monitor_name: monitor.name,
+            monitor_width: monitor.width,
+            monitor_height: monitor.height,
+            monitor_x: monitor.x,
+            monitor_y: monitor.y,
+            monitor_scale: monitor.scale,
+            monitor_identity: monitor.identity,
+            monitor_vrr: monitor.vrr,
+            session: session::current().clone(),
+            gpu_capability: gpu.capability,
+            display_capability,
         }
     }
 
-    fn fetch_cpu_info() -> (u32, u32) {
+    /// The monitor the display controls act on, as far as the snapshot
+    /// knows it.
+    pub fn monitor(&self) -> MonitorInfo {
+        MonitorInfo {
+            name: self.monitor_name.clone(),
+            width: self.monitor_width,
+            height: self.monitor_height,
+            x: self.monitor_x,
+            y: self.monitor_y,
+            scale: self.monitor_scale,
+            vrr: self.monitor_vrr,
+            identity: self.monitor_identity.clone(),
+            ..Default::default()
+        }
+    }
+
+    fn cache_path() -> PathBuf {
+        state_dir().join(CACHE_FILE)
+    }
+
+    /// The snapshot saved by the last successful fetch, if any.
+    pub fn cached() -> Option<Self> {
+        let data = fs::read_to_string(Self::cache_path()).ok()?;
+        let info: Self = serde_json::from_str(&data).ok()?;
+        Some(Self {
+            session: session::current().clone(),
+            ..info
+        })
+    }
+
+    /// Saves this snapshot for the next startup. A fetch that couldn't
+    /// even count CPUs isn't worth keeping.
+    pub fn save_cache(&self) {
+        if self.total_cpus == 0 {
+            return;
+        }
+        if let Ok(data) = serde_json::to_string(self) {
+            let _ = fs::create_dir_all(state_dir());
+            let _ = fs::write(Self::cache_path(), data);
+        }
+    }
+
+    /// Total and online CPU threads.
+    pub fn fetch_cpu_info() -> (u32, u32) {
         let cpu_path = "/sys/devices/system/cpu";
         let mut total_cpus = 0u32;
         let mut online_cpus = 0u32;
 
-        if let Ok(entries) = fs::read_dir(cpu_path) {
-            for entry in entries.flatten() {
-                let name = entry.file_name();
-                let name_str = name.to_string_lossy();
-
-                if name_str.starts_with("cpu") {
-                    if let Ok(num) = name_str[3..].parse::<u32>() {
-                        total_cpus += 1;
-
-                        if num == 0 {
+        for path in remote::read_dir(cpu_path).unwrap_or_default() {
+            let Some(name) = path.file_name() else {
+                continue;
+            };
+            let name_str = name.to_string_lossy();
+
+            if let Some(Ok(num)) = name_str.strip_prefix("cpu").map(str::parse::<u32>) {
+                total_cpus += 1;
+
+                if num == 0 {
+                    online_cpus += 1;
+                } else {
+                    let online_path = format!("{}/cpu{}/online", cpu_path, num);
+                    if let Ok(content) = remote::read_to_string(&online_path) {
+                        if content.trim() == "1" {
                             online_cpus += 1;
-                        } else {
-                            let online_path = format!("{}/cpu{}/online", cpu_path, num);
-                            if let Ok(content) = fs::read_to_string(&online_path) {
-                                if content.trim() == "1" {
-                                    online_cpus += 1;
-                                }
-                            }
                         }
                     }
                 }
@@ -117,180 +431,152 @@ impl SystemInfo {
         }
     }
 
-    fn fetch_gpu_info() -> (String, Vec<String>) {
-        let mut gpu_mode = String::from("Integrated");
-        let mut supported_modes = Vec::new();
-
-        if let Ok(output) = Command::new("supergfxctl").arg("-s").output() {
-            if output.status.success() {
-                let raw = String::from_utf8_lossy(&output.stdout);
-                let raw = raw.trim().trim_start_matches('[').trim_end_matches(']');
-                supported_modes = raw.split(',').map(|s| s.trim().to_string()).collect();
-            }
-        }
-
-        if let Ok(output) = Command::new("supergfxctl").arg("-g").output() {
-            if output.status.success() {
-                gpu_mode = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            }
-        }
-
-        if supported_modes.is_empty() {
-            gpu_mode = String::from("Unavailable");
-        }
-
-        (gpu_mode, supported_modes)
-    }
-
-    fn fetch_display_info() -> (Vec<String>, String, String, String, u32, u32, i32, i32, f64) {
+    fn fetch_display_info(display: &dyn DisplayBackend) -> (Vec<String>, String, String, MonitorInfo, Capability) {
         let mut refresh_rates = Vec::new();
         let mut current_hz = String::new();
         let mut native_hz = String::new();
-        let mut monitor_name = String::new();
-        let mut monitor_width = 0u32;
-        let mut monitor_height = 0u32;
-        let mut monitor_x = 0i32;
-        let mut monitor_y = 0i32;
-        let mut monitor_scale = 1.0f64;
-
-        if let Ok(output) = Command::new("hyprctl").args(["monitors", "-j"]).output() {
-            if output.status.success() {
-                let json_str = String::from_utf8_lossy(&output.stdout);
-                if let Ok(monitors) = serde_json::from_str::<Vec<HyprMonitor>>(&json_str) {
-                    if let Some(mon) = monitors.first() {
-                        monitor_name = mon.name.clone();
-                        current_hz = format!("{}Hz", mon.refresh_rate as u32);
-                        monitor_width = mon.width;
-                        monitor_height = mon.height;
-                        monitor_x = mon.x;
-                        monitor_y = mon.y;
-                        monitor_scale = if mon.scale > 0.0 { mon.scale } else { 1.0 };
-
-                        let mut hz_values: Vec<f64> = Vec::new();
-                        for mode in &mon.available_modes {
-                            if let Some(hz_part) = mode.split('@').nth(1) {
-                                let hz_str = hz_part.replace("Hz", "");
-                                if let Ok(hz_val) = hz_str.parse::<f64>() {
-                                    if !hz_values.iter().any(|&v| (v - hz_val).abs() < 0.5) {
-                                        hz_values.push(hz_val);
-                                    }
-                                }
-                            }
-                        }
 
-                        hz_values.sort_by(|a, b| a.partial_cmp(b).unwrap());
-
-                        if let Some(&max_hz) = hz_values.last() {
-                            native_hz = format!("{}Hz", max_hz as u32);
-                        }
+        let (monitors, capability) = match display.monitors() {
+            Ok(monitors) if !monitors.is_empty() => (monitors, Capability::Available),
+            Ok(monitors) => (monitors, Capability::Missing),
+            Err(e) => (Vec::new(), Capability::from_error(&e)),
+        };
+        let monitor = monitors.into_iter().next().unwrap_or(MonitorInfo {
+            scale: 1.0,
+            ..Default::default()
+        });
+
+        if !monitor.name.is_empty() {
+            current_hz = rate_label(monitor.refresh_hz);
+
+            if let Some(&max_hz) = monitor.available_hz.last() {
+                native_hz = rate_label(max_hz);
+            }
 
-                        for hz_val in hz_values {
-                            let hz_str = format!("{}Hz", hz_val as u32);
-                            if hz_str == native_hz {
-                                refresh_rates.push(format!("{}Hz (Native)", hz_val as u32));
-                            } else {
-                                refresh_rates.push(hz_str);
-                            }
-                        }
-                    }
+            for &hz_val in &monitor.available_hz {
+                let hz_str = rate_label(hz_val);
+                if hz_str == native_hz {
+                    refresh_rates.push(format!("{}Hz (Native)", hz_val));
+                } else {
+                    refresh_rates.push(hz_str);
                 }
             }
         }
 
-        (
-            refresh_rates,
-            current_hz,
-            native_hz,
-            monitor_name,
-            monitor_width,
-            monitor_height,
-            monitor_x,
-            monitor_y,
-            monitor_scale,
-        )
+        (refresh_rates, current_hz, native_hz, monitor, capability)
     }
 }
 
+/// Whether an executable named `name` is on `$PATH`.
+pub fn command_exists(name: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(name).is_file()))
+        .unwrap_or(false)
+}
+
 pub fn apply_cpu_threads(target: u32) -> Result<(), String> {
-    let output = Command::new("pkexec")
-        .args([HELPER_PATH, "cpu", &target.to_string()])
-        .output()
-        .map_err(|e| e.to_string())?;
+    remote::run_helper(&["cpu", &target.to_string()])
+}
 
-    if output.status.success() {
-        Ok(())
-    } else {
-        Err(String::from_utf8_lossy(&output.stderr).to_string())
-    }
+/// Applies a refresh rate (and optionally VRR) to `mon`, keeping its
+/// current resolution, position and scale.
+pub fn apply_monitor_mode(mon: &MonitorInfo, hz: u32, vrr: Option<bool>) -> Result<(), String> {
+    apply_monitor_mode_with(&Hyprctl::local(), mon, hz, vrr)
 }
 
-pub fn apply_gpu_mode(mode: &str, logout: bool) -> Result<(), String> {
-    if !VALID_GPU_MODES.contains(mode) {
-        return Err(format!("Invalid GPU mode: {}", mode));
+/// `apply_monitor_mode` through `display`.
+pub fn apply_monitor_mode_with(
+    display: &dyn DisplayBackend,
+    mon: &MonitorInfo,
+    hz: u32,
+    vrr: Option<bool>,
+) -> Result<(), String> {
+    validate::monitor_name(&mon.name)?;
+    validate::refresh_hz(hz)?;
+
+    if mon.width == 0 || mon.height == 0 {
+        return Err("Unknown monitor resolution".to_string());
     }
 
-    let mut args = vec![HELPER_PATH, "gpu", mode];
+    display.set_mode(mon, hz, vrr)
+}
+
+/// A monitor at 60Hz that can also do 120Hz, for tests. With `fail`
+/// set, every mode change is refused.
+#[cfg(test)]
+pub struct FakeDisplay {
+    monitor: std::sync::Mutex<MonitorInfo>,
+    fail: bool,
+}
 
-    let session_id = std::env::var("XDG_SESSION_ID").ok();
-    if logout {
-        if let Some(ref sid) = session_id {
-            if SESSION_ID_PATTERN.is_match(sid) {
-                args.push("--logout");
-                args.push(sid);
-            }
+#[cfg(test)]
+impl FakeDisplay {
+    pub fn new(fail: bool) -> Self {
+        Self {
+            monitor: std::sync::Mutex::new(MonitorInfo {
+                name: "eDP-1".to_string(),
+                refresh_hz: 60,
+                available_hz: vec![60, 120],
+                width: 2560,
+                height: 1600,
+                scale: 1.0,
+                ..Default::default()
+            }),
+            fail,
         }
     }
 
-    let output = Command::new("pkexec")
-        .args(&args)
-        .output()
-        .map_err(|e| e.to_string())?;
+    pub fn refresh_hz(&self) -> u32 {
+        self.monitor.lock().unwrap().refresh_hz
+    }
+}
 
-    if output.status.success() {
+#[cfg(test)]
+impl DisplayBackend for FakeDisplay {
+    fn monitors(&self) -> Result<Vec<MonitorInfo>, ProbeError> {
+        Ok(vec![self.monitor.lock().unwrap().clone()])
+    }
+
+    fn set_mode(&self, _mon: &MonitorInfo, hz: u32, _vrr: Option<bool>) -> Result<(), String> {
+        if self.fail {
+            return Err("hyprctl failed".to_string());
+        }
+        self.monitor.lock().unwrap().refresh_hz = hz;
         Ok(())
-    } else {
-        Err(String::from_utf8_lossy(&output.stderr).to_string())
     }
 }
 
-pub fn apply_refresh_rate(
-    monitor: &str,
-    hz: u32,
-    width: u32,
-    height: u32,
-    x: i32,
-    y: i32,
-    scale: f64,
-) -> Result<(), String> {
-    if !MONITOR_NAME_PATTERN.is_match(monitor) {
-        return Err("Invalid monitor name".to_string());
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::FakeSysfs;
+
+    /// A minimal EDID for `BOE` product 0x0A1B with serial 1234.
+    fn edid() -> Vec<u8> {
+        let mut edid = vec![0u8; 128];
+        edid[..8].copy_from_slice(&[0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00]);
+        edid[8..10].copy_from_slice(&0x09e5u16.to_be_bytes());
+        edid[10..12].copy_from_slice(&0x0a1bu16.to_le_bytes());
+        edid[12..16].copy_from_slice(&1234u32.to_le_bytes());
+        edid
     }
 
-    if !(30..=500).contains(&hz) {
-        return Err("Refresh rate out of valid range".to_string());
-    }
+    #[test]
+    fn reads_identity_of_the_matching_connector() {
+        let sysfs = FakeSysfs::default()
+            .with("/sys/class/drm/card1-eDP-1/edid", edid())
+            .with("/sys/class/drm/card1-DP-1/edid", "");
 
-    if width == 0 || height == 0 {
-        return Err("Unknown monitor resolution".to_string());
+        assert_eq!(read_edid_identity(&sysfs, "eDP-1").as_deref(), Some("BOE-0A1B-1234"));
+        assert_eq!(read_edid_identity(&sysfs, "DP-1"), None);
+        assert_eq!(read_edid_identity(&sysfs, "HDMI-A-1"), None);
     }
 
-    // Use explicit resolution and position to preserve the current monitor
-    // layout. Using "preferred" or "auto" can cause Hyprland to reposition
-    // monitors, which destroys layer surfaces (e.g. Waybar).
-    let scale = if scale > 0.0 { scale } else { 1.0 };
-    let monitor_arg = format!(
-        "{},{}x{}@{},{}x{},{}",
-        monitor, width, height, hz, x, y, scale
-    );
-
-    let output = Command::new("hyprctl")
-        .args(["keyword", "monitor", &monitor_arg])
-        .output()
-        .map_err(|e| e.to_string())?;
-
-    if output.status.success() {
-        Ok(())
-    } else {
-        Err(String::from_utf8_lossy(&output.stderr).to_string())
+    #[test]
+    fn rate_labels_round_trip() {
+        assert_eq!(rate_label(120), "120Hz");
+        assert_eq!(rate_from_label("120Hz"), Some(120));
+        assert_eq!(rate_from_label("165Hz (Native)"), Some(165));
+        assert_eq!(rate_from_label("fast"), None);
     }
 }
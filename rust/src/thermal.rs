@@ -0,0 +1,161 @@
+use crate::probe;
+use crate::remote;
+use std::collections::HashMap;
+use std::path::Path;
+
+const HWMON_PATH: &str = "/sys/class/hwmon";
+const THERMAL_PATH: &str = "/sys/class/thermal";
+
+/// hwmon drivers reporting the CPU package temperature, most specific first.
+const CPU_HWMON_DRIVERS: [&str; 3] = ["k10temp", "coretemp", "zenpower"];
+/// Thermal zones used when no CPU hwmon driver is loaded.
+const CPU_THERMAL_ZONES: [&str; 2] = ["x86_pkg_temp", "acpitz"];
+
+fn read_millidegrees(path: &Path) -> Option<f64> {
+    let value: i64 = remote::read_to_string(path).ok()?.trim().parse().ok()?;
+    Some(value as f64 / 1000.0)
+}
+
+fn read_name(dir: &Path, attr: &str) -> String {
+    remote::read_to_string(dir.join(attr))
+        .map(|name| name.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// CPU package temperature in °C.
+pub fn cpu_temperature() -> Option<f64> {
+    let hwmons = remote::read_dir(HWMON_PATH).unwrap_or_default();
+
+    for driver in CPU_HWMON_DRIVERS {
+        if let Some(dir) = hwmons.iter().find(|dir| read_name(dir, "name") == driver) {
+            // temp1 is Tctl on AMD and the package sensor on Intel.
+            if let Some(celsius) = read_millidegrees(&dir.join("temp1_input")) {
+                return Some(celsius);
+            }
+        }
+    }
+
+    let zones: Vec<_> = remote::read_dir(THERMAL_PATH)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with("thermal_zone"))
+        })
+        .collect();
+
+    CPU_THERMAL_ZONES.iter().find_map(|kind| {
+        let zone = zones.iter().find(|zone| read_name(zone, "type") == *kind)?;
+        read_millidegrees(&zone.join("temp"))
+    })
+}
+
+const NVME_PATH: &str = "/sys/class/nvme";
+/// Drives within this many degrees of their warning threshold show as hot.
+pub const NVME_HOT_MARGIN_C: f64 = 5.0;
+/// Consecutive throttling readings before it counts as sustained.
+const NVME_SUSTAINED_READINGS: u32 = 3;
+
+/// An NVMe drive's composite temperature and the thresholds it reports.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NvmeTemperature {
+    /// Controller name, e.g. `nvme0`.
+    pub name: String,
+    pub model: String,
+    pub celsius: f64,
+    /// Warning composite temperature; above it the drive slows down to
+    /// cool off.
+    pub warning: Option<f64>,
+    /// Critical temperature, where it throttles hard or shuts down.
+    pub critical: Option<f64>,
+    /// Thermal management transitions since power-on, from nvme-cli's
+    /// SMART log when this user may read it.
+    throttle_count: Option<u64>,
+}
+
+impl NvmeTemperature {
+    pub fn hot(&self) -> bool {
+        self.warning.is_some_and(|warning| self.celsius >= warning - NVME_HOT_MARGIN_C)
+    }
+
+    fn over_warning(&self) -> bool {
+        self.warning.is_some_and(|warning| self.celsius >= warning)
+    }
+}
+
+/// Thermal management transitions from `nvme smart-log`, which needs read
+/// access to the device node; usually only root has it.
+fn nvme_throttle_count(name: &str) -> Option<u64> {
+    let device = format!("/dev/{}", name);
+    let output = probe::run("nvme", &["smart-log", &device, "--output-format=json"]).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let log: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
+    let count = |key: &str| log.get(key).and_then(serde_json::Value::as_u64);
+    Some(count("thm_temp1_trans_count")? + count("thm_temp2_trans_count").unwrap_or(0))
+}
+
+/// Every NVMe drive with a temperature sensor, by controller name.
+pub fn nvme_temperatures() -> Vec<NvmeTemperature> {
+    let mut drives: Vec<NvmeTemperature> = remote::read_dir(NVME_PATH)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|controller| {
+            let name = controller.file_name()?.to_string_lossy().to_string();
+            let hwmon = remote::read_dir(&controller)
+                .ok()?
+                .into_iter()
+                .find(|dir| dir.file_name().is_some_and(|file| file.to_string_lossy().starts_with("hwmon")))?;
+            // temp1 is the composite temperature; the others are raw sensors.
+            Some(NvmeTemperature {
+                model: read_name(&controller, "model"),
+                celsius: read_millidegrees(&hwmon.join("temp1_input"))?,
+                warning: read_millidegrees(&hwmon.join("temp1_max")),
+                critical: read_millidegrees(&hwmon.join("temp1_crit")),
+                throttle_count: nvme_throttle_count(&name),
+                name,
+            })
+        })
+        .collect();
+    drives.sort_by(|a, b| a.name.cmp(&b.name));
+    drives
+}
+
+/// Follows readings to spot drives that keep throttling under sustained
+/// load, rather than brushing past their warning threshold once.
+#[derive(Debug, Default)]
+pub struct NvmeThrottleWatch {
+    /// Per drive: consecutive throttling readings, whether that episode
+    /// was reported, and the last thermal management count.
+    drives: HashMap<String, (u32, bool, Option<u64>)>,
+}
+
+impl NvmeThrottleWatch {
+    /// Takes new readings and returns the drives whose throttling just
+    /// became sustained. Each episode is reported once; it ends when the
+    /// drive cools down.
+    pub fn update<'a>(&mut self, readings: &'a [NvmeTemperature]) -> Vec<&'a NvmeTemperature> {
+        let mut sustained = Vec::new();
+        for drive in readings {
+            let (streak, reported, last_count) = self.drives.entry(drive.name.clone()).or_default();
+            let transitioned = matches!(
+                (*last_count, drive.throttle_count),
+                (Some(before), Some(now)) if now > before
+            );
+            *last_count = drive.throttle_count;
+
+            if drive.over_warning() || transitioned {
+                *streak += 1;
+                if *streak >= NVME_SUSTAINED_READINGS && !*reported {
+                    *reported = true;
+                    sustained.push(drive);
+                }
+            } else {
+                *streak = 0;
+                *reported = false;
+            }
+        }
+        sustained
+    }
+}
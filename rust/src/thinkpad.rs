@@ -0,0 +1,129 @@
+use crate::remote;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const MODULE_PATH: &str = "/sys/module/thinkpad_acpi";
+const FAN_PATH: &str = "/proc/acpi/ibm/fan";
+const POWER_SUPPLY_PATH: &str = "/sys/class/power_supply";
+const START_THRESHOLD_ATTR: &str = "charge_control_start_threshold";
+
+/// Lowest manual fan level offered. Levels 0 and 1 can stop or nearly stop
+/// the fan, which the EC doesn't guard against.
+pub const MIN_FAN_LEVEL: u8 = 2;
+pub const MAX_FAN_LEVEL: u8 = 7;
+/// Above this CPU temperature a manual fan level is dropped back to auto.
+pub const FAN_SAFETY_TEMP_C: f64 = 85.0;
+
+pub const MIN_START_THRESHOLD: u32 = 0;
+pub const MAX_START_THRESHOLD: u32 = 99;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FanLevel {
+    Auto,
+    Manual(u8),
+}
+
+impl FanLevel {
+    /// Every level offered in the UI, auto first.
+    pub fn choices() -> Vec<FanLevel> {
+        std::iter::once(FanLevel::Auto)
+            .chain((MIN_FAN_LEVEL..=MAX_FAN_LEVEL).map(FanLevel::Manual))
+            .collect()
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            FanLevel::Auto => "Auto".to_string(),
+            FanLevel::Manual(level) => format!("Level {}", level),
+        }
+    }
+
+    fn arg(self) -> String {
+        match self {
+            FanLevel::Auto => "auto".to_string(),
+            FanLevel::Manual(level) => level.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FanStatus {
+    pub speed_rpm: Option<u32>,
+    pub level: FanLevel,
+    /// Manual levels only work with `thinkpad_acpi fan_control=1`.
+    pub controllable: bool,
+}
+
+/// Whether thinkpad_acpi is loaded.
+pub fn present() -> bool {
+    Path::new(MODULE_PATH).exists()
+}
+
+/// Reads `/proc/acpi/ibm/fan`. Levels outside the safe range, including
+/// `full-speed` and `disengaged`, are reported as auto.
+pub fn fan_status() -> Option<FanStatus> {
+    let content = fs::read_to_string(FAN_PATH).ok()?;
+    let field = |name: &str| {
+        content.lines().find_map(|line| {
+            line.strip_prefix(name)
+                .and_then(|rest| rest.trim_start().strip_prefix(':'))
+                .map(|value| value.trim().to_string())
+        })
+    };
+
+    let level = field("level")
+        .and_then(|level| level.parse::<u8>().ok())
+        .filter(|level| (MIN_FAN_LEVEL..=MAX_FAN_LEVEL).contains(level))
+        .map_or(FanLevel::Auto, FanLevel::Manual);
+
+    Some(FanStatus {
+        speed_rpm: field("speed").and_then(|speed| speed.parse().ok()),
+        level,
+        controllable: content
+            .lines()
+            .any(|line| line.starts_with("commands:") && line.contains("level")),
+    })
+}
+
+pub fn apply_fan_level(level: FanLevel) -> Result<(), String> {
+    if let FanLevel::Manual(value) = level {
+        if !(MIN_FAN_LEVEL..=MAX_FAN_LEVEL).contains(&value) {
+            return Err(format!("Unsafe fan level: {}", value));
+        }
+    }
+    remote::run_helper(&["thinkpad-fan", &level.arg()])
+}
+
+fn start_threshold_battery() -> Option<PathBuf> {
+    let mut batteries: Vec<PathBuf> = fs::read_dir(POWER_SUPPLY_PATH)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with("BAT"))
+                && path.join(START_THRESHOLD_ATTR).exists()
+        })
+        .collect();
+    batteries.sort();
+    batteries.into_iter().next()
+}
+
+/// Charge level below which charging resumes. The stop threshold is the
+/// regular charge limit.
+pub fn start_threshold() -> Option<u32> {
+    let battery = start_threshold_battery()?;
+    fs::read_to_string(battery.join(START_THRESHOLD_ATTR))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+pub fn apply_start_threshold(percent: u32) -> Result<(), String> {
+    if !(MIN_START_THRESHOLD..=MAX_START_THRESHOLD).contains(&percent) {
+        return Err("Start threshold out of valid range".to_string());
+    }
+    remote::run_helper(&["charge-start", &percent.to_string()])
+}
+
@@ -0,0 +1,141 @@
+use crate::remote;
+
+const SATA_HOSTS_PATH: &str = "/sys/class/scsi_host";
+/// Writeback interval powertop considers good, in centiseconds.
+const GOOD_WRITEBACK_CENTISECS: u32 = 1500;
+
+/// Kernel knobs `powertop --auto-tune` would set. The helper only ever
+/// writes their power-saving value, and the kernel forgets it on reboot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tunable {
+    Writeback,
+    NmiWatchdog,
+    AudioPowerSave,
+    SataLinkPower,
+    UsbAutosuspend,
+}
+
+impl Tunable {
+    pub const ALL: [Tunable; 5] = [
+        Tunable::Writeback,
+        Tunable::NmiWatchdog,
+        Tunable::AudioPowerSave,
+        Tunable::SataLinkPower,
+        Tunable::UsbAutosuspend,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Tunable::Writeback => "VM Writeback Timeout",
+            Tunable::NmiWatchdog => "NMI Watchdog",
+            Tunable::AudioPowerSave => "Audio Codec Power Saving",
+            Tunable::SataLinkPower => "SATA Link Power Management",
+            Tunable::UsbAutosuspend => "USB Autosuspend Default",
+        }
+    }
+
+    fn helper_arg(self) -> &'static str {
+        match self {
+            Tunable::Writeback => "writeback",
+            Tunable::NmiWatchdog => "nmi-watchdog",
+            Tunable::AudioPowerSave => "audio-power-save",
+            Tunable::SataLinkPower => "sata-link-power",
+            Tunable::UsbAutosuspend => "usb-autosuspend",
+        }
+    }
+
+    /// The sysctl and the power-saving value the helper writes, for the
+    /// knobs that are sysctls.
+    pub fn sysctl(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            Tunable::Writeback => Some(("vm.dirty_writeback_centisecs", "1500")),
+            Tunable::NmiWatchdog => Some(("kernel.nmi_watchdog", "0")),
+            _ => None,
+        }
+    }
+
+    /// Current value and whether it saves power, or `None` when the
+    /// machine doesn't have this knob.
+    fn read(self) -> Option<(String, bool)> {
+        match self {
+            Tunable::Writeback => {
+                let value = read_value("/proc/sys/vm/dirty_writeback_centisecs")?;
+                let good = value.parse::<u32>().is_ok_and(|v| v >= GOOD_WRITEBACK_CENTISECS);
+                Some((format!("{} s", value.parse::<u32>().unwrap_or(0) / 100), good))
+            }
+            Tunable::NmiWatchdog => {
+                let value = read_value("/proc/sys/kernel/nmi_watchdog")?;
+                let good = value == "0";
+                Some((if good { "Off" } else { "On" }.to_string(), good))
+            }
+            Tunable::AudioPowerSave => {
+                let value = read_value("/sys/module/snd_hda_intel/parameters/power_save")?;
+                let good = value.parse::<u32>().is_ok_and(|v| v > 0);
+                let label = if good { format!("After {} s idle", value) } else { "Off".to_string() };
+                Some((label, good))
+            }
+            Tunable::SataLinkPower => {
+                let policies: Vec<String> = remote::read_dir(SATA_HOSTS_PATH)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|host| {
+                        remote::read_to_string(host.join("link_power_management_policy"))
+                            .ok()
+                            .map(|policy| policy.trim().to_string())
+                    })
+                    .collect();
+                let first = policies.first()?.clone();
+                let good = policies
+                    .iter()
+                    .all(|policy| policy == "med_power_with_dipm" || policy == "min_power");
+                Some((first, good))
+            }
+            Tunable::UsbAutosuspend => {
+                let value = read_value("/sys/module/usbcore/parameters/autosuspend")?;
+                let good = value.parse::<i32>().is_ok_and(|v| v >= 0);
+                let label = if good { format!("After {} s idle", value) } else { "Off".to_string() };
+                Some((label, good))
+            }
+        }
+    }
+}
+
+/// One line of the audit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TunableState {
+    pub tunable: Tunable,
+    /// Human-readable current value.
+    pub current: String,
+    pub good: bool,
+}
+
+fn read_value(path: &str) -> Option<String> {
+    remote::read_to_string(path)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Every tunable this machine has, with its current state.
+pub fn audit() -> Vec<TunableState> {
+    Tunable::ALL
+        .into_iter()
+        .filter_map(|tunable| {
+            let (current, good) = tunable.read()?;
+            Some(TunableState { tunable, current, good })
+        })
+        .collect()
+}
+
+/// Percentage of audited tunables already in their power-saving state.
+pub fn score(states: &[TunableState]) -> u32 {
+    if states.is_empty() {
+        return 100;
+    }
+    let good = states.iter().filter(|state| state.good).count();
+    (good * 100 / states.len()) as u32
+}
+
+pub fn apply(tunable: Tunable) -> Result<(), String> {
+    remote::run_helper(&["tunable", tunable.helper_arg()])
+}
@@ -0,0 +1,120 @@
+use crate::advice::{self, Suggestion};
+use crate::battery_history;
+use crate::config::Config;
+use crate::corepark::CpuSampler;
+use crate::rules::{self, Action};
+use gtk4::glib;
+use gtk4::prelude::*;
+use gtk4::{gio, Align, Button};
+use libadwaita as adw;
+use adw::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+use super::AppState;
+
+/// Suggestions for settings that would save power right now.
+#[derive(Clone)]
+pub(super) struct AdvicePage {
+    groups: [adw::PreferencesGroup; 1],
+    advice_rows: Rc<RefCell<Vec<adw::ActionRow>>>,
+}
+
+impl AdvicePage {
+    pub(super) fn build() -> Self {
+        let advice_group = adw::PreferencesGroup::builder()
+            .title("Suggestions")
+            .visible(false)
+            .build();
+
+        Self {
+            groups: [advice_group],
+            advice_rows: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    pub(super) fn groups(&self) -> &[adw::PreferencesGroup] {
+        &self.groups
+    }
+
+    /// Re-checks the suggestions once a minute; CPU load is averaged over
+    /// that minute so a short burst doesn't count as busy.
+    pub(super) fn bind(&self, state: &AppState) {
+        let mut sampler = CpuSampler::default();
+        sampler.sample();
+        self.refresh_advice(state, None);
+
+        let page = self.clone();
+        let state = state.clone();
+        glib::timeout_add_seconds_local(battery_history::SAMPLE_INTERVAL_SECS, move || {
+            page.refresh_advice(&state, sampler.sample());
+            glib::ControlFlow::Continue
+        });
+    }
+
+    fn refresh_advice(&self, state: &AppState, cpu_load: Option<f64>) {
+        let page = self.clone();
+        let state = state.clone();
+        glib::spawn_future_local(async move {
+            let Ok(observed) = gio::spawn_blocking(move || advice::observe(cpu_load)).await else {
+                return;
+            };
+            let found = advice::suggestions(&observed, &Config::load());
+            let group = &page.groups[0];
+            group.set_visible(!found.is_empty());
+
+            let mut rows = page.advice_rows.borrow_mut();
+            for row in rows.drain(..) {
+                group.remove(&row);
+            }
+            for suggestion in found {
+                let row = page.build_advice_row(&state, suggestion);
+                group.add(&row);
+                rows.push(row);
+            }
+        });
+    }
+
+    fn build_advice_row(&self, state: &AppState, suggestion: Suggestion) -> adw::ActionRow {
+        let row = adw::ActionRow::builder()
+            .title(&suggestion.title)
+            .subtitle(&suggestion.detail)
+            .build();
+        let fix_btn = Button::builder()
+            .label(suggestion.fix_label)
+            .valign(Align::Center)
+            .build();
+        row.add_suffix(&fix_btn);
+
+        if let Some(reason) = state.read_only_reason() {
+            fix_btn.set_sensitive(false);
+            fix_btn.set_tooltip_text(Some(&reason));
+            return row;
+        }
+
+        let page = self.clone();
+        let state = state.clone();
+        fix_btn.connect_clicked(move |btn| {
+            btn.set_sensitive(false);
+            let action = suggestion.fix.clone();
+            let page = page.clone();
+            let state = state.clone();
+            glib::spawn_future_local(async move {
+                let fix = action.clone();
+                let result = gio::spawn_blocking(move || rules::apply_action(&fix)).await;
+                match result {
+                    Ok(Ok(())) => {
+                        if let Action::ChargeLimit(limit) = action {
+                            state.set_charge_limit(limit);
+                        }
+                        state.reload();
+                    }
+                    Ok(Err(e)) => state.toast(&format!("Suggestion failed: {}", e)),
+                    Err(_) => state.toast("Suggestion failed"),
+                }
+                page.refresh_advice(&state, None);
+            });
+        });
+
+        row
+    }
+}
@@ -0,0 +1,469 @@
+use crate::api;
+use crate::battery;
+use crate::battery_history;
+use crate::config::{self, Config};
+use crate::corepark::CpuSampler;
+use crate::lighting;
+use crate::mangohud;
+use crate::mqtt;
+use crate::power;
+use crate::profiles;
+use crate::rules::{self, Context};
+use crate::script::{self, Readings, ScriptRunner};
+use crate::window_watch;
+use crate::thermal;
+use crate::usage;
+use gtk4::glib::{self, clone};
+use gtk4::prelude::*;
+use gtk4::{gio, StringList};
+use libadwaita as adw;
+use adw::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::mpsc::Receiver;
+use std::time::Instant;
+use super::{restrict, AppState};
+
+/// Profiles, and what applies them without a click: rules, scripts,
+/// MQTT and the control socket.
+#[derive(Clone)]
+pub(super) struct AutomationPage {
+    groups: [adw::PreferencesGroup; 1],
+    /// The header's profile switcher mirrors it.
+    pub(super) profile_combo: adw::ComboRow,
+    config_monitor: Rc<RefCell<Option<gio::FileMonitor>>>,
+}
+
+impl AutomationPage {
+    pub(super) fn build() -> Self {
+        let (profile_group, profile_combo) = Self::build_profile_group();
+
+        Self {
+            groups: [profile_group],
+            profile_combo,
+            config_monitor: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    pub(super) fn groups(&self) -> &[adw::PreferencesGroup] {
+        &self.groups
+    }
+
+    pub(super) fn bind(&self, state: &AppState) {
+        self.setup_profiles(state);
+        self.setup_automation(state);
+        self.setup_usage_stats();
+        self.setup_mqtt(state);
+        self.setup_control_api(state);
+
+        state.connect_profile_requested(clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            move |name| page.request_profile(&state, name)
+        ));
+        state.connect_reload(clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            move || page.check_profile_drift(&state)
+        ));
+        self.check_profile_drift(state);
+        restrict(state, &[self.profile_combo.upcast_ref()]);
+    }
+
+    fn build_profile_group() -> (adw::PreferencesGroup, adw::ComboRow) {
+        let profile_group = adw::PreferencesGroup::builder()
+            .title("Profile")
+            .description("Apply a preset of performance settings.")
+            .build();
+
+        let profile_combo = adw::ComboRow::builder()
+            .title("Active Profile")
+            .subtitle("Estimated runtime appears as battery history grows")
+            .build();
+        profile_group.add(&profile_combo);
+
+        (profile_group, profile_combo)
+    }
+
+    fn setup_automation(&self, state: &AppState) {
+        let context = Rc::new(RefCell::new(Context::default()));
+        let last_rule: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
+
+        // Only act when the matching rule changes, so manual tweaks made
+        // while a rule is in effect are left alone.
+        let evaluate: Rc<dyn Fn()> = Rc::new(clone!(
+            #[strong] state,
+            #[strong] context,
+            #[strong] last_rule,
+            move || {
+                let rules = rules::active_rules(&Config::load().automation);
+                let matched = rules::evaluate(&rules, &context.borrow()).cloned();
+                let name = matched.as_ref().map(|rule| rule.name.clone());
+
+                if *last_rule.borrow() == name {
+                    return;
+                }
+                *last_rule.borrow_mut() = name;
+
+                let Some(rule) = matched else {
+                    return;
+                };
+
+                let state = state.clone();
+                glib::spawn_future_local(async move {
+                    let action = rule.then.clone();
+                    let result = gio::spawn_blocking(move || rules::apply_action(&action)).await;
+
+                    match result {
+                        Ok(Ok(())) => {
+                            battery_history::record_event(&rule.name);
+                            state.reload();
+                        }
+                        _ => state.toast(&format!("Rule \"{}\" failed", rule.name)),
+                    }
+                });
+            }
+        ));
+
+        power::watch_power_source(clone!(
+            #[strong] context,
+            #[strong] evaluate,
+            move |source| {
+                context.borrow_mut().power = source;
+                evaluate();
+            }
+        ));
+
+        window_watch::watch_active_window(clone!(
+            #[strong] context,
+            #[strong] evaluate,
+            move |window| {
+                context.borrow_mut().window = window.clone();
+                evaluate();
+            }
+        ));
+
+        // The display page saves the toggle; a rule it turned on or off
+        // may match now.
+        state.connect_battery_refresh_notify(clone!(
+            #[strong] evaluate,
+            move |_| {
+                last_rule.borrow_mut().take();
+                evaluate();
+            }
+        ));
+
+        self.run_scripts(state, context);
+        self.watch_config(state, evaluate);
+    }
+
+    /// Evaluates scripted rules against fresh readings on a timer.
+    fn run_scripts(&self, state: &AppState, context: Rc<RefCell<Context>>) {
+        let runner = Rc::new(RefCell::new(ScriptRunner::default()));
+        let sampler = Rc::new(RefCell::new(CpuSampler::default()));
+
+        glib::timeout_add_seconds_local(script::EVAL_INTERVAL_SECS, clone!(
+            #[strong] state,
+            move || {
+                let scripts = Config::load().automation.scripts;
+                let cpu_load = sampler.borrow_mut().sample().map(|load| load * 100.0);
+                if scripts.is_empty() {
+                    return glib::ControlFlow::Continue;
+                }
+
+                let readings = {
+                    let context = context.borrow();
+                    Readings {
+                        cpu_temp: thermal::cpu_temperature(),
+                        cpu_load,
+                        battery: battery::capacity_percent().map(f64::from),
+                        power_w: battery::power_draw_mw().map(|mw| mw.max(0) as f64 / 1000.0),
+                        on_battery: context.power == power::PowerSource::Battery,
+                        on_ac: context.power == power::PowerSource::Ac,
+                        fullscreen: context.window.fullscreen,
+                    }
+                };
+
+                for result in runner.borrow_mut().tick(&scripts, &readings, Instant::now()) {
+                    let rule = match result {
+                        Ok(rule) => rule,
+                        Err(e) => {
+                            state.toast(&e);
+                            continue;
+                        }
+                    };
+
+                    let state = state.clone();
+                    glib::spawn_future_local(async move {
+                        let action = rule.then.clone();
+                        let result = gio::spawn_blocking(move || rules::apply_action(&action)).await;
+
+                        match result {
+                            Ok(Ok(())) => {
+                                battery_history::record_event(&rule.name);
+                                state.reload();
+                            }
+                            _ => state.toast(&format!("Script \"{}\" failed", rule.name)),
+                        }
+                    });
+                }
+                glib::ControlFlow::Continue
+            }
+        ));
+    }
+
+    /// Picks up hand edits of the config files: profiles are relisted and
+    /// rules re-evaluated without a restart. `evaluate` re-runs the rules.
+    fn watch_config(&self, state: &AppState, evaluate: Rc<dyn Fn()>) {
+        let monitor = config::watch(clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            move || {
+                if let Err(e) = Config::check() {
+                    state.toast(&format!("Config not reloaded: {}", e));
+                    return;
+                }
+                glib::g_debug!(crate::LOG_DOMAIN, "Config changed on disk, reloading");
+
+                state.load_config(&Config::load());
+                page.refresh_profile_list(&state);
+                evaluate();
+            }
+        ));
+        *self.config_monitor.borrow_mut() = monitor;
+    }
+
+    fn setup_profiles(&self, state: &AppState) {
+        state.connect_profile_labels_notify(clone!(
+            #[strong(rename_to = page)] self,
+            move |state| {
+                let labels = state.profile_labels();
+                let refs: Vec<&str> = labels.iter().map(|s| s.as_str()).collect();
+                page.profile_combo.set_model(Some(&StringList::new(&refs)));
+                page.select_profile(state);
+            }
+        ));
+        state.connect_profile_notify(clone!(
+            #[strong(rename_to = page)] self,
+            move |state| page.select_profile(state)
+        ));
+        self.refresh_profile_list(state);
+
+        self.profile_combo.connect_selected_notify(clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            move |combo| {
+                // Index 0 is the "Custom" placeholder.
+                let idx = combo.selected() as usize;
+                let Some(name) = idx
+                    .checked_sub(1)
+                    .and_then(|i| state.profile_names().get(i).cloned())
+                else {
+                    return;
+                };
+                if name == state.profile() {
+                    return;
+                }
+                let Some(profile) = profiles::all_profiles(&Config::load())
+                    .into_iter()
+                    .find(|p| p.name == name)
+                else {
+                    return;
+                };
+                let total_cpus = state.max_cpus();
+
+                combo.set_sensitive(false);
+                state.toast(&format!("Applying {}...", profile.name));
+
+                let page = page.clone();
+                let state = state.clone();
+                glib::spawn_future_local(async move {
+                    let to_apply = profile.clone();
+                    let result = gio::spawn_blocking(move || {
+                        profiles::apply_profile(&to_apply, total_cpus).map(|()| lighting::detect().is_some())
+                    }).await;
+
+                    page.profile_combo.set_sensitive(true);
+
+                    // The reload below brings the other pages' rows in
+                    // line with what the profile changed.
+                    match result {
+                        Ok(Ok(has_lighting)) => {
+                            let mut config = Config::load();
+                            config.active_profile = Some(profile.name.clone());
+                            if has_lighting && profile.lighting.is_some() {
+                                config.lighting = profile.lighting;
+                            }
+                            let _ = config.save();
+                            state.load_config(&Config::load());
+                            if profile.fps_limit.is_some() {
+                                state.load_mangohud(&mangohud::settings());
+                            }
+                            battery_history::record_event(&format!(
+                                "{}{}",
+                                profiles::PROFILE_EVENT_PREFIX,
+                                profile.name
+                            ));
+                            state.toast(&format!("{} profile applied", profile.name));
+                        }
+                        Ok(Err(e)) => {
+                            state.toast(&format!("Profile failed: {}", e));
+                            page.select_profile(&state);
+                        }
+                        Err(_) => {
+                            state.toast("Profile failed");
+                            page.select_profile(&state);
+                        }
+                    }
+
+                    page.refresh_profile_list(&state);
+                    state.reload();
+                });
+            }
+        ));
+
+        // Runtime estimates improve as discharge samples accumulate.
+        let page = self.clone();
+        let state = state.clone();
+        glib::timeout_add_seconds_local(battery_history::SAMPLE_INTERVAL_SECS, move || {
+            page.refresh_profile_list(&state);
+            glib::ControlFlow::Continue
+        });
+    }
+
+    /// Counts a minute towards the active profile each minute, when usage
+    /// statistics are switched on.
+    fn setup_usage_stats(&self) {
+        glib::timeout_add_seconds_local(battery_history::SAMPLE_INTERVAL_SECS, || {
+            let config = Config::load();
+            if let (true, Some(profile)) = (config.usage_stats, config.active_profile) {
+                let on_battery = power::power_source() == power::PowerSource::Battery;
+                if let Err(e) = usage::record(&profile, battery_history::SAMPLE_INTERVAL_SECS as u64, on_battery) {
+                    glib::g_debug!(crate::LOG_DOMAIN, "Failed to record usage: {}", e);
+                }
+            }
+            glib::ControlFlow::Continue
+        });
+    }
+
+    /// Publishes readings to Home Assistant and switches profiles on its
+    /// request, when MQTT is enabled in the config.
+    fn setup_mqtt(&self, state: &AppState) {
+        let config = Config::load().mqtt;
+        if config.enabled {
+            follow_profile_requests(state, mqtt::start(config));
+        }
+    }
+
+    /// Serves the local control socket, when enabled in the config.
+    fn setup_control_api(&self, state: &AppState) {
+        if !Config::load().control_socket {
+            return;
+        }
+        match api::start() {
+            Ok(requests) => follow_profile_requests(state, requests),
+            Err(e) => state.toast(&format!("Control socket unavailable: {}", e)),
+        }
+    }
+
+    /// Applies the profile called `name`, ignoring case, like a manual
+    /// pick. Returns `false` when there is no such profile.
+    fn request_profile(&self, state: &AppState, name: &str) -> bool {
+        let idx = state
+            .profile_names()
+            .iter()
+            .position(|p| p.eq_ignore_ascii_case(name));
+        match idx {
+            // Index 0 is the "Custom" placeholder.
+            Some(idx) => {
+                self.profile_combo.set_selected(idx as u32 + 1);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Flags the active profile in the header when the machine no longer
+    /// matches it, e.g. after a manual change to the refresh rate.
+    fn check_profile_drift(&self, state: &AppState) {
+        let config = Config::load();
+        let active = config.active_profile.as_ref().and_then(|name| {
+            profiles::all_profiles(&config).into_iter().find(|p| &p.name == name)
+        });
+        let Some(profile) = active else {
+            state.update("profile-changes", Vec::<String>::new());
+            return;
+        };
+
+        let state = state.clone();
+        glib::spawn_future_local(async move {
+            let changed: Vec<String> = gio::spawn_blocking(move || profiles::divergences(&profile))
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .map(String::from)
+                .collect();
+            state.update("profile-changes", changed);
+        });
+    }
+
+    /// Rebuilds the profile list, labelling each profile with its estimated
+    /// battery runtime when enough history exists.
+    fn refresh_profile_list(&self, state: &AppState) {
+        let config = Config::load();
+        let profiles = profiles::all_profiles(&config);
+
+        let since = battery_history::unix_now() - 24 * 3600;
+        let averages = battery_history::average_draw_by_profile(
+            &battery_history::load_samples(since),
+            &battery_history::load_events(since),
+        );
+        let energy_mwh = battery::energy_remaining_mwh();
+
+        let mut labels = vec!["Custom".to_string()];
+        labels.extend(profiles.iter().map(|profile| {
+            match (energy_mwh, averages.get(&profile.name)) {
+                (Some(energy), Some(&draw)) if draw > 0.0 => {
+                    format!("{} (≈ {:.1}h)", profile.name, energy as f64 / draw)
+                }
+                _ => profile.name.clone(),
+            }
+        }));
+
+        state.update(
+            "profile-names",
+            profiles.into_iter().map(|profile| profile.name).collect::<Vec<_>>(),
+        );
+        // Relisting closes an open popover, so only on a change.
+        state.update("profile-labels", labels);
+    }
+
+    /// Selects the active profile in the combo, or "Custom".
+    fn select_profile(&self, state: &AppState) {
+        let active = state.profile();
+        let selected = state
+            .profile_names()
+            .iter()
+            .position(|name| *name == active)
+            .map_or(0, |i| i as u32 + 1);
+        self.profile_combo.set_selected(selected);
+    }
+}
+
+/// Applies profiles requested by name from outside the window.
+fn follow_profile_requests(state: &AppState, requests: Receiver<String>) {
+    glib::timeout_add_seconds_local(1, clone!(
+        #[strong] state,
+        move || {
+            while let Ok(name) = requests.try_recv() {
+                if state.read_only_reason().is_some() {
+                    continue;
+                }
+                if !state.request_profile(&name) {
+                    state.toast(&format!("Unknown profile requested: {}", name));
+                }
+            }
+            glib::ControlFlow::Continue
+        }
+    ));
+}
@@ -0,0 +1,575 @@
+use crate::backlight::{self, AutoDim};
+use crate::battery;
+use crate::battery_history;
+use crate::config::Config;
+use crate::hibernate::{self, HibernateStatus};
+use crate::power::{self, PowerSource};
+use crate::profiles;
+use crate::schedule;
+use gtk4::glib::{self, clone};
+use gtk4::prelude::*;
+use gtk4::{gio, Align, Button};
+use libadwaita as adw;
+use adw::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use super::{restrict, AppState};
+
+/// Charge limit spinners of a dual-battery machine, by battery name.
+type BatteryLimitSpins = Vec<(String, adw::SpinRow)>;
+
+/// Charge limits and the travel override, low battery dimming and what
+/// happens when the battery runs out.
+#[derive(Clone)]
+pub(super) struct BatteryPage {
+    groups: [adw::PreferencesGroup; 3],
+    pub(super) charge_spin: adw::SpinRow,
+    /// Per-battery limits, on machines with more than one battery.
+    battery_limit_spins: BatteryLimitSpins,
+    charge_apply_btn: Button,
+    full_charge_entry: adw::EntryRow,
+    full_charge_row: adw::ActionRow,
+    full_charge_cancel_btn: Button,
+    charge_sync_failed: Rc<Cell<bool>>,
+    /// A charge limit request is waiting on the helper.
+    charge_sync_pending: Rc<Cell<bool>>,
+    /// The defaults are being recorded; changes wait so they aren't
+    /// mistaken for them.
+    recording_defaults: Rc<Cell<bool>>,
+    auto_dim_group: adw::PreferencesGroup,
+    auto_dim_row: adw::SwitchRow,
+    auto_dim: Rc<RefCell<AutoDim>>,
+    emergency_group: adw::PreferencesGroup,
+    emergency_row: adw::SwitchRow,
+    emergency_spin: adw::SpinRow,
+    emergency_hibernate_row: adw::SwitchRow,
+}
+
+impl BatteryPage {
+    pub(super) fn build() -> Self {
+        let (
+            battery_group,
+            charge_spin,
+            charge_apply_btn,
+            full_charge_entry,
+            full_charge_row,
+            full_charge_cancel_btn,
+            battery_limit_spins,
+        ) = Self::build_battery_group();
+        let (auto_dim_group, auto_dim_row) = Self::build_auto_dim_group();
+        let (emergency_group, emergency_row, emergency_spin, emergency_hibernate_row) =
+            Self::build_emergency_group();
+
+        Self {
+            groups: [battery_group, auto_dim_group.clone(), emergency_group.clone()],
+            charge_spin,
+            battery_limit_spins,
+            charge_apply_btn,
+            full_charge_entry,
+            full_charge_row,
+            full_charge_cancel_btn,
+            charge_sync_failed: Rc::new(Cell::new(false)),
+            charge_sync_pending: Rc::new(Cell::new(false)),
+            recording_defaults: Rc::new(Cell::new(true)),
+            auto_dim_group,
+            auto_dim_row,
+            auto_dim: Rc::new(RefCell::new(AutoDim::default())),
+            emergency_group,
+            emergency_row,
+            emergency_spin,
+            emergency_hibernate_row,
+        }
+    }
+
+    pub(super) fn groups(&self) -> &[adw::PreferencesGroup] {
+        &self.groups
+    }
+
+    pub(super) fn bind(&self, state: &AppState) {
+        self.setup_battery(state);
+        self.setup_auto_dim(state);
+        self.setup_emergency(state);
+    }
+
+    /// Lets the charge limit sync run once the defaults are on record.
+    pub(super) fn defaults_recorded(&self, state: &AppState) {
+        self.recording_defaults.set(false);
+        self.sync_charge_limit(state);
+    }
+
+    fn build_battery_group() -> (
+        adw::PreferencesGroup,
+        adw::SpinRow,
+        Button,
+        adw::EntryRow,
+        adw::ActionRow,
+        Button,
+        BatteryLimitSpins,
+    ) {
+        let battery_group = adw::PreferencesGroup::builder()
+            .title("Battery")
+            .description("Limit charging to extend battery lifespan.")
+            .build();
+
+        let charge_spin = adw::SpinRow::with_range(
+            battery::MIN_CHARGE_LIMIT as f64,
+            battery::MAX_CHARGE_LIMIT as f64,
+            5.0,
+        );
+        charge_spin.set_title("Charge Limit");
+        charge_spin.set_subtitle("Stop charging at this percentage");
+        charge_spin.set_sensitive(false);
+        battery_group.add(&charge_spin);
+
+        // Machines with two batteries can give each its own limit.
+        let names = battery::threshold_battery_names();
+        let battery_limit_spins: BatteryLimitSpins = if names.len() > 1 {
+            names
+                .into_iter()
+                .map(|name| {
+                    let spin = adw::SpinRow::with_range(
+                        battery::MIN_CHARGE_LIMIT as f64,
+                        battery::MAX_CHARGE_LIMIT as f64,
+                        5.0,
+                    );
+                    spin.set_title(&format!("{} Charge Limit", name));
+                    spin.set_sensitive(false);
+                    battery_group.add(&spin);
+                    (name, spin)
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let full_charge_entry = adw::EntryRow::builder()
+            .title("Full Charge Until (HH:MM)")
+            .show_apply_button(true)
+            .sensitive(false)
+            .build();
+        battery_group.add(&full_charge_entry);
+
+        let full_charge_row = adw::ActionRow::builder()
+            .title("Travel Override Active")
+            .visible(false)
+            .build();
+        let full_charge_cancel_btn = Button::builder()
+            .label("Cancel")
+            .valign(Align::Center)
+            .build();
+        full_charge_row.add_suffix(&full_charge_cancel_btn);
+        battery_group.add(&full_charge_row);
+
+        let charge_apply_btn = Button::builder()
+            .label("Apply")
+            .margin_top(12)
+            .css_classes(["suggested-action"])
+            .sensitive(false)
+            .build();
+        battery_group.add(&charge_apply_btn);
+
+        (
+            battery_group,
+            charge_spin,
+            charge_apply_btn,
+            full_charge_entry,
+            full_charge_row,
+            full_charge_cancel_btn,
+            battery_limit_spins,
+        )
+    }
+
+    fn build_auto_dim_group() -> (adw::PreferencesGroup, adw::SwitchRow) {
+        let steps: Vec<String> = Config::load()
+            .battery
+            .dim_steps
+            .iter()
+            .map(|step| format!("−{}% below {}%", step.dim_percent, step.below_percent))
+            .collect();
+        let auto_dim_group = adw::PreferencesGroup::builder()
+            .title("Low Battery Dimming")
+            .visible(false)
+            .build();
+
+        let auto_dim_row = adw::SwitchRow::builder()
+            .title("Dim Screen as Battery Drops")
+            .subtitle(steps.join(", "))
+            .build();
+        auto_dim_group.add(&auto_dim_row);
+
+        (auto_dim_group, auto_dim_row)
+    }
+
+    fn build_emergency_group() -> (adw::PreferencesGroup, adw::SwitchRow, adw::SpinRow, adw::SwitchRow) {
+        let emergency_group = adw::PreferencesGroup::builder()
+            .title("Critical Battery")
+            .description("What to do when the battery is about to run out.")
+            .visible(false)
+            .build();
+
+        let emergency_row = adw::SwitchRow::builder()
+            .title("Emergency Power Saving")
+            .subtitle(format!("Switch to {} and notify", profiles::power_saver_name()))
+            .build();
+        emergency_group.add(&emergency_row);
+
+        let emergency_spin = adw::SpinRow::with_range(2.0, 30.0, 1.0);
+        emergency_spin.set_title("Battery Level");
+        emergency_spin.set_subtitle("Percent at which to act");
+        emergency_group.add(&emergency_spin);
+
+        let emergency_hibernate_row = adw::SwitchRow::builder()
+            .title("Hibernate")
+            .subtitle("A minute later, unless the charger is plugged in")
+            .build();
+        emergency_group.add(&emergency_hibernate_row);
+
+        (emergency_group, emergency_row, emergency_spin, emergency_hibernate_row)
+    }
+
+    fn setup_emergency(&self, state: &AppState) {
+        if battery::capacity_percent().is_none() {
+            return;
+        }
+        self.emergency_group.set_visible(true);
+
+        state.bind_property("emergency", &self.emergency_row, "active").sync_create().build();
+        state.bind_property("emergency-percent", &self.emergency_spin, "value").sync_create().build();
+        state
+            .bind_property("emergency-hibernate", &self.emergency_hibernate_row, "active")
+            .sync_create()
+            .build();
+        if !HibernateStatus::detect().is_ready() {
+            self.emergency_hibernate_row.set_sensitive(false);
+            self.emergency_hibernate_row.set_subtitle("Set up hibernation first");
+        }
+
+        let save = clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            move || {
+                let enabled = page.emergency_row.is_active();
+                let percent = page.emergency_spin.value() as u32;
+                let hibernate = page.emergency_hibernate_row.is_active();
+                if (enabled, percent, hibernate)
+                    == (state.emergency(), state.emergency_percent(), state.emergency_hibernate())
+                {
+                    return;
+                }
+                let mut config = Config::load();
+                config.battery.emergency = enabled;
+                config.battery.emergency_percent = percent;
+                config.battery.emergency_hibernate = hibernate;
+                if config.save().is_err() {
+                    state.toast("Failed to save battery settings");
+                }
+                state.load_config(&config);
+            }
+        );
+        self.emergency_row.connect_active_notify(clone!(#[strong] save, move |_| save()));
+        self.emergency_spin.connect_value_notify(clone!(#[strong] save, move |_| save()));
+        self.emergency_hibernate_row.connect_active_notify(move |_| save());
+
+        power::watch_critical_battery(
+            || {
+                let config = Config::load().battery;
+                config.emergency.then_some(config.emergency_percent)
+            },
+            clone!(
+                #[strong(rename_to = page)] self,
+                #[strong] state,
+                move |capacity| page.handle_critical_battery(&state, capacity)
+            ),
+        );
+    }
+
+    /// Switches to the power-saving profile, tells the user and, if asked
+    /// to, hibernates after a grace minute spent on battery.
+    fn handle_critical_battery(&self, state: &AppState, capacity: u32) {
+        if state.read_only_reason().is_some() {
+            return;
+        }
+        let config = Config::load();
+        let profile = profiles::power_saver_name();
+        if config.active_profile.as_deref() != Some(profile.as_str()) {
+            state.request_profile(&profile);
+        }
+
+        let hibernate = config.battery.emergency_hibernate && HibernateStatus::detect().is_ready();
+        let body = if hibernate {
+            format!("Switched to {}. Hibernating in a minute unless plugged in.", profile)
+        } else {
+            format!("Switched to {}. Plug in soon.", profile)
+        };
+        let notification = gio::Notification::new(&format!("Battery at {}%", capacity));
+        notification.set_body(Some(&body));
+        notification.set_priority(gio::NotificationPriority::Urgent);
+        if let Some(app) = gio::Application::default() {
+            app.send_notification(Some("critical-battery"), &notification);
+        }
+        state.toast(&body);
+
+        if !hibernate {
+            return;
+        }
+        glib::timeout_add_seconds_local_once(60, clone!(
+            #[strong] state,
+            move || {
+                if power::power_source() != PowerSource::Battery {
+                    return;
+                }
+                glib::spawn_future_local(async move {
+                    let result = gio::spawn_blocking(hibernate::hibernate_now).await;
+                    if !matches!(result, Ok(Ok(()))) {
+                        state.toast("Failed to hibernate");
+                    }
+                });
+            }
+        ));
+    }
+
+    fn setup_auto_dim(&self, state: &AppState) {
+        if !backlight::supported() || battery::capacity_percent().is_none() {
+            return;
+        }
+        self.auto_dim_group.set_visible(true);
+        state
+            .bind_property("auto-dim", &self.auto_dim_row, "active")
+            .sync_create()
+            .build();
+
+        self.auto_dim_row.connect_active_notify(clone!(
+            #[strong] state,
+            move |row| {
+                if row.is_active() == state.auto_dim() {
+                    return;
+                }
+                let mut config = Config::load();
+                config.battery.auto_dim = row.is_active();
+                if config.save().is_err() {
+                    state.toast("Failed to save battery settings");
+                }
+                state.load_config(&config);
+            }
+        ));
+        state.connect_auto_dim_notify(clone!(
+            #[strong(rename_to = page)] self,
+            move |state| page.sync_auto_dim(state)
+        ));
+
+        power::watch_power_source(clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            move |_| page.sync_auto_dim(&state)
+        ));
+        let page = self.clone();
+        let state = state.clone();
+        glib::timeout_add_seconds_local(60, move || {
+            page.sync_auto_dim(&state);
+            glib::ControlFlow::Continue
+        });
+    }
+
+    /// Dims for the current battery level, or gives the brightness back on
+    /// AC and when auto-dimming is off.
+    fn sync_auto_dim(&self, state: &AppState) {
+        if !self.auto_dim_group.is_visible() {
+            return;
+        }
+        let config = Config::load().battery;
+        let target = match (state.auto_dim(), power::power_source(), battery::capacity_percent()) {
+            (true, PowerSource::Battery, Some(capacity)) => backlight::dim_for(&config.dim_steps, capacity),
+            _ => 0,
+        };
+        if let Err(e) = self.auto_dim.borrow_mut().update(target) {
+            glib::g_debug!(crate::LOG_DOMAIN, "Auto-dimming failed: {}", e);
+        }
+    }
+
+    fn setup_battery(&self, state: &AppState) {
+        let Some(current) = battery::charge_limit() else {
+            self.charge_spin.set_subtitle("Not supported on this battery");
+            return;
+        };
+
+        if Config::load().battery.charge_limit.is_none() {
+            state.set_charge_limit(current);
+        }
+        state
+            .bind_property("charge-limit", &self.charge_spin, "value")
+            .sync_create()
+            .build();
+        self.charge_spin.set_sensitive(true);
+        self.charge_apply_btn.set_sensitive(true);
+        self.full_charge_entry.set_sensitive(true);
+        self.charge_spin.set_subtitle(&format!("Currently {}%", current));
+        self.show_battery_limits(state);
+
+        self.charge_apply_btn.connect_clicked(clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            move |_| {
+                let mut config = Config::load();
+                let shared = page.charge_spin.value() as u32;
+                config.battery.charge_limit = Some(shared);
+                // A battery set to the shared limit needs no override.
+                for (name, spin) in &page.battery_limit_spins {
+                    let limit = spin.value() as u32;
+                    if limit == shared {
+                        config.battery.battery_limits.remove(name);
+                    } else {
+                        config.battery.battery_limits.insert(name.clone(), limit);
+                    }
+                }
+                if config.save().is_err() {
+                    state.toast("Failed to save battery settings");
+                    return;
+                }
+                page.charge_sync_failed.set(false);
+                page.sync_charge_limit(&state);
+            }
+        ));
+
+        self.full_charge_entry.connect_apply(clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            move |entry| {
+                let Some(minutes) = schedule::parse_time(&entry.text()) else {
+                    state.toast("Use a 24-hour time such as 08:00");
+                    return;
+                };
+                let Some(until) = schedule::next_occurrence(minutes) else {
+                    return;
+                };
+
+                let mut config = Config::load();
+                config.battery.full_charge_until = Some(until);
+                if config.save().is_err() {
+                    state.toast("Failed to save battery settings");
+                    return;
+                }
+                entry.set_text("");
+                page.charge_sync_failed.set(false);
+                page.sync_charge_limit(&state);
+            }
+        ));
+
+        self.full_charge_cancel_btn.connect_clicked(clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            move |_| {
+                let mut config = Config::load();
+                config.battery.full_charge_until = None;
+                let _ = config.save();
+                page.charge_sync_failed.set(false);
+                page.sync_charge_limit(&state);
+            }
+        ));
+
+        // Revert to the normal limit once the override expires.
+        glib::timeout_add_seconds_local(60, clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            move || {
+                page.sync_charge_limit(&state);
+                glib::ControlFlow::Continue
+            }
+        ));
+
+        self.sync_charge_limit(state);
+        restrict(
+            state,
+            &[
+                self.charge_spin.upcast_ref(),
+                self.charge_apply_btn.upcast_ref(),
+                self.full_charge_entry.upcast_ref(),
+            ],
+        );
+    }
+
+    /// Brings the hardware threshold in line with the configured limit and
+    /// any pending travel override.
+    fn sync_charge_limit(&self, state: &AppState) {
+        let mut config = Config::load();
+        let now = glib::DateTime::now_local().map(|dt| dt.to_unix()).unwrap_or(0);
+
+        if config.battery.full_charge_until.is_some_and(|until| now >= until) {
+            config.battery.full_charge_until = None;
+            let _ = config.save();
+        }
+
+        match config.battery.full_charge_until.and_then(|until| glib::DateTime::from_unix_local(until).ok()) {
+            Some(until) => {
+                let label = until.format("%a %H:%M").map(|s| s.to_string()).unwrap_or_default();
+                self.full_charge_row.set_subtitle(&format!("Charging to 100% until {}", label));
+                self.full_charge_row.set_visible(true);
+            }
+            None => self.full_charge_row.set_visible(false),
+        }
+
+        let targets = battery::effective_limits(&config.battery, now);
+        let current = battery::charge_limits();
+        if targets.iter().all(|(name, limit)| current.get(name) == Some(limit))
+            || self.charge_sync_failed.get()
+            || self.charge_sync_pending.get()
+            || self.recording_defaults.get()
+            || state.read_only_reason().is_some()
+        {
+            return;
+        }
+
+        // The timer keeps ticking while pkexec waits for a password.
+        self.charge_sync_pending.set(true);
+        let page = self.clone();
+        let state = state.clone();
+        glib::spawn_future_local(async move {
+            let to_apply = targets.clone();
+            let result = gio::spawn_blocking(move || battery::apply_charge_limits(&to_apply)).await;
+            page.charge_sync_pending.set(false);
+
+            match result {
+                Ok(Ok(())) => {
+                    let label = match targets.values().next() {
+                        Some(first) if targets.values().all(|limit| limit == first) => format!("{}%", first),
+                        _ => targets
+                            .iter()
+                            .map(|(name, limit)| format!("{} {}%", name, limit))
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    };
+                    page.show_battery_limits(&state);
+                    battery_history::record_event(&format!("Limit {}", label));
+                    state.toast(&format!("Charge limit set to {}", label));
+                }
+                _ => {
+                    // Don't re-prompt every minute after a failure.
+                    page.charge_sync_failed.set(true);
+                    state.toast("Failed to set charge limit");
+                }
+            }
+        });
+    }
+
+    /// Shows the threshold in force on the first battery and, on
+    /// dual-battery machines, the limit of each one.
+    fn show_battery_limits(&self, state: &AppState) {
+        let current = battery::charge_limits();
+        if let Some(first) = current.values().next() {
+            self.charge_spin.set_subtitle(&format!("Currently {}%", first));
+        }
+
+        let config = Config::load().battery;
+        for (name, spin) in &self.battery_limit_spins {
+            // Without a chosen limit, show what the firmware has.
+            let limit = config.battery_limits.get(name).copied().or(config.charge_limit);
+            let Some(limit) = limit.or_else(|| current.get(name).copied()) else {
+                continue;
+            };
+            spin.set_value(limit as f64);
+            spin.set_sensitive(state.read_only_reason().is_none());
+            if let Some(percent) = current.get(name) {
+                spin.set_subtitle(&format!("Currently {}%", percent));
+            }
+        }
+    }
+}
@@ -0,0 +1,773 @@
+use crate::battery_history;
+use crate::config::{AdaptiveCoresConfig, Config};
+use crate::corepark::{self, AdaptiveController, CpuSampler};
+use crate::cpufreq::{self, Governor};
+use crate::itmt;
+use crate::latency;
+use crate::remote;
+use crate::ryzenadj::{self, TdpLimits};
+use crate::system_info;
+use crate::undervolt::{self, Offsets};
+use gtk4::glib::{self, clone};
+use gtk4::prelude::*;
+use gtk4::{gio, Align, Box as GtkBox, Button, Orientation, StringList};
+use libadwaita as adw;
+use adw::prelude::*;
+use crate::latency::LatencyHold;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+use super::progress::Progress;
+use super::{apply_on_change, bind_choice, bind_switch, confirm_then, restrict, AppState};
+
+/// The processor groups: thread limit and governor, adaptive core
+/// parking, APU power limits and undervolting.
+#[derive(Clone)]
+pub(super) struct CpuPage {
+    groups: [adw::PreferencesGroup; 4],
+    pub(super) cpu_spin: adw::SpinRow,
+    pub(super) cpu_apply_btn: Button,
+    governor_combo: adw::ComboRow,
+    latency_row: adw::SwitchRow,
+    pub(super) itmt_row: adw::SwitchRow,
+    latency_hold: Rc<RefCell<Option<LatencyHold>>>,
+    pub(super) adaptive_row: adw::SwitchRow,
+    adaptive_min_spin: adw::SpinRow,
+    adaptive_max_spin: adw::SpinRow,
+    adaptive: Rc<RefCell<Option<AdaptiveController>>>,
+    tdp_stapm_scale: gtk4::Scale,
+    tdp_fast_scale: gtk4::Scale,
+    tdp_slow_scale: gtk4::Scale,
+    pub(super) tdp_apply_btn: Button,
+    pub(super) undervolt_core_spin: adw::SpinRow,
+    undervolt_cache_spin: adw::SpinRow,
+    undervolt_apply_btn: Button,
+    undervolt_reset_btn: Button,
+    progress: Progress,
+}
+
+impl CpuPage {
+    pub(super) fn build(progress: &Progress) -> Self {
+        let (cpu_group, cpu_spin, cpu_apply_btn, governor_combo, latency_row, itmt_row) = Self::build_cpu_group();
+        let (adaptive_group, adaptive_row, adaptive_min_spin, adaptive_max_spin) =
+            Self::build_adaptive_cores_group();
+        let (tdp_group, tdp_stapm_scale, tdp_fast_scale, tdp_slow_scale, tdp_apply_btn) =
+            Self::build_tdp_group();
+        let (
+            undervolt_group,
+            undervolt_core_spin,
+            undervolt_cache_spin,
+            undervolt_apply_btn,
+            undervolt_reset_btn,
+        ) = Self::build_undervolt_group();
+
+        Self {
+            groups: [cpu_group, adaptive_group, tdp_group, undervolt_group],
+            cpu_spin,
+            cpu_apply_btn,
+            governor_combo,
+            latency_row,
+            itmt_row,
+            latency_hold: Rc::new(RefCell::new(None)),
+            adaptive_row,
+            adaptive_min_spin,
+            adaptive_max_spin,
+            adaptive: Rc::new(RefCell::new(None)),
+            tdp_stapm_scale,
+            tdp_fast_scale,
+            tdp_slow_scale,
+            tdp_apply_btn,
+            undervolt_core_spin,
+            undervolt_cache_spin,
+            undervolt_apply_btn,
+            undervolt_reset_btn,
+            progress: progress.clone(),
+        }
+    }
+
+    pub(super) fn groups(&self) -> &[adw::PreferencesGroup] {
+        &self.groups
+    }
+
+    pub(super) fn bind(&self, state: &AppState) {
+        state
+            .bind_property("online-cpus", &self.cpu_spin, "value")
+            .transform_to(|_, cpus: u32| Some(cpus as f64))
+            .build();
+        state
+            .bind_property("apply-on-change", &self.cpu_apply_btn, "visible")
+            .invert_boolean()
+            .sync_create()
+            .build();
+        // Raise the limits before the thread counts arrive, which the spins
+        // would otherwise clamp to the old ones.
+        state.connect_max_cpus_notify(clone!(
+            #[strong(rename_to = page)] self,
+            move |state| {
+                let total = state.max_cpus() as f64;
+                for spin in [&page.cpu_spin, &page.adaptive_min_spin, &page.adaptive_max_spin] {
+                    spin.adjustment().set_upper(total);
+                }
+                state.notify_adaptive_min();
+                state.notify_adaptive_max();
+            }
+        ));
+
+        self.setup_cpu_limit(state);
+        self.setup_adaptive_cores(state);
+        self.setup_governor(state);
+        self.setup_latency(state);
+        self.setup_itmt(state);
+        self.setup_tdp(state);
+        self.setup_undervolt(state);
+    }
+
+    /// Enables the controls once `info` is fresh.
+    pub(super) fn show_system_info(&self, state: &AppState, stale: bool) {
+        if stale {
+            return;
+        }
+        self.cpu_apply_btn.set_sensitive(true);
+        self.adaptive_row.set_sensitive(true);
+        self.sync_adaptive_controller(state);
+    }
+
+    fn build_cpu_group() -> (adw::PreferencesGroup, adw::SpinRow, Button, adw::ComboRow, adw::SwitchRow, adw::SwitchRow) {
+        let cpu_group = adw::PreferencesGroup::builder()
+            .title("Processor")
+            .description("Limit active threads for power savings.")
+            .build();
+
+        let cpu_spin = adw::SpinRow::with_range(1.0, 16.0, 1.0);
+        cpu_spin.set_title("CPU Thread Limit");
+        cpu_spin.set_subtitle("Number of online logical cores");
+        cpu_group.add(&cpu_spin);
+
+        let governor_combo = adw::ComboRow::builder()
+            .title("Governor")
+            .subtitle("How the CPU picks its clock speed")
+            .visible(false)
+            .build();
+        cpu_group.add(&governor_combo);
+
+        let latency_row = adw::SwitchRow::builder()
+            .title("Low Latency")
+            .subtitle("Keep cores out of deep sleep states; greatly raises idle power draw")
+            .visible(false)
+            .build();
+        cpu_group.add(&latency_row);
+
+        let itmt_row = adw::SwitchRow::builder()
+            .title("Prefer P-Cores")
+            .subtitle(
+                "Busy threads start on performance cores (ITMT). Off treats E-cores alike. \
+                 The thread limit parks E-cores first; pinned processes stay put.",
+            )
+            .visible(false)
+            .build();
+        cpu_group.add(&itmt_row);
+
+        let cpu_apply_btn = Button::builder()
+            .label("Apply")
+            .margin_top(12)
+            .css_classes(["suggested-action"])
+            .sensitive(false)
+            .build();
+        cpu_group.add(&cpu_apply_btn);
+
+        (cpu_group, cpu_spin, cpu_apply_btn, governor_combo, latency_row, itmt_row)
+    }
+
+    fn build_adaptive_cores_group() -> (adw::PreferencesGroup, adw::SwitchRow, adw::SpinRow, adw::SpinRow) {
+        let adaptive_group = adw::PreferencesGroup::builder()
+            .title("Adaptive Core Parking")
+            .description("Park idle threads automatically under light load.")
+            .build();
+
+        let adaptive_row = adw::SwitchRow::builder()
+            .title("Adaptive Mode")
+            .subtitle("Overrides the manual thread limit")
+            .sensitive(false)
+            .build();
+        adaptive_group.add(&adaptive_row);
+
+        let adaptive_min_spin = adw::SpinRow::with_range(1.0, 16.0, 1.0);
+        adaptive_min_spin.set_title("Minimum Threads");
+        adaptive_group.add(&adaptive_min_spin);
+
+        let adaptive_max_spin = adw::SpinRow::with_range(1.0, 16.0, 1.0);
+        adaptive_max_spin.set_title("Maximum Threads");
+        adaptive_group.add(&adaptive_max_spin);
+
+        (adaptive_group, adaptive_row, adaptive_min_spin, adaptive_max_spin)
+    }
+
+    fn build_tdp_group() -> (adw::PreferencesGroup, gtk4::Scale, gtk4::Scale, gtk4::Scale, Button) {
+        let tdp_group = adw::PreferencesGroup::builder()
+            .title("APU Power Limits")
+            .description("Sustained and boost power limits set through ryzenadj.")
+            .build();
+
+        let slider = |title: &str, subtitle: &str| {
+            let scale = gtk4::Scale::with_range(
+                Orientation::Horizontal,
+                ryzenadj::MIN_TDP_W as f64,
+                ryzenadj::MAX_TDP_W as f64,
+                1.0,
+            );
+            scale.set_digits(0);
+            scale.set_draw_value(true);
+            scale.set_value_pos(gtk4::PositionType::Right);
+            scale.set_hexpand(true);
+            scale.set_width_request(180);
+            scale.set_valign(Align::Center);
+
+            let row = adw::ActionRow::builder()
+                .title(title)
+                .subtitle(subtitle)
+                .build();
+            row.add_suffix(&scale);
+            tdp_group.add(&row);
+            scale
+        };
+
+        let stapm_scale = slider("Sustained (W)", "STAPM limit");
+        let slow_scale = slider("Slow Boost (W)", "Held for a few minutes");
+        let fast_scale = slider("Fast Boost (W)", "Held for a few seconds");
+
+        let apply_btn = Button::builder()
+            .label("Apply")
+            .margin_top(12)
+            .css_classes(["suggested-action"])
+            .build();
+        tdp_group.add(&apply_btn);
+
+        (tdp_group, stapm_scale, fast_scale, slow_scale, apply_btn)
+    }
+
+    fn build_undervolt_group() -> (adw::PreferencesGroup, adw::SpinRow, adw::SpinRow, Button, Button) {
+        let undervolt_group = adw::PreferencesGroup::builder()
+            .title("Undervolting")
+            .description(format!(
+                "Lower CPU voltages for less heat. New offsets are stress-tested for {}s and rolled back if the machine becomes unstable.",
+                undervolt::STRESS_TEST_SECS
+            ))
+            .build();
+
+        let offset_row = |title: &str| {
+            let spin = adw::SpinRow::with_range(
+                undervolt::MIN_OFFSET_MV as f64,
+                undervolt::MAX_OFFSET_MV as f64,
+                5.0,
+            );
+            spin.set_title(title);
+            spin.set_subtitle("mV");
+            undervolt_group.add(&spin);
+            spin
+        };
+        let core_spin = offset_row("Core Offset");
+        let cache_spin = offset_row("Cache Offset");
+
+        let button_box = GtkBox::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(12)
+            .margin_top(12)
+            .homogeneous(true)
+            .build();
+        let reset_btn = Button::builder()
+            .label("Reset")
+            .build();
+        button_box.append(&reset_btn);
+        let apply_btn = Button::builder()
+            .label("Test & Apply")
+            .css_classes(["suggested-action"])
+            .build();
+        button_box.append(&apply_btn);
+        undervolt_group.add(&button_box);
+
+        (undervolt_group, core_spin, cache_spin, apply_btn, reset_btn)
+    }
+
+    fn setup_cpu_limit(&self, state: &AppState) {
+        self.cpu_apply_btn.connect_clicked(clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            move |_| {
+                let target = page.cpu_spin.value() as u32;
+                let online = state.online_cpus();
+                page.cpu_apply_btn.set_sensitive(false);
+
+                let apply = clone!(
+                    #[strong] page,
+                    #[strong] state,
+                    move || {
+                        state.toast("Applying CPU settings...");
+                        glib::spawn_future_local(async move {
+                            let result = gio::spawn_blocking(move || {
+                                system_info::apply_cpu_threads(target)
+                            }).await;
+
+                            page.cpu_apply_btn.set_sensitive(true);
+
+                            match result {
+                                Ok(Ok(())) => {
+                                    state.set_online_cpus(target);
+                                    battery_history::record_event(&format!("{} threads", target));
+                                    state.toast("CPU thread limit applied.");
+                                }
+                                _ => {
+                                    state.toast("Failed to apply CPU settings.");
+                                }
+                            }
+                        });
+                    }
+                );
+
+                let btn = page.cpu_apply_btn.clone();
+                confirm_then(
+                    &page.cpu_apply_btn,
+                    target < online && Config::load().confirm.cpu_offline,
+                    "Take Cores Offline?",
+                    &format!(
+                        "{} of {} threads will stop running work until the limit is raised again.",
+                        online - target.min(online),
+                        online
+                    ),
+                    apply,
+                    move || btn.set_sensitive(true),
+                );
+            }
+        ));
+
+        apply_on_change(
+            state,
+            &self.cpu_spin,
+            &self.cpu_apply_btn,
+            clone!(
+                #[weak] state,
+                #[upgrade_or] false,
+                move |value| value as u32 == state.online_cpus()
+            ),
+        );
+    }
+
+    fn setup_adaptive_cores(&self, state: &AppState) {
+        state.bind_property("adaptive-cores", &self.adaptive_row, "active").sync_create().build();
+        state.bind_property("adaptive-min", &self.adaptive_min_spin, "value").sync_create().build();
+        state
+            .bind_property("adaptive-max", &self.adaptive_max_spin, "value")
+            .transform_to(|binding, max: u32| {
+                // 0 allows every thread, which isn't known before the first probe.
+                let total = binding.source().and_downcast::<AppState>().map_or(0, |state| state.max_cpus());
+                match (max, total) {
+                    (0, 0) => None,
+                    (0, total) => Some(total as f64),
+                    (max, _) => Some(max as f64),
+                }
+            })
+            .sync_create()
+            .build();
+
+        self.adaptive_row.connect_active_notify(clone!(
+            #[strong] state,
+            move |row| {
+                if row.is_active() != state.adaptive_cores() {
+                    save_adaptive_config(&state, |c| c.enabled = row.is_active());
+                }
+            }
+        ));
+
+        // A spin clamping a saved value to the thread count isn't a change.
+        self.adaptive_min_spin.connect_value_notify(clone!(
+            #[strong] state,
+            move |spin| {
+                let min = spin.value() as u32;
+                if min != state.adaptive_min().min(spin.adjustment().upper() as u32) {
+                    save_adaptive_config(&state, |c| c.min_threads = min);
+                }
+            }
+        ));
+
+        self.adaptive_max_spin.connect_value_notify(clone!(
+            #[strong] state,
+            move |spin| {
+                let max = spin.value() as u32;
+                let upper = spin.adjustment().upper() as u32;
+                let saved = match state.adaptive_max() {
+                    0 => upper,
+                    saved => saved.min(upper),
+                };
+                if max != saved {
+                    save_adaptive_config(&state, |c| c.max_threads = max);
+                }
+            }
+        ));
+
+        let sync = clone!(
+            #[strong(rename_to = page)] self,
+            move |state: &AppState| page.sync_adaptive_controller(state)
+        );
+        state.connect_adaptive_cores_notify(sync.clone());
+        state.connect_adaptive_min_notify(sync.clone());
+        state.connect_adaptive_max_notify(sync);
+
+        let page = self.clone();
+        let state = state.clone();
+        let applying = Rc::new(Cell::new(false));
+        let mut sampler = CpuSampler::default();
+
+        glib::timeout_add_seconds_local(corepark::SAMPLE_INTERVAL_SECS, move || {
+            let Some(usage) = sampler.sample() else {
+                return glib::ControlFlow::Continue;
+            };
+            if applying.get() {
+                return glib::ControlFlow::Continue;
+            }
+
+            let current = state.online_cpus();
+            let target = match page.adaptive.borrow_mut().as_mut() {
+                Some(controller) if current > 0 => controller.next_target(current, usage),
+                _ => None,
+            };
+
+            if let Some(target) = target {
+                applying.set(true);
+                let page = page.clone();
+                let state = state.clone();
+                let applying = applying.clone();
+
+                glib::spawn_future_local(async move {
+                    let result = gio::spawn_blocking(move || corepark::apply(target)).await;
+
+                    applying.set(false);
+
+                    match result {
+                        Ok(Ok(())) => {
+                            state.set_online_cpus(target);
+                            if let Some(controller) = page.adaptive.borrow_mut().as_mut() {
+                                controller.applied();
+                            }
+                        }
+                        // Dismissed or refused authentication: wait before
+                        // prompting again instead of on the next sample.
+                        Ok(Err(failure)) if failure.not_authorized => {
+                            let backoff = page.adaptive.borrow_mut().as_mut().map(|controller| controller.auth_failed());
+                            if backoff == Some(corepark::AUTH_BACKOFF) {
+                                state.toast("Adaptive core parking wasn't authorized and will ask again later");
+                            }
+                        }
+                        result => {
+                            // Stop retrying until the user re-enables it.
+                            page.adaptive.borrow_mut().take();
+                            page.adaptive_row.set_active(false);
+                            let message = match result {
+                                Ok(Err(failure)) => format!("Adaptive core parking was disabled: {}", failure.error),
+                                _ => "Adaptive core parking failed and was disabled".to_string(),
+                            };
+                            state.toast(&message);
+                        }
+                    }
+                });
+            }
+
+            glib::ControlFlow::Continue
+        });
+    }
+
+    fn sync_adaptive_controller(&self, state: &AppState) {
+        let total = state.max_cpus().max(1);
+        let max = match state.adaptive_max() {
+            0 => total,
+            max => max.min(total),
+        };
+
+        let enabled = state.adaptive_cores() && state.read_only_reason().is_none() && remote::host().is_none();
+        *self.adaptive.borrow_mut() = enabled
+            .then(|| AdaptiveController::new(state.adaptive_min().min(max), max));
+
+        self.cpu_spin.set_sensitive(!state.adaptive_cores());
+        self.cpu_apply_btn.set_sensitive(!state.adaptive_cores());
+        self.restrict_privileged(state);
+    }
+
+    /// Keeps the pkexec-backed controls disabled in read-only mode, after
+    /// anything that turns them back on.
+    pub(super) fn restrict_privileged(&self, state: &AppState) {
+        restrict(
+            state,
+            &[
+                self.cpu_spin.upcast_ref(),
+                self.cpu_apply_btn.upcast_ref(),
+                self.itmt_row.upcast_ref(),
+                self.adaptive_row.upcast_ref(),
+            ],
+        );
+    }
+
+    /// The request is held only while TuxTuner runs, so it isn't saved.
+    fn setup_latency(&self, state: &AppState) {
+        self.latency_row.set_visible(latency::supported());
+
+        bind_switch(
+            state,
+            "latency",
+            &self.latency_row,
+            "Low latency failed",
+            |on| if on { latency::hold().map(Some) } else { Ok(None) },
+            clone!(
+                #[strong(rename_to = page)] self,
+                #[strong] state,
+                move |_, hold| match hold {
+                    Some(hold) => {
+                        page.latency_hold.replace(Some(hold));
+                        state.toast("Low latency on until turned off or TuxTuner quits; expect higher power draw");
+                    }
+                    None => {
+                        if let Some(hold) = page.latency_hold.borrow_mut().take() {
+                            gio::spawn_blocking(move || hold.release());
+                        }
+                    }
+                }
+            ),
+        );
+    }
+
+    /// Offers the governors the cpufreq driver has; hidden without
+    /// cpufreq or with a single governor.
+    fn setup_governor(&self, state: &AppState) {
+        let page = self.clone();
+        let state = state.clone();
+        glib::spawn_future_local(async move {
+            let Ok((choices, current)) =
+                gio::spawn_blocking(|| (cpufreq::available_governors(), cpufreq::governor())).await
+            else {
+                return;
+            };
+            if choices.len() < 2 {
+                return;
+            }
+            page.fill_governors(&state, choices, current);
+        });
+    }
+
+    fn fill_governors(&self, state: &AppState, choices: Vec<Governor>, current: Option<Governor>) {
+        let labels: Vec<&str> = choices.iter().map(|governor| governor.label()).collect();
+        self.governor_combo.set_model(Some(&StringList::new(&labels)));
+        state.set_governor(current.map(Governor::as_str).unwrap_or_default());
+        self.governor_combo.set_visible(true);
+
+        if let Some(reason) = state.read_only_reason() {
+            self.governor_combo.set_sensitive(false);
+            self.governor_combo.set_tooltip_text(Some(&reason));
+        }
+
+        bind_choice(
+            state,
+            "governor",
+            &self.governor_combo,
+            choices.into_iter().map(|governor| (governor.as_str().to_string(), governor)).collect(),
+            "Governor change failed",
+            cpufreq::set_governor,
+        );
+    }
+
+    /// Shown on hybrid Intel CPUs only, where the ranking decides between
+    /// P- and E-cores.
+    fn setup_itmt(&self, state: &AppState) {
+        let row = self.itmt_row.clone();
+        glib::spawn_future_local(clone!(
+            #[strong] state,
+            async move {
+                let Ok(Some(enabled)) = gio::spawn_blocking(itmt::enabled).await else {
+                    return;
+                };
+                state.set_itmt(enabled);
+                row.set_visible(true);
+            }
+        ));
+
+        bind_switch(
+            state,
+            "itmt",
+            &self.itmt_row,
+            "Changing core preference failed",
+            itmt::set_enabled,
+            clone!(
+                #[strong] state,
+                move |enabled, ()| {
+                    state.toast(if enabled {
+                        "P-cores preferred until reboot"
+                    } else {
+                        "All cores treated alike until reboot"
+                    });
+                }
+            ),
+        );
+    }
+
+    fn setup_tdp(&self, state: &AppState) {
+        if !ryzenadj::available() {
+            if let Some(group) = self.tdp_apply_btn.ancestor(adw::PreferencesGroup::static_type()) {
+                group.set_visible(false);
+            }
+            return;
+        }
+
+        let limits = Config::load().tdp.unwrap_or_default();
+        self.tdp_stapm_scale.set_value(limits.stapm_w as f64);
+        self.tdp_fast_scale.set_value(limits.fast_w as f64);
+        self.tdp_slow_scale.set_value(limits.slow_w as f64);
+
+        if let Some(reason) = state.read_only_reason() {
+            self.tdp_apply_btn.set_sensitive(false);
+            self.tdp_apply_btn.set_tooltip_text(Some(&reason));
+            return;
+        }
+
+        self.tdp_apply_btn.connect_clicked(clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            move |btn| {
+                let limits = TdpLimits {
+                    stapm_w: page.tdp_stapm_scale.value() as u32,
+                    fast_w: page.tdp_fast_scale.value() as u32,
+                    slow_w: page.tdp_slow_scale.value() as u32,
+                };
+                if let Err(e) = limits.validate() {
+                    state.toast(&e);
+                    return;
+                }
+
+                btn.set_sensitive(false);
+
+                let state = state.clone();
+                let btn = btn.clone();
+                glib::spawn_future_local(async move {
+                    let result = gio::spawn_blocking(move || ryzenadj::apply_limits(limits)).await;
+
+                    btn.set_sensitive(true);
+
+                    match result {
+                        Ok(Ok(())) => {
+                            let mut config = Config::load();
+                            config.tdp = Some(limits);
+                            if config.save().is_err() {
+                                state.toast("Failed to save power limit settings");
+                            } else {
+                                state.toast(&format!(
+                                    "Power limits set to {}/{}/{} W",
+                                    limits.stapm_w, limits.slow_w, limits.fast_w
+                                ));
+                            }
+                        }
+                        Ok(Err(e)) => state.toast(&format!("Power limit change failed: {}", e)),
+                        Err(_) => state.toast("Power limit change failed"),
+                    }
+                });
+            }
+        ));
+    }
+
+    fn setup_undervolt(&self, state: &AppState) {
+        if !undervolt::supported() {
+            if let Some(group) = self.undervolt_apply_btn.ancestor(adw::PreferencesGroup::static_type()) {
+                group.set_visible(false);
+            }
+            return;
+        }
+
+        let offsets = undervolt::saved().unwrap_or_default();
+        self.undervolt_core_spin.set_value(offsets.core_mv as f64);
+        self.undervolt_cache_spin.set_value(offsets.cache_mv as f64);
+
+        if let Some(rejected) = undervolt::rolled_back() {
+            state.toast(&format!(
+                "Undervolt of {}/{} mV was rolled back after an unclean shutdown",
+                rejected.core_mv, rejected.cache_mv
+            ));
+        }
+
+        if let Some(reason) = state.read_only_reason() {
+            for btn in [&self.undervolt_apply_btn, &self.undervolt_reset_btn] {
+                btn.set_sensitive(false);
+                btn.set_tooltip_text(Some(&reason));
+            }
+            return;
+        }
+
+        self.undervolt_apply_btn.connect_clicked(clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            move |_| {
+                let offsets = Offsets {
+                    core_mv: page.undervolt_core_spin.value() as i32,
+                    cache_mv: page.undervolt_cache_spin.value() as i32,
+                };
+                let cancel = Arc::new(AtomicBool::new(false));
+                page.progress.show(
+                    "Testing Undervolt",
+                    &format!(
+                        "Loading every core for {} seconds to check {}/{} mV is stable",
+                        undervolt::STRESS_TEST_SECS, offsets.core_mv, offsets.cache_mv
+                    ),
+                    Some(Duration::from_secs(undervolt::STRESS_TEST_SECS)),
+                    Some(Arc::clone(&cancel)),
+                );
+                page.run_undervolt_task(
+                    &state,
+                    move || undervolt::test_and_apply(offsets, cancel),
+                    format!("Undervolt of {}/{} mV is stable and saved", offsets.core_mv, offsets.cache_mv),
+                );
+            }
+        ));
+
+        self.undervolt_reset_btn.connect_clicked(clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            move |_| {
+                page.undervolt_core_spin.set_value(0.0);
+                page.undervolt_cache_spin.set_value(0.0);
+                page.run_undervolt_task(&state, undervolt::reset, "Stock voltages restored".to_string());
+            }
+        ));
+    }
+
+    fn run_undervolt_task(
+        &self,
+        state: &AppState,
+        task: impl FnOnce() -> Result<(), String> + Send + 'static,
+        success: String,
+    ) {
+        for btn in [&self.undervolt_apply_btn, &self.undervolt_reset_btn] {
+            btn.set_sensitive(false);
+        }
+
+        let page = self.clone();
+        let state = state.clone();
+        glib::spawn_future_local(async move {
+            let result = gio::spawn_blocking(task).await;
+
+            page.progress.hide();
+            for btn in [&page.undervolt_apply_btn, &page.undervolt_reset_btn] {
+                btn.set_sensitive(true);
+            }
+
+            match result {
+                Ok(Ok(())) => state.toast(&success),
+                Ok(Err(e)) => state.toast(&format!("Undervolt failed: {}", e)),
+                Err(_) => state.toast("Undervolt failed"),
+            }
+        });
+    }
+}
+
+fn save_adaptive_config(state: &AppState, update: impl FnOnce(&mut AdaptiveCoresConfig)) {
+    let mut config = Config::load();
+    update(&mut config.adaptive_cores);
+    if config.save().is_err() {
+        state.toast("Failed to save adaptive core settings");
+    }
+    state.load_config(&config);
+}
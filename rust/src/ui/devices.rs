@@ -0,0 +1,597 @@
+use crate::bluetooth;
+use crate::devpower::{self, DeviceKind, PowerDevice, PowerRule};
+use crate::nic::{self, NetInterface};
+use crate::privacy::{self, PrivacyDevice};
+use crate::rfkill::{self, Radio};
+use crate::wakeup::{self, WakeupSource};
+use gtk4::glib::{self, clone};
+use gtk4::prelude::*;
+use gtk4::{gio, Align, Button};
+use libadwaita as adw;
+use adw::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use super::{bind_switch, connect_switch, AppState};
+
+/// How often radio states are re-read for changes made elsewhere.
+const RADIO_POLL_SECS: u32 = 5;
+
+/// What may wake the machine and the power of its radios, network cards,
+/// cameras and other peripherals.
+#[derive(Clone)]
+pub(super) struct DevicesPage {
+    groups: [adw::PreferencesGroup; 5],
+    pub(super) wakeup_acpi_row: adw::ExpanderRow,
+    wakeup_usb_row: adw::ExpanderRow,
+    network_group: adw::PreferencesGroup,
+    radio_group: adw::PreferencesGroup,
+    airplane_row: adw::SwitchRow,
+    /// Per-radio rows below the airplane switch, by rfkill index.
+    radio_rows: Rc<RefCell<Vec<(u32, adw::SwitchRow)>>>,
+    privacy_group: adw::PreferencesGroup,
+    privacy_rows: Rc<RefCell<Vec<adw::SwitchRow>>>,
+    bluetooth_row: adw::SwitchRow,
+    pub(super) device_usb_row: adw::ExpanderRow,
+    device_pci_row: adw::ExpanderRow,
+    power_rules_row: adw::ExpanderRow,
+    power_rule_rows: Rc<RefCell<Vec<adw::ActionRow>>>,
+}
+
+impl DevicesPage {
+    pub(super) fn build() -> Self {
+        let (wakeup_group, wakeup_acpi_row, wakeup_usb_row) = Self::build_wakeup_group();
+
+        let network_group = adw::PreferencesGroup::builder()
+            .title("Wired Network")
+            .description("Wired cards can keep drawing power in standby to listen for wake packets.")
+            .visible(false)
+            .build();
+
+        let (radio_group, airplane_row) = Self::build_radio_group();
+
+        let privacy_group = adw::PreferencesGroup::builder()
+            .title("Privacy")
+            .description("Cut off USB cameras and microphones until you turn them back on.")
+            .visible(false)
+            .build();
+
+        let (device_group, bluetooth_row, device_usb_row, device_pci_row, power_rules_row) =
+            Self::build_device_power_group();
+
+        Self {
+            groups: [
+                wakeup_group,
+                network_group.clone(),
+                radio_group.clone(),
+                privacy_group.clone(),
+                device_group,
+            ],
+            wakeup_acpi_row,
+            wakeup_usb_row,
+            network_group,
+            radio_group,
+            airplane_row,
+            radio_rows: Rc::new(RefCell::new(Vec::new())),
+            privacy_group,
+            privacy_rows: Rc::new(RefCell::new(Vec::new())),
+            bluetooth_row,
+            device_usb_row,
+            device_pci_row,
+            power_rules_row,
+            power_rule_rows: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    pub(super) fn groups(&self) -> &[adw::PreferencesGroup] {
+        &self.groups
+    }
+
+    pub(super) fn bind(&self, state: &AppState) {
+        // A profile can switch radios, cameras and Bluetooth.
+        state.connect_reload(clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            move || {
+                page.refresh_radios(&state);
+                page.refresh_privacy_devices(&state);
+                page.sync_bluetooth_row(&state);
+            }
+        ));
+
+        self.setup_wakeup_sources(state);
+        self.setup_network_interfaces(state);
+        self.setup_bluetooth(state);
+        self.setup_radios(state);
+        self.refresh_privacy_devices(state);
+        self.setup_device_power(state);
+    }
+
+    fn build_wakeup_group() -> (adw::PreferencesGroup, adw::ExpanderRow, adw::ExpanderRow) {
+        let wakeup_group = adw::PreferencesGroup::builder()
+            .title("Wakeup Sources")
+            .description("Devices allowed to wake the laptop from sleep.")
+            .build();
+
+        let wakeup_acpi_row = adw::ExpanderRow::builder()
+            .title("ACPI Devices")
+            .subtitle("Lid, power button, controllers")
+            .build();
+        wakeup_group.add(&wakeup_acpi_row);
+
+        let wakeup_usb_row = adw::ExpanderRow::builder()
+            .title("USB Devices")
+            .subtitle("Touchpads, mice, keyboards and other peripherals")
+            .build();
+        wakeup_group.add(&wakeup_usb_row);
+
+        (wakeup_group, wakeup_acpi_row, wakeup_usb_row)
+    }
+
+    fn setup_wakeup_sources(&self, state: &AppState) {
+        let lists = [
+            (&self.wakeup_acpi_row, wakeup::acpi_sources()),
+            (&self.wakeup_usb_row, wakeup::usb_sources()),
+        ];
+
+        let mut any = false;
+        for (expander, sources) in lists {
+            expander.set_visible(!sources.is_empty());
+            any |= !sources.is_empty();
+
+            for source in sources {
+                expander.add_row(&self.build_wakeup_row(state, source));
+            }
+        }
+
+        if !any {
+            self.groups[0].set_visible(false);
+        }
+    }
+
+    fn build_wakeup_row(&self, state: &AppState, source: WakeupSource) -> adw::SwitchRow {
+        let row = adw::SwitchRow::builder()
+            .title(glib::markup_escape_text(&source.id))
+            .subtitle(glib::markup_escape_text(&source.description))
+            .active(source.enabled)
+            .build();
+
+        if let Some(reason) = state.read_only_reason() {
+            row.set_sensitive(false);
+            row.set_tooltip_text(Some(&reason));
+        }
+
+        let applied = Rc::new(Cell::new(source.enabled));
+        connect_switch(
+            &row,
+            state,
+            "Wakeup change failed",
+            clone!(#[strong] applied, move || applied.get()),
+            move |enabled| wakeup::apply_wakeup(&source, enabled),
+            move |enabled, ()| applied.set(enabled),
+        );
+
+        row
+    }
+
+    fn build_radio_group() -> (adw::PreferencesGroup, adw::SwitchRow) {
+        let radio_group = adw::PreferencesGroup::builder()
+            .title("Radios")
+            .description("Wireless transmitters, blocked through rfkill.")
+            .visible(false)
+            .build();
+
+        let airplane_row = adw::SwitchRow::builder()
+            .title("Airplane Mode")
+            .subtitle("Turn off every radio")
+            .build();
+        radio_group.add(&airplane_row);
+
+        (radio_group, airplane_row)
+    }
+
+    fn build_device_power_group() -> (
+        adw::PreferencesGroup,
+        adw::SwitchRow,
+        adw::ExpanderRow,
+        adw::ExpanderRow,
+        adw::ExpanderRow,
+    ) {
+        let device_group = adw::PreferencesGroup::builder()
+            .title("Device Power")
+            .description("Let idle devices power down. Choices persist across reboots and replugs.")
+            .build();
+
+        let bluetooth_row = adw::SwitchRow::builder()
+            .title("Bluetooth")
+            .subtitle("Adapter power")
+            .visible(false)
+            .build();
+        device_group.add(&bluetooth_row);
+
+        let device_usb_row = adw::ExpanderRow::builder()
+            .title("USB Autosuspend")
+            .subtitle("Suspend idle USB devices")
+            .build();
+        device_group.add(&device_usb_row);
+
+        let device_pci_row = adw::ExpanderRow::builder()
+            .title("PCI Runtime Power Management")
+            .subtitle("Power down idle controllers")
+            .build();
+        device_group.add(&device_pci_row);
+
+        let power_rules_row = adw::ExpanderRow::builder()
+            .title("Saved Rules")
+            .subtitle("udev rules installed by TuxTuner")
+            .build();
+        device_group.add(&power_rules_row);
+
+        (device_group, bluetooth_row, device_usb_row, device_pci_row, power_rules_row)
+    }
+
+    fn setup_network_interfaces(&self, state: &AppState) {
+        let page = self.clone();
+        let state = state.clone();
+        glib::spawn_future_local(async move {
+            let Ok(interfaces) = gio::spawn_blocking(nic::interfaces).await else {
+                return;
+            };
+            page.network_group.set_visible(!interfaces.is_empty());
+
+            for interface in interfaces {
+                page.network_group.add(&page.build_network_row(&state, interface));
+            }
+        });
+    }
+
+    fn build_network_row(&self, state: &AppState, interface: NetInterface) -> adw::ExpanderRow {
+        let expander = adw::ExpanderRow::builder()
+            .title(glib::markup_escape_text(&interface.name))
+            .subtitle(interface.link_label())
+            .build();
+
+        if interface.wol_supported {
+            let name = interface.name.clone();
+            expander.add_row(&build_network_switch(
+                state,
+                "Wake-on-LAN",
+                "Wake from sleep on a magic packet",
+                interface.wol_enabled,
+                move |enabled| nic::apply_wol(&name, enabled),
+            ));
+        }
+        if let Some(eee) = interface.eee {
+            let name = interface.name.clone();
+            expander.add_row(&build_network_switch(
+                state,
+                "Energy-Efficient Ethernet",
+                "Idle the link between packets",
+                eee,
+                move |enabled| nic::apply_eee(&name, enabled),
+            ));
+        }
+        // Nothing to toggle without ethtool or driver support.
+        expander.set_enable_expansion(interface.wol_supported || interface.eee.is_some());
+
+        expander
+    }
+
+    fn setup_bluetooth(&self, state: &AppState) {
+        bind_switch(
+            state,
+            "bluetooth",
+            &self.bluetooth_row,
+            "Bluetooth change failed",
+            bluetooth::set_powered,
+            |_, ()| {},
+        );
+
+        self.sync_bluetooth_row(state);
+    }
+
+    /// Reads the adapter state off the main thread; the row stays hidden
+    /// without an adapter.
+    fn sync_bluetooth_row(&self, state: &AppState) {
+        let page = self.clone();
+        let state = state.clone();
+        glib::spawn_future_local(async move {
+            let powered = gio::spawn_blocking(bluetooth::powered).await.ok().flatten();
+            page.bluetooth_row.set_visible(powered.is_some());
+            if let Some(powered) = powered {
+                state.set_bluetooth(powered);
+            }
+        });
+    }
+
+    fn setup_radios(&self, state: &AppState) {
+        state
+            .bind_property("airplane-mode", &self.airplane_row, "active")
+            .sync_create()
+            .build();
+        self.airplane_row.connect_active_notify(clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            move |row| {
+                if row.is_active() == state.airplane_mode() {
+                    return;
+                }
+                if let Err(e) = rfkill::set_airplane_mode(row.is_active()) {
+                    state.toast(&format!("Airplane mode change failed: {}", e));
+                }
+                page.refresh_radios(&state);
+                page.sync_bluetooth_row(&state);
+            }
+        ));
+
+        self.refresh_radios(state);
+
+        // Hotkeys and hardware switches change radios behind our back.
+        glib::timeout_add_seconds_local(RADIO_POLL_SECS, clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            move || {
+                page.refresh_radios(&state);
+                glib::ControlFlow::Continue
+            }
+        ));
+    }
+
+    /// Syncs the radio rows with rfkill, rebuilding them only when radios
+    /// come or go.
+    fn refresh_radios(&self, state: &AppState) {
+        let radios = rfkill::radios();
+        self.radio_group.set_visible(!radios.is_empty());
+
+        let mut rows = self.radio_rows.borrow_mut();
+        let unchanged = rows.len() == radios.len()
+            && rows.iter().zip(&radios).all(|((index, _), radio)| *index == radio.index);
+        if !unchanged {
+            for (_, row) in rows.drain(..) {
+                self.radio_group.remove(&row);
+            }
+            for radio in &radios {
+                let row = self.build_radio_row(state, radio);
+                self.radio_group.add(&row);
+                rows.push((radio.index, row));
+            }
+        }
+
+        state.set_airplane_mode(rfkill::airplane_mode());
+        for ((_, row), radio) in rows.iter().zip(&radios) {
+            row.set_active(!radio.soft_blocked && !radio.hard_blocked);
+            row.set_sensitive(!radio.hard_blocked);
+            row.set_subtitle(&if radio.hard_blocked {
+                "Blocked by a hardware switch".to_string()
+            } else {
+                glib::markup_escape_text(&radio.name).to_string()
+            });
+        }
+    }
+
+    fn build_radio_row(&self, state: &AppState, radio: &Radio) -> adw::SwitchRow {
+        let row = adw::SwitchRow::builder()
+            .title(radio.label())
+            .build();
+
+        let radio = radio.clone();
+        row.connect_active_notify(clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            move |row| {
+                // Matches rfkill already, e.g. when set by `refresh_radios`.
+                let blocked = rfkill::radios()
+                    .iter()
+                    .find(|current| current.index == radio.index)
+                    .map(|current| current.soft_blocked || current.hard_blocked);
+                if blocked == Some(!row.is_active()) {
+                    return;
+                }
+                if let Err(e) = rfkill::set_blocked(&radio, !row.is_active()) {
+                    state.toast(&format!("{} change failed: {}", radio.label(), e));
+                }
+                page.refresh_radios(&state);
+                if radio.kind == "bluetooth" {
+                    page.sync_bluetooth_row(&state);
+                }
+            }
+        ));
+
+        row
+    }
+
+    fn refresh_privacy_devices(&self, state: &AppState) {
+        let devices = privacy::devices();
+        self.privacy_group.set_visible(!devices.is_empty());
+
+        let mut rows = self.privacy_rows.borrow_mut();
+        for row in rows.drain(..) {
+            self.privacy_group.remove(&row);
+        }
+        for device in devices {
+            let row = self.build_privacy_row(state, device);
+            self.privacy_group.add(&row);
+            rows.push(row);
+        }
+    }
+
+    fn build_privacy_row(&self, state: &AppState, device: PrivacyDevice) -> adw::SwitchRow {
+        let row = adw::SwitchRow::builder()
+            .title(glib::markup_escape_text(&device.description))
+            .subtitle(format!("{} · USB {}", device.kind_label(), device.id))
+            .active(!device.blocked)
+            .build();
+
+        if let Some(reason) = state.read_only_reason() {
+            row.set_sensitive(false);
+            row.set_tooltip_text(Some(&reason));
+        }
+
+        row.connect_active_notify(clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            move |row| {
+                if row.is_active() != device.blocked {
+                    return;
+                }
+
+                let blocked = !row.is_active();
+                row.set_sensitive(false);
+
+                let page = page.clone();
+                let state = state.clone();
+                let device = device.clone();
+                glib::spawn_future_local(async move {
+                    let result = gio::spawn_blocking(move || {
+                        privacy::set_blocked(&device, blocked)
+                    }).await;
+
+                    if !matches!(result, Ok(Ok(()))) {
+                        let message = match result {
+                            Ok(Err(e)) => format!("Privacy change failed: {}", e),
+                            _ => "Privacy change failed".to_string(),
+                        };
+                        state.toast(&message);
+                    }
+                    page.refresh_privacy_devices(&state);
+                });
+            }
+        ));
+
+        row
+    }
+
+    fn setup_device_power(&self, state: &AppState) {
+        let lists = [
+            (&self.device_usb_row, devpower::usb_devices()),
+            (&self.device_pci_row, devpower::pci_devices()),
+        ];
+
+        for (expander, devices) in lists {
+            expander.set_visible(!devices.is_empty());
+            for device in devices {
+                expander.add_row(&self.build_device_power_row(state, device));
+            }
+        }
+
+        self.refresh_power_rules(state);
+    }
+
+    fn build_device_power_row(&self, state: &AppState, device: PowerDevice) -> adw::SwitchRow {
+        let row = adw::SwitchRow::builder()
+            .title(glib::markup_escape_text(&device.description))
+            .subtitle(glib::markup_escape_text(&device.id))
+            .active(device.auto)
+            .build();
+
+        if let Some(reason) = state.read_only_reason() {
+            row.set_sensitive(false);
+            row.set_tooltip_text(Some(&reason));
+        }
+
+        let applied = Rc::new(Cell::new(device.auto));
+        connect_switch(
+            &row,
+            state,
+            "Power setting failed",
+            clone!(#[strong] applied, move || applied.get()),
+            move |auto| devpower::apply_device_power(&device, auto),
+            clone!(
+                #[strong(rename_to = page)] self,
+                #[strong] state,
+                move |auto, ()| {
+                    applied.set(auto);
+                    page.refresh_power_rules(&state);
+                }
+            ),
+        );
+
+        row
+    }
+
+    /// Lists the udev rules TuxTuner owns, each with a remove button.
+    fn refresh_power_rules(&self, state: &AppState) {
+        for row in self.power_rule_rows.borrow_mut().drain(..) {
+            self.power_rules_row.remove(&row);
+        }
+
+        let rules = devpower::owned_rules();
+        self.power_rules_row.set_visible(!rules.is_empty());
+
+        for rule in rules {
+            let kind = match rule.kind {
+                DeviceKind::Usb => "USB",
+                DeviceKind::Pci => "PCI",
+            };
+            let row = adw::ActionRow::builder()
+                .title(format!("{} {}", kind, rule.key))
+                .subtitle(if rule.auto { "Power saving" } else { "Always on" })
+                .build();
+
+            let remove_btn = Button::builder()
+                .icon_name("user-trash-symbolic")
+                .tooltip_text("Remove rule")
+                .valign(Align::Center)
+                .css_classes(["flat"])
+                .sensitive(state.read_only_reason().is_none())
+                .build();
+            remove_btn.connect_clicked(clone!(
+                #[strong(rename_to = page)] self,
+                #[strong] state,
+                move |button| {
+                    button.set_sensitive(false);
+                    page.remove_power_rule(&state, rule.clone());
+                }
+            ));
+            row.add_suffix(&remove_btn);
+
+            self.power_rules_row.add_row(&row);
+            self.power_rule_rows.borrow_mut().push(row);
+        }
+    }
+
+    fn remove_power_rule(&self, state: &AppState, rule: PowerRule) {
+        let page = self.clone();
+        let state = state.clone();
+        glib::spawn_future_local(async move {
+            let result = gio::spawn_blocking(move || devpower::remove_rule(&rule)).await;
+
+            match result {
+                Ok(Ok(())) => state.toast("Rule removed; takes effect after replug or reboot"),
+                Ok(Err(e)) => state.toast(&format!("Removing rule failed: {}", e)),
+                Err(_) => state.toast("Removing rule failed"),
+            }
+            page.refresh_power_rules(&state);
+        });
+    }
+}
+
+fn build_network_switch(
+    state: &AppState,
+    title: &str,
+    subtitle: &str,
+    active: bool,
+    apply: impl Fn(bool) -> Result<(), String> + Clone + Send + 'static,
+) -> adw::SwitchRow {
+    let row = adw::SwitchRow::builder()
+        .title(title)
+        .subtitle(subtitle)
+        .active(active)
+        .build();
+
+    if let Some(reason) = state.read_only_reason() {
+        row.set_sensitive(false);
+        row.set_tooltip_text(Some(&reason));
+    }
+
+    let applied = Rc::new(Cell::new(active));
+    connect_switch(
+        &row,
+        state,
+        &format!("{} change failed", title),
+        clone!(#[strong] applied, move || applied.get()),
+        apply,
+        move |enabled, ()| applied.set(enabled),
+    );
+
+    row
+}
@@ -0,0 +1,1272 @@
+use crate::affinity;
+use crate::audit;
+use crate::autostart;
+use crate::config::{self, Config};
+use crate::diagnostics;
+use crate::export::{self, ExportFormat};
+use crate::fwupd::{self, FirmwareUpdate};
+use crate::immutable::{self, Immutable};
+use crate::kernel;
+use crate::launch;
+use crate::mac;
+use crate::metrics;
+use crate::modparams::{self, ModuleParam};
+use crate::remote;
+use crate::report;
+use crate::system_info;
+use crate::updater::{self, Updater};
+use crate::usage::{self, Usage};
+use crate::validate;
+use crate::vmhost;
+use gtk4::glib::{self, clone};
+use gtk4::prelude::*;
+use gtk4::{gio, Align, Button, StringList};
+use libadwaita as adw;
+use adw::prelude::*;
+use std::cell::Cell;
+use std::rc::Rc;
+
+const SHORTCUTS_UI: &str = include_str!("shortcuts.ui");
+
+/// Picks a boolean setting out of the config.
+type ConfigFlag = fn(&mut Config) -> &mut bool;
+
+pub fn setup_actions(app: &adw::Application) {
+    let about = gio::ActionEntry::builder("about")
+        .activate(|app: &adw::Application, _, _| show_about(app))
+        .build();
+    let preferences = gio::ActionEntry::builder("preferences")
+        .activate(|app: &adw::Application, _, _| show_preferences(app))
+        .build();
+    let diagnostics = gio::ActionEntry::builder("diagnostics")
+        .activate(|app: &adw::Application, _, _| show_diagnostics(app))
+        .build();
+    let change_history = gio::ActionEntry::builder("change-history")
+        .activate(|app: &adw::Application, _, _| show_change_history(app))
+        .build();
+    let export_setup = gio::ActionEntry::builder("export-setup")
+        .activate(|app: &adw::Application, _, _| show_export_setup(app))
+        .build();
+    let launch_options = gio::ActionEntry::builder("launch-options")
+        .activate(|app: &adw::Application, _, _| show_launch_options(app))
+        .build();
+    let pin_process = gio::ActionEntry::builder("pin-process")
+        .activate(|app: &adw::Application, _, _| show_pin_process(app))
+        .build();
+    let vm_host = gio::ActionEntry::builder("vm-host")
+        .activate(|app: &adw::Application, _, _| show_vm_host(app))
+        .build();
+    let module_parameters = gio::ActionEntry::builder("module-parameters")
+        .activate(|app: &adw::Application, _, _| show_module_parameters(app))
+        .build();
+    let shortcuts = gio::ActionEntry::builder("shortcuts")
+        .activate(|app: &adw::Application, _, _| show_shortcuts(app))
+        .build();
+    let quit = gio::ActionEntry::builder("quit")
+        .activate(|app: &adw::Application, _, _| app.quit())
+        .build();
+    app.add_action_entries([
+        about,
+        preferences,
+        diagnostics,
+        change_history,
+        export_setup,
+        launch_options,
+        pin_process,
+        vm_host,
+        module_parameters,
+        shortcuts,
+        quit,
+    ]);
+
+    // The process list is read from this machine's /proc.
+    if remote::host().is_some() {
+        if let Some(action) = app
+            .lookup_action("pin-process")
+            .and_then(|action| action.downcast::<gio::SimpleAction>().ok())
+        {
+            action.set_enabled(false);
+        }
+    }
+
+    app.set_accels_for_action("app.preferences", &["<Control>comma"]);
+    app.set_accels_for_action("win.refresh", &["<Control>r"]);
+    app.set_accels_for_action("app.shortcuts", &["<Control>question"]);
+    app.set_accels_for_action("app.quit", &["<Control>q"]);
+}
+
+fn show_about(app: &adw::Application) {
+    let app = app.clone();
+    // The report runs a few probe commands; keep them off the main thread.
+    glib::spawn_future_local(async move {
+        let report = gio::spawn_blocking(report::hardware_report)
+            .await
+            .unwrap_or_default();
+
+        let dialog = adw::AboutDialog::builder()
+            .application_name("TuxTuner")
+            .application_icon("preferences-system")
+            .developer_name("Xavrir")
+            .version(env!("CARGO_PKG_VERSION"))
+            .website(env!("CARGO_PKG_REPOSITORY"))
+            .issue_url(format!("{}/issues", env!("CARGO_PKG_REPOSITORY")))
+            .license_type(gtk4::License::MitX11)
+            .debug_info(report)
+            .debug_info_filename("tuxtuner-hardware-report.txt")
+            .comments("Found a bug? Attach the hardware report from Troubleshooting → Debugging Information.")
+            .build();
+        dialog.present(app.active_window().as_ref());
+    });
+}
+
+fn show_preferences(app: &adw::Application) {
+    let page = adw::PreferencesPage::new();
+
+    let storage_group = adw::PreferencesGroup::builder()
+        .title("Storage")
+        .description("Settings are kept in plain TOML and can be edited by hand.")
+        .build();
+    page.add(&storage_group);
+
+    let config_dir = config::config_dir();
+    let config_row = adw::ActionRow::builder()
+        .title("Configuration Folder")
+        .subtitle(glib::markup_escape_text(&config_dir.to_string_lossy()))
+        .build();
+    let open_btn = Button::builder()
+        .icon_name("folder-open-symbolic")
+        .tooltip_text("Open Folder")
+        .valign(Align::Center)
+        .css_classes(["flat"])
+        .build();
+    open_btn.connect_clicked(move |_| {
+        let _ = std::fs::create_dir_all(&config_dir);
+        let folder = gio::File::for_path(&config_dir);
+        gtk4::FileLauncher::new(Some(&folder)).launch(
+            None::<&gtk4::Window>,
+            None::<&gio::Cancellable>,
+            |_| {},
+        );
+    });
+    config_row.add_suffix(&open_btn);
+    storage_group.add(&config_row);
+
+    let dialog = adw::PreferencesDialog::new();
+
+    let metrics_group = adw::PreferencesGroup::builder()
+        .title("Metrics")
+        .description("Export readings for Prometheus and Grafana. Address and port are set in the config file.")
+        .build();
+    page.add(&metrics_group);
+
+    let metrics_config = Config::load().metrics;
+    let metrics_row = adw::SwitchRow::builder()
+        .title("Prometheus Exporter")
+        .subtitle(glib::markup_escape_text(&metrics_config.url()))
+        .active(metrics_config.enabled)
+        .build();
+    metrics_row.connect_active_notify(clone!(
+        #[weak] dialog,
+        move |row| {
+            let mut config = Config::load();
+            config.metrics.enabled = row.is_active();
+
+            let result = if row.is_active() {
+                metrics::start(&config.metrics)
+            } else {
+                metrics::stop();
+                Ok(())
+            };
+            if let Err(e) = result {
+                dialog.add_toast(adw::Toast::new(&format!("Exporter failed to start: {}", e)));
+                row.set_active(false);
+                return;
+            }
+            if config.save().is_err() {
+                dialog.add_toast(adw::Toast::new("Failed to save metrics settings"));
+            }
+        }
+    ));
+    metrics_group.add(&metrics_row);
+
+    let controls_group = adw::PreferencesGroup::builder()
+        .title("Controls")
+        .build();
+    page.add(&controls_group);
+
+    let apply_row = adw::SwitchRow::builder()
+        .title("Apply Changes Immediately")
+        .subtitle("Apply spinners such as the CPU thread limit once you stop adjusting them")
+        .active(Config::load().apply_on_change)
+        .build();
+    apply_row.connect_active_notify(clone!(
+        #[weak] dialog,
+        move |row| {
+            let mut config = Config::load();
+            config.apply_on_change = row.is_active();
+            if config.save().is_err() {
+                dialog.add_toast(adw::Toast::new("Failed to save preference"));
+            }
+        }
+    ));
+    controls_group.add(&apply_row);
+
+    let confirm_group = adw::PreferencesGroup::builder()
+        .title("Confirmations")
+        .description("Graphics mode switches always ask first, since they end the session.")
+        .build();
+    page.add(&confirm_group);
+
+    let confirm_rows: [(&str, ConfigFlag); 2] = [
+        ("Confirm Refresh Rate Changes", |config| &mut config.confirm.refresh_rate),
+        ("Confirm Taking Cores Offline", |config| &mut config.confirm.cpu_offline),
+    ];
+    for (title, field) in confirm_rows {
+        let row = adw::SwitchRow::builder()
+            .title(title)
+            .active(*field(&mut Config::load()))
+            .build();
+        row.connect_active_notify(clone!(
+            #[weak] dialog,
+            move |row| {
+                let mut config = Config::load();
+                *field(&mut config) = row.is_active();
+                if config.save().is_err() {
+                    dialog.add_toast(adw::Toast::new("Failed to save preference"));
+                }
+            }
+        ));
+        confirm_group.add(&row);
+    }
+
+    let usage = Usage::load();
+    let usage_note = "Kept on this computer only and never uploaded.";
+    let usage_group = adw::PreferencesGroup::builder()
+        .title("Usage Statistics")
+        .description(
+            usage
+                .insight()
+                .unwrap_or_else(|| usage_note.to_string()),
+        )
+        .build();
+    page.add(&usage_group);
+
+    let clear_btn = Button::builder()
+        .label("Clear")
+        .valign(Align::Center)
+        .css_classes(["flat"])
+        .sensitive(!usage.profiles.is_empty())
+        .build();
+    usage_group.set_header_suffix(Some(&clear_btn));
+
+    let usage_row = adw::SwitchRow::builder()
+        .title("Track Profile Usage")
+        .subtitle("Count how long each profile is active, on battery and plugged in")
+        .active(Config::load().usage_stats)
+        .build();
+    usage_row.connect_active_notify(clone!(
+        #[weak] dialog,
+        move |row| {
+            let mut config = Config::load();
+            config.usage_stats = row.is_active();
+            if config.save().is_err() {
+                dialog.add_toast(adw::Toast::new("Failed to save preference"));
+            }
+        }
+    ));
+    usage_group.add(&usage_row);
+
+    let mut profile_rows = Vec::new();
+    for (name, profile_usage) in &usage.profiles {
+        let row = adw::ActionRow::builder()
+            .title(glib::markup_escape_text(name))
+            .subtitle(format!(
+                "{} active, {} on battery",
+                usage::format_duration(profile_usage.active_secs),
+                usage::format_duration(profile_usage.battery_secs)
+            ))
+            .build();
+        usage_group.add(&row);
+        profile_rows.push(row);
+    }
+
+    clear_btn.connect_clicked(clone!(
+        #[weak] dialog,
+        #[weak] usage_group,
+        move |btn| {
+            if let Err(e) = usage::clear() {
+                dialog.add_toast(adw::Toast::new(&format!("Failed to clear statistics: {}", e)));
+                return;
+            }
+            for row in &profile_rows {
+                usage_group.remove(row);
+            }
+            usage_group.set_description(Some(usage_note));
+            btn.set_sensitive(false);
+        }
+    ));
+
+    let startup_group = adw::PreferencesGroup::builder()
+        .title("Startup")
+        .description("Automation rules only run while TuxTuner does.")
+        .build();
+    page.add(&startup_group);
+
+    let autostart_row = adw::SwitchRow::builder()
+        .title("Run in Background at Login")
+        .subtitle("Starts hidden; closing the window keeps it running")
+        .active(autostart::enabled())
+        .build();
+    autostart_row.connect_active_notify(clone!(
+        #[weak] dialog,
+        move |row| {
+            if let Err(e) = autostart::set_enabled(row.is_active()) {
+                dialog.add_toast(adw::Toast::new(&format!("Failed to update autostart: {}", e)));
+            }
+        }
+    ));
+    startup_group.add(&autostart_row);
+
+    let remote_group = adw::PreferencesGroup::builder()
+        .title("Remote Host")
+        .description(format!(
+            "Tune another machine over SSH, e.g. user@htpc. It needs key-based login and a passwordless sudo rule for {}.",
+            system_info::HELPER_PATH
+        ))
+        .build();
+    page.add(&remote_group);
+
+    let host_row = adw::EntryRow::builder()
+        .title("Host")
+        .text(Config::load().remote_host.unwrap_or_default())
+        .show_apply_button(true)
+        .build();
+    host_row.connect_apply(clone!(
+        #[weak] dialog,
+        move |row| {
+            let host = row.text().trim().to_string();
+            if !host.is_empty() {
+                if let Err(e) = validate::remote_host(&host) {
+                    dialog.add_toast(adw::Toast::new(&e));
+                    return;
+                }
+            }
+            let mut config = Config::load();
+            config.remote_host = (!host.is_empty()).then_some(host);
+            let message = if config.save().is_err() {
+                "Failed to save remote host"
+            } else {
+                "Restart TuxTuner to switch machines"
+            };
+            dialog.add_toast(adw::Toast::new(message));
+        }
+    ));
+    remote_group.add(&host_row);
+
+    dialog.add(&page);
+    dialog.present(app.active_window().as_ref());
+}
+
+fn show_diagnostics(app: &adw::Application) {
+    let app = app.clone();
+    glib::spawn_future_local(async move {
+        let checks = gio::spawn_blocking(diagnostics::run_checks)
+            .await
+            .unwrap_or_default();
+        let kernel_info = gio::spawn_blocking(kernel::info).await.ok();
+        let (firmware_updates, updater) = gio::spawn_blocking(|| {
+            let updates = fwupd::pending_updates();
+            let firmware = updates.as_ref().is_ok_and(|updates| !updates.is_empty());
+            // Updaters open on this machine, which isn't the tuned one in
+            // remote mode.
+            let updater = remote::host().is_none().then(|| updater::find(firmware)).flatten();
+            (updates, updater)
+        })
+        .await
+        .unwrap_or_else(|_| (Err("The check crashed".to_string()), None));
+        let access_control = gio::spawn_blocking(mac::active).await.ok().flatten();
+        let nixos_snippet = gio::spawn_blocking(|| {
+            (immutable::current() == Some(Immutable::NixOS))
+                .then(|| export::Setup::persisted().render(ExportFormat::NixOS))
+        })
+        .await
+        .ok()
+        .flatten();
+        let failed = checks.iter().filter(|check| !check.passed).count();
+
+        let group = adw::PreferencesGroup::builder()
+            .title("Self-Check")
+            .description(if failed == 0 {
+                "Everything TuxTuner relies on is in place.".to_string()
+            } else {
+                format!("{} problem(s) found. Suggested fixes are listed below each one.", failed)
+            })
+            .build();
+
+        for check in checks {
+            let subtitle = match check.fix {
+                Some(fix) => format!("{}\n{}", check.detail, fix),
+                None => check.detail,
+            };
+            let row = adw::ActionRow::builder()
+                .title(check.name)
+                .subtitle(glib::markup_escape_text(&subtitle))
+                .build();
+
+            let (icon, class) = if check.passed {
+                ("emblem-ok-symbolic", "success")
+            } else {
+                ("dialog-warning-symbolic", "error")
+            };
+            row.add_prefix(&gtk4::Image::builder().icon_name(icon).css_classes([class]).build());
+            group.add(&row);
+        }
+
+        let page = adw::PreferencesPage::new();
+        page.add(&group);
+        let dialog = adw::PreferencesDialog::builder().title("Diagnostics").build();
+        page.add(&build_updates_group(&dialog, kernel_info.as_ref(), firmware_updates, updater));
+        if let Some(info) = kernel_info {
+            page.add(&build_kernel_group(&info));
+        }
+
+        if access_control == Some(mac::Mac::AppArmor) {
+            page.add(&build_apparmor_group(&dialog));
+        }
+        if let Some(snippet) = nixos_snippet {
+            page.add(&build_nixos_group(&dialog, snippet));
+        }
+        dialog.add(&page);
+        dialog.present(app.active_window().as_ref());
+    });
+}
+
+/// Renders the current setup for NixOS or Ansible, to copy or save.
+fn show_export_setup(app: &adw::Application) {
+    let app = app.clone();
+    glib::spawn_future_local(async move {
+        let rendered = gio::spawn_blocking(|| {
+            let setup = export::Setup::current();
+            ExportFormat::ALL.map(|format| (format, setup.render(format)))
+        })
+        .await;
+        let Ok(rendered) = rendered else {
+            return;
+        };
+
+        let dialog = adw::PreferencesDialog::builder().title("Export Setup").build();
+        let page = adw::PreferencesPage::new();
+        let group = adw::PreferencesGroup::builder()
+            .description(
+                "The active profile, charge limits, tunables, udev rules, module options and boot \
+                 services, for codifying in your configuration management.",
+            )
+            .build();
+        page.add(&group);
+
+        for (format, text) in rendered {
+            let row = adw::ExpanderRow::builder().title(format.label()).build();
+            let text_row = adw::ActionRow::builder()
+                .subtitle(glib::markup_escape_text(&text))
+                .subtitle_selectable(true)
+                .build();
+            text_row.add_css_class("monospace");
+            row.add_row(&text_row);
+
+            let copy_btn = Button::builder()
+                .icon_name("edit-copy-symbolic")
+                .tooltip_text("Copy")
+                .valign(Align::Center)
+                .css_classes(["flat"])
+                .build();
+            copy_btn.connect_clicked(clone!(
+                #[weak]
+                dialog,
+                #[strong]
+                text,
+                move |_| {
+                    dialog.clipboard().set_text(&text);
+                    dialog.add_toast(adw::Toast::new("Copied"));
+                }
+            ));
+            let save_btn = Button::builder()
+                .icon_name("document-save-symbolic")
+                .tooltip_text("Save…")
+                .valign(Align::Center)
+                .css_classes(["flat"])
+                .build();
+            save_btn.connect_clicked(clone!(
+                #[weak]
+                dialog,
+                move |_| save_text(&dialog, format.file_name(), text.clone())
+            ));
+            row.add_suffix(&copy_btn);
+            row.add_suffix(&save_btn);
+            group.add(&row);
+        }
+
+        dialog.add(&page);
+        dialog.present(app.active_window().as_ref());
+    });
+}
+
+/// Asks where to save `text`, suggesting `name`, and reports the outcome
+/// as a toast on `dialog`.
+fn save_text(dialog: &adw::PreferencesDialog, name: &str, text: String) {
+    let file_dialog = gtk4::FileDialog::builder().initial_name(name).build();
+    let parent = dialog.root().and_downcast::<gtk4::Window>();
+    file_dialog.save(
+        parent.as_ref(),
+        None::<&gio::Cancellable>,
+        clone!(
+            #[weak]
+            dialog,
+            move |result| {
+                let Some(path) = result.ok().and_then(|file| file.path()) else {
+                    return;
+                };
+                let message = match std::fs::write(&path, text) {
+                    Ok(()) => format!("Saved to {}", path.display()),
+                    Err(e) => format!("Couldn't save: {}", e),
+                };
+                dialog.add_toast(adw::Toast::new(&message));
+            }
+        ),
+    );
+}
+
+/// Newest entries the change history shows.
+const CHANGE_HISTORY_LIMIT: usize = 200;
+
+/// Lists the privileged changes the helper logged to the journal.
+fn show_change_history(app: &adw::Application) {
+    let app = app.clone();
+    glib::spawn_future_local(async move {
+        let entries = gio::spawn_blocking(|| audit::entries(CHANGE_HISTORY_LIMIT))
+            .await
+            .unwrap_or_else(|_| Err("Couldn't read the journal".to_string()));
+
+        let group = adw::PreferencesGroup::builder()
+            .description("Every change made with administrator rights, newest first, as logged to the system journal.")
+            .build();
+
+        match entries {
+            Err(error) => {
+                let row = adw::ActionRow::builder()
+                    .title(glib::markup_escape_text(&error))
+                    .build();
+                row.add_prefix(&gtk4::Image::from_icon_name("dialog-information-symbolic"));
+                group.add(&row);
+            }
+            Ok(entries) if entries.is_empty() => {
+                group.add(&adw::ActionRow::builder().title("No changes logged yet").build());
+            }
+            Ok(entries) => {
+                for entry in entries {
+                    let time = glib::DateTime::from_unix_local(entry.time)
+                        .ok()
+                        .and_then(|time| time.format("%a %d %b %H:%M").ok())
+                        .map(|time| time.to_string())
+                        .unwrap_or_default();
+                    let mut subtitle = if entry.user.is_empty() {
+                        time
+                    } else {
+                        format!("{} · {}", time, entry.user)
+                    };
+                    if let Some(old) = &entry.old {
+                        subtitle.push_str(&format!("\nWas: {}", old));
+                    }
+
+                    let row = adw::ActionRow::builder()
+                        .title(glib::markup_escape_text(&entry.command))
+                        .subtitle(glib::markup_escape_text(&subtitle))
+                        .subtitle_selectable(true)
+                        .build();
+                    let (icon, class) = if entry.ok {
+                        ("emblem-ok-symbolic", "success")
+                    } else {
+                        ("dialog-warning-symbolic", "error")
+                    };
+                    row.add_prefix(&gtk4::Image::builder().icon_name(icon).css_classes([class]).build());
+                    group.add(&row);
+                }
+            }
+        }
+
+        let page = adw::PreferencesPage::new();
+        page.add(&group);
+        let dialog = adw::PreferencesDialog::builder().title("Change History").build();
+        dialog.add(&page);
+        dialog.present(app.active_window().as_ref());
+    });
+}
+
+/// Offers an AppArmor profile for the helper, for systems whose policy
+/// refuses its writes.
+fn build_apparmor_group(dialog: &adw::PreferencesDialog) -> adw::PreferencesGroup {
+    let group = adw::PreferencesGroup::builder()
+        .title("AppArmor")
+        .description("Optional. Only needed when AppArmor refuses TuxTuner's changes.")
+        .build();
+
+    let row = adw::ActionRow::builder()
+        .title("Helper Profile")
+        .subtitle(format!("Save a profile to install as /etc/apparmor.d/{}", mac::APPARMOR_PROFILE_FILE))
+        .build();
+    let save_btn = Button::builder()
+        .label("Save…")
+        .valign(Align::Center)
+        .build();
+    save_btn.connect_clicked(clone!(
+        #[weak]
+        dialog,
+        move |_| save_text(&dialog, mac::APPARMOR_PROFILE_FILE, mac::apparmor_profile())
+    ));
+    row.add_suffix(&save_btn);
+    group.add(&row);
+
+    group
+}
+
+/// NixOS builds udev rules, module options and services from its
+/// configuration; offers what TuxTuner persisted in that form.
+fn build_nixos_group(dialog: &adw::PreferencesDialog, snippet: String) -> adw::PreferencesGroup {
+    let group = adw::PreferencesGroup::builder()
+        .title("NixOS Configuration")
+        .description("Add this to configuration.nix and rebuild so TuxTuner's saved settings survive reboots.")
+        .build();
+
+    group.add(&build_copy_row(dialog, "Configuration Snippet", snippet));
+
+    group
+}
+
+/// A row showing `text` in monospace, with a button copying it.
+fn build_copy_row(dialog: &adw::PreferencesDialog, title: &str, text: String) -> adw::ActionRow {
+    let row = adw::ActionRow::builder()
+        .title(title)
+        .subtitle(glib::markup_escape_text(&text))
+        .subtitle_selectable(true)
+        .build();
+    row.add_css_class("monospace");
+    let copy_btn = Button::builder()
+        .icon_name("edit-copy-symbolic")
+        .tooltip_text("Copy")
+        .valign(Align::Center)
+        .css_classes(["flat"])
+        .build();
+    let toast = format!("{} copied", title);
+    copy_btn.connect_clicked(clone!(
+        #[weak]
+        dialog,
+        move |_| {
+            dialog.clipboard().set_text(&text);
+            dialog.add_toast(adw::Toast::new(&toast));
+        }
+    ));
+    row.add_suffix(&copy_btn);
+    row
+}
+
+/// Scheduling limits of the running kernel that no runtime setting lifts.
+/// Whether the running kernel is the newest installed, and pending
+/// firmware updates for the parts behind power and thermal behavior.
+fn build_updates_group(
+    dialog: &adw::PreferencesDialog,
+    kernel_info: Option<&kernel::KernelInfo>,
+    firmware_updates: Result<Vec<FirmwareUpdate>, String>,
+    updater: Option<Updater>,
+) -> adw::PreferencesGroup {
+    let group = adw::PreferencesGroup::builder()
+        .title("Updates")
+        .description(
+            "Old embedded controller or BIOS firmware is a common cause of fans and thermal limits misbehaving, \
+             whatever TuxTuner sets.",
+        )
+        .build();
+    let status_row = |title: &str, subtitle: &str, ok: bool| {
+        let row = adw::ActionRow::builder()
+            .title(title)
+            .subtitle(glib::markup_escape_text(subtitle))
+            .build();
+        let icon = if ok {
+            gtk4::Image::builder().icon_name("emblem-ok-symbolic").css_classes(["success"]).build()
+        } else {
+            gtk4::Image::from_icon_name("dialog-information-symbolic")
+        };
+        row.add_prefix(&icon);
+        row
+    };
+
+    if let Some(info) = kernel_info {
+        group.add(&match &info.newer_installed {
+            Some(newer) => status_row(
+                "Restart to Update the Kernel",
+                &format!("Running {}; {} is installed and runs after a restart.", info.release, newer),
+                false,
+            ),
+            None => status_row(
+                "Kernel",
+                &format!("Running the newest installed kernel, {}", info.release),
+                true,
+            ),
+        });
+    }
+
+    match firmware_updates {
+        Ok(updates) if updates.is_empty() => group.add(&status_row(
+            "Firmware",
+            "No updates for system, embedded controller or battery firmware, as of fwupd's last metadata refresh",
+            true,
+        )),
+        Ok(updates) => {
+            for update in updates {
+                let mut subtitle = format!("{}: {} → {}", update.device, update.current, update.available);
+                if let Some(summary) = update.summary {
+                    subtitle.push_str(&format!("\n{}", summary));
+                }
+                group.add(&status_row(&format!("{} Update", update.kind.label()), &subtitle, false));
+            }
+        }
+        Err(err) => group.add(&status_row(
+            "Firmware",
+            &format!("Couldn't ask fwupd: {}", err),
+            false,
+        )),
+    }
+
+    if let Some(updater) = updater {
+        let open_btn = Button::builder()
+            .label(format!("Open {}", updater.name))
+            .valign(Align::Center)
+            .css_classes(["flat"])
+            .build();
+        open_btn.connect_clicked(clone!(
+            #[weak]
+            dialog,
+            move |_| {
+                if let Err(err) = updater.launch() {
+                    dialog.add_toast(adw::Toast::new(&format!("Couldn't open {}: {}", updater.name, err)));
+                }
+            }
+        ));
+        group.set_header_suffix(Some(&open_btn));
+    }
+
+    group
+}
+
+fn build_kernel_group(info: &kernel::KernelInfo) -> adw::PreferencesGroup {
+    let group = adw::PreferencesGroup::builder()
+        .title("Kernel")
+        .description("Fixed when the kernel is built; TuxTuner can't change these.")
+        .build();
+
+    let unknown = "Unknown (kernel config not readable)".to_string();
+    let release = match info.flavor {
+        Some(flavor) => format!("{} ({})", info.release, flavor),
+        None => info.release.clone(),
+    };
+    let preemption = match info.preemption {
+        Some(model) if info.dynamic => format!("{}, selectable at boot", model.label()),
+        Some(model) => model.label().to_string(),
+        None => unknown.clone(),
+    };
+    for (title, value) in [
+        ("Release", release),
+        ("Timer Frequency", info.hz.map_or(unknown, |hz| format!("{} Hz", hz))),
+        ("Preemption", preemption),
+    ] {
+        group.add(
+            &adw::ActionRow::builder()
+                .title(title)
+                .subtitle(glib::markup_escape_text(&value))
+                .subtitle_selectable(true)
+                .build(),
+        );
+    }
+
+    let tips = info.recommendations();
+    if tips.is_empty() {
+        let row = adw::ActionRow::builder()
+            .title("This kernel is already set up for low latency")
+            .build();
+        row.add_prefix(&gtk4::Image::builder().icon_name("emblem-ok-symbolic").css_classes(["success"]).build());
+        group.add(&row);
+    }
+    for tip in tips {
+        let row = adw::ActionRow::builder()
+            .title(glib::markup_escape_text(&tip))
+            .build();
+        row.add_prefix(&gtk4::Image::from_icon_name("dialog-information-symbolic"));
+        group.add(&row);
+    }
+
+    group
+}
+
+/// Builds Steam launch options from presets, remembering the choice.
+fn show_launch_options(app: &adw::Application) {
+    let dialog = adw::PreferencesDialog::builder().title("Launch Options").build();
+    let page = adw::PreferencesPage::new();
+
+    let presets_group = adw::PreferencesGroup::builder()
+        .title("Presets")
+        .description("Per-game tweaks that system settings can't make.")
+        .build();
+    page.add(&presets_group);
+
+    let output_group = adw::PreferencesGroup::builder()
+        .title("Steam Launch Options")
+        .description("Paste into a game's Properties › General › Launch Options.")
+        .build();
+    page.add(&output_group);
+
+    let selected = Config::load().launch_presets;
+    let output_row = adw::ActionRow::builder()
+        .title(glib::markup_escape_text(&launch::launch_options(&selected)))
+        .title_selectable(true)
+        .css_classes(["monospace"])
+        .build();
+    let copy_btn = Button::builder()
+        .icon_name("edit-copy-symbolic")
+        .tooltip_text("Copy")
+        .valign(Align::Center)
+        .css_classes(["flat"])
+        .build();
+    copy_btn.connect_clicked(clone!(
+        #[weak] dialog,
+        move |button| {
+            button
+                .clipboard()
+                .set_text(&launch::launch_options(&Config::load().launch_presets));
+            dialog.add_toast(adw::Toast::new("Copied launch options"));
+        }
+    ));
+    output_row.add_suffix(&copy_btn);
+    output_group.add(&output_row);
+
+    for preset in launch::available_presets() {
+        let mut subtitle = preset.description.to_string();
+        if let Some(wrapper) = preset.wrapper {
+            subtitle = format!("{} ({})", subtitle, wrapper);
+        }
+        let row = adw::SwitchRow::builder()
+            .title(preset.title)
+            .subtitle(glib::markup_escape_text(&subtitle))
+            .active(selected.iter().any(|id| id == preset.id))
+            .build();
+        row.connect_active_notify(clone!(
+            #[weak] dialog,
+            #[weak] output_row,
+            move |row| {
+                let mut config = Config::load();
+                config.launch_presets.retain(|id| id != preset.id);
+                if row.is_active() {
+                    config.launch_presets.push(preset.id.to_string());
+                }
+                output_row.set_title(&glib::markup_escape_text(&launch::launch_options(&config.launch_presets)));
+                if config.save().is_err() {
+                    dialog.add_toast(adw::Toast::new("Failed to save launch presets"));
+                }
+            }
+        ));
+        presets_group.add(&row);
+    }
+
+    dialog.add(&page);
+    dialog.present(app.active_window().as_ref());
+}
+
+/// Pins a running process, typically a game, to a set of cores.
+fn show_pin_process(app: &adw::Application) {
+    let dialog = adw::PreferencesDialog::builder().title("Pin Process to Cores").build();
+    let page = adw::PreferencesPage::new();
+    let group = adw::PreferencesGroup::builder()
+        .description("Keeps every thread of the process on the chosen CPUs until it exits.")
+        .build();
+    page.add(&group);
+
+    let processes = Rc::new(affinity::processes());
+    let labels: Vec<String> = processes
+        .iter()
+        .map(|process| format!("{} ({})", process.name, process.pid))
+        .collect();
+    let label_refs: Vec<&str> = labels.iter().map(String::as_str).collect();
+    let process_combo = adw::ComboRow::builder()
+        .title("Process")
+        .model(&StringList::new(&label_refs))
+        .expression(gtk4::PropertyExpression::new(
+            gtk4::StringObject::static_type(),
+            None::<gtk4::Expression>,
+            "string",
+        ))
+        .enable_search(true)
+        .sensitive(!processes.is_empty())
+        .build();
+    group.add(&process_combo);
+
+    let presets = Rc::new(affinity::presets());
+    let titles: Vec<&str> = presets.iter().map(|preset| preset.title).collect();
+    let cpus_combo = adw::ComboRow::builder()
+        .title("Cores")
+        .model(&StringList::new(&titles))
+        .build();
+    group.add(&cpus_combo);
+
+    let update_subtitles = clone!(
+        #[strong] processes,
+        #[strong] presets,
+        #[weak] process_combo,
+        #[weak] cpus_combo,
+        move || {
+            if let Some(process) = processes.get(process_combo.selected() as usize) {
+                process_combo.set_subtitle(&format!("Runs on CPUs {}", process.cpus));
+            }
+            if let Some(preset) = presets.get(cpus_combo.selected() as usize) {
+                cpus_combo.set_subtitle(&format!("CPUs {}", affinity::format_cpu_list(&preset.cpus)));
+            }
+        }
+    );
+    update_subtitles();
+    let update = update_subtitles.clone();
+    process_combo.connect_selected_notify(move |_| update());
+    cpus_combo.connect_selected_notify(move |_| update_subtitles());
+
+    let pin_row = adw::ActionRow::builder().title("Apply").build();
+    let pin_btn = Button::builder()
+        .label("Pin")
+        .valign(Align::Center)
+        .css_classes(["suggested-action"])
+        .sensitive(!processes.is_empty())
+        .build();
+    pin_btn.connect_clicked(clone!(
+        #[weak] dialog,
+        #[weak] process_combo,
+        #[weak] cpus_combo,
+        move |button| {
+            let (Some(process), Some(preset)) = (
+                processes.get(process_combo.selected() as usize).cloned(),
+                presets.get(cpus_combo.selected() as usize).cloned(),
+            ) else {
+                return;
+            };
+
+            button.set_sensitive(false);
+            let button = button.clone();
+            glib::spawn_future_local(async move {
+                let cpus = preset.cpus.clone();
+                let result = gio::spawn_blocking(move || affinity::pin(process.pid, &cpus)).await;
+                button.set_sensitive(true);
+
+                let message = match result {
+                    Ok(Ok(())) => {
+                        let cpus = affinity::format_cpu_list(&preset.cpus);
+                        process_combo.set_subtitle(&format!("Runs on CPUs {}", cpus));
+                        format!("Pinned {} to CPUs {}", process.name, cpus)
+                    }
+                    Ok(Err(e)) => format!("Pinning failed: {}", e),
+                    Err(_) => "Pinning failed".to_string(),
+                };
+                dialog.add_toast(adw::Toast::new(&message));
+            });
+        }
+    ));
+    pin_row.add_suffix(&pin_btn);
+    group.add(&pin_row);
+
+    dialog.add(&page);
+    dialog.present(app.active_window().as_ref());
+}
+
+/// Splits the CPUs between host and VM and reserves huge pages for VM
+/// memory: the usual tuning for GPU passthrough.
+fn show_vm_host(app: &adw::Application) {
+    let app = app.clone();
+    glib::spawn_future_local(async move {
+        let (split, isolated, hugepages) =
+            gio::spawn_blocking(|| (vmhost::suggest_split(), vmhost::isolated(), vmhost::HugePages::read()))
+                .await
+                .unwrap_or_default();
+
+        let dialog = adw::PreferencesDialog::builder().title("VM Host Preset").build();
+        let page = adw::PreferencesPage::new();
+        page.add(&build_vm_pinning_group(&dialog, &split));
+        if !split.vm_cores.is_empty() {
+            page.add(&build_isolation_group(&dialog, &split, &isolated));
+        }
+        if let Some(hugepages) = hugepages {
+            page.add(&build_hugepages_group(&dialog, hugepages));
+        }
+
+        dialog.add(&page);
+        dialog.present(app.active_window().as_ref());
+    });
+}
+
+fn build_vm_pinning_group(dialog: &adw::PreferencesDialog, split: &vmhost::CpuSplit) -> adw::PreferencesGroup {
+    let group = adw::PreferencesGroup::builder()
+        .title("CPU Pinning")
+        .description("Give the VM whole cores, and leave the host and QEMU's own threads the rest.")
+        .build();
+
+    if split.vm_cores.is_empty() {
+        group.add(&adw::ActionRow::builder().title("Too few cores to set any aside for a VM").build());
+        return group;
+    }
+
+    group.add(
+        &adw::ActionRow::builder()
+            .title("Host")
+            .subtitle(format!("CPUs {}", affinity::format_cpu_list(&split.host)))
+            .build(),
+    );
+    group.add(
+        &adw::ActionRow::builder()
+            .title("VM")
+            .subtitle(format!(
+                "CPUs {} ({} cores)",
+                affinity::format_cpu_list(&split.vm_cpus()),
+                split.vm_cores.len()
+            ))
+            .build(),
+    );
+    group.add(&build_copy_row(dialog, "Domain XML", split.domain_xml()));
+
+    group
+}
+
+fn build_isolation_group(
+    dialog: &adw::PreferencesDialog,
+    split: &vmhost::CpuSplit,
+    isolated: &[u32],
+) -> adw::PreferencesGroup {
+    let args = split.kernel_args();
+    let command = vmhost::kernel_args_command(&args);
+    let mut description =
+        "Keeps other tasks, timer ticks and kernel housekeeping off the VM's cores. Takes effect after a reboot."
+            .to_string();
+    if command.is_none() {
+        description.push_str(" Add the parameters to GRUB_CMDLINE_LINUX_DEFAULT in /etc/default/grub and regenerate grub.cfg.");
+    }
+    let group = adw::PreferencesGroup::builder()
+        .title("Core Isolation")
+        .description(description)
+        .build();
+
+    let now = if isolated.is_empty() {
+        "None".to_string()
+    } else if *isolated == split.vm_cpus() {
+        format!("CPUs {}, as suggested", affinity::format_cpu_list(isolated))
+    } else {
+        format!("CPUs {}", affinity::format_cpu_list(isolated))
+    };
+    group.add(&adw::ActionRow::builder().title("Isolated Now").subtitle(now).build());
+    group.add(&build_copy_row(dialog, "Kernel Parameters", args));
+    if let Some(command) = command {
+        let title = match immutable::current() {
+            Some(Immutable::NixOS) => "Configuration Snippet",
+            _ => "Command",
+        };
+        group.add(&build_copy_row(dialog, title, command));
+    }
+
+    group
+}
+
+fn build_hugepages_group(dialog: &adw::PreferencesDialog, hugepages: vmhost::HugePages) -> adw::PreferencesGroup {
+    let group = adw::PreferencesGroup::builder()
+        .title("Huge Pages")
+        .description(format!(
+            "Backs VM memory with {} MiB pages, sparing the CPU page table walks. Turn on huge \
+             pages in the domain's memory backing to use them. Resets on reboot.",
+            hugepages.size_kib / 1024
+        ))
+        .build();
+
+    let spin = adw::SpinRow::with_range(0.0, f64::from(hugepages.max_gib()), 1.0);
+    spin.set_title("Reserved (GiB)");
+    spin.set_value(hugepages.gib(hugepages.total).floor());
+    spin.set_subtitle(&format!(
+        "{:.1} GiB reserved, {:.1} GiB unused",
+        hugepages.gib(hugepages.total),
+        hugepages.gib(hugepages.free)
+    ));
+    let apply_btn = Button::builder()
+        .label("Apply")
+        .valign(Align::Center)
+        .build();
+    spin.add_suffix(&apply_btn);
+    group.add(&spin);
+
+    apply_btn.connect_clicked(clone!(
+        #[weak] dialog,
+        #[weak] spin,
+        move |button| {
+            let gib = spin.value() as u32;
+            let count = hugepages.pages_for_gib(gib);
+            button.set_sensitive(false);
+            let button = button.clone();
+            glib::spawn_future_local(async move {
+                let result = gio::spawn_blocking(move || {
+                    vmhost::set_hugepages(count).map(|()| vmhost::HugePages::read())
+                })
+                .await;
+                button.set_sensitive(true);
+
+                let message = match result {
+                    Ok(Ok(Some(now))) => {
+                        spin.set_subtitle(&format!(
+                            "{:.1} GiB reserved, {:.1} GiB unused",
+                            now.gib(now.total),
+                            now.gib(now.free)
+                        ));
+                        if now.total < count {
+                            format!(
+                                "Only {:.1} of {} GiB could be reserved; memory is too fragmented",
+                                now.gib(now.total),
+                                gib
+                            )
+                        } else {
+                            format!("Reserved {} GiB of huge pages", gib)
+                        }
+                    }
+                    Ok(Ok(None)) => format!("Reserved {} GiB of huge pages", gib),
+                    Ok(Err(e)) => format!("Failed to reserve huge pages: {}", e),
+                    Err(_) => "Failed to reserve huge pages".to_string(),
+                };
+                dialog.add_toast(adw::Toast::new(&message));
+            });
+        }
+    ));
+
+    group
+}
+
+fn show_module_parameters(app: &adw::Application) {
+    let app = app.clone();
+    glib::spawn_future_local(async move {
+        // Parameters of modules that are neither loaded nor configured
+        // have nothing to show.
+        let params = gio::spawn_blocking(|| {
+            modparams::PARAMS
+                .iter()
+                .filter_map(|param| {
+                    let current = param.current();
+                    let saved = modparams::saved(param);
+                    (current.is_some() || saved.is_some()).then_some((*param, current, saved))
+                })
+                .collect::<Vec<_>>()
+        })
+        .await
+        .unwrap_or_default();
+
+        let dialog = adw::PreferencesDialog::builder().title("Module Parameters").build();
+        let page = adw::PreferencesPage::new();
+        let group = adw::PreferencesGroup::builder()
+            .description(
+                "Saved to modprobe.d and applied straight away where the driver allows; the rest \
+                 take effect after a reboot. Drivers loaded from the initramfs may also need it rebuilt. \
+                 Settings of code built into the kernel last until reboot.",
+            )
+            .build();
+        page.add(&group);
+
+        if params.is_empty() {
+            group.add(&adw::ActionRow::builder().title("No supported modules are loaded").build());
+        }
+        for (param, current, saved) in params {
+            group.add(&build_module_param_row(&dialog, param, current, saved));
+        }
+
+        dialog.add(&page);
+        dialog.present(app.active_window().as_ref());
+    });
+}
+
+fn build_module_param_row(
+    dialog: &adw::PreferencesDialog,
+    param: ModuleParam,
+    current: Option<String>,
+    saved: Option<String>,
+) -> adw::ComboRow {
+    let mut labels = vec!["Kernel Default"];
+    labels.extend(param.choices.iter().map(|(_, label)| *label));
+    let selected = saved
+        .as_deref()
+        .and_then(|saved| param.choices.iter().position(|(value, _)| *value == saved))
+        .map(|i| i as u32 + 1)
+        .unwrap_or(0);
+    let now = current
+        .map(|value| format!("Now {}", param.label_for(&value)))
+        .unwrap_or_else(|| "Not loaded".to_string());
+
+    let row = adw::ComboRow::builder()
+        .title(param.title)
+        .subtitle(glib::markup_escape_text(&format!(
+            "{} · {}.{}\n{}",
+            now, param.module, param.name, param.description
+        )))
+        .model(&StringList::new(&labels))
+        .selected(selected)
+        .build();
+
+    let previous = Rc::new(Cell::new(selected));
+    row.connect_selected_notify(clone!(
+        #[weak] dialog,
+        move |row| {
+            let selected = row.selected();
+            if selected == previous.get() {
+                return;
+            }
+            let value = selected
+                .checked_sub(1)
+                .and_then(|i| param.choices.get(i as usize))
+                .map(|(value, _)| *value);
+
+            row.set_sensitive(false);
+            let row = row.clone();
+            let previous = previous.clone();
+            glib::spawn_future_local(async move {
+                let result = gio::spawn_blocking(move || modparams::set(&param, value)).await;
+                row.set_sensitive(true);
+
+                let message = match result {
+                    Ok(Ok(live)) => {
+                        previous.set(selected);
+                        match (value, live) {
+                            (_, true) if param.built_in => {
+                                let value = value.or(param.choices.first().map(|(value, _)| *value));
+                                format!(
+                                    "{} set to {} until reboot",
+                                    param.title,
+                                    param.label_for(value.unwrap_or_default())
+                                )
+                            }
+                            (Some(value), true) => format!("{} set to {}", param.title, param.label_for(value)),
+                            (Some(_), false) => format!("{} saved; takes effect after a reboot", param.title),
+                            (None, _) => format!("{} back to the kernel default after a reboot", param.title),
+                        }
+                    }
+                    Ok(Err(e)) => format!("Failed to set {}: {}", param.title, e),
+                    Err(_) => format!("Failed to set {}", param.title),
+                };
+                if previous.get() != selected {
+                    row.set_selected(previous.get());
+                }
+                dialog.add_toast(adw::Toast::new(&message));
+            });
+        }
+    ));
+
+    row
+}
+
+fn show_shortcuts(app: &adw::Application) {
+    let builder = gtk4::Builder::from_string(SHORTCUTS_UI);
+    let Some(window) = builder.object::<gtk4::ShortcutsWindow>("shortcuts") else {
+        return;
+    };
+    window.set_transient_for(app.active_window().as_ref());
+    window.present();
+}
@@ -0,0 +1,978 @@
+use crate::asus::{self, PanelFeature};
+use crate::backends::Backends;
+use crate::battery_history;
+use crate::config::Config;
+use crate::effects;
+use crate::hotplug::{self, MonitorEvent};
+use crate::hyprland;
+use crate::idle::{self, IdleControl};
+use crate::lighting::{self, LightingLevel};
+use crate::mangohud;
+use crate::modparams;
+use crate::nightlight::{self, NightLight};
+use crate::probe::Capability;
+use crate::schedule;
+use crate::system_info::{self, DisplayBackend, SystemInfo};
+use gtk4::glib::{self, clone};
+use gtk4::prelude::*;
+use gtk4::{gio, StringList};
+use libadwaita as adw;
+use adw::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::Arc;
+use super::{bind_choice, bind_switch, confirm_then, AppState};
+
+/// The monitor and what draws on it: refresh rate and panel options,
+/// night light, RGB lighting and the MangoHud overlay.
+#[derive(Clone)]
+pub(super) struct DisplayPage {
+    groups: [adw::PreferencesGroup; 4],
+    pub(super) hz_combo: adw::ComboRow,
+    vrr_row: adw::SwitchRow,
+    pub(super) battery_refresh_row: adw::SwitchRow,
+    panel_od_row: adw::SwitchRow,
+    mini_led_row: adw::SwitchRow,
+    effects_row: adw::SwitchRow,
+    effects_backend: Rc<Cell<Option<effects::Backend>>>,
+    idle_frames_row: adw::SwitchRow,
+    psr_row: adw::SwitchRow,
+    idle_combo: adw::ComboRow,
+    idle: Rc<RefCell<IdleControl>>,
+    pub(super) night_light_row: adw::SwitchRow,
+    night_temp_spin: adw::SpinRow,
+    night_schedule_row: adw::SwitchRow,
+    night_start_entry: adw::EntryRow,
+    night_end_entry: adw::EntryRow,
+    night_light: Rc<RefCell<NightLight>>,
+    pub(super) lighting_combo: adw::ComboRow,
+    lighting_backend: Option<lighting::Backend>,
+    mangohud_overlay_row: adw::SwitchRow,
+    mangohud_fps_spin: adw::SpinRow,
+    mangohud_frame_timing_row: adw::SwitchRow,
+}
+
+impl DisplayPage {
+    pub(super) fn build() -> Self {
+        let (
+            display_group,
+            hz_combo,
+            vrr_row,
+            battery_refresh_row,
+            panel_od_row,
+            mini_led_row,
+            effects_row,
+            idle_frames_row,
+            psr_row,
+            idle_combo,
+        ) = Self::build_display_group();
+        let (
+            night_group,
+            night_light_row,
+            night_temp_spin,
+            night_schedule_row,
+            night_start_entry,
+            night_end_entry,
+        ) = Self::build_night_light_group();
+        let (lighting_group, lighting_combo) = Self::build_lighting_group();
+        let (mangohud_group, mangohud_overlay_row, mangohud_fps_spin, mangohud_frame_timing_row) =
+            Self::build_mangohud_group();
+
+        Self {
+            groups: [display_group, night_group, lighting_group, mangohud_group],
+            hz_combo,
+            vrr_row,
+            battery_refresh_row,
+            panel_od_row,
+            mini_led_row,
+            effects_row,
+            effects_backend: Rc::new(Cell::new(None)),
+            idle_frames_row,
+            psr_row,
+            idle_combo,
+            idle: Rc::new(RefCell::new(IdleControl::detect())),
+            night_light_row,
+            night_temp_spin,
+            night_schedule_row,
+            night_start_entry,
+            night_end_entry,
+            night_light: Rc::new(RefCell::new(NightLight::detect())),
+            lighting_combo,
+            lighting_backend: lighting::detect(),
+            mangohud_overlay_row,
+            mangohud_fps_spin,
+            mangohud_frame_timing_row,
+        }
+    }
+
+    pub(super) fn groups(&self) -> &[adw::PreferencesGroup] {
+        &self.groups
+    }
+
+    /// Changes modes through `backends.display` and watches for monitors
+    /// through `backends.sysfs`.
+    pub(super) fn bind(&self, state: &AppState, backends: &Backends) {
+        state.connect_current_hz_notify(clone!(
+            #[strong(rename_to = page)] self,
+            move |state| page.select_refresh_rate(state)
+        ));
+        // Profiles and rules change these behind the rows' backs.
+        state.connect_reload(clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            move || {
+                page.sync_effects_row(&state);
+                page.sync_idle_frames_row(&state);
+                if mangohud::installed() {
+                    state.load_mangohud(&mangohud::settings());
+                }
+            }
+        ));
+
+        self.setup_refresh_rate(state, &backends.display);
+        self.setup_battery_refresh(state);
+        self.watch_hotplug(state, backends);
+        self.setup_panel_features(state);
+        self.setup_effects(state);
+        self.setup_idle_frames(state);
+        self.setup_psr(state);
+        self.setup_idle(state);
+        self.setup_mangohud(state);
+        self.setup_night_light(state);
+        self.setup_lighting(state);
+    }
+
+    /// Shows the refresh rate controls where hyprctl can drive them, and
+    /// enables them once `info` is fresh.
+    pub(super) fn show_system_info(&self, info: &SystemInfo, stale: bool) {
+        if stale {
+            return;
+        }
+
+        // Refresh rate control goes through hyprctl; other desktops don't
+        // get rows that could never work.
+        let display_control = info.session.may_be_hyprland();
+        let display_rows: [&gtk4::Widget; 3] = [
+            self.hz_combo.upcast_ref(),
+            self.vrr_row.upcast_ref(),
+            self.battery_refresh_row.upcast_ref(),
+        ];
+        for row in display_rows {
+            row.set_visible(display_control);
+        }
+
+        if !info.refresh_rates.is_empty() {
+            self.hz_combo.set_sensitive(true);
+            self.vrr_row.set_sensitive(true);
+        } else {
+            self.hz_combo.set_subtitle(match info.display_capability {
+                Capability::TimedOut => "hyprctl is not responding",
+                _ => "Could not detect refresh rates",
+            });
+            self.hz_combo.set_sensitive(false);
+            self.vrr_row.set_sensitive(false);
+        }
+    }
+
+    /// Selects the current refresh rate. The combo's handler ignores the
+    /// rate already in force, so this doesn't apply anything.
+    fn select_refresh_rate(&self, state: &AppState) {
+        let hz = state.current_hz();
+        let position = state
+            .refresh_rates()
+            .iter()
+            .position(|rate| rate.replace(" (Native)", "") == hz);
+        if let Some(idx) = position {
+            self.hz_combo.set_selected(idx as u32);
+        }
+    }
+
+    /// The battery rule itself runs on the automation page, which follows
+    /// the saved toggle.
+    fn setup_battery_refresh(&self, state: &AppState) {
+        state
+            .bind_property("battery-refresh-hz", &self.battery_refresh_row, "subtitle")
+            .transform_to(|_, hz: u32| Some(format!("Drop to {}Hz unless a game or video is running", hz)))
+            .sync_create()
+            .build();
+        state
+            .bind_property("battery-refresh", &self.battery_refresh_row, "active")
+            .sync_create()
+            .build();
+        self.battery_refresh_row.connect_active_notify(clone!(
+            #[strong] state,
+            move |row| {
+                if row.is_active() == state.battery_refresh() {
+                    return;
+                }
+                let mut config = Config::load();
+                config.automation.battery_refresh = row.is_active();
+                if config.save().is_err() {
+                    state.toast("Failed to save automation settings");
+                }
+                state.load_config(&Config::load());
+            }
+        ));
+    }
+
+    fn build_display_group() -> (
+        adw::PreferencesGroup,
+        adw::ComboRow,
+        adw::SwitchRow,
+        adw::SwitchRow,
+        adw::SwitchRow,
+        adw::SwitchRow,
+        adw::SwitchRow,
+        adw::SwitchRow,
+        adw::SwitchRow,
+        adw::ComboRow,
+    ) {
+        let display_group = adw::PreferencesGroup::builder()
+            .title("Display")
+            .description("Control monitor refresh rate.")
+            .build();
+
+        let hz_combo = adw::ComboRow::builder()
+            .title("Refresh Rate")
+            .subtitle("Higher rates use more power")
+            .sensitive(false)
+            .build();
+        hz_combo.set_model(Some(&StringList::new(&["Loading..."])));
+        display_group.add(&hz_combo);
+
+        let vrr_row = adw::SwitchRow::builder()
+            .title("Variable Refresh Rate")
+            .subtitle("Adaptive sync, remembered per monitor")
+            .sensitive(false)
+            .build();
+        display_group.add(&vrr_row);
+
+        let battery_refresh_row = adw::SwitchRow::builder()
+            .title("Lower Rate on Battery")
+            .build();
+        display_group.add(&battery_refresh_row);
+
+        let panel_od_row = adw::SwitchRow::builder()
+            .title("Panel Overdrive")
+            .subtitle("Lower response time, slightly higher power draw")
+            .visible(false)
+            .build();
+        display_group.add(&panel_od_row);
+
+        let mini_led_row = adw::SwitchRow::builder()
+            .title("Mini-LED Local Dimming")
+            .subtitle("Better contrast, higher power draw")
+            .visible(false)
+            .build();
+        display_group.add(&mini_led_row);
+
+        let effects_row = adw::SwitchRow::builder()
+            .title("Animations")
+            .subtitle("Turn off for smoother games on slower GPUs")
+            .visible(false)
+            .build();
+        display_group.add(&effects_row);
+
+        let idle_frames_row = adw::SwitchRow::builder()
+            .title("Skip Idle Frames")
+            .subtitle("Render only when the screen changes, saving GPU power")
+            .visible(false)
+            .build();
+        display_group.add(&idle_frames_row);
+
+        let psr_row = adw::SwitchRow::builder()
+            .title("Panel Self Refresh")
+            .visible(false)
+            .build();
+        display_group.add(&psr_row);
+
+        let mut idle_labels = vec!["Session Default".to_string()];
+        idle_labels.extend(idle::TIMEOUT_CHOICES.iter().map(|&secs| match secs {
+            0 => "Never".to_string(),
+            60 => "After 1 Minute".to_string(),
+            secs => format!("After {} Minutes", secs / 60),
+        }));
+        let idle_labels: Vec<&str> = idle_labels.iter().map(String::as_str).collect();
+        let idle_combo = adw::ComboRow::builder()
+            .title("Blank Screen")
+            .subtitle("Profiles can change this, e.g. never while gaming")
+            .model(&StringList::new(&idle_labels))
+            .visible(false)
+            .build();
+        display_group.add(&idle_combo);
+
+        (
+            display_group,
+            hz_combo,
+            vrr_row,
+            battery_refresh_row,
+            panel_od_row,
+            mini_led_row,
+            effects_row,
+            idle_frames_row,
+            psr_row,
+            idle_combo,
+        )
+    }
+
+    fn build_night_light_group() -> (
+        adw::PreferencesGroup,
+        adw::SwitchRow,
+        adw::SpinRow,
+        adw::SwitchRow,
+        adw::EntryRow,
+        adw::EntryRow,
+    ) {
+        let night_group = adw::PreferencesGroup::builder()
+            .title("Night Light")
+            .description("Reduce blue light for evening comfort.")
+            .build();
+
+        let night_light_row = adw::SwitchRow::builder()
+            .title("Night Light")
+            .build();
+        night_group.add(&night_light_row);
+
+        let night_temp_spin = adw::SpinRow::with_range(
+            nightlight::MIN_TEMPERATURE as f64,
+            nightlight::MAX_TEMPERATURE as f64,
+            100.0,
+        );
+        night_temp_spin.set_title("Color Temperature");
+        night_temp_spin.set_subtitle("Kelvin, lower is warmer");
+        night_group.add(&night_temp_spin);
+
+        let night_schedule_row = adw::SwitchRow::builder()
+            .title("Scheduled")
+            .subtitle("Only filter between the times below")
+            .build();
+        night_group.add(&night_schedule_row);
+
+        let night_start_entry = adw::EntryRow::builder()
+            .title("From (HH:MM)")
+            .show_apply_button(true)
+            .build();
+        night_group.add(&night_start_entry);
+
+        let night_end_entry = adw::EntryRow::builder()
+            .title("Until (HH:MM)")
+            .show_apply_button(true)
+            .build();
+        night_group.add(&night_end_entry);
+
+        (
+            night_group,
+            night_light_row,
+            night_temp_spin,
+            night_schedule_row,
+            night_start_entry,
+            night_end_entry,
+        )
+    }
+
+    fn build_lighting_group() -> (adw::PreferencesGroup, adw::ComboRow) {
+        let lighting_group = adw::PreferencesGroup::builder()
+            .title("Lighting")
+            .description("RGB and keyboard backlight brightness.")
+            .build();
+
+        let labels: Vec<&str> = LightingLevel::ALL.iter().map(|level| level.label()).collect();
+        let lighting_combo = adw::ComboRow::builder()
+            .title("Brightness")
+            .model(&StringList::new(&labels))
+            .build();
+        lighting_group.add(&lighting_combo);
+
+        (lighting_group, lighting_combo)
+    }
+
+    fn build_mangohud_group() -> (adw::PreferencesGroup, adw::SwitchRow, adw::SpinRow, adw::SwitchRow) {
+        let mangohud_group = adw::PreferencesGroup::builder()
+            .title("MangoHud")
+            .description("Performance overlay settings, shared by every game.")
+            .build();
+
+        let mangohud_overlay_row = adw::SwitchRow::builder()
+            .title("Show Overlay")
+            .subtitle("Hidden overlays can still be toggled in game")
+            .build();
+        mangohud_group.add(&mangohud_overlay_row);
+
+        let mangohud_fps_spin = adw::SpinRow::with_range(0.0, mangohud::MAX_FPS_LIMIT as f64, 1.0);
+        mangohud_fps_spin.set_title("FPS Limit");
+        mangohud_fps_spin.set_subtitle("0 for unlimited");
+        mangohud_group.add(&mangohud_fps_spin);
+
+        let mangohud_frame_timing_row = adw::SwitchRow::builder()
+            .title("Frame Timing")
+            .subtitle("Graph of frame times")
+            .build();
+        mangohud_group.add(&mangohud_frame_timing_row);
+
+        (mangohud_group, mangohud_overlay_row, mangohud_fps_spin, mangohud_frame_timing_row)
+    }
+
+    fn setup_refresh_rate(&self, state: &AppState, display: &Arc<dyn DisplayBackend>) {
+        let handler = self.hz_combo.connect_selected_notify(clone!(
+            #[strong] state,
+            #[strong] display,
+            move |combo| {
+                let Some(label) = state.refresh_rates().get(combo.selected() as usize).cloned() else {
+                    return;
+                };
+                let Some(hz_val) = system_info::rate_from_label(&label).filter(|&hz| hz > 0) else {
+                    state.toast("Invalid refresh rate format");
+                    return;
+                };
+                let new_hz = system_info::rate_label(hz_val);
+                let current = state.current_hz();
+                if new_hz == current {
+                    return;
+                }
+
+                let monitor = state.monitor();
+                let monitor_name = monitor.name.clone();
+                let vrr = state.vrr();
+                combo.set_sensitive(false);
+
+                let state_clone = state.clone();
+                let combo_clone = combo.clone();
+                let new_hz_clone = new_hz.clone();
+                let display = display.clone();
+
+                let apply = move || {
+                    glib::spawn_future_local(async move {
+                        let result = gio::spawn_blocking(move || {
+                            system_info::apply_monitor_mode_with(display.as_ref(), &monitor, hz_val, None)?;
+
+                            // Remember the choice so it is restored on replug.
+                            let _ = hotplug::save_monitor_preference(&monitor, hz_val, vrr);
+                            Ok::<(), String>(())
+                        }).await;
+
+                        combo_clone.set_sensitive(true);
+
+                        match result {
+                            Ok(Ok(())) => {
+                                state_clone.set_current_rate(hz_val);
+                                battery_history::record_event(&new_hz_clone);
+                                state_clone.toast(&format!("Refresh rate set to {}", new_hz_clone));
+                            }
+                            _ => {
+                                // Select the rate still in force again.
+                                state_clone.notify_current_hz();
+                                state_clone.toast("Failed to change refresh rate");
+                            }
+                        }
+                    });
+                };
+
+                let state_clone = state.clone();
+                let combo_clone = combo.clone();
+                confirm_then(
+                    combo,
+                    Config::load().confirm.refresh_rate,
+                    "Change Refresh Rate?",
+                    &format!("Switch {} from {} to {}.", monitor_name, current, new_hz),
+                    apply,
+                    move || {
+                        combo_clone.set_sensitive(true);
+                        state_clone.notify_current_hz();
+                    },
+                );
+            }
+        ));
+
+        // A new model resets the selection, which isn't a user choice.
+        state.connect_refresh_rates_notify(clone!(
+            #[strong(rename_to = page)] self,
+            move |state| {
+                let rates = state.refresh_rates();
+                if rates.is_empty() {
+                    return;
+                }
+                let rates: Vec<&str> = rates.iter().map(|s| s.as_str()).collect();
+                page.hz_combo.block_signal(&handler);
+                page.hz_combo.set_model(Some(&StringList::new(&rates)));
+                page.select_refresh_rate(state);
+                page.hz_combo.unblock_signal(&handler);
+            }
+        ));
+
+        state
+            .bind_property("vrr", &self.vrr_row, "active")
+            .sync_create()
+            .build();
+        self.vrr_row.connect_active_notify(clone!(
+            #[strong] state,
+            #[strong] display,
+            move |row| {
+                let enabled = row.is_active();
+                if enabled == state.vrr() {
+                    return;
+                }
+                row.set_sensitive(false);
+
+                let monitor = state.monitor();
+                let hz = system_info::rate_from_label(&state.current_hz()).unwrap_or(0);
+                let state = state.clone();
+                let row = row.clone();
+                let display = display.clone();
+                glib::spawn_future_local(async move {
+                    let result = gio::spawn_blocking(move || {
+                        system_info::apply_monitor_mode_with(display.as_ref(), &monitor, hz, Some(enabled))?;
+                        let _ = hotplug::save_monitor_preference(&monitor, hz, enabled);
+                        Ok::<(), String>(())
+                    }).await;
+
+                    row.set_sensitive(true);
+
+                    match result {
+                        Ok(Ok(())) => {
+                            state.set_vrr(enabled);
+                            let label = if enabled { "enabled" } else { "disabled" };
+                            state.toast(&format!("Variable refresh rate {}", label));
+                        }
+                        result => {
+                            state.toast(&match result {
+                                Ok(Err(e)) => format!("Failed to change variable refresh rate: {}", e),
+                                _ => "Failed to change variable refresh rate".to_string(),
+                            });
+                            state.notify_vrr();
+                        }
+                    }
+                });
+            }
+        ));
+    }
+
+    fn watch_hotplug(&self, state: &AppState, backends: &Backends) {
+        let state = state.clone();
+        let display = backends.display.clone();
+
+        hotplug::watch_monitors(backends.sysfs.clone(), move |event| {
+            let state = state.clone();
+            let display = display.clone();
+
+            glib::spawn_future_local(async move {
+                if let MonitorEvent::Added(name) = event {
+                    let result = gio::spawn_blocking(move || {
+                        hotplug::restore_monitor_preference(display.as_ref(), &name)
+                    }).await;
+
+                    match result {
+                        Ok(Some(Ok(hz))) => {
+                            state.toast(&format!("Restored saved {}Hz refresh rate", hz));
+                        }
+                        Ok(Some(Err(_))) => {
+                            state.toast("Failed to restore saved refresh rate");
+                        }
+                        _ => {}
+                    }
+                }
+
+                state.reload();
+            });
+        });
+    }
+
+    fn setup_night_light(&self, state: &AppState) {
+        state.bind_property("night-light", &self.night_light_row, "active").sync_create().build();
+        state.bind_property("night-temperature", &self.night_temp_spin, "value").sync_create().build();
+        state.bind_property("night-scheduled", &self.night_schedule_row, "active").sync_create().build();
+        state.bind_property("night-start", &self.night_start_entry, "text").sync_create().build();
+        state.bind_property("night-end", &self.night_end_entry, "text").sync_create().build();
+        for entry in [&self.night_start_entry, &self.night_end_entry] {
+            state.bind_property("night-scheduled", entry, "sensitive").sync_create().build();
+        }
+
+        match self.night_light.borrow().backend() {
+            Some(backend) => self.night_light_row.set_subtitle(&format!("Using {}", backend.name())),
+            None => {
+                self.night_light_row.set_subtitle("Install hyprsunset, wlsunset or gammastep");
+                self.night_light_row.set_sensitive(false);
+                self.night_temp_spin.set_sensitive(false);
+                self.night_schedule_row.set_sensitive(false);
+            }
+        }
+
+        self.night_light_row.connect_active_notify(clone!(
+            #[strong] state,
+            move |row| {
+                if row.is_active() != state.night_light() {
+                    update_night_light_config(&state, |c| c.enabled = row.is_active());
+                }
+            }
+        ));
+
+        self.night_temp_spin.connect_value_notify(clone!(
+            #[strong] state,
+            move |spin| {
+                let temperature = spin.value() as u32;
+                if temperature != state.night_temperature() {
+                    update_night_light_config(&state, |c| c.temperature = temperature);
+                }
+            }
+        ));
+
+        self.night_schedule_row.connect_active_notify(clone!(
+            #[strong] state,
+            move |row| {
+                if row.is_active() != state.night_scheduled() {
+                    update_night_light_config(&state, |c| c.scheduled = row.is_active());
+                }
+            }
+        ));
+
+        for (entry, is_start) in [(&self.night_start_entry, true), (&self.night_end_entry, false)] {
+            entry.connect_apply(clone!(
+                #[strong] state,
+                move |entry| {
+                    let text = entry.text().to_string();
+                    if schedule::parse_time(&text).is_none() {
+                        state.toast("Use a 24-hour time such as 20:30");
+                        return;
+                    }
+                    update_night_light_config(&state, |c| {
+                        if is_start {
+                            c.start = text.clone();
+                        } else {
+                            c.end = text.clone();
+                        }
+                    });
+                }
+            ));
+        }
+
+        let apply = clone!(
+            #[strong(rename_to = page)] self,
+            move |state: &AppState| page.apply_night_light(state)
+        );
+        state.connect_night_light_notify(apply.clone());
+        state.connect_night_temperature_notify(apply.clone());
+        state.connect_night_scheduled_notify(apply.clone());
+        state.connect_night_start_notify(apply.clone());
+        state.connect_night_end_notify(apply);
+
+        // Re-evaluate the schedule every minute.
+        schedule::watch_minutes(clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            move |_| page.apply_night_light(&state)
+        ));
+
+        // The timers keep the page, and so the controller, alive until
+        // the process exits, so it's never dropped.
+        if let Some(app) = gio::Application::default() {
+            let night_light = self.night_light.clone();
+            app.connect_shutdown(move |_| night_light.borrow_mut().stop());
+        }
+
+        self.apply_night_light(state);
+    }
+
+    fn apply_night_light(&self, state: &AppState) {
+        let active = state.night_light()
+            && (!state.night_scheduled() || {
+                match (schedule::parse_time(&state.night_start()), schedule::parse_time(&state.night_end())) {
+                    (Some(start), Some(end)) => schedule::in_schedule(schedule::minutes_now(), start, end),
+                    _ => true,
+                }
+            });
+
+        let target = active.then_some(state.night_temperature());
+        if let Err(e) = self.night_light.borrow_mut().set(target) {
+            state.toast(&format!("Night light failed: {}", e));
+        }
+    }
+
+    /// Shows the ASUS panel toggles the firmware supports.
+    fn setup_panel_features(&self, state: &AppState) {
+        let rows = [
+            (PanelFeature::Overdrive, "panel-overdrive", &self.panel_od_row),
+            (PanelFeature::MiniLed, "mini-led", &self.mini_led_row),
+        ];
+
+        for (feature, property, row) in rows {
+            let Some(enabled) = asus::panel_feature(feature) else {
+                continue;
+            };
+            state.set_property(property, enabled);
+            row.set_visible(true);
+
+            bind_switch(
+                state,
+                property,
+                row,
+                &format!("{} change failed", row.title()),
+                move |enabled| asus::apply_panel_feature(feature, enabled),
+                |_, ()| {},
+            );
+        }
+    }
+
+    /// PSR lowers power on a static screen but makes some panels flicker.
+    /// The driver only reads its setting at load, so changes wait for a
+    /// reboot.
+    fn setup_psr(&self, state: &AppState) {
+        let Some((param, off)) = modparams::psr_control() else {
+            return;
+        };
+        let enabled = modparams::saved(&param).as_deref() != Some(off);
+        let running = param.current().as_deref() != Some(off);
+        state.set_psr(enabled);
+        self.psr_row.set_subtitle(psr_subtitle(enabled, running));
+        self.psr_row.set_visible(true);
+
+        bind_switch(
+            state,
+            "psr",
+            &self.psr_row,
+            "Panel Self Refresh change failed",
+            move |enabled| {
+                modparams::set(&param, (!enabled).then_some(off))?;
+                Ok(param.current().as_deref() != Some(off))
+            },
+            clone!(
+                #[strong(rename_to = page)] self,
+                #[strong] state,
+                move |enabled, running| {
+                    page.psr_row.set_subtitle(psr_subtitle(enabled, running));
+                    if !enabled {
+                        state.toast("Panel Self Refresh turns off after a reboot");
+                    }
+                }
+            ),
+        );
+
+        if let Some(reason) = state.read_only_reason() {
+            self.psr_row.set_sensitive(false);
+            self.psr_row.set_tooltip_text(Some(&reason));
+        }
+    }
+
+    fn setup_idle(&self, state: &AppState) {
+        if self.idle.borrow().backend().is_none() {
+            return;
+        }
+        self.idle_combo.set_visible(true);
+
+        state
+            .bind_property("idle-timeout", &self.idle_combo, "selected")
+            .transform_to(|_, secs: i64| idle_choice(u32::try_from(secs).ok()))
+            .sync_create()
+            .build();
+
+        self.idle_combo.connect_selected_notify(clone!(
+            #[strong] state,
+            move |combo| {
+                let timeout = (combo.selected() as usize)
+                    .checked_sub(1)
+                    .and_then(|i| idle::TIMEOUT_CHOICES.get(i).copied());
+                if timeout == state.idle_timeout_secs() {
+                    return;
+                }
+                let mut config = Config::load();
+                config.idle_timeout_secs = timeout;
+                if config.save().is_err() {
+                    state.toast("Failed to save screen blanking");
+                }
+                state.load_config(&config);
+            }
+        ));
+
+        state.connect_idle_timeout_notify(clone!(
+            #[strong(rename_to = page)] self,
+            move |state| page.sync_idle(state)
+        ));
+        self.sync_idle(state);
+    }
+
+    /// Brings the idle daemon in line with the config, where profiles
+    /// leave their screen blanking choice.
+    fn sync_idle(&self, state: &AppState) {
+        if self.idle.borrow().backend().is_none() {
+            return;
+        }
+        if let Err(e) = self.idle.borrow_mut().set(state.idle_timeout_secs()) {
+            state.toast(&format!("Screen blanking not changed: {}", e));
+        }
+    }
+
+    fn setup_effects(&self, state: &AppState) {
+        let page = self.clone();
+        let state = state.clone();
+        glib::spawn_future_local(async move {
+            let Ok(Some(backend)) = gio::spawn_blocking(effects::detect).await else {
+                return;
+            };
+            page.effects_backend.set(Some(backend));
+            page.effects_row.set_subtitle(match backend {
+                effects::Backend::Hyprland => "Hyprland animations and blur",
+                effects::Backend::Gnome => "GNOME animations",
+            });
+            bind_switch(
+                &state,
+                "effects",
+                &page.effects_row,
+                "Animation change failed",
+                move |on| effects::set_enabled(backend, on),
+                |_, ()| {},
+            );
+            page.sync_effects_row(&state);
+        });
+    }
+
+    fn sync_effects_row(&self, state: &AppState) {
+        let Some(backend) = self.effects_backend.get() else {
+            return;
+        };
+        let page = self.clone();
+        let state = state.clone();
+        glib::spawn_future_local(async move {
+            let enabled = gio::spawn_blocking(move || effects::enabled(backend)).await.ok().flatten();
+            page.effects_row.set_visible(enabled.is_some());
+            if let Some(enabled) = enabled {
+                state.set_effects(enabled);
+            }
+        });
+    }
+
+    fn setup_idle_frames(&self, state: &AppState) {
+        bind_switch(
+            state,
+            "skip-idle-frames",
+            &self.idle_frames_row,
+            "Frame skipping change failed",
+            hyprland::set_skip_idle_frames,
+            |_, ()| {},
+        );
+
+        self.sync_idle_frames_row(state);
+    }
+
+    fn sync_idle_frames_row(&self, state: &AppState) {
+        let page = self.clone();
+        let state = state.clone();
+        glib::spawn_future_local(async move {
+            let skip = gio::spawn_blocking(hyprland::skip_idle_frames).await.ok().flatten();
+            page.idle_frames_row.set_visible(skip.is_some());
+            if let Some(skip) = skip {
+                state.set_skip_idle_frames(skip);
+            }
+        });
+    }
+
+    fn setup_mangohud(&self, state: &AppState) {
+        if !mangohud::installed() {
+            if let Some(group) = self.mangohud_overlay_row.ancestor(adw::PreferencesGroup::static_type()) {
+                group.set_visible(false);
+            }
+            return;
+        }
+
+        state.load_mangohud(&mangohud::settings());
+        state.bind_property("mangohud-overlay", &self.mangohud_overlay_row, "active").sync_create().build();
+        state.bind_property("mangohud-fps", &self.mangohud_fps_spin, "value").sync_create().build();
+        state
+            .bind_property("mangohud-frame-timing", &self.mangohud_frame_timing_row, "active")
+            .sync_create()
+            .build();
+
+        self.mangohud_overlay_row.connect_active_notify(clone!(
+            #[strong] state,
+            move |row| {
+                if row.is_active() != state.mangohud_overlay() {
+                    update_mangohud(&state, |settings| settings.overlay = row.is_active());
+                }
+            }
+        ));
+
+        self.mangohud_fps_spin.connect_value_notify(clone!(
+            #[strong] state,
+            move |spin| {
+                let fps_limit = spin.value() as u32;
+                if fps_limit != state.mangohud_fps() {
+                    update_mangohud(&state, |settings| settings.fps_limit = fps_limit);
+                }
+            }
+        ));
+
+        self.mangohud_frame_timing_row.connect_active_notify(clone!(
+            #[strong] state,
+            move |row| {
+                if row.is_active() != state.mangohud_frame_timing() {
+                    update_mangohud(&state, |settings| settings.frame_timing = row.is_active());
+                }
+            }
+        ));
+    }
+
+    fn setup_lighting(&self, state: &AppState) {
+        let Some(backend) = self.lighting_backend else {
+            if let Some(group) = self.lighting_combo.ancestor(adw::PreferencesGroup::static_type()) {
+                group.set_visible(false);
+            }
+            return;
+        };
+
+        let subtitle = match backend {
+            lighting::Backend::OpenRgb => "Via OpenRGB",
+            lighting::Backend::Asusctl => "Via asusctl",
+        };
+        self.lighting_combo.set_subtitle(subtitle);
+
+        bind_choice(
+            state,
+            "lighting",
+            &self.lighting_combo,
+            LightingLevel::ALL.iter().map(|&level| (level.label().to_string(), level)).collect(),
+            "Lighting change failed",
+            move |level| {
+                lighting::apply(backend, level)?;
+                let mut config = Config::load();
+                config.lighting = Some(level);
+                let _ = config.save();
+                Ok(())
+            },
+        );
+    }
+}
+
+fn update_night_light_config(state: &AppState, update: impl FnOnce(&mut crate::config::NightLightConfig)) {
+    let mut config = Config::load();
+    update(&mut config.night_light);
+    if config.save().is_err() {
+        state.toast("Failed to save night light settings");
+    }
+    state.load_config(&config);
+}
+
+fn update_mangohud(state: &AppState, change: impl FnOnce(&mut mangohud::Settings)) {
+    let mut settings = mangohud::settings();
+    change(&mut settings);
+    if let Err(e) = mangohud::apply(settings) {
+        state.toast(&format!("Failed to save MangoHud settings: {}", e));
+    }
+    state.load_mangohud(&settings);
+}
+
+/// The blank screen entry for `secs`, the session default first.
+fn idle_choice(secs: Option<u32>) -> Option<u32> {
+    match secs {
+        None => Some(0),
+        Some(secs) => idle::TIMEOUT_CHOICES
+            .iter()
+            .position(|&choice| choice == secs)
+            .map(|i| i as u32 + 1),
+    }
+}
+
+fn psr_subtitle(enabled: bool, running: bool) -> &'static str {
+    match (enabled, running) {
+        (true, true) => "Saves battery on a still screen; turn off if the panel flickers",
+        (false, false) => "Off, costing some battery life",
+        (true, false) => "Turns back on after a reboot",
+        (false, true) => "Turns off after a reboot, costing some battery life",
+    }
+}
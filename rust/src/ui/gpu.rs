@@ -0,0 +1,675 @@
+use crate::config::Config;
+use crate::gpu::{self, GpuBackend, GpuMode, GpuStatus};
+use crate::gpu_driver::{self, GpuDriver};
+use crate::gpu_priority;
+use crate::gpufan::{self, FanCurve, GpuFan};
+use crate::hooks::{self, HookEvent};
+use crate::libvirt;
+use crate::platform;
+use crate::system_info::SystemInfo;
+use crate::probe::Capability;
+use gtk4::glib::{self, clone};
+use gtk4::prelude::*;
+use gtk4::{gio, Align, Button, StringList};
+use libadwaita as adw;
+use adw::prelude::*;
+use std::sync::Arc;
+use super::progress::Progress;
+use super::{bind_switch, restrict, window_of, AppState, FAN_GUARD_INTERVAL_SECS};
+
+/// Graphics mode switching, the compositor's GPU priority and drivers,
+/// and the discrete card's fan and power limit on desktops.
+#[derive(Clone)]
+pub(super) struct GpuPage {
+    groups: [adw::PreferencesGroup; 2],
+    pub(super) gpu_combo: adw::ComboRow,
+    gpu_priority_row: adw::SwitchRow,
+    gpu_driver_row: adw::ExpanderRow,
+    gpu_fan: Option<GpuFan>,
+    pub(super) gpu_fan_row: adw::ActionRow,
+    gpu_fan_curve_entry: adw::EntryRow,
+    gpu_fan_reset_btn: Button,
+    gpu_power_spin: adw::SpinRow,
+    gpu_power_btn: Button,
+    /// Offers the pending mode switch, above the preference groups.
+    pub(super) banner: adw::Banner,
+    progress: Progress,
+}
+
+impl GpuPage {
+    pub(super) fn build(progress: &Progress) -> Self {
+        let (gpu_group, gpu_combo, gpu_priority_row, gpu_driver_row) = Self::build_gpu_group();
+        let (
+            gpu_fan_group,
+            gpu_fan_row,
+            gpu_fan_curve_entry,
+            gpu_fan_reset_btn,
+            gpu_power_spin,
+            gpu_power_btn,
+        ) = Self::build_gpu_fan_group();
+
+        Self {
+            groups: [gpu_group, gpu_fan_group],
+            gpu_combo,
+            gpu_priority_row,
+            gpu_driver_row,
+            gpu_fan: platform::form_factor().is_desktop().then(gpufan::detect).flatten(),
+            gpu_fan_row,
+            gpu_fan_curve_entry,
+            gpu_fan_reset_btn,
+            gpu_power_spin,
+            gpu_power_btn,
+            banner: adw::Banner::builder()
+                .title("Graphics mode change requires logout.")
+                .button_label("Switch & Log Out")
+                .build(),
+            progress: progress.clone(),
+        }
+    }
+
+    pub(super) fn groups(&self) -> &[adw::PreferencesGroup] {
+        &self.groups
+    }
+
+    /// Switches modes through `backend`.
+    pub(super) fn bind(&self, state: &AppState, backend: &Arc<dyn GpuBackend>) {
+        state.connect_gpu_mode_notify(clone!(
+            #[strong(rename_to = page)] self,
+            move |state| page.select_gpu_mode(state)
+        ));
+
+        self.setup_gpu_mode(state, backend);
+        self.watch_gpu_mode(state, backend);
+        self.setup_gpu_priority(state);
+        self.setup_gpu_drivers();
+        self.setup_gpu_fan(state);
+    }
+
+    /// Enables mode switching once `info` is fresh, or says why it can't.
+    pub(super) fn show_system_info(&self, state: &AppState, info: &SystemInfo, stale: bool) {
+        if !info.supported_gpu_modes.is_empty() && info.gpu_mux {
+            self.gpu_combo
+                .set_subtitle("MUX modes require a reboot, others a logout");
+        }
+        if stale {
+            return;
+        }
+
+        if !info.supported_gpu_modes.is_empty() {
+            self.gpu_combo.set_sensitive(true);
+        } else {
+            self.gpu_combo.set_subtitle(&match info.gpu_capability {
+                Capability::TimedOut => format!("{} is not responding", info.gpu_tool),
+                _ if info.gpu_mode == Some(GpuMode::Hybrid) => {
+                    "Hybrid graphics detected; install supergfxctl, system76-power or envycontrol to switch modes".to_string()
+                }
+                _ => "No GPU switching tool found".to_string(),
+            });
+            self.gpu_combo.set_sensitive(false);
+        }
+        restrict(state, &[self.gpu_combo.upcast_ref()]);
+    }
+
+    /// Selects the current GPU mode. The combo's handler ignores the
+    /// mode already in force, so this doesn't start a switch.
+    fn select_gpu_mode(&self, state: &AppState) {
+        let mode = state.current_gpu_mode();
+        let position = state.supported_gpu_modes().iter().position(|m| Some(*m) == mode);
+        if let Some(idx) = position {
+            self.gpu_combo.set_selected(idx as u32);
+        }
+    }
+
+    fn build_gpu_group() -> (adw::PreferencesGroup, adw::ComboRow, adw::SwitchRow, adw::ExpanderRow) {
+        let gpu_group = adw::PreferencesGroup::builder()
+            .title("Graphics")
+            .description("Select GPU operation mode.")
+            .build();
+
+        let gpu_combo = adw::ComboRow::builder()
+            .title("Graphics Mode")
+            .subtitle("Requires logout to take effect")
+            .sensitive(false)
+            .build();
+        gpu_combo.set_model(Some(&StringList::new(&["Loading..."])));
+        gpu_group.add(&gpu_combo);
+
+        let gpu_priority_row = adw::SwitchRow::builder()
+            .title("Compositor GPU Priority")
+            .subtitle("Keep the desktop smooth while games load the GPU")
+            .visible(false)
+            .build();
+        gpu_group.add(&gpu_priority_row);
+
+        let gpu_driver_row = adw::ExpanderRow::builder()
+            .title("Drivers")
+            .visible(false)
+            .build();
+        gpu_group.add(&gpu_driver_row);
+
+        (gpu_group, gpu_combo, gpu_priority_row, gpu_driver_row)
+    }
+
+    fn build_gpu_fan_group() -> (
+        adw::PreferencesGroup,
+        adw::ActionRow,
+        adw::EntryRow,
+        Button,
+        adw::SpinRow,
+        Button,
+    ) {
+        let gpu_fan_group = adw::PreferencesGroup::builder()
+            .title("Graphics Card")
+            .description(format!(
+                "Power limit and fan curve of the discrete GPU. Above {}°C the fan always runs at full speed.",
+                gpufan::SAFETY_TEMP_C
+            ))
+            .build();
+
+        let gpu_fan_row = adw::ActionRow::builder()
+            .title("Current")
+            .subtitle("...")
+            .build();
+        gpu_fan_group.add(&gpu_fan_row);
+
+        let gpu_power_spin = adw::SpinRow::with_range(0.0, 1000.0, 5.0);
+        gpu_power_spin.set_title("Power Limit (W)");
+        gpu_power_spin.set_subtitle("Resets to the card default on reboot");
+        gpu_power_spin.set_visible(false);
+        let gpu_power_btn = Button::builder()
+            .label("Apply")
+            .valign(Align::Center)
+            .build();
+        gpu_power_spin.add_suffix(&gpu_power_btn);
+        gpu_fan_group.add(&gpu_power_spin);
+
+        let gpu_fan_curve_entry = adw::EntryRow::builder()
+            .title("Fan Curve (°C:%)")
+            .show_apply_button(true)
+            .build();
+        gpu_fan_group.add(&gpu_fan_curve_entry);
+
+        let gpu_fan_reset_btn = Button::builder()
+            .label("Reset to Hardware Default")
+            .margin_top(12)
+            .build();
+        gpu_fan_group.add(&gpu_fan_reset_btn);
+
+        (
+            gpu_fan_group,
+            gpu_fan_row,
+            gpu_fan_curve_entry,
+            gpu_fan_reset_btn,
+            gpu_power_spin,
+            gpu_power_btn,
+        )
+    }
+
+    fn setup_gpu_mode(&self, state: &AppState, backend: &Arc<dyn GpuBackend>) {
+        let handler = self.gpu_combo.connect_selected_notify(clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            #[strong] backend,
+            move |combo| {
+                let Some(&new_mode) = state.supported_gpu_modes().get(combo.selected() as usize) else {
+                    return;
+                };
+                let current = state.current_gpu_mode();
+                if Some(new_mode) == current {
+                    page.banner.set_revealed(false);
+                    return;
+                }
+
+                if backend.requires_reboot(current, new_mode) {
+                    page.banner.set_title("Graphics mode change requires a reboot.");
+                    page.banner.set_button_label(Some("Switch & Reboot"));
+                } else {
+                    page.banner.set_title("Graphics mode change requires logout.");
+                    page.banner.set_button_label(Some("Switch & Log Out"));
+                }
+                page.banner.set_revealed(true);
+            }
+        ));
+
+        // A new model resets the selection, which isn't a user choice.
+        state.connect_gpu_modes_notify(clone!(
+            #[strong(rename_to = page)] self,
+            move |state| {
+                let modes = state.supported_gpu_modes();
+                if modes.is_empty() {
+                    return;
+                }
+                let labels: Vec<&str> = modes.iter().map(|m| m.label()).collect();
+                page.gpu_combo.block_signal(&handler);
+                page.gpu_combo.set_model(Some(&StringList::new(&labels)));
+                page.select_gpu_mode(state);
+                page.gpu_combo.unblock_signal(&handler);
+            }
+        ));
+
+        self.banner.connect_button_clicked(clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            #[strong] backend,
+            move |_| {
+                let Some(&pending) = state.supported_gpu_modes().get(page.gpu_combo.selected() as usize) else {
+                    return;
+                };
+                let current = state.current_gpu_mode();
+
+                if Some(pending) == current {
+                    return;
+                }
+
+                let reboot = backend.requires_reboot(current, pending);
+                // A VM started before a reboot wouldn't outlive it, so
+                // one is only offered when the switch just logs out.
+                if pending == GpuMode::Vfio && !reboot {
+                    let page = page.clone();
+                    let state = state.clone();
+                    let backend = backend.clone();
+                    glib::spawn_future_local(async move {
+                        let vms = gio::spawn_blocking(libvirt::gpu_domains).await.unwrap_or_default();
+                        page.confirm_gpu_switch(&state, &backend, pending, reboot, vms);
+                    });
+                } else {
+                    page.confirm_gpu_switch(&state, &backend, pending, reboot, Vec::new());
+                }
+            }
+        ));
+    }
+
+    /// Asks before switching to `mode`, offering to start one of `vms` on
+    /// the GPU afterwards.
+    fn confirm_gpu_switch(
+        &self,
+        state: &AppState,
+        backend: &Arc<dyn GpuBackend>,
+        mode: GpuMode,
+        reboot: bool,
+        vms: Vec<String>,
+    ) {
+        let (body, confirm_label) = if reboot {
+            (
+                format!(
+                    "Switching to {} needs a restart to take effect. Your computer will reboot immediately. You will lose unsaved work.",
+                    mode.label()
+                ),
+                "Switch & Reboot",
+            )
+        } else {
+            (
+                format!(
+                    "Switching to {} mode will terminate your session immediately. You will lose unsaved work.",
+                    mode.label()
+                ),
+                "Switch & Log Out",
+            )
+        };
+
+        let dialog = adw::MessageDialog::builder()
+            .heading("Change Graphics Mode?")
+            .body(body)
+            .build();
+        dialog.set_transient_for(window_of(&self.gpu_combo).as_ref());
+
+        let vm_row = (!vms.is_empty()).then(|| {
+            let choices: Vec<&str> = std::iter::once("Don't Start a VM")
+                .chain(vms.iter().map(String::as_str))
+                .collect();
+            let row = adw::ComboRow::builder()
+                .title("Start VM")
+                .subtitle("Switches back to Hybrid when it shuts down")
+                .model(&StringList::new(&choices))
+                .build();
+            let list = gtk4::ListBox::builder()
+                .selection_mode(gtk4::SelectionMode::None)
+                .css_classes(["boxed-list"])
+                .build();
+            list.append(&row);
+            dialog.set_extra_child(Some(&list));
+            row
+        });
+
+        dialog.add_response("cancel", "Cancel");
+        dialog.add_response("logout", confirm_label);
+        dialog.set_response_appearance("logout", adw::ResponseAppearance::Destructive);
+        dialog.set_default_response(Some("cancel"));
+        dialog.set_close_response("cancel");
+
+        let page = self.clone();
+        let state = state.clone();
+        let backend = backend.clone();
+
+        dialog.connect_response(None, move |_, response| {
+            if response != "logout" {
+                return;
+            }
+            let vm = vm_row
+                .as_ref()
+                .and_then(|row| (row.selected() as usize).checked_sub(1))
+                .and_then(|idx| vms.get(idx).cloned());
+            let page = page.clone();
+            let state = state.clone();
+            let backend = backend.clone();
+            page.progress.show(
+                "Switching Graphics Mode",
+                &format!("Changing to {}; this can take a minute", mode.label()),
+                None,
+                None,
+            );
+
+            glib::spawn_future_local(async move {
+                let result = gio::spawn_blocking(move || {
+                    hooks::run(HookEvent::PreGpuSwitch, &[("TUXTUNER_GPU_MODE", mode.as_str())]);
+                    match vm {
+                        Some(vm) => backend.apply_for_vm(&vm),
+                        None => backend.apply(mode, reboot),
+                    }
+                }).await;
+
+                // On success the session ends or the machine
+                // reboots, so the page only clears on failure.
+                match result {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => {
+                        page.progress.hide();
+                        state.toast(&format!("GPU switch failed: {}", e));
+                    }
+                    Err(_) => {
+                        page.progress.hide();
+                        state.toast("GPU switch failed");
+                    }
+                }
+            });
+        });
+
+        dialog.present();
+    }
+
+    /// Lists each GPU's driver, version and Vulkan driver: the first
+    /// things to check when a mode switch fails.
+    fn setup_gpu_drivers(&self) {
+        let page = self.clone();
+        glib::spawn_future_local(async move {
+            let Ok(drivers) = gio::spawn_blocking(gpu_driver::drivers).await else {
+                return;
+            };
+            page.gpu_driver_row.set_visible(!drivers.is_empty());
+            page.gpu_driver_row.set_subtitle(
+                &drivers.iter().map(GpuDriver::label).collect::<Vec<_>>().join(" · "),
+            );
+
+            for gpu in drivers {
+                let vulkan = match &gpu.vulkan_icd {
+                    Some(icd) => format!("Vulkan via {}", icd),
+                    None => "No Vulkan driver installed".to_string(),
+                };
+                let row = adw::ActionRow::builder()
+                    .title(gpu.label())
+                    .subtitle(glib::markup_escape_text(&format!(
+                        "{} · {} · PCI {}",
+                        gpu.version, vulkan, gpu.pci_id
+                    )))
+                    .subtitle_selectable(true)
+                    .build();
+                page.gpu_driver_row.add_row(&row);
+            }
+        });
+    }
+
+    fn watch_gpu_mode(&self, state: &AppState, backend: &Arc<dyn GpuBackend>) {
+        let page = self.clone();
+        let state = state.clone();
+        gpu::watch_mode(backend.clone(), move |status| page.follow_gpu_status(&state, status));
+    }
+
+    /// Picks up a mode switch made by another tool, dropping whatever
+    /// switch was pending here.
+    fn follow_gpu_status(&self, state: &AppState, status: GpuStatus) {
+        let current = state.gpu_mode();
+        // Not loaded yet, unchanged, or the daemon went away: nothing to follow.
+        let Some(mode) = status.mode else {
+            return;
+        };
+        if current.is_empty() || Some(mode) == state.current_gpu_mode() || status.modes.is_empty() {
+            return;
+        }
+        glib::g_debug!(crate::LOG_DOMAIN, "GPU mode changed externally: {} -> {}", current, mode);
+
+        state.set_supported_gpu_modes(&status.modes);
+        state.set_current_gpu_mode(Some(mode));
+        self.banner.set_revealed(false);
+        state.toast(&format!("Graphics mode changed to {}", mode.label()));
+
+        gio::spawn_blocking(move || {
+            hooks::run(HookEvent::PostGpuSwitch, &[("TUXTUNER_GPU_MODE", mode.as_str())]);
+        });
+    }
+
+    fn setup_gpu_priority(&self, state: &AppState) {
+        let page = self.clone();
+        let state = state.clone();
+        glib::spawn_future_local(async move {
+            let detected = gio::spawn_blocking(|| {
+                gpu_priority::detect().map(|compositor| {
+                    let enabled = gpu_priority::enabled(&compositor);
+                    (compositor, enabled)
+                })
+            })
+            .await;
+            let Ok(Some((compositor, enabled))) = detected else {
+                return;
+            };
+
+            page.gpu_priority_row.set_subtitle(&format!(
+                "Let {} keep a high-priority GPU context so games can't starve it; cleared by package updates",
+                compositor.name
+            ));
+            state.set_gpu_priority(enabled);
+            page.gpu_priority_row.set_visible(true);
+
+            let name = compositor.name.clone();
+            bind_switch(
+                &state,
+                "gpu-priority",
+                &page.gpu_priority_row,
+                "GPU priority change failed",
+                move |on| gpu_priority::set_enabled(&compositor, on),
+                clone!(
+                    #[strong] state,
+                    move |_, ()| {
+                        state.toast(&format!("Takes effect when {} restarts, e.g. after logging in again", name));
+                    }
+                ),
+            );
+        });
+    }
+
+    fn setup_gpu_fan(&self, state: &AppState) {
+        let Some(fan) = self.gpu_fan.clone() else {
+            if let Some(group) = self.gpu_fan_row.ancestor(adw::PreferencesGroup::static_type()) {
+                group.set_visible(false);
+            }
+            return;
+        };
+
+        self.setup_gpu_power_limit(state, &fan);
+
+        let curve = Config::load().gpu_fan_curve;
+        self.gpu_fan_curve_entry
+            .set_text(&curve.clone().unwrap_or_default().to_text());
+        self.refresh_gpu_fan();
+
+        if let Some(reason) = state.read_only_reason() {
+            let widgets: [&gtk4::Widget; 2] = [
+                self.gpu_fan_curve_entry.upcast_ref(),
+                self.gpu_fan_reset_btn.upcast_ref(),
+            ];
+            for widget in widgets {
+                widget.set_sensitive(false);
+                widget.set_tooltip_text(Some(&reason));
+            }
+        }
+
+        self.gpu_fan_curve_entry.connect_apply(clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            #[strong] fan,
+            move |entry| {
+                let curve = match FanCurve::parse(&entry.text()) {
+                    Ok(curve) => curve,
+                    Err(e) => {
+                        state.toast(&e);
+                        return;
+                    }
+                };
+                entry.set_text(&curve.to_text());
+
+                let fan = fan.clone();
+                let saved = curve.clone();
+                page.run_gpu_fan_task(
+                    &state,
+                    move || fan.apply_curve(&curve),
+                    Some(saved),
+                    "GPU fan curve applied",
+                );
+            }
+        ));
+
+        self.gpu_fan_reset_btn.connect_clicked(clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            #[strong] fan,
+            move |_| {
+                page.gpu_fan_curve_entry
+                    .set_text(&FanCurve::default().to_text());
+                let fan = fan.clone();
+                page.run_gpu_fan_task(&state, move || fan.reset(), None, "GPU fan returned to hardware control");
+            }
+        ));
+
+        glib::timeout_add_seconds_local(
+            FAN_GUARD_INTERVAL_SECS,
+            clone!(
+                #[strong(rename_to = page)] self,
+                move || {
+                    page.refresh_gpu_fan();
+                    glib::ControlFlow::Continue
+                }
+            ),
+        );
+
+        // nvidia-settings has no daemon to follow the curve, so don't leave
+        // the fan pinned at whatever speed it last had once we're gone.
+        // A background instance only hides its window and keeps the fan.
+        if let (GpuFan::Nvidia, Some(app)) = (&fan, gio::Application::default()) {
+            app.connect_shutdown(|_| {
+                if Config::load().gpu_fan_curve.is_some() {
+                    let _ = GpuFan::Nvidia.reset();
+                }
+            });
+        }
+    }
+
+    fn setup_gpu_power_limit(&self, state: &AppState, fan: &GpuFan) {
+        let Some(limit) = fan.power_limit() else {
+            return;
+        };
+
+        self.gpu_power_spin
+            .set_range(limit.min_w as f64, limit.max_w as f64);
+        self.gpu_power_spin.set_value(limit.current_w as f64);
+        self.gpu_power_spin.set_visible(true);
+
+        if let Some(reason) = state.read_only_reason() {
+            self.gpu_power_spin.set_sensitive(false);
+            self.gpu_power_spin.set_tooltip_text(Some(&reason));
+            return;
+        }
+
+        self.gpu_power_btn.connect_clicked(clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            #[strong] fan,
+            move |btn| {
+                let watts = page.gpu_power_spin.value() as u32;
+                btn.set_sensitive(false);
+
+                let state = state.clone();
+                let btn = btn.clone();
+                let fan = fan.clone();
+                glib::spawn_future_local(async move {
+                    let result = gio::spawn_blocking(move || fan.apply_power_limit(watts)).await;
+
+                    btn.set_sensitive(true);
+
+                    match result {
+                        Ok(Ok(())) => state.toast(&format!("GPU power limit set to {} W", watts)),
+                        Ok(Err(e)) => state.toast(&format!("GPU power limit change failed: {}", e)),
+                        Err(_) => state.toast("GPU power limit change failed"),
+                    }
+                });
+            }
+        ));
+    }
+
+    /// Applies or resets the fan curve, saving `curve` once it took.
+    fn run_gpu_fan_task(
+        &self,
+        state: &AppState,
+        task: impl FnOnce() -> Result<(), String> + Send + 'static,
+        curve: Option<FanCurve>,
+        success: &'static str,
+    ) {
+        self.gpu_fan_reset_btn.set_sensitive(false);
+
+        let page = self.clone();
+        let state = state.clone();
+        glib::spawn_future_local(async move {
+            let result = gio::spawn_blocking(task).await;
+
+            page.gpu_fan_reset_btn.set_sensitive(true);
+
+            match result {
+                Ok(Ok(())) => {
+                    let mut config = Config::load();
+                    config.gpu_fan_curve = curve;
+                    if config.save().is_err() {
+                        state.toast("Failed to save GPU fan settings");
+                    } else {
+                        state.toast(success);
+                    }
+                }
+                Ok(Err(e)) => state.toast(&format!("GPU fan change failed: {}", e)),
+                Err(_) => state.toast("GPU fan change failed"),
+            }
+        });
+    }
+
+    /// Updates the readout and, for NVIDIA, steps the fan along the curve.
+    fn refresh_gpu_fan(&self) {
+        let Some(fan) = self.gpu_fan.clone() else {
+            return;
+        };
+        let curve = Config::load().gpu_fan_curve;
+
+        let row = self.gpu_fan_row.clone();
+        glib::spawn_future_local(async move {
+            let reading = gio::spawn_blocking(move || {
+                if let (GpuFan::Nvidia, Some(curve)) = (&fan, &curve) {
+                    let _ = fan.apply_curve(curve);
+                }
+                (fan.temperature(), fan.speed_percent())
+            })
+            .await;
+
+            let subtitle = match reading {
+                Ok((Some(temp), Some(speed))) => format!("{}°C, fan at {}%", temp, speed),
+                Ok((Some(temp), None)) => format!("{}°C", temp),
+                _ => "Unavailable".to_string(),
+            };
+            row.set_subtitle(&subtitle);
+        });
+    }
+}
@@ -0,0 +1,137 @@
+use super::advice::AdvicePage;
+use super::automation::AutomationPage;
+use super::battery::BatteryPage;
+use super::cpu::CpuPage;
+use super::devices::DevicesPage;
+use super::display::DisplayPage;
+use super::gpu::GpuPage;
+use super::monitoring::MonitoringPage;
+use super::progress::Progress;
+use super::sleep::SleepPage;
+use super::state::AppState;
+use super::status::StatusPage;
+use super::tuning::TuningPage;
+use super::vendor::VendorPage;
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gtk4::{glib, CompositeTemplate, TemplateChild};
+use libadwaita as adw;
+
+/// The window shell comes from `window.ui`; the pages build their
+/// preference groups in code, and `constructed` adds them to `page`.
+/// They are bound to `state` once the window has its backends.
+#[derive(CompositeTemplate)]
+#[template(file = "window.ui")]
+pub struct TuxTunerWindow {
+    #[template_child]
+    pub toast_overlay: TemplateChild<adw::ToastOverlay>,
+    #[template_child]
+    pub header_bar: TemplateChild<adw::HeaderBar>,
+    #[template_child]
+    pub profile_switcher: TemplateChild<gtk4::DropDown>,
+    #[template_child]
+    pub profile_modified_label: TemplateChild<gtk4::Label>,
+    #[template_child]
+    pub main_content: TemplateChild<gtk4::Box>,
+    #[template_child]
+    pub read_only_banner: TemplateChild<adw::Banner>,
+    #[template_child]
+    pub page: TemplateChild<adw::PreferencesPage>,
+    #[template_child]
+    pub content_stack: TemplateChild<gtk4::Stack>,
+    /// What the pages show and change, shared between them.
+    pub(super) state: AppState,
+    pub(super) progress: Progress,
+    pub(super) status: StatusPage,
+    pub(super) advice: AdvicePage,
+    pub(super) automation: AutomationPage,
+    pub(super) cpu: CpuPage,
+    pub(super) gpu: GpuPage,
+    pub(super) display: DisplayPage,
+    pub(super) battery: BatteryPage,
+    pub(super) vendor: VendorPage,
+    pub(super) devices: DevicesPage,
+    pub(super) tuning: TuningPage,
+    pub(super) sleep: SleepPage,
+    pub(super) monitoring: MonitoringPage,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for TuxTunerWindow {
+    const NAME: &'static str = "TuxTunerWindow";
+    type Type = super::TuxTunerWindow;
+    type ParentType = adw::ApplicationWindow;
+
+    fn new() -> Self {
+        let progress = Progress::build();
+        Self {
+            toast_overlay: TemplateChild::default(),
+            header_bar: TemplateChild::default(),
+            profile_switcher: TemplateChild::default(),
+            profile_modified_label: TemplateChild::default(),
+            main_content: TemplateChild::default(),
+            read_only_banner: TemplateChild::default(),
+            page: TemplateChild::default(),
+            content_stack: TemplateChild::default(),
+            state: AppState::default(),
+            status: StatusPage::build(),
+            advice: AdvicePage::build(),
+            automation: AutomationPage::build(),
+            cpu: CpuPage::build(&progress),
+            gpu: GpuPage::build(&progress),
+            display: DisplayPage::build(),
+            battery: BatteryPage::build(),
+            vendor: VendorPage::build(),
+            devices: DevicesPage::build(),
+            tuning: TuningPage::build(),
+            sleep: SleepPage::build(&progress),
+            monitoring: MonitoringPage::build(),
+            progress,
+        }
+    }
+
+    fn class_init(klass: &mut Self::Class) {
+        klass.bind_template();
+        klass.install_action("win.refresh", None, |win, _, _| win.imp().state.reload());
+        klass.install_action("win.snapshot", None, |win, _, _| win.take_snapshot());
+        klass.install_action("win.restore-snapshot", None, |win, _, _| win.restore_snapshot());
+        klass.install_action("win.factory-reset", None, |win, _, _| win.factory_reset());
+    }
+
+    fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+        obj.init_template();
+    }
+}
+
+impl ObjectImpl for TuxTunerWindow {
+    fn constructed(&self) {
+        self.parent_constructed();
+
+        self.main_content.prepend(&self.gpu.banner);
+        self.main_content.prepend(&self.status.header);
+        self.content_stack.add_named(&self.progress.page, Some("progress"));
+
+        let groups = [
+            self.status.groups(),
+            self.advice.groups(),
+            self.automation.groups(),
+            self.cpu.groups(),
+            self.gpu.groups(),
+            self.display.groups(),
+            self.battery.groups(),
+            self.vendor.groups(),
+            self.devices.groups(),
+            self.tuning.groups(),
+            self.sleep.groups(),
+            self.monitoring.groups(),
+        ];
+        for group in groups.into_iter().flatten() {
+            self.page.add(group);
+        }
+    }
+}
+
+impl WidgetImpl for TuxTunerWindow {}
+impl WindowImpl for TuxTunerWindow {}
+impl ApplicationWindowImpl for TuxTunerWindow {}
+impl AdwApplicationWindowImpl for TuxTunerWindow {}
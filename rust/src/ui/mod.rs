@@ -0,0 +1,695 @@
+use crate::backends::Backends;
+use crate::config::Config;
+use crate::platform;
+use crate::privileges;
+use crate::probe::Capability;
+use crate::remote;
+use crate::system_info::SystemInfo;
+use crate::virt::{self, VirtKind};
+use gtk4::glib::{self, clone};
+use gtk4::prelude::*;
+use gtk4::subclass::prelude::ObjectSubclassIsExt;
+use gtk4::{gio, Button, CssProvider};
+use libadwaita as adw;
+use adw::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+mod advice;
+mod automation;
+mod battery;
+mod cpu;
+mod devices;
+mod dialogs;
+mod display;
+mod gpu;
+mod imp;
+mod monitoring;
+mod progress;
+mod sleep;
+mod snapshot;
+mod state;
+mod status;
+mod tuning;
+mod vendor;
+
+pub use dialogs::setup_actions;
+use state::AppState;
+
+const APP_CSS: &str = r#"
+.tuxtuner-header {
+    background: linear-gradient(135deg, #1a1a2e 0%, #16213e 50%, #0f3460 100%);
+    border-radius: 0 0 20px 20px;
+    padding: 16px 20px 18px 20px;
+}
+
+.tuxtuner-title {
+    font-size: 18px;
+    font-weight: 800;
+    letter-spacing: 3px;
+    color: #e94560;
+    text-shadow: 0 2px 12px rgba(233, 69, 96, 0.5);
+}
+
+.dashboard-tile {
+    background: rgba(255, 255, 255, 0.06);
+    border-radius: 12px;
+    padding: 8px 10px;
+}
+
+.dashboard-caption {
+    font-size: 9px;
+    font-weight: 700;
+    letter-spacing: 1px;
+    color: rgba(255, 255, 255, 0.6);
+}
+
+.dashboard-value {
+    font-size: 14px;
+    font-weight: 700;
+    color: white;
+}
+
+.tuxtuner-penguin {
+    font-size: 22px;
+    margin-right: 10px;
+}
+
+.status-value {
+    font-size: 14px;
+    font-weight: 700;
+    color: #e94560;
+}
+
+.status-value-green {
+    font-size: 14px;
+    font-weight: 700;
+    color: #4ade80;
+}
+
+.native-badge {
+    background: linear-gradient(135deg, #e94560 0%, #ff6b9d 100%);
+    color: white;
+    font-size: 9px;
+    font-weight: 700;
+    padding: 2px 6px;
+    border-radius: 6px;
+    margin-left: 6px;
+}
+"#;
+
+pub fn load_css() {
+    let provider = CssProvider::new();
+    provider.load_from_string(APP_CSS);
+
+    gtk4::style_context_add_provider_for_display(
+        &gtk4::gdk::Display::default().expect("Could not get default display"),
+        &provider,
+        gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION,
+    );
+}
+
+
+/// How often fan speeds, fan curves and the thermal guard are refreshed.
+const FAN_GUARD_INTERVAL_SECS: u32 = 5;
+/// How long a spinner must rest before an apply-on-change fires.
+const APPLY_DELAY_MS: u64 = 800;
+
+glib::wrapper! {
+    pub struct TuxTunerWindow(ObjectSubclass<imp::TuxTunerWindow>)
+        @extends adw::ApplicationWindow, gtk4::ApplicationWindow, gtk4::Window, gtk4::Widget,
+        @implements gio::ActionGroup, gio::ActionMap, gtk4::Accessible, gtk4::Buildable,
+            gtk4::ConstraintTarget, gtk4::Native, gtk4::Root, gtk4::ShortcutManager;
+}
+
+impl TuxTunerWindow {
+    pub fn new(app: &adw::Application) -> Self {
+        Self::with_backends(app, Backends::local())
+    }
+
+    /// A window that reads and drives the machine through `backends`.
+    pub fn with_backends(app: &adw::Application, backends: Backends) -> Self {
+        let window: Self = glib::Object::builder()
+            .property("application", app)
+            .build();
+        window.setup(backends);
+        window
+    }
+
+    /// Binds the pages to the shared state and loads it.
+    fn setup(&self, backends: Backends) {
+        let imp = self.imp();
+        if let Some(host) = remote::host() {
+            imp.header_bar
+                .set_title_widget(Some(&adw::WindowTitle::new("TuxTuner", &format!("Remote: {}", host))));
+        }
+
+        let state = &imp.state;
+        if let Some(reason) = privileges::read_only_reason() {
+            imp.read_only_banner.set_title(&format!("Read-only mode: {}", reason));
+            imp.read_only_banner.set_revealed(true);
+            state.set_read_only(reason);
+        }
+        state.load_config(&Config::load());
+        let toast_overlay = imp.toast_overlay.get();
+        state.connect_toast(move |message| show_toast(&toast_overlay, message));
+
+        self.bind_header();
+        imp.status.bind(state);
+        imp.cpu.bind(state);
+        imp.gpu.bind(state, &backends.gpu);
+        imp.display.bind(state, &backends);
+        imp.battery.bind(state);
+        imp.vendor.bind(state);
+        imp.devices.bind(state);
+        imp.tuning.bind(state);
+        imp.sleep.bind(state);
+        imp.monitoring.bind(state);
+        imp.advice.bind(state);
+        imp.automation.bind(state);
+        state.connect_reload(clone!(
+            #[weak(rename_to = win)] self,
+            #[strong] backends,
+            move || win.load_data(&backends)
+        ));
+
+        self.record_defaults();
+        self.load_data(&backends);
+        self.adapt_to_form_factor();
+        self.adapt_to_remote();
+        self.adapt_to_virtualization();
+    }
+
+    /// Applies the profile called `name`, as the profile combo does.
+    /// Returns whether such a profile exists.
+    pub fn request_profile(&self, name: &str) -> bool {
+        self.imp().state.request_profile(name)
+    }
+
+    /// Makes the header's profile switcher mirror the profile combo, and
+    /// flags the active profile once the machine no longer matches it.
+    fn bind_header(&self) {
+        let imp = self.imp();
+        // The switcher shares the combo's list and selection, so picking
+        // there applies through the combo's handler.
+        let combo = &imp.automation.profile_combo;
+        combo.bind_property("model", &*imp.profile_switcher, "model").sync_create().build();
+        combo
+            .bind_property("selected", &*imp.profile_switcher, "selected")
+            .bidirectional()
+            .sync_create()
+            .build();
+        combo.bind_property("sensitive", &*imp.profile_switcher, "sensitive").sync_create().build();
+
+        imp.state
+            .bind_property("profile-changes", &*imp.profile_modified_label, "visible")
+            .transform_to(|_, changed: Vec<String>| Some(!changed.is_empty()))
+            .sync_create()
+            .build();
+        imp.state
+            .bind_property("profile-changes", &*imp.profile_modified_label, "tooltip-text")
+            .transform_to(|_, changed: Vec<String>| Some(format!("Changed since applied: {}", changed.join(", "))))
+            .build();
+    }
+
+    /// Hides battery and lid features on desktops. Runs after the pages
+    /// are bound so nothing shows them again.
+    fn adapt_to_form_factor(&self) {
+        if !platform::form_factor().is_desktop() {
+            return;
+        }
+        let imp = self.imp();
+
+        let battery_rows: [&gtk4::Widget; 4] = [
+            imp.display.battery_refresh_row.upcast_ref(),
+            imp.vendor.conservation_row.upcast_ref(),
+            imp.vendor.rapid_charge_row.upcast_ref(),
+            imp.vendor.charge_start_spin.upcast_ref(),
+        ];
+        for widget in battery_rows {
+            widget.set_visible(false);
+        }
+        if let Some(tile) = imp.status.dashboard.battery.parent() {
+            tile.set_visible(false);
+        }
+
+        for widget in [
+            imp.battery.charge_spin.upcast_ref::<gtk4::Widget>(),
+            imp.monitoring.history_chart.upcast_ref(),
+        ] {
+            if let Some(group) = widget.ancestor(adw::PreferencesGroup::static_type()) {
+                group.set_visible(false);
+            }
+        }
+
+        imp.devices.wakeup_acpi_row.set_subtitle("Power button, controllers");
+        if let Some(group) = imp
+            .devices
+            .wakeup_acpi_row
+            .ancestor(adw::PreferencesGroup::static_type())
+            .and_downcast::<adw::PreferencesGroup>()
+        {
+            group.set_description(Some("Devices allowed to wake the computer from sleep."));
+        }
+    }
+
+    /// Hides the groups that still read this machine's sysfs or desktop
+    /// session directly, so a remote session never mixes the two.
+    fn adapt_to_remote(&self) {
+        if remote::host().is_none() {
+            return;
+        }
+        let imp = self.imp();
+
+        // Adaptive parking would size the remote CPU by this machine's load.
+        let local_only: [&gtk4::Widget; 12] = [
+            imp.cpu.adaptive_row.upcast_ref(),
+            imp.cpu.undervolt_core_spin.upcast_ref(),
+            imp.gpu.gpu_fan_row.upcast_ref(),
+            imp.display.night_light_row.upcast_ref(),
+            imp.display.lighting_combo.upcast_ref(),
+            imp.vendor.conservation_row.upcast_ref(),
+            imp.vendor.fan_combo.upcast_ref(),
+            imp.devices.wakeup_acpi_row.upcast_ref(),
+            imp.devices.device_usb_row.upcast_ref(),
+            imp.sleep.hibernate_row.upcast_ref(),
+            imp.monitoring.history_chart.upcast_ref(),
+            imp.monitoring.process_list.upcast_ref(),
+        ];
+        for widget in local_only {
+            if let Some(group) = widget.ancestor(adw::PreferencesGroup::static_type()) {
+                group.set_visible(false);
+            }
+        }
+        imp.vendor.firmware_group.set_visible(false);
+    }
+
+    /// Disables the groups that drive real hardware inside a VM or
+    /// container, saying why. A VM keeps the CPU, display and GPU controls,
+    /// which work with GPU passthrough; a container can't write sysfs, so
+    /// it loses those too.
+    fn adapt_to_virtualization(&self) {
+        let Some(virtualization) = virt::current() else {
+            return;
+        };
+        let imp = self.imp();
+
+        let mut hardware: Vec<&gtk4::Widget> = vec![
+            imp.cpu.undervolt_core_spin.upcast_ref(),
+            imp.cpu.tdp_apply_btn.upcast_ref(),
+            imp.gpu.gpu_fan_row.upcast_ref(),
+            imp.display.lighting_combo.upcast_ref(),
+            imp.vendor.conservation_row.upcast_ref(),
+            imp.vendor.fan_combo.upcast_ref(),
+            imp.vendor.firmware_group.upcast_ref(),
+            imp.devices.wakeup_acpi_row.upcast_ref(),
+            imp.devices.device_usb_row.upcast_ref(),
+            imp.sleep.hibernate_row.upcast_ref(),
+        ];
+        let reason = match virtualization.kind {
+            VirtKind::Vm => format!(
+                "Not available in a {}: the hypervisor doesn't pass this hardware through.",
+                virtualization.label()
+            ),
+            VirtKind::Container => {
+                hardware.extend([
+                    imp.cpu.cpu_spin.upcast_ref::<gtk4::Widget>(),
+                    imp.cpu.adaptive_row.upcast_ref(),
+                    imp.gpu.gpu_combo.upcast_ref(),
+                    imp.battery.charge_spin.upcast_ref(),
+                    imp.tuning.tunables_group.upcast_ref(),
+                    imp.tuning.dirty_ratio_spin.upcast_ref(),
+                ]);
+                format!(
+                    "Not available in a {}: the host owns the hardware and its settings.",
+                    virtualization.label()
+                )
+            }
+        };
+
+        for widget in hardware {
+            let group = if widget.is::<adw::PreferencesGroup>() {
+                Some(widget.clone())
+            } else {
+                widget.ancestor(adw::PreferencesGroup::static_type())
+            };
+            if let Some(group) = group.and_downcast::<adw::PreferencesGroup>() {
+                group.set_sensitive(false);
+                group.set_description(Some(&reason));
+            }
+        }
+    }
+
+    fn load_data(&self, backends: &Backends) {
+        // Render the last known values straight away so startup doesn't sit
+        // on placeholders while the probes run.
+        if let Some(cached) = SystemInfo::cached() {
+            self.show_system_info(cached, true);
+        }
+
+        let win = self.clone();
+        let (gpu, display) = (backends.gpu.clone(), backends.display.clone());
+        glib::spawn_future_local(async move {
+            let info = gio::spawn_blocking(move || {
+                let info = SystemInfo::fetch_with(gpu.as_ref(), display.as_ref());
+                info.save_cache();
+                info
+            })
+            .await
+            .unwrap_or_default();
+            win.show_system_info(info, false);
+        });
+    }
+
+    /// Hands `info` to the pages. A `stale` snapshot comes from the
+    /// startup cache: it is shown dimmed and every control stays
+    /// insensitive until fresh data replaces it.
+    fn show_system_info(&self, info: SystemInfo, stale: bool) {
+        let imp = self.imp();
+        imp.status.show_stale(stale);
+        imp.state.show_system_info(&info);
+        imp.cpu.show_system_info(&imp.state, stale);
+        imp.gpu.show_system_info(&imp.state, &info, stale);
+        imp.display.show_system_info(&info, stale);
+
+        if stale {
+            return;
+        }
+
+        let hung: Vec<&str> = [
+            (info.gpu_capability, info.gpu_tool.as_str()),
+            (info.display_capability, "hyprctl"),
+        ]
+        .into_iter()
+        .filter(|(capability, _)| *capability == Capability::TimedOut)
+        .map(|(_, tool)| tool)
+        .collect();
+        if !hung.is_empty() {
+            imp.state
+                .toast(&format!("{} timed out; related controls are disabled", hung.join(" and ")));
+        }
+    }
+}
+
+/// Disables `widgets` when the session can't authorize pkexec, saying why
+/// instead of failing at click time. Pages call it after anything that
+/// turns them back on.
+fn restrict(state: &AppState, widgets: &[&gtk4::Widget]) {
+    let Some(reason) = state.read_only_reason() else {
+        return;
+    };
+    for widget in widgets {
+        widget.set_sensitive(false);
+        widget.set_tooltip_text(Some(&reason));
+    }
+}
+
+/// Clicks `button` once `spin` has rested for a moment, when the
+/// "apply on change" preference is on. `is_current` filters out values
+/// that are already in effect, such as readings shown after a refresh.
+fn apply_on_change(
+    state: &AppState,
+    spin: &adw::SpinRow,
+    button: &Button,
+    is_current: impl Fn(f64) -> bool + 'static,
+) {
+    let pending: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
+    let button = button.clone();
+    spin.connect_value_notify(clone!(
+        #[weak] state,
+        move |spin| {
+            if let Some(source) = pending.borrow_mut().take() {
+                source.remove();
+            }
+            if !state.apply_on_change() || is_current(spin.value()) {
+                return;
+            }
+            let button = button.clone();
+            let fired = pending.clone();
+            let source = glib::timeout_add_local_once(Duration::from_millis(APPLY_DELAY_MS), move || {
+                fired.borrow_mut().take();
+                if button.is_sensitive() {
+                    button.emit_clicked();
+                }
+            });
+            *pending.borrow_mut() = Some(source);
+        }
+    ));
+}
+
+/// Runs `apply` straight away, or once the user agrees when `ask` is
+/// set. `cancel` runs if they don't. The dialog sits over `widget`'s
+/// window.
+fn confirm_then(
+    widget: &impl IsA<gtk4::Widget>,
+    ask: bool,
+    heading: &str,
+    body: &str,
+    apply: impl FnOnce() + 'static,
+    cancel: impl FnOnce() + 'static,
+) {
+    if !ask {
+        apply();
+        return;
+    }
+
+    let dialog = adw::MessageDialog::builder()
+        .heading(heading)
+        .body(body)
+        .build();
+    dialog.set_transient_for(window_of(widget).as_ref());
+    dialog.add_response("cancel", "Cancel");
+    dialog.add_response("apply", "Apply");
+    dialog.set_response_appearance("apply", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("apply"));
+    dialog.set_close_response("cancel");
+
+    let actions = RefCell::new(Some((apply, cancel)));
+    dialog.connect_response(None, move |_, response| {
+        if let Some((apply, cancel)) = actions.borrow_mut().take() {
+            if response == "apply" {
+                apply();
+            } else {
+                cancel();
+            }
+        }
+    });
+    dialog.present();
+}
+
+/// The window `widget` is shown in, for parenting dialogs.
+fn window_of(widget: &impl IsA<gtk4::Widget>) -> Option<gtk4::Window> {
+    widget.root().and_downcast()
+}
+
+/// Shows `property` of `state` on `row` and applies the user's flips with
+/// `apply`, off the main thread. `done` gets what a successful `apply`
+/// returned; a failed one is shown as `failed` and the row flips back.
+fn bind_switch<T: Send + 'static>(
+    state: &AppState,
+    property: &'static str,
+    row: &adw::SwitchRow,
+    failed: &str,
+    apply: impl Fn(bool) -> Result<T, String> + Clone + Send + 'static,
+    done: impl Fn(bool, T) + 'static,
+) {
+    state.bind_property(property, row, "active").sync_create().build();
+    connect_switch(
+        row,
+        state,
+        failed,
+        clone!(
+            #[weak] state,
+            #[upgrade_or] false,
+            move || state.property(property)
+        ),
+        apply,
+        clone!(
+            #[weak] state,
+            move |on, value| {
+                state.set_property(property, on);
+                done(on, value);
+            }
+        ),
+    );
+}
+
+/// Like `bind_switch`, for rows built per device: `applied` says what
+/// the device is set to, and `done` must keep it in step.
+fn connect_switch<T: Send + 'static>(
+    row: &adw::SwitchRow,
+    state: &AppState,
+    failed: &str,
+    applied: impl Fn() -> bool + 'static,
+    apply: impl Fn(bool) -> Result<T, String> + Clone + Send + 'static,
+    done: impl Fn(bool, T) + 'static,
+) {
+    let failed = failed.to_string();
+    let applied = Rc::new(applied);
+    let done = Rc::new(done);
+    row.connect_active_notify(clone!(
+        #[strong] state,
+        move |row| {
+            let on = row.is_active();
+            if on == applied() {
+                return;
+            }
+            row.set_sensitive(false);
+
+            let apply = apply.clone();
+            let state = state.clone();
+            let row = row.clone();
+            let failed = failed.clone();
+            let applied = applied.clone();
+            let done = done.clone();
+            glib::spawn_future_local(async move {
+                let result = gio::spawn_blocking(move || apply(on)).await;
+
+                row.set_sensitive(true);
+
+                match result {
+                    Ok(Ok(value)) => done(on, value),
+                    result => {
+                        state.toast(&match result {
+                            Ok(Err(e)) => format!("{}: {}", failed, e),
+                            _ => failed,
+                        });
+                        row.set_active(applied());
+                    }
+                }
+            });
+        }
+    ));
+}
+
+/// Shows `property` of `state` on `combo`, whose entries are `choices`
+/// by name, and applies the user's picks with `apply` off the main
+/// thread. A failed pick is shown as `failed` and undone.
+fn bind_choice<C: Clone + Send + 'static>(
+    state: &AppState,
+    property: &'static str,
+    combo: &adw::ComboRow,
+    choices: Vec<(String, C)>,
+    failed: &str,
+    apply: impl Fn(C) -> Result<(), String> + Clone + Send + 'static,
+) {
+    let names: Vec<String> = choices.iter().map(|(name, _)| name.clone()).collect();
+    state
+        .bind_property(property, combo, "selected")
+        .transform_to(move |_, name: String| names.iter().position(|n| *n == name).map(|i| i as u32))
+        .sync_create()
+        .build();
+
+    let failed = failed.to_string();
+    combo.connect_selected_notify(clone!(
+        #[strong] state,
+        move |combo| {
+            let Some((name, choice)) = choices.get(combo.selected() as usize).cloned() else {
+                return;
+            };
+            if name == state.property::<String>(property) {
+                return;
+            }
+            combo.set_sensitive(false);
+
+            let apply = apply.clone();
+            let state = state.clone();
+            let combo = combo.clone();
+            let failed = failed.clone();
+            glib::spawn_future_local(async move {
+                let result = gio::spawn_blocking(move || apply(choice)).await;
+
+                combo.set_sensitive(true);
+
+                match result {
+                    Ok(Ok(())) => state.set_property(property, name),
+                    result => {
+                        state.toast(&match result {
+                            Ok(Err(e)) => format!("{}: {}", failed, e),
+                            _ => failed,
+                        });
+                        state.notify(property);
+                    }
+                }
+            });
+        }
+    ));
+}
+
+fn show_toast(overlay: &adw::ToastOverlay, message: &str) {
+    let toast = adw::Toast::new(message);
+    overlay.add_toast(toast);
+}
+
+/// Drives a window against fake backends. Needs a display, so it only
+/// runs with `--features gtk-tests` (under `xvfb-run` on a headless
+/// machine).
+#[cfg(all(test, feature = "gtk-tests"))]
+mod tests {
+    use super::*;
+    use crate::gpu::NoGpu;
+    use crate::sysfs::FakeSysfs;
+    use crate::system_info::FakeDisplay;
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    /// Runs the main loop until `done` holds.
+    fn wait_until(what: &str, done: impl Fn() -> bool) {
+        let context = glib::MainContext::default();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !done() {
+            assert!(Instant::now() < deadline, "timed out waiting for {}", what);
+            context.iteration(false);
+        }
+    }
+
+    fn window(display: Arc<FakeDisplay>) -> TuxTunerWindow {
+        // Keep the user's config, cache and compositor out of it.
+        let home = std::env::temp_dir().join(format!("tuxtuner-gtk-test-{}", std::process::id()));
+        std::env::set_var("XDG_CONFIG_HOME", home.join("config"));
+        std::env::set_var("XDG_STATE_HOME", home.join("state"));
+        std::env::set_var("XDG_CURRENT_DESKTOP", "Hyprland");
+        std::env::remove_var("HYPRLAND_INSTANCE_SIGNATURE");
+
+        let app = adw::Application::new(Some("io.github.xavrir.TuxTunerTest"), gio::ApplicationFlags::NON_UNIQUE);
+        app.register(gio::Cancellable::NONE).expect("application registers");
+        let backends = Backends {
+            sysfs: Arc::new(FakeSysfs::default()),
+            gpu: Arc::new(NoGpu),
+            display,
+        };
+        TuxTunerWindow::with_backends(&app, backends)
+    }
+
+    #[test]
+    fn selecting_a_rate_applies_it_and_updates_the_status() {
+        let display = Arc::new(FakeDisplay::new(false));
+        let win = window(display.clone());
+        let imp = win.imp();
+        let state = &imp.state;
+        wait_until("the first probe", || state.current_hz() == "60Hz");
+
+        assert!(imp.display.hz_combo.is_visible());
+        assert!(imp.display.hz_combo.is_sensitive());
+        assert!(!imp.status.native_badge.is_visible());
+        assert!(!imp.gpu.gpu_combo.is_sensitive());
+        assert_eq!(
+            imp.gpu.gpu_combo.subtitle().as_deref(),
+            Some("No GPU switching tool found")
+        );
+
+        let native = state
+            .refresh_rates()
+            .iter()
+            .position(|label| label == "120Hz (Native)")
+            .expect("120Hz is offered as native");
+        imp.display.hz_combo.set_selected(native as u32);
+        wait_until("the new rate", || state.current_hz() == "120Hz");
+
+        assert_eq!(display.refresh_hz(), 120);
+        assert!(state.at_native_hz());
+        assert!(imp.status.native_badge.is_visible());
+        assert!(imp.display.hz_combo.is_sensitive());
+    }
+}
@@ -0,0 +1,303 @@
+use crate::battery;
+use crate::battery_history::{self, Sample};
+use crate::chart::{self, TimeSeries};
+use crate::processes::{self, ProcessSampler, ProcessUsage};
+use crate::thermal::{self, NvmeThrottleWatch};
+use gtk4::glib::{self, clone};
+use gtk4::prelude::*;
+use gtk4::{gio, Align, Button, Label};
+use libadwaita as adw;
+use adw::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+use super::AppState;
+
+/// How often NVMe temperatures are read.
+const NVME_INTERVAL_SECS: u32 = 10;
+
+/// The battery history chart, SSD temperatures and the busiest processes.
+#[derive(Clone)]
+pub(super) struct MonitoringPage {
+    groups: [adw::PreferencesGroup; 2],
+    pub(super) history_chart: gtk4::DrawingArea,
+    nvme_list: gtk4::ListBox,
+    pub(super) process_list: gtk4::ListBox,
+}
+
+impl MonitoringPage {
+    pub(super) fn build() -> Self {
+        let (history_group, history_chart) = Self::build_battery_history_group();
+        let (monitoring_group, nvme_list, process_list) = Self::build_monitoring_group();
+
+        Self {
+            groups: [history_group, monitoring_group],
+            history_chart,
+            nvme_list,
+            process_list,
+        }
+    }
+
+    pub(super) fn groups(&self) -> &[adw::PreferencesGroup] {
+        &self.groups
+    }
+
+    pub(super) fn bind(&self, state: &AppState) {
+        self.setup_battery_history();
+        self.setup_nvme_monitor(state);
+        self.setup_process_monitor(state);
+    }
+
+    fn build_battery_history_group() -> (adw::PreferencesGroup, gtk4::DrawingArea) {
+        let history_group = adw::PreferencesGroup::builder()
+            .title("Battery History")
+            .description("Discharge rate over the last hours, with setting changes.")
+            .build();
+
+        let history_chart = gtk4::DrawingArea::builder()
+            .content_height(160)
+            .hexpand(true)
+            .css_classes(["card"])
+            .build();
+        history_group.add(&history_chart);
+
+        (history_group, history_chart)
+    }
+
+    fn build_monitoring_group() -> (adw::PreferencesGroup, gtk4::ListBox, gtk4::ListBox) {
+        let monitoring_group = adw::PreferencesGroup::builder()
+            .title("Monitoring")
+            .description("SSD temperatures, and the processes using the most CPU right now.")
+            .build();
+
+        let nvme_list = gtk4::ListBox::builder()
+            .selection_mode(gtk4::SelectionMode::None)
+            .css_classes(["boxed-list"])
+            .margin_bottom(12)
+            .visible(false)
+            .build();
+        monitoring_group.add(&nvme_list);
+
+        let process_list = gtk4::ListBox::builder()
+            .selection_mode(gtk4::SelectionMode::None)
+            .css_classes(["boxed-list"])
+            .build();
+        process_list.set_placeholder(Some(
+            &Label::builder()
+                .label("Sampling...")
+                .margin_top(12)
+                .margin_bottom(12)
+                .css_classes(["dim-label"])
+                .build(),
+        ));
+        monitoring_group.add(&process_list);
+
+        (monitoring_group, nvme_list, process_list)
+    }
+
+    fn setup_battery_history(&self) {
+        if battery::power_draw_mw().is_none() {
+            if let Some(group) = self.history_chart.ancestor(adw::PreferencesGroup::static_type()) {
+                group.set_visible(false);
+            }
+            return;
+        }
+
+        const HISTORY_HOURS: i64 = 6;
+        let samples: Rc<RefCell<Vec<Sample>>> = Rc::new(RefCell::new(Vec::new()));
+        let events: Rc<RefCell<Vec<(i64, String)>>> = Rc::new(RefCell::new(Vec::new()));
+
+        let reload = clone!(
+            #[strong] samples,
+            #[strong] events,
+            #[strong(rename_to = chart)] self.history_chart,
+            move || {
+                let since = battery_history::unix_now() - HISTORY_HOURS * 3600;
+                *samples.borrow_mut() = battery_history::load_samples(since);
+                *events.borrow_mut() = battery_history::load_events(since)
+                    .into_iter()
+                    .map(|event| (event.time, event.label))
+                    .collect();
+                chart.queue_draw();
+            }
+        );
+
+        self.history_chart.set_draw_func(clone!(
+            #[strong] samples,
+            #[strong] events,
+            move |_, cr, width, height| {
+                let end = battery_history::unix_now();
+                let points: Vec<(i64, f64)> = samples
+                    .borrow()
+                    .iter()
+                    .map(|s| (s.time, s.power_mw.max(0) as f64 / 1000.0))
+                    .collect();
+                chart::draw_time_series(
+                    cr,
+                    width as f64,
+                    height as f64,
+                    &TimeSeries {
+                        start: end - HISTORY_HOURS * 3600,
+                        end,
+                        points: &points,
+                        events: &events.borrow(),
+                        unit: "W",
+                        max_gap: battery_history::SAMPLE_INTERVAL_SECS as i64 * 3,
+                    },
+                );
+            }
+        ));
+
+        reload();
+
+        glib::timeout_add_seconds_local(battery_history::SAMPLE_INTERVAL_SECS, move || {
+            if let Some(power_mw) = battery::power_draw_mw() {
+                let _ = battery_history::append_sample(power_mw);
+            }
+            reload();
+            glib::ControlFlow::Continue
+        });
+    }
+
+    fn setup_process_monitor(&self, state: &AppState) {
+        let page = self.clone();
+        let state = state.clone();
+        let mut sampler = ProcessSampler::default();
+        sampler.sample(processes::TOP_COUNT);
+
+        glib::timeout_add_seconds_local(processes::SAMPLE_INTERVAL_SECS, move || {
+            // Keep sampling so the first visible refresh has fresh deltas,
+            // but only rebuild rows someone can see.
+            let top = sampler.sample(processes::TOP_COUNT);
+            if page.process_list.is_mapped() {
+                page.show_processes(&state, &top);
+            }
+            glib::ControlFlow::Continue
+        });
+    }
+
+    fn setup_nvme_monitor(&self, state: &AppState) {
+        let watch = Rc::new(RefCell::new(NvmeThrottleWatch::default()));
+        self.refresh_nvme(state, &watch);
+
+        let page = self.clone();
+        let state = state.clone();
+        glib::timeout_add_seconds_local(NVME_INTERVAL_SECS, move || {
+            page.refresh_nvme(&state, &watch);
+            glib::ControlFlow::Continue
+        });
+    }
+
+    /// Lists NVMe temperatures against their thresholds and warns once
+    /// when a drive keeps throttling.
+    fn refresh_nvme(&self, state: &AppState, watch: &Rc<RefCell<NvmeThrottleWatch>>) {
+        let page = self.clone();
+        let state = state.clone();
+        let watch = watch.clone();
+        glib::spawn_future_local(async move {
+            let Ok(drives) = gio::spawn_blocking(thermal::nvme_temperatures).await else {
+                return;
+            };
+
+            for drive in watch.borrow_mut().update(&drives) {
+                let body = format!(
+                    "{} is slowing down to cool off. Sustained transfers will be slower until it does.",
+                    drive.model
+                );
+                let notification = gio::Notification::new(&format!("SSD throttling at {:.0}°C", drive.celsius));
+                notification.set_body(Some(&body));
+                if let Some(app) = gio::Application::default() {
+                    app.send_notification(Some("nvme-throttle"), &notification);
+                }
+                state.toast(&body);
+            }
+
+            page.nvme_list.set_visible(!drives.is_empty());
+            if !page.nvme_list.is_mapped() {
+                return;
+            }
+            page.nvme_list.remove_all();
+            for drive in &drives {
+                let thresholds = match (drive.warning, drive.critical) {
+                    (Some(warning), Some(critical)) => {
+                        format!("Throttles at {:.0}°C, critical at {:.0}°C", warning, critical)
+                    }
+                    (Some(warning), None) => format!("Throttles at {:.0}°C", warning),
+                    (None, Some(critical)) => format!("Critical at {:.0}°C", critical),
+                    (None, None) => "No thresholds reported".to_string(),
+                };
+                let row = adw::ActionRow::builder()
+                    .title(glib::markup_escape_text(&drive.model))
+                    .subtitle(format!("{} · {}", drive.name, thresholds))
+                    .build();
+
+                let temperature = Label::builder()
+                    .label(format!("{:.0}°C", drive.celsius))
+                    .css_classes(["status-value"])
+                    .build();
+                if drive.hot() {
+                    temperature.add_css_class("warning");
+                }
+                row.add_suffix(&temperature);
+                page.nvme_list.append(&row);
+            }
+        });
+    }
+
+    fn show_processes(&self, state: &AppState, top: &[ProcessUsage]) {
+        self.process_list.remove_all();
+
+        for process in top {
+            let row = adw::ActionRow::builder()
+                .title(glib::markup_escape_text(&process.name))
+                .subtitle(format!("PID {} · nice {}", process.pid, process.nice))
+                .build();
+
+            let usage = Label::builder()
+                .label(format!("{:.1}%", process.cpu_percent))
+                .css_classes(["status-value"])
+                .build();
+            row.add_suffix(&usage);
+
+            let adjustments = [
+                ("go-down-symbolic", "Lower priority", processes::NICE_STEP),
+                ("go-up-symbolic", "Raise priority", -processes::NICE_STEP),
+            ];
+            for (icon, tooltip, step) in adjustments {
+                let target = (process.nice + step).clamp(processes::MIN_NICE, processes::MAX_NICE);
+                let button = Button::builder()
+                    .icon_name(icon)
+                    .tooltip_text(tooltip)
+                    .valign(Align::Center)
+                    .css_classes(["flat"])
+                    .sensitive(target != process.nice && state.read_only_reason().is_none())
+                    .build();
+
+                let pid = process.pid;
+                let name = process.name.clone();
+                button.connect_clicked(clone!(
+                    #[strong] state,
+                    move |button| {
+                        button.set_sensitive(false);
+                        let state = state.clone();
+                        let name = name.clone();
+
+                        glib::spawn_future_local(async move {
+                            let result = gio::spawn_blocking(move || {
+                                processes::apply_nice(pid, target)
+                            }).await;
+
+                            match result {
+                                Ok(Ok(())) => state.toast(&format!("{} nice set to {}", name, target)),
+                                Ok(Err(e)) => state.toast(&format!("Priority change failed: {}", e)),
+                                Err(_) => state.toast("Failed to change priority"),
+                            }
+                        });
+                    }
+                ));
+                row.add_suffix(&button);
+            }
+
+            self.process_list.append(&row);
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use gtk4::glib;
+use gtk4::prelude::*;
+use libadwaita as adw;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const PULSE_INTERVAL_MS: u64 = 100;
+
+/// The page that covers the window while an operation that takes seconds
+/// runs, shared by the pages that start one.
+#[derive(Clone)]
+pub(super) struct Progress {
+    /// Added to the window's content stack next to the main page.
+    pub(super) page: adw::StatusPage,
+    bar: gtk4::ProgressBar,
+    cancel_btn: gtk4::Button,
+    /// Advances `bar` while the page is shown.
+    timer: Rc<RefCell<Option<glib::SourceId>>>,
+    /// Set by the Cancel button for operations that can stop early.
+    cancel: Rc<RefCell<Option<Arc<AtomicBool>>>>,
+}
+
+impl Progress {
+    pub(super) fn build() -> Self {
+        let bar = gtk4::ProgressBar::new();
+        let cancel_btn = gtk4::Button::builder()
+            .label("Cancel")
+            .halign(gtk4::Align::Center)
+            .css_classes(["pill"])
+            .build();
+
+        let content = gtk4::Box::builder()
+            .orientation(gtk4::Orientation::Vertical)
+            .spacing(24)
+            .halign(gtk4::Align::Center)
+            .width_request(240)
+            .build();
+        content.append(&bar);
+        content.append(&cancel_btn);
+        let page = adw::StatusPage::builder().child(&content).build();
+
+        let progress = Self {
+            page,
+            bar,
+            cancel_btn,
+            timer: Rc::new(RefCell::new(None)),
+            cancel: Rc::new(RefCell::new(None)),
+        };
+
+        let cancel = progress.cancel.clone();
+        progress.cancel_btn.connect_clicked(move |btn| {
+            if let Some(cancel) = cancel.borrow().as_ref() {
+                cancel.store(true, Ordering::Relaxed);
+                btn.set_sensitive(false);
+                btn.set_label("Cancelling...");
+            }
+        });
+        progress
+    }
+
+    /// The stack the page was added to.
+    fn stack(&self) -> Option<gtk4::Stack> {
+        self.page.parent().and_downcast()
+    }
+
+    /// Covers the page while an operation that takes seconds runs. With
+    /// an `expected` duration the bar fills over that time, otherwise it
+    /// pulses. Passing `cancel` shows a Cancel button that sets the flag;
+    /// the operation itself decides how soon it stops.
+    pub(super) fn show(
+        &self,
+        title: &str,
+        description: &str,
+        expected: Option<Duration>,
+        cancel: Option<Arc<AtomicBool>>,
+    ) {
+        self.page.set_title(title);
+        self.page.set_description(Some(description));
+        self.bar.set_fraction(0.0);
+        self.cancel_btn.set_visible(cancel.is_some());
+        self.cancel_btn.set_sensitive(true);
+        self.cancel_btn.set_label("Cancel");
+        self.cancel.replace(cancel);
+
+        let bar = self.bar.clone();
+        let started = Instant::now();
+        let timer = glib::timeout_add_local(Duration::from_millis(PULSE_INTERVAL_MS), move || {
+            match expected {
+                Some(total) => {
+                    let done = started.elapsed().as_secs_f64() / total.as_secs_f64().max(1.0);
+                    bar.set_fraction(done.min(1.0));
+                }
+                None => bar.pulse(),
+            }
+            glib::ControlFlow::Continue
+        });
+        if let Some(previous) = self.timer.replace(Some(timer)) {
+            previous.remove();
+        }
+
+        if let Some(stack) = self.stack() {
+            stack.set_visible_child(&self.page);
+        }
+    }
+
+    pub(super) fn hide(&self) {
+        if let Some(timer) = self.timer.take() {
+            timer.remove();
+        }
+        self.cancel.replace(None);
+        if let Some(stack) = self.stack() {
+            stack.set_visible_child_name("main");
+        }
+    }
+}
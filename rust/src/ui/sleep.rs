@@ -0,0 +1,334 @@
+use crate::battery_history;
+use crate::config::Config;
+use crate::hibernate::{self, HibernateStatus};
+use crate::schedule::{self, SleepAction};
+use gtk4::glib::{self, clone};
+use gtk4::prelude::*;
+use gtk4::{gio, Align, Button, StringList};
+use libadwaita as adw;
+use adw::prelude::*;
+use super::progress::Progress;
+use super::{window_of, AppState};
+
+/// Hibernation setup and the nightly sleep schedule.
+#[derive(Clone)]
+pub(super) struct SleepPage {
+    groups: [adw::PreferencesGroup; 2],
+    pub(super) hibernate_row: adw::ActionRow,
+    hibernate_btn: Button,
+    sleep_schedule_row: adw::SwitchRow,
+    sleep_action_combo: adw::ComboRow,
+    sleep_at_entry: adw::EntryRow,
+    wake_at_entry: adw::EntryRow,
+    progress: Progress,
+}
+
+impl SleepPage {
+    pub(super) fn build(progress: &Progress) -> Self {
+        let (hibernate_group, hibernate_row, hibernate_btn) = Self::build_hibernate_group();
+        let (sleep_group, sleep_schedule_row, sleep_action_combo, sleep_at_entry, wake_at_entry) =
+            Self::build_sleep_schedule_group();
+
+        Self {
+            groups: [hibernate_group, sleep_group],
+            hibernate_row,
+            hibernate_btn,
+            sleep_schedule_row,
+            sleep_action_combo,
+            sleep_at_entry,
+            wake_at_entry,
+            progress: progress.clone(),
+        }
+    }
+
+    pub(super) fn groups(&self) -> &[adw::PreferencesGroup] {
+        &self.groups
+    }
+
+    pub(super) fn bind(&self, state: &AppState) {
+        self.setup_hibernate(state);
+        self.setup_sleep_schedule(state);
+    }
+
+    fn build_hibernate_group() -> (adw::PreferencesGroup, adw::ActionRow, Button) {
+        let hibernate_group = adw::PreferencesGroup::builder()
+            .title("Hibernation")
+            .description("Save memory to disk so a sleeping laptop stops draining its battery.")
+            .build();
+
+        let hibernate_row = adw::ActionRow::builder()
+            .title("Hibernation")
+            .subtitle("Checking...")
+            .build();
+
+        let hibernate_btn = Button::builder()
+            .label("Set Up...")
+            .valign(Align::Center)
+            .build();
+        hibernate_row.add_suffix(&hibernate_btn);
+        hibernate_group.add(&hibernate_row);
+
+        (hibernate_group, hibernate_row, hibernate_btn)
+    }
+
+    fn build_sleep_schedule_group() -> (
+        adw::PreferencesGroup,
+        adw::SwitchRow,
+        adw::ComboRow,
+        adw::EntryRow,
+        adw::EntryRow,
+    ) {
+        let sleep_group = adw::PreferencesGroup::builder()
+            .title("Scheduled Sleep")
+            .description("Sleep at night and wake up ready, using the RTC alarm.")
+            .build();
+
+        let sleep_schedule_row = adw::SwitchRow::builder()
+            .title("Sleep on Schedule")
+            .subtitle("TuxTuner must be running at the sleep time")
+            .build();
+        sleep_group.add(&sleep_schedule_row);
+
+        let labels: Vec<&str> = SleepAction::ALL.iter().map(|action| action.label()).collect();
+        let sleep_action_combo = adw::ComboRow::builder()
+            .title("Action")
+            .model(&StringList::new(&labels))
+            .build();
+        sleep_group.add(&sleep_action_combo);
+
+        let sleep_at_entry = adw::EntryRow::builder()
+            .title("Sleep At (HH:MM)")
+            .show_apply_button(true)
+            .build();
+        sleep_group.add(&sleep_at_entry);
+
+        let wake_at_entry = adw::EntryRow::builder()
+            .title("Wake At (HH:MM)")
+            .show_apply_button(true)
+            .build();
+        sleep_group.add(&wake_at_entry);
+
+        (sleep_group, sleep_schedule_row, sleep_action_combo, sleep_at_entry, wake_at_entry)
+    }
+
+    fn setup_hibernate(&self, state: &AppState) {
+        self.refresh_hibernate_status();
+
+        self.hibernate_btn.connect_clicked(clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            move |_| page.show_hibernate_assistant(&state)
+        ));
+    }
+
+    fn refresh_hibernate_status(&self) {
+        let status = HibernateStatus::detect();
+        let subtitle = if status.is_ready() {
+            if status.hybrid_sleep { "Ready, including hybrid sleep" } else { "Ready" }
+        } else if status.can_configure() {
+            "Not configured"
+        } else {
+            "Unavailable on this system"
+        };
+        self.hibernate_row.set_subtitle(subtitle);
+    }
+
+    /// Walks through each hibernation requirement and offers to configure
+    /// the resume device once nothing else stands in the way.
+    fn show_hibernate_assistant(&self, state: &AppState) {
+        let status = HibernateStatus::detect();
+        let mark = |ok: bool| if ok { "✓" } else { "✗" };
+
+        let swap_line = match &status.swap {
+            Some(swap) => format!(
+                "{} Swap: {} ({:.1} GiB, RAM {:.1} GiB)",
+                mark(status.swap_sufficient()),
+                swap.path,
+                hibernate::gib(swap.size_bytes),
+                hibernate::gib(status.ram_bytes),
+            ),
+            None => format!("{} Swap: no disk swap area", mark(false)),
+        };
+        let mut lines = vec![
+            format!("{} Kernel support", mark(status.kernel_support)),
+            format!(
+                "{} Secure Boot lockdown: {}",
+                mark(status.lockdown.is_none()),
+                status.lockdown.as_deref().unwrap_or("none"),
+            ),
+            swap_line,
+            format!("{} Resume device on kernel command line", mark(status.resume_configured)),
+        ];
+        if status.kernel_support {
+            lines.push(format!("{} Hybrid sleep", mark(status.hybrid_sleep)));
+        }
+
+        let body = if status.is_ready() {
+            format!("{}\n\nHibernation is ready to use.", lines.join("\n"))
+        } else if status.can_configure() {
+            format!(
+                "{}\n\nTuxTuner can set the resume device and rebuild the boot files. This takes a minute and applies after a reboot.",
+                lines.join("\n")
+            )
+        } else {
+            format!("{}\n\n{}.", lines.join("\n"), status.blockers().join(".\n"))
+        };
+
+        let dialog = adw::MessageDialog::builder()
+            .heading("Hibernation Setup")
+            .body(body)
+            .build();
+        dialog.set_transient_for(window_of(&self.hibernate_btn).as_ref());
+
+        dialog.add_response("close", "Close");
+        dialog.set_close_response("close");
+        if status.can_configure() && state.read_only_reason().is_none() {
+            dialog.add_response("configure", "Configure");
+            dialog.set_response_appearance("configure", adw::ResponseAppearance::Suggested);
+            dialog.set_default_response(Some("configure"));
+        }
+
+        let page = self.clone();
+        let state = state.clone();
+        dialog.connect_response(None, move |_, response| {
+            if response != "configure" {
+                return;
+            }
+            let Some(swap) = status.swap.clone() else {
+                return;
+            };
+
+            page.hibernate_btn.set_sensitive(false);
+            page.progress.show(
+                "Configuring Hibernation",
+                "Adding the resume device and rebuilding boot files",
+                None,
+                None,
+            );
+
+            let page = page.clone();
+            let state = state.clone();
+            glib::spawn_future_local(async move {
+                let result = gio::spawn_blocking(move || hibernate::configure(&swap)).await;
+
+                page.progress.hide();
+                page.hibernate_btn.set_sensitive(true);
+
+                match result {
+                    Ok(Ok(())) => {
+                        page.hibernate_row.set_subtitle("Configured, reboot to enable");
+                        state.toast("Hibernation configured; reboot to enable it");
+                    }
+                    Ok(Err(e)) => {
+                        state.toast(&format!("Hibernation setup failed: {}", e));
+                    }
+                    Err(_) => {
+                        state.toast("Hibernation setup failed");
+                    }
+                }
+            });
+        });
+
+        dialog.present();
+    }
+
+    fn setup_sleep_schedule(&self, state: &AppState) {
+        state
+            .bind_property("sleep-schedule", &self.sleep_schedule_row, "active")
+            .sync_create()
+            .build();
+        state
+            .bind_property("sleep-action", &self.sleep_action_combo, "selected")
+            .transform_to(|_, label: String| {
+                SleepAction::ALL.iter().position(|action| action.label() == label).map(|i| i as u32)
+            })
+            .sync_create()
+            .build();
+        state.bind_property("sleep-at", &self.sleep_at_entry, "text").sync_create().build();
+        state.bind_property("wake-at", &self.wake_at_entry, "text").sync_create().build();
+
+        if let Some(reason) = state.read_only_reason() {
+            self.sleep_schedule_row.set_sensitive(false);
+            self.sleep_schedule_row.set_tooltip_text(Some(&reason));
+        }
+
+        self.sleep_schedule_row.connect_active_notify(clone!(
+            #[strong] state,
+            move |row| {
+                if row.is_active() != state.sleep_schedule() {
+                    update_sleep_schedule(&state, |c| c.enabled = row.is_active());
+                }
+            }
+        ));
+
+        self.sleep_action_combo.connect_selected_notify(clone!(
+            #[strong] state,
+            move |combo| {
+                let Some(&action) = SleepAction::ALL.get(combo.selected() as usize) else {
+                    return;
+                };
+                if action.label() != state.sleep_action() {
+                    update_sleep_schedule(&state, |c| c.action = action);
+                }
+            }
+        ));
+
+        for (entry, is_sleep) in [(&self.sleep_at_entry, true), (&self.wake_at_entry, false)] {
+            entry.connect_apply(clone!(
+                #[strong] state,
+                move |entry| {
+                    let text = entry.text().to_string();
+                    if schedule::parse_time(&text).is_none() {
+                        state.toast("Use a 24-hour time such as 01:00");
+                        return;
+                    }
+                    update_sleep_schedule(&state, |c| {
+                        if is_sleep {
+                            c.sleep_at = text.clone();
+                        } else {
+                            c.wake_at = text.clone();
+                        }
+                    });
+                }
+            ));
+        }
+
+        let state = state.clone();
+        schedule::watch_minutes(move |now| {
+            let config = Config::load().power_schedule;
+            if !config.enabled || state.read_only_reason().is_some() {
+                return;
+            }
+            if schedule::parse_time(&config.sleep_at) != Some(now) {
+                return;
+            }
+            let Some(wake_at) = schedule::parse_time(&config.wake_at).and_then(schedule::next_occurrence) else {
+                return;
+            };
+
+            battery_history::record_event(&format!("Scheduled {}", config.action.label()));
+
+            let state = state.clone();
+            glib::spawn_future_local(async move {
+                let result = gio::spawn_blocking(move || {
+                    schedule::sleep_until(config.action, wake_at)
+                }).await;
+
+                match result {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => state.toast(&format!("Scheduled sleep failed: {}", e)),
+                    Err(_) => state.toast("Scheduled sleep failed"),
+                }
+            });
+        });
+    }
+}
+
+fn update_sleep_schedule(state: &AppState, update: impl FnOnce(&mut crate::config::PowerScheduleConfig)) {
+    let mut config = Config::load();
+    update(&mut config.power_schedule);
+    if config.save().is_err() {
+        state.toast("Failed to save sleep schedule");
+    }
+    state.load_config(&config);
+}
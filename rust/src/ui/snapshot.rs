@@ -0,0 +1,130 @@
+use crate::config::Config;
+use crate::snapshot;
+use gtk4::gio;
+use gtk4::glib;
+use gtk4::subclass::prelude::ObjectSubclassIsExt;
+use libadwaita as adw;
+use adw::prelude::*;
+use super::TuxTunerWindow;
+
+impl TuxTunerWindow {
+    /// Records the state TuxTuner found before it changes anything, then
+    /// lets the startup charge limit sync run.
+    pub(super) fn record_defaults(&self) {
+        // Before anything writes config or state, which would make every
+        // run look like an upgrade.
+        let first_run = snapshot::first_run();
+        let win = self.clone();
+        glib::spawn_future_local(async move {
+            let _ = gio::spawn_blocking(move || snapshot::record_defaults(first_run)).await;
+            let imp = win.imp();
+            imp.battery.defaults_recorded(&imp.state);
+        });
+    }
+
+    pub(super) fn take_snapshot(&self) {
+        let win = self.clone();
+        glib::spawn_future_local(async move {
+            let result = gio::spawn_blocking(|| snapshot::save(&snapshot::capture())).await;
+            match result {
+                Ok(Ok(())) => win.imp().state.toast("Snapshot saved"),
+                Ok(Err(e)) => win.imp().state.toast(&format!("Failed to save snapshot: {}", e)),
+                Err(_) => win.imp().state.toast("Failed to save snapshot"),
+            }
+        });
+    }
+
+    pub(super) fn restore_snapshot(&self) {
+        let Some(saved) = snapshot::load() else {
+            self.imp().state.toast("No snapshot saved yet");
+            return;
+        };
+        if let Some(reason) = self.imp().state.read_only_reason() {
+            self.imp().state.toast(&reason);
+            return;
+        }
+
+        let taken = glib::DateTime::from_unix_local(saved.taken_at)
+            .ok()
+            .and_then(|time| time.format("%a %d %b %H:%M").ok())
+            .map(|time| time.to_string())
+            .unwrap_or_default();
+        let dialog = adw::MessageDialog::builder()
+            .transient_for(self)
+            .heading("Restore Snapshot?")
+            .body(format!(
+                "Sets CPU, battery, display and firmware options back to how they were on {}.",
+                taken
+            ))
+            .build();
+        dialog.add_response("cancel", "Cancel");
+        dialog.add_response("restore", "Restore");
+        dialog.set_response_appearance("restore", adw::ResponseAppearance::Suggested);
+        dialog.set_close_response("cancel");
+
+        let win = self.clone();
+        dialog.connect_response(None, move |_, response| {
+            if response != "restore" {
+                return;
+            }
+            let saved = saved.clone();
+            let win = win.clone();
+            glib::spawn_future_local(async move {
+                let result = gio::spawn_blocking(move || snapshot::restore(&saved)).await;
+                match result {
+                    Ok(Ok(())) => win.imp().state.toast("Snapshot restored"),
+                    Ok(Err(e)) => win.imp().state.toast(&format!("Restore incomplete: {}", e)),
+                    Err(_) => win.imp().state.toast("Failed to restore snapshot"),
+                }
+                win.imp().state.load_config(&Config::load());
+                win.imp().state.reload();
+            });
+        });
+        dialog.present();
+    }
+
+    pub(super) fn factory_reset(&self) {
+        if let Some(reason) = self.imp().state.read_only_reason() {
+            self.imp().state.toast(&reason);
+            return;
+        }
+
+        let body = if snapshot::factory_defaults().is_some() {
+            "Returns every setting to how it was when TuxTuner first ran, and removes the \
+             undervolt, fan curve, wakeup, device power and network rules it installed."
+        } else {
+            // Defaults recorded after an upgrade may be tuned values.
+            "Removes the undervolt, fan curve, wakeup, device power and network rules \
+             TuxTuner installed. Other settings stay as they are, since TuxTuner didn't see \
+             them before they were first changed."
+        };
+        let dialog = adw::MessageDialog::builder()
+            .transient_for(self)
+            .heading("Reset Everything?")
+            .body(body)
+            .build();
+        dialog.add_response("cancel", "Cancel");
+        dialog.add_response("reset", "Reset");
+        dialog.set_response_appearance("reset", adw::ResponseAppearance::Destructive);
+        dialog.set_close_response("cancel");
+
+        let win = self.clone();
+        dialog.connect_response(None, move |_, response| {
+            if response != "reset" {
+                return;
+            }
+            let win = win.clone();
+            glib::spawn_future_local(async move {
+                let result = gio::spawn_blocking(snapshot::factory_reset).await;
+                match result {
+                    Ok(Ok(())) => win.imp().state.toast("Everything reset"),
+                    Ok(Err(e)) => win.imp().state.toast(&format!("Reset incomplete: {}", e)),
+                    Err(_) => win.imp().state.toast("Reset failed"),
+                }
+                win.imp().state.load_config(&Config::load());
+                win.imp().state.reload();
+            });
+        });
+        dialog.present();
+    }
+}
@@ -0,0 +1,413 @@
+use crate::config::Config;
+use crate::gpu::GpuMode;
+use crate::mangohud;
+use crate::system_info::{self, MonitorInfo, SystemInfo};
+use gtk4::glib;
+use gtk4::glib::subclass::prelude::*;
+use gtk4::glib::subclass::Signal;
+use gtk4::glib::value::FromValue;
+use gtk4::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::sync::OnceLock;
+
+mod imp {
+    use super::*;
+
+    /// What the window shows about the machine and the saved settings.
+    /// Widgets follow its properties; their handlers act only on values
+    /// that differ from it, so loading a value never applies it again.
+    #[derive(Debug, Default, glib::Properties)]
+    #[properties(wrapper_type = super::AppState)]
+    pub struct AppState {
+        /// Why privileged controls are disabled; empty when they work.
+        /// See `read_only_reason`.
+        #[property(get, set)]
+        read_only: RefCell<String>,
+        #[property(get, set)]
+        online_cpus: Cell<u32>,
+        #[property(get, set)]
+        max_cpus: Cell<u32>,
+        /// `GpuMode` name, e.g. `Hybrid`, or `Unavailable`; empty until
+        /// loaded. See `current_gpu_mode`.
+        #[property(get, set)]
+        gpu_mode: RefCell<String>,
+        /// Names of the modes the GPU backend can switch to.
+        #[property(get, set)]
+        gpu_modes: RefCell<Vec<String>>,
+        /// e.g. `144Hz`; empty when unknown.
+        #[property(get, set)]
+        current_hz: RefCell<String>,
+        #[property(get, set)]
+        native_hz: RefCell<String>,
+        /// Rate labels offered for the monitor, the native one marked.
+        #[property(get, set)]
+        refresh_rates: RefCell<Vec<String>>,
+        /// The monitor the display controls act on.
+        #[property(get, set)]
+        monitor: RefCell<MonitorInfo>,
+        #[property(get, set)]
+        vrr: Cell<bool>,
+
+        // Machine settings read back from the hardware.
+        #[property(get, set)]
+        latency: Cell<bool>,
+        #[property(get, set)]
+        itmt: Cell<bool>,
+        /// Kernel name of the cpufreq governor.
+        #[property(get, set)]
+        governor: RefCell<String>,
+        /// ACPI platform profile.
+        #[property(get, set)]
+        power_mode: RefCell<String>,
+        #[property(get, set)]
+        gpu_priority: Cell<bool>,
+        #[property(get, set)]
+        panel_overdrive: Cell<bool>,
+        #[property(get, set)]
+        mini_led: Cell<bool>,
+        #[property(get, set)]
+        effects: Cell<bool>,
+        #[property(get, set)]
+        skip_idle_frames: Cell<bool>,
+        #[property(get, set)]
+        psr: Cell<bool>,
+        #[property(get, set)]
+        bluetooth: Cell<bool>,
+        #[property(get, set)]
+        airplane_mode: Cell<bool>,
+        #[property(get, set)]
+        trim_timer: Cell<bool>,
+        #[property(get, set)]
+        conservation_mode: Cell<bool>,
+        #[property(get, set)]
+        rapid_charge: Cell<bool>,
+        #[property(get, set)]
+        fn_lock: Cell<bool>,
+        /// `FanLevel` label of the ThinkPad fan.
+        #[property(get, set)]
+        fan_level: RefCell<String>,
+        #[property(get, set)]
+        mangohud_overlay: Cell<bool>,
+        #[property(get, set)]
+        mangohud_fps: Cell<u32>,
+        #[property(get, set)]
+        mangohud_frame_timing: Cell<bool>,
+
+        // Saved settings; see `load_config`.
+        #[property(get, set)]
+        apply_on_change: Cell<bool>,
+        #[property(get, set)]
+        battery_refresh: Cell<bool>,
+        /// The rate `battery_refresh` drops to.
+        #[property(get, set)]
+        battery_refresh_hz: Cell<u32>,
+        /// Screen blanking in seconds, -1 for the session default.
+        #[property(get, set, minimum = -1)]
+        idle_timeout: Cell<i64>,
+        #[property(get, set)]
+        auto_dim: Cell<bool>,
+        /// `LightingLevel` label.
+        #[property(get, set)]
+        lighting: RefCell<String>,
+        #[property(get, set)]
+        charge_limit: Cell<u32>,
+        #[property(get, set)]
+        adaptive_cores: Cell<bool>,
+        #[property(get, set)]
+        adaptive_min: Cell<u32>,
+        /// 0 for every thread.
+        #[property(get, set)]
+        adaptive_max: Cell<u32>,
+        #[property(get, set)]
+        night_light: Cell<bool>,
+        #[property(get, set)]
+        night_temperature: Cell<u32>,
+        #[property(get, set)]
+        night_scheduled: Cell<bool>,
+        #[property(get, set)]
+        night_start: RefCell<String>,
+        #[property(get, set)]
+        night_end: RefCell<String>,
+        #[property(get, set)]
+        emergency: Cell<bool>,
+        #[property(get, set)]
+        emergency_percent: Cell<u32>,
+        #[property(get, set)]
+        emergency_hibernate: Cell<bool>,
+        #[property(get, set)]
+        sleep_schedule: Cell<bool>,
+        /// `SleepAction` label.
+        #[property(get, set)]
+        sleep_action: RefCell<String>,
+        #[property(get, set)]
+        sleep_at: RefCell<String>,
+        #[property(get, set)]
+        wake_at: RefCell<String>,
+        /// Active profile name; empty for custom settings.
+        #[property(get, set)]
+        profile: RefCell<String>,
+        /// Every profile, in list order.
+        #[property(get, set)]
+        profile_names: RefCell<Vec<String>>,
+        /// `profile_names` with runtime estimates, as listed.
+        #[property(get, set)]
+        profile_labels: RefCell<Vec<String>>,
+        /// What no longer matches the active profile.
+        #[property(get, set)]
+        profile_changes: RefCell<Vec<String>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for AppState {
+        const NAME: &'static str = "TuxTunerAppState";
+        type Type = super::AppState;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for AppState {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("toast").param_types([String::static_type()]).build(),
+                    Signal::builder("reload").build(),
+                    Signal::builder("profile-requested")
+                        .param_types([String::static_type()])
+                        .return_type::<bool>()
+                        .build(),
+                ]
+            })
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct AppState(ObjectSubclass<imp::AppState>);
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
+
+impl AppState {
+    pub fn read_only_reason(&self) -> Option<String> {
+        Some(self.read_only()).filter(|reason| !reason.is_empty())
+    }
+
+    /// `gpu_mode` as a mode; `None` while loading or when unavailable.
+    pub fn current_gpu_mode(&self) -> Option<GpuMode> {
+        self.gpu_mode().parse().ok()
+    }
+
+    pub fn set_current_gpu_mode(&self, mode: Option<GpuMode>) {
+        self.set_gpu_mode(mode.map_or("Unavailable", GpuMode::as_str));
+    }
+
+    /// `gpu_modes` as modes.
+    pub fn supported_gpu_modes(&self) -> Vec<GpuMode> {
+        self.gpu_modes().iter().filter_map(|name| name.parse().ok()).collect()
+    }
+
+    pub fn set_supported_gpu_modes(&self, modes: &[GpuMode]) {
+        let names: Vec<String> = modes.iter().map(|mode| mode.as_str().to_string()).collect();
+        self.update("gpu-modes", names);
+    }
+
+    /// Takes the rates `SystemInfo` found.
+    pub fn show_refresh_rates(&self, current_hz: &str, native_hz: &str) {
+        self.set_native_hz(native_hz.replace(" (Native)", ""));
+        self.set_current_hz(current_hz);
+    }
+
+    /// Records a rate that was just applied.
+    pub fn set_current_rate(&self, hz: u32) {
+        self.set_current_hz(system_info::rate_label(hz));
+    }
+
+    /// Whether the monitor runs at its highest rate, which the Native
+    /// badge shows.
+    pub fn at_native_hz(&self) -> bool {
+        let hz = self.current_hz();
+        !hz.is_empty() && hz == self.native_hz()
+    }
+
+    /// Takes what `SystemInfo` found. Lists are only replaced when they
+    /// changed, so the combos showing them keep their selection.
+    pub fn show_system_info(&self, info: &SystemInfo) {
+        self.set_max_cpus(info.total_cpus);
+        self.set_online_cpus(info.online_cpus);
+        self.set_supported_gpu_modes(&info.supported_gpu_modes);
+        self.set_current_gpu_mode(info.gpu_mode);
+        self.update("refresh-rates", info.refresh_rates.clone());
+        self.set_monitor(info.monitor());
+        self.set_vrr(info.monitor_vrr);
+        self.show_refresh_rates(&info.current_hz, &info.native_hz);
+    }
+
+    /// Takes the saved settings, e.g. after the config changed on disk or
+    /// a profile rewrote it. Only changed values are set, so nothing
+    /// following them reacts to a reload that kept them.
+    pub fn load_config(&self, config: &Config) {
+        self.update("apply-on-change", config.apply_on_change);
+        self.update("battery-refresh", config.automation.battery_refresh);
+        self.update("battery-refresh-hz", config.automation.battery_refresh_hz);
+        self.update("idle-timeout", config.idle_timeout_secs.map_or(-1, i64::from));
+        self.update("auto-dim", config.battery.auto_dim);
+        self.update("lighting", config.lighting.unwrap_or_default().label().to_string());
+        if let Some(limit) = config.battery.charge_limit {
+            self.update("charge-limit", limit);
+        }
+
+        let adaptive = &config.adaptive_cores;
+        self.update("adaptive-cores", adaptive.enabled);
+        self.update("adaptive-min", adaptive.min_threads);
+        self.update("adaptive-max", adaptive.max_threads);
+
+        let night = &config.night_light;
+        self.update("night-light", night.enabled);
+        self.update("night-temperature", night.temperature);
+        self.update("night-scheduled", night.scheduled);
+        self.update("night-start", night.start.clone());
+        self.update("night-end", night.end.clone());
+
+        let battery = &config.battery;
+        self.update("emergency", battery.emergency);
+        self.update("emergency-percent", battery.emergency_percent);
+        self.update("emergency-hibernate", battery.emergency_hibernate);
+
+        let schedule = &config.power_schedule;
+        self.update("sleep-schedule", schedule.enabled);
+        self.update("sleep-action", schedule.action.label().to_string());
+        self.update("sleep-at", schedule.sleep_at.clone());
+        self.update("wake-at", schedule.wake_at.clone());
+
+        self.update("profile", config.active_profile.clone().unwrap_or_default());
+    }
+
+    /// Takes the MangoHud settings, which live in MangoHud's own file.
+    pub fn load_mangohud(&self, settings: &mangohud::Settings) {
+        self.update("mangohud-overlay", settings.overlay);
+        self.update("mangohud-fps", settings.fps_limit);
+        self.update("mangohud-frame-timing", settings.frame_timing);
+    }
+
+    /// `idle_timeout` as the config stores it.
+    pub fn idle_timeout_secs(&self) -> Option<u32> {
+        u32::try_from(self.idle_timeout()).ok()
+    }
+
+    /// Sets `property` unless it already holds `value`; the generated
+    /// setters notify either way.
+    pub fn update<V>(&self, property: &str, value: V)
+    where
+        V: ToValue + Into<glib::Value> + PartialEq + for<'a> FromValue<'a> + 'static,
+    {
+        if self.property::<V>(property) != value {
+            self.set_property(property, value);
+        }
+    }
+
+    /// Shows `message` in the window.
+    pub fn toast(&self, message: &str) {
+        self.emit_by_name::<()>("toast", &[&message]);
+    }
+
+    pub fn connect_toast<F: Fn(&str) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "toast",
+            false,
+            glib::closure_local!(move |_: &AppState, message: &str| f(message)),
+        )
+    }
+
+    /// Says that something outside the controls, such as a profile or a
+    /// rule, changed the machine, so everything shown is read again.
+    pub fn reload(&self) {
+        self.emit_by_name::<()>("reload", &[]);
+    }
+
+    pub fn connect_reload<F: Fn() + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure("reload", false, glib::closure_local!(move |_: &AppState| f()))
+    }
+
+    /// Applies the profile called `name`, ignoring case, like a manual
+    /// pick. Returns `false` when there is no such profile.
+    pub fn request_profile(&self, name: &str) -> bool {
+        self.emit_by_name::<bool>("profile-requested", &[&name])
+    }
+
+    pub fn connect_profile_requested<F: Fn(&str) -> bool + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "profile-requested",
+            false,
+            glib::closure_local!(move |_: &AppState, name: &str| f(name)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpu::NoGpu;
+    use crate::system_info::{DisplayBackend, FakeDisplay, SystemInfo};
+
+    /// Loads the fake machine, picks `choice` from the rate list the way
+    /// the refresh rate combo does and applies it.
+    fn select_and_apply(display: &FakeDisplay, choice: &str) -> (AppState, Result<(), String>) {
+        let info = SystemInfo::fetch_with(&NoGpu, display);
+        let state = AppState::default();
+        state.show_refresh_rates(&info.current_hz, &info.native_hz);
+
+        let label = info
+            .refresh_rates
+            .iter()
+            .find(|rate| rate.starts_with(choice))
+            .expect("rate offered");
+        let hz = system_info::rate_from_label(label).expect("rate parses");
+        let monitor = display.monitors().unwrap().remove(0);
+        let result = system_info::apply_monitor_mode_with(display, &monitor, hz, None);
+        if result.is_ok() {
+            state.set_current_rate(hz);
+        }
+        (state, result)
+    }
+
+    #[test]
+    fn offers_highest_rate_as_native() {
+        let info = SystemInfo::fetch_with(&NoGpu, &FakeDisplay::new(false));
+        assert_eq!(info.refresh_rates, ["60Hz", "120Hz (Native)"]);
+        assert_eq!(info.current_hz, "60Hz");
+        assert_eq!(info.native_hz, "120Hz");
+    }
+
+    #[test]
+    fn applying_native_rate_updates_state() {
+        let display = FakeDisplay::new(false);
+        let (state, result) = select_and_apply(&display, "120Hz");
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(display.refresh_hz(), 120);
+        assert_eq!(state.current_hz(), "120Hz");
+        assert!(state.at_native_hz());
+    }
+
+    #[test]
+    fn failed_apply_keeps_state() {
+        let (state, result) = select_and_apply(&FakeDisplay::new(true), "120Hz");
+
+        assert!(result.is_err());
+        assert_eq!(state.current_hz(), "60Hz");
+        assert!(!state.at_native_hz());
+    }
+
+    #[test]
+    fn badge_needs_a_known_rate() {
+        let state = AppState::default();
+        assert!(!state.at_native_hz());
+        state.set_native_hz("120Hz");
+        state.set_current_rate(120);
+        assert!(state.at_native_hz());
+    }
+}
@@ -0,0 +1,397 @@
+use crate::battery;
+use crate::chart;
+use crate::config::Config;
+use crate::gpu::GpuMode;
+use crate::power;
+use crate::profiles;
+use crate::thermal;
+use gtk4::glib::{self, clone};
+use gtk4::prelude::*;
+use gtk4::{Align, Box as GtkBox, Label, Orientation};
+use libadwaita as adw;
+use adw::prelude::*;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use super::AppState;
+
+/// Refresh interval of the header dashboard.
+const DASHBOARD_INTERVAL_SECS: u32 = 5;
+/// Points kept per sparkline (five minutes).
+const TREND_SAMPLES: usize = 60;
+
+/// At-a-glance readings shown in the header.
+#[derive(Clone)]
+pub(super) struct Dashboard {
+    profile: Label,
+    power: Label,
+    pub(super) battery: Label,
+    temperature: Label,
+    gpu: Label,
+    battery_trend: gtk4::DrawingArea,
+    temperature_trend: gtk4::DrawingArea,
+    battery_samples: Rc<RefCell<VecDeque<f64>>>,
+    temperature_samples: Rc<RefCell<VecDeque<f64>>>,
+}
+
+/// The header dashboard and the System Status group.
+#[derive(Clone)]
+pub(super) struct StatusPage {
+    /// Goes above the preferences page rather than in it.
+    pub(super) header: GtkBox,
+    pub(super) dashboard: Dashboard,
+    groups: [adw::PreferencesGroup; 1],
+    status_mode_val: Label,
+    status_cpu_val: Label,
+    status_hz_val: Label,
+    pub(super) native_badge: Label,
+    charger_row: adw::ActionRow,
+    charger_val: Label,
+}
+
+impl StatusPage {
+    pub(super) fn build() -> Self {
+        let (header, dashboard) = Self::build_dashboard();
+        let (status_group, status_mode_val, status_cpu_val, status_hz_val, native_badge, charger_row, charger_val) =
+            Self::build_status_group();
+
+        Self {
+            header,
+            dashboard,
+            groups: [status_group],
+            status_mode_val,
+            status_cpu_val,
+            status_hz_val,
+            native_badge,
+            charger_row,
+            charger_val,
+        }
+    }
+
+    pub(super) fn groups(&self) -> &[adw::PreferencesGroup] {
+        &self.groups
+    }
+
+    /// Makes the status rows follow `state` and starts the dashboard.
+    pub(super) fn bind(&self, state: &AppState) {
+        let cpus_label = clone!(
+            #[strong(rename_to = label)] self.status_cpu_val,
+            move |state: &AppState| {
+                label.set_label(&format!("{}/{}", state.online_cpus(), state.max_cpus()));
+            }
+        );
+        state.connect_online_cpus_notify(cpus_label.clone());
+        state.connect_max_cpus_notify(cpus_label);
+
+        for label in [&self.status_mode_val, &self.dashboard.gpu] {
+            state
+                .bind_property("gpu-mode", label, "label")
+                .transform_to(|_, name: String| {
+                    let label = match name.parse::<GpuMode>() {
+                        Ok(mode) => mode.label(),
+                        Err(_) if name.is_empty() => "...",
+                        Err(_) => name.as_str(),
+                    };
+                    Some(label.to_string())
+                })
+                .sync_create()
+                .build();
+        }
+
+        state
+            .bind_property("current-hz", &self.status_hz_val, "label")
+            .transform_to(|_, hz: String| Some(if hz.is_empty() { "Unknown".to_string() } else { hz }))
+            .build();
+        let native_badge = clone!(
+            #[strong(rename_to = badge)] self.native_badge,
+            move |state: &AppState| badge.set_visible(state.at_native_hz())
+        );
+        state.connect_native_hz_notify(native_badge.clone());
+        state.connect_current_hz_notify(native_badge);
+
+        self.setup_dashboard();
+    }
+
+    /// Dims the status values while they come from the startup cache.
+    pub(super) fn show_stale(&self, stale: bool) {
+        for label in [&self.status_mode_val, &self.status_cpu_val, &self.status_hz_val] {
+            if stale {
+                label.add_css_class("dim-label");
+                label.set_tooltip_text(Some("Last known value (stale), refreshing..."));
+            } else {
+                label.remove_css_class("dim-label");
+                label.set_tooltip_text(None);
+            }
+        }
+    }
+
+    fn build_dashboard() -> (GtkBox, Dashboard) {
+        let header_box = GtkBox::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(12)
+            .css_classes(["tuxtuner-header"])
+            .halign(Align::Fill)
+            .build();
+
+        let title_box = GtkBox::builder()
+            .orientation(Orientation::Horizontal)
+            .valign(Align::Center)
+            .build();
+
+        let penguin_label = Label::builder()
+            .label("🐧")
+            .css_classes(["tuxtuner-penguin"])
+            .build();
+        title_box.append(&penguin_label);
+
+        let title_label = Label::builder()
+            .label("TUXTUNER")
+            .css_classes(["tuxtuner-title"])
+            .halign(Align::Start)
+            .build();
+        title_box.append(&title_label);
+        header_box.append(&title_box);
+
+        let grid = gtk4::Grid::builder()
+            .column_spacing(8)
+            .row_spacing(8)
+            .column_homogeneous(true)
+            .build();
+        header_box.append(&grid);
+
+        let tile = |caption: &str, column: i32, row: i32, trend: Option<&gtk4::DrawingArea>| {
+            let tile_box = GtkBox::builder()
+                .orientation(Orientation::Vertical)
+                .spacing(2)
+                .css_classes(["dashboard-tile"])
+                .build();
+            tile_box.append(
+                &Label::builder()
+                    .label(caption)
+                    .css_classes(["dashboard-caption"])
+                    .halign(Align::Start)
+                    .build(),
+            );
+            let value = Label::builder()
+                .label("...")
+                .css_classes(["dashboard-value"])
+                .halign(Align::Start)
+                .ellipsize(gtk4::pango::EllipsizeMode::End)
+                .build();
+            tile_box.append(&value);
+            if let Some(trend) = trend {
+                tile_box.append(trend);
+            }
+            grid.attach(&tile_box, column, row, 1, 1);
+            value
+        };
+
+        let sparkline = || {
+            gtk4::DrawingArea::builder()
+                .content_height(18)
+                .hexpand(true)
+                .build()
+        };
+        let battery_trend = sparkline();
+        let temperature_trend = sparkline();
+
+        let dashboard = Dashboard {
+            profile: tile("PROFILE", 0, 0, None),
+            power: tile("POWER", 1, 0, None),
+            gpu: tile("GPU", 2, 0, None),
+            battery: tile("BATTERY", 0, 1, Some(&battery_trend)),
+            temperature: tile("CPU TEMP", 1, 1, Some(&temperature_trend)),
+            battery_trend,
+            temperature_trend,
+            battery_samples: Rc::new(RefCell::new(VecDeque::with_capacity(TREND_SAMPLES))),
+            temperature_samples: Rc::new(RefCell::new(VecDeque::with_capacity(TREND_SAMPLES))),
+        };
+
+        (header_box, dashboard)
+    }
+
+    fn build_status_group() -> (
+        adw::PreferencesGroup,
+        Label,
+        Label,
+        Label,
+        Label,
+        adw::ActionRow,
+        Label,
+    ) {
+        let status_group = adw::PreferencesGroup::builder()
+            .title("System Status")
+            .build();
+
+        let status_mode_row = adw::ActionRow::builder()
+            .title("Graphics Mode")
+            .subtitle("Current GPU operation mode")
+            .build();
+        let status_mode_val = Label::builder()
+            .label("...")
+            .css_classes(["status-value"])
+            .valign(Align::Center)
+            .build();
+        status_mode_row.add_suffix(&status_mode_val);
+        status_group.add(&status_mode_row);
+
+        let status_cpu_row = adw::ActionRow::builder()
+            .title("Active Threads")
+            .subtitle("Online CPU cores")
+            .build();
+        let status_cpu_val = Label::builder()
+            .label("...")
+            .css_classes(["status-value-green"])
+            .valign(Align::Center)
+            .build();
+        status_cpu_row.add_suffix(&status_cpu_val);
+        status_group.add(&status_cpu_row);
+
+        let status_hz_row = adw::ActionRow::builder()
+            .title("Refresh Rate")
+            .subtitle("Monitor Hz")
+            .build();
+        let hz_status_box = GtkBox::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(4)
+            .valign(Align::Center)
+            .build();
+        let status_hz_val = Label::builder()
+            .label("...")
+            .css_classes(["status-value"])
+            .build();
+        hz_status_box.append(&status_hz_val);
+        let native_badge = Label::builder()
+            .label("NATIVE")
+            .css_classes(["native-badge"])
+            .visible(false)
+            .build();
+        hz_status_box.append(&native_badge);
+        status_hz_row.add_suffix(&hz_status_box);
+        status_group.add(&status_hz_row);
+
+        let charger_row = adw::ActionRow::builder()
+            .title("Charger")
+            .visible(false)
+            .build();
+        let charger_val = Label::builder()
+            .label("...")
+            .css_classes(["status-value"])
+            .valign(Align::Center)
+            .build();
+        charger_row.add_suffix(&charger_val);
+        status_group.add(&charger_row);
+
+        (status_group, status_mode_val, status_cpu_val, status_hz_val, native_badge, charger_row, charger_val)
+    }
+
+    fn setup_dashboard(&self) {
+        let trends = [
+            (&self.dashboard.battery_trend, &self.dashboard.battery_samples),
+            (&self.dashboard.temperature_trend, &self.dashboard.temperature_samples),
+        ];
+        for (area, samples) in trends {
+            area.set_draw_func(clone!(
+                #[strong] samples,
+                move |_, cr, width, height| {
+                    let values: Vec<f64> = samples.borrow().iter().copied().collect();
+                    chart::draw_sparkline(cr, width as f64, height as f64, &values);
+                }
+            ));
+        }
+
+        self.refresh_dashboard();
+
+        let page = self.clone();
+        glib::timeout_add_seconds_local(DASHBOARD_INTERVAL_SECS, move || {
+            page.refresh_dashboard();
+            glib::ControlFlow::Continue
+        });
+    }
+
+    fn refresh_dashboard(&self) {
+        let dashboard = &self.dashboard;
+
+        let profile = Config::load().active_profile.unwrap_or_else(|| "Custom".to_string());
+        dashboard.profile.set_label(&profile);
+
+        let charger = power::charger();
+        let watts = charger.as_ref().and_then(|charger| charger.watts);
+        dashboard.power.set_label(&match (power::power_source(), watts) {
+            (power::PowerSource::Ac, Some(watts)) => format!("AC · {:.0} W", watts),
+            (power::PowerSource::Ac, None) => "AC".to_string(),
+            (power::PowerSource::Battery, _) => "Battery".to_string(),
+            (power::PowerSource::Unknown, _) => "Unknown".to_string(),
+        });
+        self.refresh_charger(charger);
+
+        // The tile shows the combined charge; name each battery on hover.
+        let capacities = battery::capacities();
+        let per_battery = (capacities.len() > 1).then(|| {
+            capacities
+                .iter()
+                .map(|(name, percent)| format!("{} {}%", name, percent))
+                .collect::<Vec<_>>()
+                .join(" · ")
+        });
+        dashboard.battery.set_tooltip_text(per_battery.as_deref());
+
+        let readings = [
+            (
+                &dashboard.battery,
+                &dashboard.battery_trend,
+                &dashboard.battery_samples,
+                battery::capacity_percent().map(f64::from),
+                "%",
+            ),
+            (
+                &dashboard.temperature,
+                &dashboard.temperature_trend,
+                &dashboard.temperature_samples,
+                thermal::cpu_temperature(),
+                "°C",
+            ),
+        ];
+        for (label, trend, samples, reading, unit) in readings {
+            if let Some(tile) = label.parent() {
+                tile.set_visible(reading.is_some());
+            }
+            let Some(value) = reading else {
+                continue;
+            };
+
+            label.set_label(&format!("{:.0}{}", value, unit));
+            let mut samples = samples.borrow_mut();
+            if samples.len() == TREND_SAMPLES {
+                samples.pop_front();
+            }
+            samples.push_back(value);
+            trend.queue_draw();
+        }
+    }
+
+    /// Shows what the charger negotiated, warning when it can't keep up
+    /// with the Performance platform profile.
+    fn refresh_charger(&self, charger: Option<power::Charger>) {
+        let Some(charger) = charger else {
+            self.charger_row.set_visible(false);
+            return;
+        };
+        self.charger_row.set_visible(true);
+        self.charger_val.set_label(&charger.watts.map_or("Connected".to_string(), |watts| format!("{:.0} W", watts)));
+
+        let performance = profiles::platform_profile().as_deref() == Some("performance");
+        let subtitle = if performance && charger.underpowered() {
+            self.charger_val.set_css_classes(&["status-value", "warning"]);
+            "Too weak to sustain Performance mode; the battery may drain".to_string()
+        } else {
+            self.charger_val.set_css_classes(&["status-value"]);
+            let kind = if charger.usb_pd { "USB Power Delivery" } else { "Power adapter" };
+            match charger.volts {
+                Some(volts) => format!("{} at {:.0} V", kind, volts),
+                None => kind.to_string(),
+            }
+        };
+        self.charger_row.set_subtitle(&subtitle);
+    }
+}
@@ -0,0 +1,372 @@
+use crate::privileges;
+use crate::storage::{self, Writeback};
+use crate::tunables::{self, Tunable, TunableState};
+use gtk4::glib::{self, clone};
+use gtk4::prelude::*;
+use gtk4::{gio, Align, Button};
+use libadwaita as adw;
+use adw::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+use super::{bind_switch, AppState};
+
+/// Kernel power tunables and disk write-back.
+#[derive(Clone)]
+pub(super) struct TuningPage {
+    groups: [adw::PreferencesGroup; 2],
+    pub(super) tunables_group: adw::PreferencesGroup,
+    tunables_apply_all_btn: Button,
+    tunable_rows: Rc<RefCell<Vec<adw::ActionRow>>>,
+    tunable_states: Rc<RefCell<Vec<TunableState>>>,
+    pub(super) dirty_ratio_spin: adw::SpinRow,
+    writeback_spin: adw::SpinRow,
+    writeback_apply_btn: Button,
+    trim_btn: Button,
+    trim_timer_row: adw::SwitchRow,
+}
+
+impl TuningPage {
+    pub(super) fn build() -> Self {
+        let (tunables_group, tunables_apply_all_btn) = Self::build_tunables_group();
+        let (storage_group, dirty_ratio_spin, writeback_spin, writeback_apply_btn, trim_btn, trim_timer_row) =
+            Self::build_storage_group();
+
+        Self {
+            groups: [tunables_group.clone(), storage_group],
+            tunables_group,
+            tunables_apply_all_btn,
+            tunable_rows: Rc::new(RefCell::new(Vec::new())),
+            tunable_states: Rc::new(RefCell::new(Vec::new())),
+            dirty_ratio_spin,
+            writeback_spin,
+            writeback_apply_btn,
+            trim_btn,
+            trim_timer_row,
+        }
+    }
+
+    pub(super) fn groups(&self) -> &[adw::PreferencesGroup] {
+        &self.groups
+    }
+
+    pub(super) fn bind(&self, state: &AppState) {
+        self.setup_tunables(state);
+        self.setup_storage(state);
+    }
+
+    fn build_tunables_group() -> (adw::PreferencesGroup, Button) {
+        let tunables_group = adw::PreferencesGroup::builder()
+            .title("Power Tunables")
+            .visible(false)
+            .build();
+
+        let tunables_apply_all_btn = Button::builder()
+            .label("Fix All")
+            .valign(Align::Center)
+            .css_classes(["flat"])
+            .build();
+        tunables_group.set_header_suffix(Some(&tunables_apply_all_btn));
+
+        (tunables_group, tunables_apply_all_btn)
+    }
+
+    fn setup_tunables(&self, state: &AppState) {
+        if let Some(reason) = state.read_only_reason() {
+            self.tunables_apply_all_btn.set_sensitive(false);
+            self.tunables_apply_all_btn.set_tooltip_text(Some(&reason));
+        } else {
+            self.tunables_apply_all_btn.connect_clicked(clone!(
+                #[strong(rename_to = page)] self,
+                #[strong] state,
+                move |_| {
+                    let pending: Vec<Tunable> = page
+                        .tunable_states
+                        .borrow()
+                        .iter()
+                        .filter(|tunable| !tunable.good)
+                        .map(|tunable| tunable.tunable)
+                        .collect();
+                    page.apply_tunables(&state, pending);
+                }
+            ));
+        }
+
+        self.refresh_tunables(state);
+    }
+
+    /// Re-audits the tunables and rebuilds the checklist, scoring the
+    /// machine by the share already set for power saving.
+    fn refresh_tunables(&self, state: &AppState) {
+        let page = self.clone();
+        let state = state.clone();
+        glib::spawn_future_local(async move {
+            let states = gio::spawn_blocking(tunables::audit).await.unwrap_or_default();
+
+            page.tunables_group.set_visible(!states.is_empty());
+            let bad = states.iter().filter(|tunable| !tunable.good).count();
+            page.tunables_group.set_description(Some(&format!(
+                "Power health {}%: {} of {} kernel settings favour battery life. Fixes last until reboot.",
+                tunables::score(&states),
+                states.len() - bad,
+                states.len()
+            )));
+            page.tunables_apply_all_btn.set_visible(bad > 1);
+
+            let mut rows = page.tunable_rows.borrow_mut();
+            for row in rows.drain(..) {
+                page.tunables_group.remove(&row);
+            }
+            for tunable in &states {
+                let row = page.build_tunable_row(&state, tunable);
+                page.tunables_group.add(&row);
+                rows.push(row);
+            }
+            *page.tunable_states.borrow_mut() = states;
+        });
+    }
+
+    fn build_tunable_row(&self, state: &AppState, audit: &TunableState) -> adw::ActionRow {
+        let row = adw::ActionRow::builder()
+            .title(audit.tunable.label())
+            .subtitle(glib::markup_escape_text(&audit.current))
+            .build();
+
+        if audit.good {
+            row.add_suffix(&gtk4::Image::from_icon_name("emblem-ok-symbolic"));
+            return row;
+        }
+
+        let fix_btn = Button::builder()
+            .label("Fix")
+            .valign(Align::Center)
+            .build();
+        row.add_suffix(&fix_btn);
+
+        if let Some(reason) = state.read_only_reason() {
+            fix_btn.set_sensitive(false);
+            fix_btn.set_tooltip_text(Some(&reason));
+            return row;
+        }
+
+        let tunable = audit.tunable;
+        fix_btn.connect_clicked(clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            move |_| page.apply_tunables(&state, vec![tunable])
+        ));
+
+        row
+    }
+
+    /// Sets each of `pending` to its power-saving value, asking for the
+    /// password once.
+    fn apply_tunables(&self, state: &AppState, pending: Vec<Tunable>) {
+        if pending.is_empty() {
+            return;
+        }
+        self.tunables_group.set_sensitive(false);
+
+        let page = self.clone();
+        let state = state.clone();
+        glib::spawn_future_local(async move {
+            let result = gio::spawn_blocking(move || {
+                privileges::preauthorize()?;
+                let errors: Vec<String> = pending
+                    .into_iter()
+                    .filter_map(|tunable| {
+                        tunables::apply(tunable)
+                            .err()
+                            .map(|e| format!("{}: {}", tunable.label(), e.trim()))
+                    })
+                    .collect();
+                if errors.is_empty() {
+                    Ok(())
+                } else {
+                    Err(errors.join("; "))
+                }
+            }).await;
+
+            page.tunables_group.set_sensitive(true);
+            match result {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => state.toast(&format!("Tunable not set: {}", e)),
+                Err(_) => state.toast("Tunable not set"),
+            }
+            page.refresh_tunables(&state);
+        });
+    }
+
+    fn build_storage_group() -> (
+        adw::PreferencesGroup,
+        adw::SpinRow,
+        adw::SpinRow,
+        Button,
+        Button,
+        adw::SwitchRow,
+    ) {
+        let storage_group = adw::PreferencesGroup::builder()
+            .title("Storage")
+            .description(
+                "Fewer, larger writes let the disk sleep longer, but a crash loses more unsaved data. \
+                 Write-back settings last until reboot.",
+            )
+            .visible(false)
+            .build();
+
+        let writeback_apply_btn = Button::builder()
+            .label("Apply")
+            .valign(Align::Center)
+            .css_classes(["flat"])
+            .build();
+        storage_group.set_header_suffix(Some(&writeback_apply_btn));
+
+        let (min_ratio, max_ratio) = storage::DIRTY_RATIO_RANGE;
+        let dirty_ratio_spin = adw::SpinRow::with_range(min_ratio as f64, max_ratio as f64, 1.0);
+        dirty_ratio_spin.set_title("Dirty Memory Limit (%)");
+        dirty_ratio_spin.set_subtitle("Unwritten data held in memory before programs wait on the disk");
+        storage_group.add(&dirty_ratio_spin);
+
+        let (min_secs, max_secs) = storage::WRITEBACK_SECS_RANGE;
+        let writeback_spin = adw::SpinRow::with_range(min_secs as f64, max_secs as f64, 1.0);
+        writeback_spin.set_title("Write-Back Interval (s)");
+        writeback_spin.set_subtitle("How often unwritten data is flushed to disk");
+        storage_group.add(&writeback_spin);
+
+        let trim_row = adw::ActionRow::builder()
+            .title("Trim Now")
+            .subtitle("Tell SSDs which blocks are free")
+            .build();
+        let trim_btn = Button::builder()
+            .label("Trim")
+            .valign(Align::Center)
+            .build();
+        trim_row.add_suffix(&trim_btn);
+        storage_group.add(&trim_row);
+
+        let trim_timer_row = adw::SwitchRow::builder()
+            .title("Weekly Trim")
+            .visible(false)
+            .build();
+        storage_group.add(&trim_timer_row);
+
+        (
+            storage_group,
+            dirty_ratio_spin,
+            writeback_spin,
+            writeback_apply_btn,
+            trim_btn,
+            trim_timer_row,
+        )
+    }
+
+    fn setup_storage(&self, state: &AppState) {
+        if let Some(reason) = state.read_only_reason() {
+            for widget in [
+                self.writeback_apply_btn.upcast_ref::<gtk4::Widget>(),
+                self.trim_btn.upcast_ref(),
+                self.trim_timer_row.upcast_ref(),
+            ] {
+                widget.set_sensitive(false);
+                widget.set_tooltip_text(Some(&reason));
+            }
+        }
+
+        self.writeback_apply_btn.connect_clicked(clone!(
+            #[strong(rename_to = page)] self,
+            #[strong] state,
+            move |btn| {
+                let writeback = Writeback {
+                    dirty_ratio: page.dirty_ratio_spin.value() as u32,
+                    interval_secs: page.writeback_spin.value() as u32,
+                };
+                btn.set_sensitive(false);
+
+                let page = page.clone();
+                let state = state.clone();
+                let btn = btn.clone();
+                glib::spawn_future_local(async move {
+                    let result = gio::spawn_blocking(move || writeback.apply()).await;
+
+                    btn.set_sensitive(true);
+                    match result {
+                        Ok(Ok(())) => state.toast(&format!(
+                                "Write-back set to {}% every {} s",
+                                writeback.dirty_ratio, writeback.interval_secs
+                            )),
+                        Ok(Err(e)) => state.toast(&format!("Write-back change failed: {}", e)),
+                        Err(_) => state.toast("Write-back change failed"),
+                    }
+                    // The interval is also one of the power tunables.
+                    page.refresh_tunables(&state);
+                });
+            }
+        ));
+
+        self.trim_btn.connect_clicked(clone!(
+            #[strong] state,
+            move |btn| {
+                btn.set_sensitive(false);
+
+                let state = state.clone();
+                let btn = btn.clone();
+                glib::spawn_future_local(async move {
+                    let result = gio::spawn_blocking(storage::trim_now).await;
+
+                    btn.set_sensitive(true);
+                    match result {
+                        Ok(Ok(summary)) => state.toast(&summary),
+                        Ok(Err(e)) => state.toast(&format!("Trim failed: {}", e)),
+                        Err(_) => state.toast("Trim failed"),
+                    }
+                });
+            }
+        ));
+
+        bind_switch(
+            state,
+            "trim-timer",
+            &self.trim_timer_row,
+            "Weekly trim change failed",
+            storage::set_trim_timer,
+            clone!(
+                #[strong(rename_to = page)] self,
+                #[strong] state,
+                move |_, ()| page.refresh_storage(&state)
+            ),
+        );
+
+        self.refresh_storage(state);
+    }
+
+    /// Reads the write-back settings and trim timer off the main thread.
+    fn refresh_storage(&self, state: &AppState) {
+        let page = self.clone();
+        let state = state.clone();
+        glib::spawn_future_local(async move {
+            let Ok((writeback, timer)) =
+                gio::spawn_blocking(|| (Writeback::read(), storage::trim_timer())).await
+            else {
+                return;
+            };
+
+            if let Some(writeback) = writeback {
+                page.dirty_ratio_spin.set_value(writeback.dirty_ratio as f64);
+                page.writeback_spin.set_value(writeback.interval_secs as f64);
+            }
+            page.dirty_ratio_spin.set_visible(writeback.is_some());
+            page.writeback_spin.set_visible(writeback.is_some());
+            page.writeback_apply_btn.set_visible(writeback.is_some());
+
+            page.trim_timer_row.set_visible(timer.is_some());
+            if let Some(timer) = timer {
+                state.set_trim_timer(timer.enabled);
+                page.trim_timer_row.set_subtitle(&match timer.last_run {
+                    Some(last) => format!("Runs fstrim.timer; last trim {}", last),
+                    None => "Runs fstrim.timer".to_string(),
+                });
+            }
+
+            page.groups[1].set_visible(true);
+        });
+    }
+}
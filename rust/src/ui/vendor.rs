@@ -0,0 +1,461 @@
+use crate::firmware::{self, AttributeKind, FirmwareAttribute};
+use crate::lenovo::{self, LenovoFeature};
+use crate::profiles;
+use crate::remote;
+use crate::thermal;
+use crate::thinkpad::{self, FanLevel};
+use gtk4::glib::{self, clone};
+use gtk4::prelude::*;
+use gtk4::{gio, Align, Button, StringList};
+use libadwaita as adw;
+use adw::prelude::*;
+use std::cell::Cell;
+use std::rc::Rc;
+use super::{bind_choice, bind_switch, AppState, FAN_GUARD_INTERVAL_SECS};
+
+/// Extras from the laptop makers' drivers: Lenovo's switches and power
+/// mode, ThinkPad thresholds and fan, and BIOS settings.
+#[derive(Clone)]
+pub(super) struct VendorPage {
+    groups: [adw::PreferencesGroup; 3],
+    pub(super) conservation_row: adw::SwitchRow,
+    fn_lock_row: adw::SwitchRow,
+    pub(super) rapid_charge_row: adw::SwitchRow,
+    power_mode_combo: adw::ComboRow,
+    pub(super) charge_start_spin: adw::SpinRow,
+    charge_start_btn: Button,
+    pub(super) fan_combo: adw::ComboRow,
+    pub(super) firmware_group: adw::PreferencesGroup,
+}
+
+impl VendorPage {
+    pub(super) fn build() -> Self {
+        let (lenovo_group, conservation_row, fn_lock_row, rapid_charge_row, power_mode_combo) =
+            Self::build_lenovo_group();
+        let (thinkpad_group, charge_start_spin, charge_start_btn, fan_combo) =
+            Self::build_thinkpad_group();
+        let firmware_group = adw::PreferencesGroup::builder()
+            .title("Firmware Settings")
+            .description("Thermal and battery options stored in the BIOS.")
+            .visible(false)
+            .build();
+
+        Self {
+            groups: [lenovo_group, thinkpad_group, firmware_group.clone()],
+            conservation_row,
+            fn_lock_row,
+            rapid_charge_row,
+            power_mode_combo,
+            charge_start_spin,
+            charge_start_btn,
+            fan_combo,
+            firmware_group,
+        }
+    }
+
+    pub(super) fn groups(&self) -> &[adw::PreferencesGroup] {
+        &self.groups
+    }
+
+    pub(super) fn bind(&self, state: &AppState) {
+        self.setup_lenovo(state);
+        self.setup_thinkpad(state);
+        self.setup_firmware_attributes(state);
+    }
+
+    fn build_lenovo_group() -> (
+        adw::PreferencesGroup,
+        adw::SwitchRow,
+        adw::SwitchRow,
+        adw::SwitchRow,
+        adw::ComboRow,
+    ) {
+        let lenovo_group = adw::PreferencesGroup::builder()
+            .title("Lenovo")
+            .description("Extras exposed by the ideapad and Legion drivers.")
+            .build();
+
+        let conservation_row = adw::SwitchRow::builder()
+            .title("Conservation Mode")
+            .subtitle("Keep the battery around 60% while plugged in")
+            .visible(false)
+            .build();
+        lenovo_group.add(&conservation_row);
+
+        let rapid_charge_row = adw::SwitchRow::builder()
+            .title("Rapid Charge")
+            .subtitle("Charge faster at the cost of battery wear")
+            .visible(false)
+            .build();
+        lenovo_group.add(&rapid_charge_row);
+
+        let fn_lock_row = adw::SwitchRow::builder()
+            .title("Fn Lock")
+            .subtitle("Use F1-F12 without holding Fn")
+            .visible(false)
+            .build();
+        lenovo_group.add(&fn_lock_row);
+
+        let power_mode_combo = adw::ComboRow::builder()
+            .title("Power Mode")
+            .subtitle("Firmware fan and power limits")
+            .visible(false)
+            .build();
+        lenovo_group.add(&power_mode_combo);
+
+        (lenovo_group, conservation_row, fn_lock_row, rapid_charge_row, power_mode_combo)
+    }
+
+    fn build_thinkpad_group() -> (adw::PreferencesGroup, adw::SpinRow, Button, adw::ComboRow) {
+        let thinkpad_group = adw::PreferencesGroup::builder()
+            .title("ThinkPad")
+            .description("Battery thresholds and fan control from thinkpad_acpi.")
+            .build();
+
+        let charge_start_spin = adw::SpinRow::with_range(
+            thinkpad::MIN_START_THRESHOLD as f64,
+            thinkpad::MAX_START_THRESHOLD as f64,
+            5.0,
+        );
+        charge_start_spin.set_title("Start Charging Below");
+        charge_start_spin.set_subtitle("Avoid topping up a nearly full battery");
+        charge_start_spin.set_visible(false);
+        let charge_start_btn = Button::builder()
+            .label("Apply")
+            .valign(Align::Center)
+            .build();
+        charge_start_spin.add_suffix(&charge_start_btn);
+        thinkpad_group.add(&charge_start_spin);
+
+        let labels: Vec<String> = FanLevel::choices().into_iter().map(FanLevel::label).collect();
+        let labels: Vec<&str> = labels.iter().map(|l| l.as_str()).collect();
+        let fan_combo = adw::ComboRow::builder()
+            .title("Fan Level")
+            .model(&StringList::new(&labels))
+            .visible(false)
+            .build();
+        thinkpad_group.add(&fan_combo);
+
+        (thinkpad_group, charge_start_spin, charge_start_btn, fan_combo)
+    }
+
+    fn setup_lenovo(&self, state: &AppState) {
+        if !lenovo::loaded() {
+            if let Some(group) = self.conservation_row.ancestor(adw::PreferencesGroup::static_type()) {
+                group.set_visible(false);
+            }
+            return;
+        }
+
+        let rows = [
+            (LenovoFeature::ConservationMode, "conservation-mode", &self.conservation_row),
+            (LenovoFeature::RapidCharge, "rapid-charge", &self.rapid_charge_row),
+            (LenovoFeature::FnLock, "fn-lock", &self.fn_lock_row),
+        ];
+
+        for (feature, property, row) in rows {
+            let Some(enabled) = lenovo::feature(feature) else {
+                continue;
+            };
+            state.set_property(property, enabled);
+            row.set_visible(true);
+
+            bind_switch(
+                state,
+                property,
+                row,
+                &format!("{} change failed", row.title()),
+                move |enabled| lenovo::apply_feature(feature, enabled),
+                |_, ()| {},
+            );
+
+            if let Some(reason) = state.read_only_reason() {
+                row.set_sensitive(false);
+                row.set_tooltip_text(Some(&reason));
+            }
+        }
+
+        self.setup_power_mode(state);
+    }
+
+    /// Power mode maps onto the ACPI platform profile, which both Lenovo
+    /// drivers register.
+    fn setup_power_mode(&self, state: &AppState) {
+        let choices = profiles::platform_profile_choices();
+        if choices.is_empty() {
+            return;
+        }
+
+        let labels: Vec<&str> = choices.iter().map(|c| c.as_str()).collect();
+        self.power_mode_combo.set_model(Some(&StringList::new(&labels)));
+        state.set_power_mode(profiles::platform_profile().unwrap_or_default());
+        self.power_mode_combo.set_visible(true);
+
+        if let Some(reason) = state.read_only_reason() {
+            self.power_mode_combo.set_sensitive(false);
+            self.power_mode_combo.set_tooltip_text(Some(&reason));
+        }
+
+        bind_choice(
+            state,
+            "power-mode",
+            &self.power_mode_combo,
+            choices.into_iter().map(|profile| (profile.clone(), profile)).collect(),
+            "Power mode change failed",
+            |profile| profiles::apply_platform_profile(&profile),
+        );
+    }
+
+    fn setup_thinkpad(&self, state: &AppState) {
+        if !thinkpad::present() {
+            if let Some(group) = self.fan_combo.ancestor(adw::PreferencesGroup::static_type()) {
+                group.set_visible(false);
+            }
+            return;
+        }
+
+        if let Some(start) = thinkpad::start_threshold() {
+            self.charge_start_spin.set_value(start as f64);
+            self.charge_start_spin.set_visible(true);
+
+            if let Some(reason) = state.read_only_reason() {
+                self.charge_start_spin.set_sensitive(false);
+                self.charge_start_spin.set_tooltip_text(Some(&reason));
+            }
+
+            self.charge_start_btn.connect_clicked(clone!(
+                #[strong(rename_to = page)] self,
+                #[strong] state,
+                move |btn| {
+                    let percent = page.charge_start_spin.value() as u32;
+                    btn.set_sensitive(false);
+
+                    let state = state.clone();
+                    let btn = btn.clone();
+                    glib::spawn_future_local(async move {
+                        let result = gio::spawn_blocking(move || {
+                            thinkpad::apply_start_threshold(percent)
+                        }).await;
+
+                        btn.set_sensitive(true);
+
+                        match result {
+                            Ok(Ok(())) => state.toast(&format!("Charging resumes below {}%", percent)),
+                            Ok(Err(e)) => state.toast(&format!("Start threshold change failed: {}", e)),
+                            Err(_) => state.toast("Start threshold change failed"),
+                        }
+                    });
+                }
+            ));
+        }
+
+        self.setup_thinkpad_fan(state);
+    }
+
+    fn setup_thinkpad_fan(&self, state: &AppState) {
+        let Some(status) = thinkpad::fan_status() else {
+            return;
+        };
+        state.set_fan_level(status.level.label());
+        self.fan_combo.set_visible(true);
+        self.update_fan_speed();
+
+        let choices = FanLevel::choices().into_iter().map(|level| (level.label(), level)).collect();
+        bind_choice(
+            state,
+            "fan-level",
+            &self.fan_combo,
+            choices,
+            "Fan level change failed",
+            thinkpad::apply_fan_level,
+        );
+
+        if let Some(reason) = state.read_only_reason() {
+            self.fan_combo.set_sensitive(false);
+            self.fan_combo.set_tooltip_text(Some(&reason));
+            return;
+        } else if !status.controllable {
+            self.fan_combo.set_sensitive(false);
+            self.fan_combo
+                .set_tooltip_text(Some("Load thinkpad_acpi with fan_control=1 to set fan levels"));
+            return;
+        }
+
+        // A manual level is a fixed fan speed; if the CPU runs hot anyway,
+        // hand control back to the EC.
+        glib::timeout_add_seconds_local(
+            FAN_GUARD_INTERVAL_SECS,
+            clone!(
+                #[strong(rename_to = page)] self,
+                #[strong] state,
+                move || {
+                    page.update_fan_speed();
+
+                    let manual = state.fan_level() != FanLevel::Auto.label();
+                    let hot = thermal::cpu_temperature()
+                        .filter(|celsius| *celsius > thinkpad::FAN_SAFETY_TEMP_C);
+                    if let (true, Some(celsius)) = (manual, hot) {
+                        // Routes through the combo handler, which applies it.
+                        page.fan_combo.set_selected(0);
+                        state.toast(&format!("CPU at {:.0}°C; fan returned to automatic control", celsius));
+                    }
+                    glib::ControlFlow::Continue
+                }
+            ),
+        );
+    }
+
+    fn update_fan_speed(&self) {
+        let subtitle = thinkpad::fan_status()
+            .and_then(|status| status.speed_rpm)
+            .map(|rpm| format!("{} RPM", rpm))
+            .unwrap_or_default();
+        self.fan_combo.set_subtitle(&subtitle);
+    }
+
+    fn setup_firmware_attributes(&self, state: &AppState) {
+        let attributes = firmware::attributes();
+        self.firmware_group
+            .set_visible(!attributes.is_empty() && remote::host().is_none());
+
+        for attribute in attributes {
+            let row = match attribute.kind.clone() {
+                AttributeKind::Enumeration(values) => build_firmware_combo(state, attribute, values),
+                AttributeKind::Integer { min, max, step } => {
+                    build_firmware_spin(state, attribute, min, max, step)
+                }
+            };
+
+            if let Some(reason) = state.read_only_reason() {
+                row.set_sensitive(false);
+                row.set_tooltip_text(Some(&reason));
+            }
+            self.firmware_group.add(&row);
+        }
+    }
+}
+
+fn build_firmware_combo(state: &AppState, attribute: FirmwareAttribute, values: Vec<String>) -> gtk4::Widget {
+    let labels: Vec<&str> = values.iter().map(|v| v.as_str()).collect();
+    let combo = adw::ComboRow::builder()
+        .title(glib::markup_escape_text(&attribute.display_name))
+        .subtitle(&attribute.driver)
+        .model(&StringList::new(&labels))
+        .build();
+    if let Some(idx) = values.iter().position(|v| *v == attribute.current) {
+        combo.set_selected(idx as u32);
+    }
+
+    let previous = Rc::new(Cell::new(combo.selected()));
+    combo.connect_selected_notify(clone!(
+        #[strong] state,
+        move |combo| {
+            if combo.selected() == previous.get() {
+                return;
+            }
+            let Some(value) = values.get(combo.selected() as usize).cloned() else {
+                return;
+            };
+
+            let revert_to = previous.get();
+            previous.set(combo.selected());
+            apply_firmware_attribute(
+                &state,
+                attribute.clone(),
+                value,
+                combo.clone().upcast(),
+                clone!(
+                    #[strong] combo,
+                    #[strong] previous,
+                    move || {
+                        previous.set(revert_to);
+                        combo.set_selected(revert_to);
+                    }
+                ),
+            );
+        }
+    ));
+
+    combo.upcast()
+}
+
+fn build_firmware_spin(state: &AppState, attribute: FirmwareAttribute, min: i64, max: i64, step: i64) -> gtk4::Widget {
+    let row = adw::ActionRow::builder()
+        .title(glib::markup_escape_text(&attribute.display_name))
+        .subtitle(&attribute.driver)
+        .build();
+
+    let spin = gtk4::SpinButton::with_range(min as f64, max as f64, step as f64);
+    spin.set_valign(Align::Center);
+    spin.set_value(attribute.current.parse::<f64>().unwrap_or(min as f64));
+    row.add_suffix(&spin);
+
+    let apply_btn = Button::builder()
+        .label("Apply")
+        .valign(Align::Center)
+        .build();
+    row.add_suffix(&apply_btn);
+
+    let applied = Rc::new(Cell::new(spin.value()));
+    apply_btn.connect_clicked(clone!(
+        #[strong] state,
+        #[strong] row,
+        #[strong] spin,
+        move |_| {
+            let value = spin.value();
+            if value == applied.get() {
+                return;
+            }
+
+            let revert_to = applied.get();
+            applied.set(value);
+            apply_firmware_attribute(
+                &state,
+                attribute.clone(),
+                (value as i64).to_string(),
+                row.clone().upcast(),
+                clone!(
+                    #[strong] spin,
+                    #[strong] applied,
+                    move || {
+                        applied.set(revert_to);
+                        spin.set_value(revert_to);
+                    }
+                ),
+            );
+        }
+    ));
+
+    row.upcast()
+}
+
+/// Writes a firmware attribute off the main thread, calling `revert`
+/// if the helper refuses.
+fn apply_firmware_attribute(
+    state: &AppState,
+    attribute: FirmwareAttribute,
+    value: String,
+    row: gtk4::Widget,
+    revert: impl FnOnce() + 'static,
+) {
+    row.set_sensitive(false);
+
+    let state = state.clone();
+    glib::spawn_future_local(async move {
+        let name = attribute.display_name.clone();
+        let result = gio::spawn_blocking(move || {
+            firmware::apply_attribute(&attribute, &value)
+        }).await;
+
+        row.set_sensitive(true);
+
+        if !matches!(result, Ok(Ok(()))) {
+            let message = match result {
+                Ok(Err(e)) => format!("{} change failed: {}", name, e),
+                _ => format!("{} change failed", name),
+            };
+            state.toast(&message);
+            revert();
+        }
+    });
+}
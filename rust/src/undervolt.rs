@@ -0,0 +1,135 @@
+use crate::remote;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Deepest offset offered; the helper enforces the same bound.
+pub const MIN_OFFSET_MV: i32 = -150;
+pub const MAX_OFFSET_MV: i32 = 0;
+/// How long new offsets are hammered before they are saved.
+pub const STRESS_TEST_SECS: u64 = 30;
+
+const CONFIG_PATH: &str = "/etc/tuxtuner/undervolt.conf";
+/// Left behind by the boot service when it rolled offsets back.
+const REJECTED_PATH: &str = "/etc/tuxtuner/undervolt.conf.rejected";
+
+/// Core and cache voltage offsets in mV; negative undervolts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Offsets {
+    pub core_mv: i32,
+    pub cache_mv: i32,
+}
+
+fn parse_offsets(content: &str) -> Option<Offsets> {
+    let mut values = content.split_whitespace().map(|v| v.parse::<i32>().ok());
+    Some(Offsets {
+        core_mv: values.next()??,
+        cache_mv: values.next()??,
+    })
+}
+
+/// Intel CPUs with msr-tools installed. Many newer models lock the
+/// voltage MSR after Plundervolt; writes then silently do nothing.
+pub fn supported() -> bool {
+    let intel = remote::read_to_string("/proc/cpuinfo")
+        .map(|info| info.lines().any(|line| line.starts_with("vendor_id") && line.contains("GenuineIntel")))
+        .unwrap_or(false);
+    intel && remote::command_exists("wrmsr")
+}
+
+/// Offsets reapplied at boot, if any.
+pub fn saved() -> Option<Offsets> {
+    parse_offsets(&remote::read_to_string(CONFIG_PATH).ok()?)
+}
+
+/// Offsets the boot service dropped because the machine didn't shut down
+/// cleanly while they were active: the saved ones, or unsaved ones that
+/// were being tested.
+pub fn rolled_back() -> Option<Offsets> {
+    parse_offsets(&remote::read_to_string(REJECTED_PATH).ok()?)
+}
+
+fn validate(offsets: Offsets) -> Result<(), String> {
+    for offset in [offsets.core_mv, offsets.cache_mv] {
+        if !(MIN_OFFSET_MV..=MAX_OFFSET_MV).contains(&offset) {
+            return Err(format!(
+                "Offsets must be between {} and {} mV",
+                MIN_OFFSET_MV, MAX_OFFSET_MV
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// One deterministic unit of mixed integer and floating point work.
+fn stress_round(seed: u64) -> u64 {
+    let mut state = seed;
+    let mut acc = 1.0f64;
+    for i in 0..200_000u64 {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        acc = (acc * 1.000_000_1 + (state >> 40) as f64).sqrt() + (i as f64).sin();
+    }
+    state ^ acc.to_bits()
+}
+
+/// Loads every CPU for `duration`, checking that each thread keeps
+/// computing the same results. An unstable undervolt shows up as a
+/// mismatch long before it corrupts anything that matters. Setting
+/// `cancel` stops every thread early and fails the test.
+pub fn stress_test(duration: Duration, cancel: &Arc<AtomicBool>) -> Result<(), String> {
+    let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let expected = stress_round(threads as u64);
+    let deadline = Instant::now() + duration;
+
+    let handles: Vec<_> = (0..threads)
+        .map(|_| {
+            let cancel = Arc::clone(cancel);
+            thread::spawn(move || {
+                while Instant::now() < deadline && !cancel.load(Ordering::Relaxed) {
+                    if stress_round(threads as u64) != expected {
+                        return false;
+                    }
+                }
+                true
+            })
+        })
+        .collect();
+
+    let results: Vec<bool> = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap_or(false))
+        .collect();
+    let stable = results.into_iter().all(|ok| ok);
+    if cancel.load(Ordering::Relaxed) {
+        Err("Test cancelled".to_string())
+    } else if stable {
+        Ok(())
+    } else {
+        Err("Calculation errors under load".to_string())
+    }
+}
+
+/// Applies `offsets`, stress-tests them and saves them only if the machine
+/// stays stable; otherwise goes back to the saved offsets. A crash during
+/// the test leaves the saved offsets in place for the next boot.
+/// Cancelling counts as a failed test. Blocking.
+pub fn test_and_apply(offsets: Offsets, cancel: Arc<AtomicBool>) -> Result<(), String> {
+    validate(offsets)?;
+    let core = offsets.core_mv.to_string();
+    let cache = offsets.cache_mv.to_string();
+
+    remote::run_helper(&["undervolt", &core, &cache])?;
+
+    if let Err(e) = stress_test(Duration::from_secs(STRESS_TEST_SECS), &cancel) {
+        let _ = remote::run_helper(&["undervolt-revert"]);
+        return Err(format!("{}; undervolt rolled back", e));
+    }
+
+    remote::run_helper(&["undervolt-save", &core, &cache])
+}
+
+/// Back to stock voltages, forgetting any saved offsets.
+pub fn reset() -> Result<(), String> {
+    remote::run_helper(&["undervolt-reset"])
+}
@@ -0,0 +1,81 @@
+use crate::system_info::command_exists;
+use gtk4::gio;
+use gtk4::prelude::*;
+
+/// A graphical app that installs updates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Updater {
+    pub name: &'static str,
+    command: &'static str,
+    /// Installs firmware from fwupd as well as packages.
+    firmware: bool,
+    packages: bool,
+}
+
+/// In order of preference.
+const UPDATERS: &[Updater] = &[
+    Updater {
+        name: "GNOME Software",
+        command: "gnome-software --mode=updates",
+        firmware: true,
+        packages: true,
+    },
+    Updater {
+        name: "Discover",
+        command: "plasma-discover --mode update",
+        firmware: true,
+        packages: true,
+    },
+    Updater {
+        name: "Firmware",
+        command: "gnome-firmware",
+        firmware: true,
+        packages: false,
+    },
+    Updater {
+        name: "Software Updater",
+        command: "update-manager",
+        firmware: false,
+        packages: true,
+    },
+    Updater {
+        name: "Update Manager",
+        command: "mintupdate",
+        firmware: false,
+        packages: true,
+    },
+    Updater {
+        name: "Pamac",
+        command: "pamac-manager --updates",
+        firmware: false,
+        packages: true,
+    },
+];
+
+impl Updater {
+    fn program(&self) -> &'static str {
+        self.command.split_whitespace().next().unwrap_or(self.command)
+    }
+
+    /// Starts the updater on this machine's desktop.
+    pub fn launch(&self) -> Result<(), String> {
+        let app = gio::AppInfo::create_from_commandline(
+            self.command,
+            Some(self.name),
+            gio::AppInfoCreateFlags::SUPPORTS_STARTUP_NOTIFICATION,
+        )
+        .map_err(|e| e.message().to_string())?;
+        app.launch(&[], None::<&gio::AppLaunchContext>)
+            .map_err(|e| e.message().to_string())
+    }
+}
+
+/// The installed updater for firmware, or for packages such as the
+/// kernel.
+pub fn find(firmware: bool) -> Option<Updater> {
+    UPDATERS
+        .iter()
+        .filter(|updater| if firmware { updater.firmware } else { updater.packages })
+        .find(|updater| command_exists(updater.program()))
+        .copied()
+}
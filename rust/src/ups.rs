@@ -0,0 +1,44 @@
+use crate::probe;
+use once_cell::sync::Lazy;
+
+/// The UPS monitoring daemon found at startup, if any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Monitor {
+    /// Network UPS Tools, with the name of the first configured UPS.
+    Nut(String),
+    Apcupsd,
+}
+
+static MONITOR: Lazy<Option<Monitor>> = Lazy::new(detect);
+
+fn stdout_of(program: &str, args: &[&str]) -> Option<String> {
+    let output = probe::run(program, args).ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn detect() -> Option<Monitor> {
+    if let Some(name) = stdout_of("upsc", &["-l"]).and_then(|list| list.lines().next().map(String::from)) {
+        return Some(Monitor::Nut(name));
+    }
+    stdout_of("apcaccess", &["-p", "STATUS"]).map(|_| Monitor::Apcupsd)
+}
+
+/// Whether the UPS feeding this machine runs from its battery, or `None`
+/// without a UPS daemon to ask. Lets desktops on a UPS follow the same
+/// battery rules as laptops during an outage.
+pub fn on_battery() -> Option<bool> {
+    match MONITOR.as_ref()? {
+        // NUT status flags: OL on line, OB on battery, LB low battery.
+        Monitor::Nut(name) => {
+            let status = stdout_of("upsc", &[name, "ups.status"])?;
+            Some(status.split_whitespace().any(|flag| flag == "OB"))
+        }
+        Monitor::Apcupsd => {
+            let status = stdout_of("apcaccess", &["-p", "STATUS"])?;
+            Some(status.contains("ONBATT"))
+        }
+    }
+}
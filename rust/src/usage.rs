@@ -0,0 +1,122 @@
+use crate::battery_history::unix_now;
+use crate::config::state_dir;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+const USAGE_FILE: &str = "usage.json";
+/// Battery time needed before a share is worth quoting.
+const MIN_BATTERY_SECS: u64 = 3600;
+/// Builtin profiles and the lighter one to suggest when most battery
+/// time goes to them.
+const LIGHTER_PROFILES: [(&str, &str); 2] = [("Performance", "Balanced"), ("Balanced", "Battery Saver")];
+/// Share of battery time above which a lighter profile is suggested.
+const SUGGEST_SHARE: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileUsage {
+    pub active_secs: u64,
+    /// The part of `active_secs` spent unplugged.
+    pub battery_secs: u64,
+}
+
+/// How long each profile has been active, kept only in the state
+/// directory and only while `Config::usage_stats` is on.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Usage {
+    /// When counting started, as unix time.
+    pub since: i64,
+    pub profiles: BTreeMap<String, ProfileUsage>,
+}
+
+fn usage_path() -> PathBuf {
+    state_dir().join(USAGE_FILE)
+}
+
+impl Usage {
+    pub fn load() -> Self {
+        fs::read_to_string(usage_path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::create_dir_all(state_dir()).map_err(|e| e.to_string())?;
+        fs::write(usage_path(), content).map_err(|e| e.to_string())
+    }
+
+    pub fn battery_secs(&self) -> u64 {
+        self.profiles.values().map(|usage| usage.battery_secs).sum()
+    }
+
+    /// Profiles by battery time, most used first, with their share of it.
+    pub fn battery_shares(&self) -> Vec<(&str, f64)> {
+        let total = self.battery_secs();
+        if total == 0 {
+            return Vec::new();
+        }
+        let mut shares: Vec<(&str, f64)> = self
+            .profiles
+            .iter()
+            .filter(|(_, usage)| usage.battery_secs > 0)
+            .map(|(name, usage)| (name.as_str(), usage.battery_secs as f64 / total as f64))
+            .collect();
+        shares.sort_by(|a, b| b.1.total_cmp(&a.1));
+        shares
+    }
+
+    /// One line about where battery time goes, suggesting a lighter
+    /// profile when a heavy one dominates. `None` until there's an hour
+    /// of battery use to go on.
+    pub fn insight(&self) -> Option<String> {
+        if self.battery_secs() < MIN_BATTERY_SECS {
+            return None;
+        }
+        let (name, share) = *self.battery_shares().first()?;
+        let summary = format!("You spent {:.0}% of battery time in {}", share * 100.0, name);
+        let lighter = LIGHTER_PROFILES
+            .iter()
+            .find(|(heavy, _)| *heavy == name)
+            .map(|(_, lighter)| *lighter);
+        match lighter {
+            Some(lighter) if share > SUGGEST_SHARE => Some(format!("{} — consider {}", summary, lighter)),
+            _ => Some(summary),
+        }
+    }
+}
+
+/// Adds `secs` of activity to `profile`.
+pub fn record(profile: &str, secs: u64, on_battery: bool) -> Result<(), String> {
+    let mut usage = Usage::load();
+    if usage.since == 0 {
+        usage.since = unix_now();
+    }
+    let entry = usage.profiles.entry(profile.to_string()).or_default();
+    entry.active_secs += secs;
+    if on_battery {
+        entry.battery_secs += secs;
+    }
+    usage.save()
+}
+
+pub fn clear() -> Result<(), String> {
+    match fs::remove_file(usage_path()) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// "3h 20m", or "12m" under an hour.
+pub fn format_duration(secs: u64) -> String {
+    let minutes = secs / 60;
+    if minutes < 60 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}h {}m", minutes / 60, minutes % 60)
+    }
+}
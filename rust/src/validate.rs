@@ -0,0 +1,136 @@
+use crate::cpufreq::Governor;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Refresh rates Hyprland is asked for; anything outside is a typo or a
+/// corrupt config.
+pub const MIN_REFRESH_HZ: u32 = 30;
+pub const MAX_REFRESH_HZ: u32 = 500;
+
+static MONITOR_NAME_PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[A-Za-z0-9_-]+$").unwrap());
+
+/// Connector names like `eDP-1`, which end up in hyprctl arguments.
+pub fn monitor_name(name: &str) -> Result<(), String> {
+    if MONITOR_NAME_PATTERN.is_match(name) {
+        Ok(())
+    } else {
+        Err(format!("Invalid monitor name: {}", name))
+    }
+}
+
+pub fn refresh_hz(hz: u32) -> Result<(), String> {
+    if (MIN_REFRESH_HZ..=MAX_REFRESH_HZ).contains(&hz) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Refresh rate {}Hz is outside {}-{}Hz",
+            hz, MIN_REFRESH_HZ, MAX_REFRESH_HZ
+        ))
+    }
+}
+
+/// A rate as Hyprland reports it, e.g. `59.951`, rounded to the whole
+/// hertz it's asked for.
+pub fn refresh_rate(hz: f64) -> Result<u32, String> {
+    if !hz.is_finite() {
+        return Err(format!("Invalid refresh rate: {}", hz));
+    }
+    let rounded = hz.round().clamp(0.0, u32::MAX as f64) as u32;
+    refresh_hz(rounded)?;
+    Ok(rounded)
+}
+
+/// Online thread count for a request of `requested`, where 0 means all
+/// and larger requests are capped to the `total` the CPU has.
+pub fn cpu_threads(requested: u32, total: u32) -> u32 {
+    let total = total.max(1);
+    if requested == 0 {
+        total
+    } else {
+        requested.clamp(1, total)
+    }
+}
+
+/// A governor name from a profile, before it reaches the helper.
+pub fn governor(name: &str) -> Result<Governor, String> {
+    name.parse()
+}
+
+/// A `[user@]host` for ssh; one starting with `-` would be read as an
+/// option.
+pub fn remote_host(host: &str) -> Result<(), String> {
+    if host.is_empty() || host.starts_with('-') || host.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        Err(format!("Invalid remote host: {}", host))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_connector_names() {
+        for name in ["DP-1", "eDP-1", "HDMI-A-1", "DVI_D_1"] {
+            assert!(monitor_name(name).is_ok(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn rejects_names_that_would_break_hyprctl_arguments() {
+        for name in ["", "DP 1", "DP-1;reboot", "DP-1,1920x1080", "eDP-1\n", "$(id)"] {
+            assert!(monitor_name(name).is_err(), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn refresh_hz_bounds_are_inclusive() {
+        assert!(refresh_hz(MIN_REFRESH_HZ).is_ok());
+        assert!(refresh_hz(MAX_REFRESH_HZ).is_ok());
+        assert!(refresh_hz(MIN_REFRESH_HZ - 1).is_err());
+        assert!(refresh_hz(MAX_REFRESH_HZ + 1).is_err());
+        assert!(refresh_hz(0).is_err());
+    }
+
+    #[test]
+    fn refresh_rate_rounds_and_rejects_non_finite() {
+        assert_eq!(refresh_rate(59.951), Ok(60));
+        assert_eq!(refresh_rate(143.856), Ok(144));
+        assert!(refresh_rate(f64::NAN).is_err());
+        assert!(refresh_rate(f64::INFINITY).is_err());
+        assert!(refresh_rate(-60.0).is_err());
+        assert!(refresh_rate(29.4).is_err());
+        assert!(refresh_rate(500.6).is_err());
+    }
+
+    #[test]
+    fn cpu_threads_caps_to_the_cpu() {
+        assert_eq!(cpu_threads(0, 16), 16);
+        assert_eq!(cpu_threads(4, 16), 4);
+        assert_eq!(cpu_threads(16, 16), 16);
+        assert_eq!(cpu_threads(17, 16), 16);
+        assert_eq!(cpu_threads(u32::MAX, 16), 16);
+        assert_eq!(cpu_threads(0, 0), 1);
+        assert_eq!(cpu_threads(3, 0), 1);
+    }
+
+    #[test]
+    fn governor_accepts_only_kernel_names() {
+        assert_eq!(governor("schedutil"), Ok(Governor::Schedutil));
+        assert_eq!(governor("powersave\n"), Ok(Governor::Powersave));
+        for name in ["", "Performance", "turbo", "performance;reboot", "performance powersave"] {
+            assert!(governor(name).is_err(), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn remote_host_rejects_ssh_options() {
+        for host in ["htpc", "user@htpc", "user@192.168.1.20", "htpc.local"] {
+            assert!(remote_host(host).is_ok(), "{}", host);
+        }
+        for host in ["", "-oProxyCommand=id", "-J", "user@htpc id", "htpc\n"] {
+            assert!(remote_host(host).is_err(), "{:?}", host);
+        }
+    }
+}
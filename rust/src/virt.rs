@@ -0,0 +1,73 @@
+use crate::probe;
+use crate::remote;
+use once_cell::sync::Lazy;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VirtKind {
+    /// A full virtual machine: the kernel is ours, but the hardware is
+    /// emulated except for what's passed through (often a GPU).
+    Vm,
+    /// A container sharing the host's kernel, whose sysfs it can't write.
+    Container,
+}
+
+/// Virtualization the tuned machine runs under.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Virtualization {
+    pub kind: VirtKind,
+    /// The technology as systemd-detect-virt names it, e.g. "kvm" or
+    /// "docker".
+    pub name: String,
+}
+
+static VIRTUALIZATION: Lazy<Option<Virtualization>> = Lazy::new(detect);
+
+/// The virtualization detected at startup; `None` on bare metal.
+pub fn current() -> Option<&'static Virtualization> {
+    VIRTUALIZATION.as_ref()
+}
+
+impl Virtualization {
+    /// Short description such as "virtual machine (kvm)".
+    pub fn label(&self) -> String {
+        match self.kind {
+            VirtKind::Vm => format!("virtual machine ({})", self.name),
+            VirtKind::Container => format!("container ({})", self.name),
+        }
+    }
+}
+
+fn detect() -> Option<Virtualization> {
+    // systemd-detect-virt prints "none" and fails on bare metal.
+    if let Ok(output) = probe::run("systemd-detect-virt", &[]) {
+        let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if name == "none" {
+            return None;
+        }
+        if output.status.success() && !name.is_empty() {
+            let container = probe::run("systemd-detect-virt", &["--container"])
+                .is_ok_and(|output| output.status.success());
+            let kind = if container { VirtKind::Container } else { VirtKind::Vm };
+            return Some(Virtualization { kind, name });
+        }
+    }
+
+    // Without systemd: the container engines' marker files, then the
+    // CPU flag hypervisors set.
+    for (marker, name) in [("/.dockerenv", "docker"), ("/run/.containerenv", "podman")] {
+        if remote::exists(marker) {
+            return Some(Virtualization {
+                kind: VirtKind::Container,
+                name: name.to_string(),
+            });
+        }
+    }
+    let cpuinfo = remote::read_to_string("/proc/cpuinfo").unwrap_or_default();
+    cpuinfo
+        .lines()
+        .any(|line| line.starts_with("flags") && line.split_whitespace().any(|flag| flag == "hypervisor"))
+        .then(|| Virtualization {
+            kind: VirtKind::Vm,
+            name: "unknown".to_string(),
+        })
+}
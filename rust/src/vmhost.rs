@@ -0,0 +1,189 @@
+use crate::affinity;
+use crate::immutable::{self, Immutable};
+use crate::remote;
+use crate::system_info::command_exists;
+use std::path::Path;
+
+const CPU_PATH: &str = "/sys/devices/system/cpu";
+/// CPU list of the efficiency cores on Intel hybrid CPUs.
+const EFFICIENCY_CORES_PATH: &str = "/sys/devices/cpu_atom/cpus";
+/// From this many physical cores, the host keeps two instead of one.
+const HOST_TWO_CORES_FROM: usize = 8;
+/// Share of RAM huge pages may take; keep in sync with the helper's
+/// `MAX_HUGEPAGES_PERCENT`.
+const MAX_HUGEPAGES_PERCENT: u64 = 90;
+
+/// A split of the CPUs between the host and a VM's vCPUs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CpuSplit {
+    /// CPUs left to the host, its interrupts and QEMU's own threads.
+    pub host: Vec<u32>,
+    /// Physical cores for the VM, each as its sibling threads.
+    pub vm_cores: Vec<Vec<u32>>,
+}
+
+fn read_cpu_list(path: impl AsRef<Path>) -> Vec<u32> {
+    remote::read_to_string(path)
+        .map(|list| affinity::parse_cpu_list(&list))
+        .unwrap_or_default()
+}
+
+/// Online CPUs grouped into physical cores.
+fn cores() -> Vec<Vec<u32>> {
+    let online = read_cpu_list(Path::new(CPU_PATH).join("online"));
+    online
+        .iter()
+        .filter_map(|&cpu| {
+            let siblings: Vec<u32> = read_cpu_list(
+                Path::new(CPU_PATH)
+                    .join(format!("cpu{}", cpu))
+                    .join("topology/thread_siblings_list"),
+            )
+            .into_iter()
+            .filter(|sibling| online.contains(sibling))
+            .collect();
+            match siblings.first() {
+                None => Some(vec![cpu]),
+                Some(&first) => (first == cpu).then_some(siblings),
+            }
+        })
+        .collect()
+}
+
+/// Whole cores for a VM: the performance cores on hybrid CPUs, otherwise
+/// all but the first one or two, which the host keeps.
+pub fn suggest_split() -> CpuSplit {
+    let mut cores = cores();
+    let efficiency = read_cpu_list(EFFICIENCY_CORES_PATH);
+
+    let (mut host, vm_cores): (Vec<Vec<u32>>, Vec<Vec<u32>>) =
+        if cores.iter().any(|core| efficiency.contains(&core[0])) && cores.iter().any(|core| !efficiency.contains(&core[0])) {
+            cores.into_iter().partition(|core| efficiency.contains(&core[0]))
+        } else {
+            let keep = if cores.len() >= HOST_TWO_CORES_FROM { 2 } else { 1 };
+            let vm_cores = cores.split_off(keep.min(cores.len()));
+            (cores, vm_cores)
+        };
+    host.sort();
+
+    CpuSplit {
+        host: host.concat(),
+        vm_cores,
+    }
+}
+
+/// CPUs the running kernel was told to isolate with `isolcpus`.
+pub fn isolated() -> Vec<u32> {
+    read_cpu_list(Path::new(CPU_PATH).join("isolated"))
+}
+
+impl CpuSplit {
+    pub fn vm_cpus(&self) -> Vec<u32> {
+        let mut cpus = self.vm_cores.concat();
+        cpus.sort_unstable();
+        cpus
+    }
+
+    /// Kernel parameters keeping the scheduler, timer ticks and RCU
+    /// callbacks off the VM's CPUs.
+    pub fn kernel_args(&self) -> String {
+        let cpus = affinity::format_cpu_list(&self.vm_cpus());
+        format!("isolcpus=managed_irq,domain,{cpus} nohz_full={cpus} rcu_nocbs={cpus}", cpus = cpus)
+    }
+
+    /// libvirt domain XML pinning one vCPU per VM thread, siblings next to
+    /// each other so the guest sees the same core layout.
+    pub fn domain_xml(&self) -> String {
+        let vcpus: Vec<u32> = self.vm_cores.concat();
+        let mut xml = format!("<vcpu placement=\"static\">{}</vcpu>\n<cputune>\n", vcpus.len());
+        for (vcpu, cpu) in vcpus.iter().enumerate() {
+            xml.push_str(&format!("  <vcpupin vcpu=\"{}\" cpuset=\"{}\"/>\n", vcpu, cpu));
+        }
+        xml.push_str(&format!(
+            "  <emulatorpin cpuset=\"{}\"/>\n</cputune>\n",
+            affinity::format_cpu_list(&self.host)
+        ));
+
+        // Mixed core sizes don't fit a single topology line.
+        let threads = self.vm_cores.first().map_or(1, Vec::len);
+        if self.vm_cores.iter().all(|core| core.len() == threads) {
+            xml.push_str(&format!(
+                "<cpu mode=\"host-passthrough\">\n  <topology sockets=\"1\" dies=\"1\" cores=\"{}\" threads=\"{}\"/>\n</cpu>\n",
+                self.vm_cores.len(),
+                threads
+            ));
+        }
+        xml
+    }
+}
+
+/// A command adding `args` to the kernel command line with this system's
+/// tools, or `None` when the bootloader's configuration needs editing.
+pub fn kernel_args_command(args: &str) -> Option<String> {
+    let each = |prefix: &str| {
+        args.split_whitespace()
+            .map(|arg| format!("{}{}", prefix, arg))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    match immutable::current() {
+        Some(Immutable::NixOS) => Some(format!(
+            "boot.kernelParams = [ {} ];",
+            args.split_whitespace()
+                .map(|arg| format!("\"{}\"", arg))
+                .collect::<Vec<_>>()
+                .join(" ")
+        )),
+        Some(Immutable::Ostree) => Some(format!("sudo rpm-ostree kargs {}", each("--append="))),
+        None if command_exists("kernelstub") => Some(format!("sudo kernelstub {}", each("-a "))),
+        None if command_exists("grubby") => Some(format!("sudo grubby --update-kernel=ALL --args=\"{}\"", args)),
+        None => None,
+    }
+}
+
+/// The kernel's pool of default-size huge pages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HugePages {
+    pub total: u64,
+    pub free: u64,
+    pub size_kib: u64,
+    mem_kib: u64,
+}
+
+impl HugePages {
+    /// `None` when the kernel has no huge pages.
+    pub fn read() -> Option<Self> {
+        let meminfo = remote::read_to_string("/proc/meminfo").ok()?;
+        let field = |name: &str| {
+            meminfo
+                .lines()
+                .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+                .and_then(|rest| rest.split_whitespace().next()?.parse::<u64>().ok())
+        };
+        Some(Self {
+            total: field("HugePages_Total")?,
+            free: field("HugePages_Free")?,
+            size_kib: field("Hugepagesize").filter(|&size| size > 0)?,
+            mem_kib: field("MemTotal")?,
+        })
+    }
+
+    pub fn gib(&self, pages: u64) -> f64 {
+        (pages * self.size_kib) as f64 / (1024.0 * 1024.0)
+    }
+
+    pub fn pages_for_gib(&self, gib: u32) -> u64 {
+        u64::from(gib) * 1024 * 1024 / self.size_kib
+    }
+
+    /// Most whole GiB the helper lets huge pages take.
+    pub fn max_gib(&self) -> u32 {
+        (self.mem_kib * MAX_HUGEPAGES_PERCENT / 100 / (1024 * 1024)) as u32
+    }
+}
+
+/// Sets `vm.nr_hugepages`; the kernel may reserve fewer when memory is
+/// fragmented. Lasts until reboot. Blocking.
+pub fn set_hugepages(count: u64) -> Result<(), String> {
+    remote::run_helper(&["hugepages", &count.to_string()])
+}
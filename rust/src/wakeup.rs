@@ -0,0 +1,97 @@
+use crate::remote;
+use std::fs;
+use std::path::Path;
+
+const ACPI_WAKEUP_PATH: &str = "/proc/acpi/wakeup";
+const USB_DEVICES_PATH: &str = "/sys/bus/usb/devices";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WakeupKind {
+    Acpi,
+    Usb,
+}
+
+impl WakeupKind {
+    fn helper_arg(self) -> &'static str {
+        match self {
+            WakeupKind::Acpi => "acpi",
+            WakeupKind::Usb => "usb",
+        }
+    }
+}
+
+/// A device allowed (or not) to wake the system from suspend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WakeupSource {
+    pub kind: WakeupKind,
+    /// ACPI device name (e.g. `XHC0`) or USB port (e.g. `1-2`).
+    pub id: String,
+    pub description: String,
+    pub enabled: bool,
+}
+
+/// Parses `/proc/acpi/wakeup`, e.g. `XHC0  S4  *enabled  pci:0000:03:00.3`.
+pub fn acpi_sources() -> Vec<WakeupSource> {
+    fs::read_to_string(ACPI_WAKEUP_PATH)
+        .unwrap_or_default()
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let status = fields.iter().position(|f| f.starts_with('*'))?;
+            Some(WakeupSource {
+                kind: WakeupKind::Acpi,
+                id: fields.first()?.to_string(),
+                description: fields.get(status + 1).unwrap_or(&"").to_string(),
+                enabled: fields[status] == "*enabled",
+            })
+        })
+        .collect()
+}
+
+fn read_attr(dir: &Path, attr: &str) -> Option<String> {
+    fs::read_to_string(dir.join(attr))
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// USB devices with a wakeup toggle. Root hubs (`usbN`) and interfaces
+/// (`1-2:1.0`) are left out.
+pub fn usb_sources() -> Vec<WakeupSource> {
+    let mut sources: Vec<WakeupSource> = fs::read_dir(USB_DEVICES_PATH)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let id = entry.file_name().to_string_lossy().to_string();
+            if !id.contains('-') || id.contains(':') {
+                return None;
+            }
+
+            let path = entry.path();
+            let wakeup = read_attr(&path, "power/wakeup")?;
+            let description = match (read_attr(&path, "manufacturer"), read_attr(&path, "product")) {
+                (Some(vendor), Some(product)) => format!("{} {}", vendor, product),
+                (None, Some(product)) => product,
+                _ => "Unknown device".to_string(),
+            };
+
+            Some(WakeupSource {
+                kind: WakeupKind::Usb,
+                id,
+                description,
+                enabled: wakeup == "enabled",
+            })
+        })
+        .collect();
+    sources.sort_by(|a, b| a.id.cmp(&b.id));
+    sources
+}
+
+/// Enables or disables waking through `source`; the helper remembers the
+/// choice across reboots.
+pub fn apply_wakeup(source: &WakeupSource, enabled: bool) -> Result<(), String> {
+    let state = if enabled { "enabled" } else { "disabled" };
+    remote::run_helper(&["wakeup", source.kind.helper_arg(), &source.id, state])
+}
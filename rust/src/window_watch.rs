@@ -0,0 +1,103 @@
+use crate::hyprland;
+use crate::probe;
+use gtk4::{gio, glib};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// The focused window as far as automation rules are concerned.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ActiveWindow {
+    pub class: String,
+    pub title: String,
+    pub fullscreen: bool,
+}
+
+fn query_active_window() -> ActiveWindow {
+    let Some(value) = probe::run("hyprctl", &["activewindow", "-j"])
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| serde_json::from_slice::<serde_json::Value>(&output.stdout).ok())
+    else {
+        return ActiveWindow::default();
+    };
+
+    // Older Hyprland reports `fullscreen` as a bool, newer as a mode number.
+    let fullscreen = match &value["fullscreen"] {
+        serde_json::Value::Bool(b) => *b,
+        serde_json::Value::Number(n) => n.as_u64().unwrap_or(0) > 0,
+        _ => false,
+    };
+
+    ActiveWindow {
+        class: value["class"].as_str().unwrap_or_default().to_string(),
+        title: value["title"].as_str().unwrap_or_default().to_string(),
+        fullscreen,
+    }
+}
+
+/// Applies `change` to the tracked window and reports the result if it
+/// differs.
+fn update(current: &RefCell<ActiveWindow>, callback: &dyn Fn(&ActiveWindow), change: impl FnOnce(&mut ActiveWindow)) {
+    let mut window = current.borrow_mut();
+    let previous = window.clone();
+    change(&mut window);
+
+    if *window != previous {
+        let snapshot = window.clone();
+        drop(window);
+        callback(&snapshot);
+    }
+}
+
+/// Calls `callback` with the focused window now and whenever focus or
+/// fullscreen state changes. Returns `false` if no supported compositor
+/// is running. hyprctl is only asked off the main loop, since a busy
+/// compositor can keep it waiting.
+pub fn watch_active_window<F: Fn(&ActiveWindow) + 'static>(callback: F) -> bool {
+    if hyprland::event_socket().is_none() {
+        return false;
+    }
+
+    let current = Rc::new(RefCell::new(ActiveWindow::default()));
+    let callback: Rc<dyn Fn(&ActiveWindow)> = Rc::new(callback);
+
+    {
+        let current = current.clone();
+        let callback = callback.clone();
+        glib::spawn_future_local(async move {
+            let window = gio::spawn_blocking(query_active_window).await.unwrap_or_default();
+            *current.borrow_mut() = window.clone();
+            callback(&window);
+        });
+    }
+
+    hyprland::listen_events(
+        move |event, data| match event {
+            "activewindow" => {
+                // The payload is `class,title`; the title may hold commas.
+                let (class, title) = data.split_once(',').unwrap_or((data, ""));
+                let (class, title) = (class.to_string(), title.to_string());
+                update(&current, &*callback, |window| {
+                    window.class = class.clone();
+                    window.title = title;
+                });
+
+                // Fullscreen state follows the window; ask for it, and keep
+                // the answer only if focus hasn't moved on meanwhile.
+                let current = current.clone();
+                let callback = callback.clone();
+                glib::spawn_future_local(async move {
+                    let queried = gio::spawn_blocking(query_active_window).await.unwrap_or_default();
+                    update(&current, &*callback, |window| {
+                        if window.class == class && queried.class == class {
+                            window.fullscreen = queried.fullscreen;
+                        }
+                    });
+                });
+            }
+            "fullscreen" => update(&current, &*callback, |window| window.fullscreen = data == "1"),
+            _ => {}
+        },
+        || {},
+    )
+}
@@ -0,0 +1,133 @@
+#!/bin/bash
+
+# Tests for tuxtuner-helper --json. Runs unprivileged: the helper is copied
+# with /proc/sys pointed at a scratch directory, and logger is stubbed so
+# nothing reaches the journal.
+# Usage: tests/helper-json.sh
+
+set -euo pipefail
+
+HELPER="$(dirname "$(readlink -f "$0")")/../src/tuxtuner-helper"
+SCRATCH="$(mktemp -d)"
+trap 'rm -rf "$SCRATCH"' EXIT
+
+mkdir -p "$SCRATCH/bin" "$SCRATCH/proc/sys/vm"
+sed "s|/proc/sys/|$SCRATCH/proc/sys/|g" "$HELPER" > "$SCRATCH/tuxtuner-helper"
+printf '#!/bin/sh\ncat > /dev/null\n' > "$SCRATCH/bin/logger"
+chmod +x "$SCRATCH/bin/logger"
+
+FAILURES=0
+
+# Puts the writeback knobs back to 40% and 5 s
+reset_sysctl() {
+    echo 40 > "$SCRATCH/proc/sys/vm/dirty_ratio"
+    echo 500 > "$SCRATCH/proc/sys/vm/dirty_writeback_centisecs"
+}
+
+sysctl_state() {
+    echo "$(<"$SCRATCH/proc/sys/vm/dirty_ratio") $(<"$SCRATCH/proc/sys/vm/dirty_writeback_centisecs")"
+}
+
+# Sends $1 as the request, leaving the reply in REPLY and the exit status
+# in STATUS
+request() {
+    STATUS=0
+    REPLY="$(printf '%s' "$1" | PATH="$SCRATCH/bin:$PATH" bash "$SCRATCH/tuxtuner-helper" --json 2>&1)" || STATUS=$?
+}
+
+pass() {
+    echo "ok - $1"
+}
+
+fail() {
+    echo "FAIL - $1"
+    echo "$REPLY" | sed 's/^/    /'
+    FAILURES=$((FAILURES + 1))
+}
+
+# Checks that the request $2 is refused with an error containing $3 and
+# that nothing ran
+expect_rejected() {
+    local name="$1" body="$2" error="$3"
+    reset_sysctl
+    request "$body"
+    if [[ "$STATUS" -ne 0 && "$REPLY" == *'"ok":false'* && "$REPLY" == *"$error"* && "$(sysctl_state)" == "40 500" ]]; then
+        pass "$name"
+    else
+        fail "$name"
+    fi
+}
+
+expect_rejected "unknown field" \
+    '{"version": 1, "sudo": true, "commands": [["writeback", "20", "10"]]}' \
+    "Unknown request field: sudo"
+expect_rejected "unsupported version" \
+    '{"version": 2, "commands": [["writeback", "20", "10"]]}' \
+    "Unsupported request version: 2"
+expect_rejected "escaped quote stays inside the string" \
+    '{"version": 1, "commands": [["writeback\", \"20", "10"]]}' \
+    'Unknown command: writeback\", \"20'
+expect_rejected "control character" \
+    $'{"version": 1, "commands": [["writeback", "20\x01", "10"]]}' \
+    "control character in string"
+expect_rejected "unicode escape" \
+    '{"version": 1, "commands": [["writeback", "\u0032\u0030", "10"]]}' \
+    "unsupported escape"
+expect_rejected "nested array" \
+    '{"version": 1, "commands": [[["writeback", "20", "10"]]]}' \
+    "expected '\\\"'"
+expect_rejected "number argument" \
+    '{"version": 1, "commands": [["writeback", 20, 10]]}' \
+    "expected '\\\"'"
+expect_rejected "trailing data" \
+    '{"version": 1, "commands": [["writeback", "20", "10"]]} {}' \
+    "trailing data"
+expect_rejected "unterminated string" \
+    '{"version": 1, "commands": [["writeback' \
+    "unterminated string"
+expect_rejected "oversized request" \
+    "{\"version\": 1, \"commands\": [[\"writeback\", \"20\", \"$(head -c 70000 /dev/zero | tr '\0' 1)\"]]}" \
+    "Request too large"
+expect_rejected "option as command" \
+    '{"version": 1, "commands": [["--version"]]}' \
+    "Command not available over JSON: --version"
+expect_rejected "excluded command" \
+    '{"version": 1, "commands": [["latency-hold"]]}' \
+    "Command not available over JSON: latency-hold"
+expect_rejected "irreversible command in an atomic request" \
+    '{"version": 1, "atomic": true, "commands": [["writeback", "20", "10"], ["fstrim"]]}' \
+    "Command can't be rolled back: fstrim"
+
+reset_sysctl
+request '{"version": 1, "commands": [["writeback", "20", "10"]]}'
+if [[ "$STATUS" -eq 0 && "$REPLY" == *'{"index":0,"ok":true'* && "$(sysctl_state)" == "20 1000" ]]; then
+    pass "runs a command"
+else
+    fail "runs a command"
+fi
+
+reset_sysctl
+request '{"version": 1, "atomic": false, "commands": [["writeback", "20", "10"], ["writeback", "99", "10"]]}'
+if [[ "$STATUS" -ne 0 && "$REPLY" == *'"failed":1'* && "$(sysctl_state)" == "20 1000" ]]; then
+    pass "batch stops at the first failure and keeps what ran"
+else
+    fail "batch stops at the first failure and keeps what ran"
+fi
+
+reset_sysctl
+request '{"version": 1, "atomic": true, "commands": [["writeback", "20", "10"], ["writeback", "30", "15"], ["writeback", "99", "10"]]}'
+if [[ "$STATUS" -ne 0 \
+    && "$REPLY" == *'{"index":2,"ok":false'* \
+    && "$REPLY" == *'{"index":1,"rolled_back":true}'*'{"index":0,"rolled_back":true}'* \
+    && "$REPLY" == *'"failed":2'* \
+    && "$(sysctl_state)" == "40 500" ]]; then
+    pass "atomic batch rolls back in reverse order"
+else
+    fail "atomic batch rolls back in reverse order"
+fi
+
+if [[ "$FAILURES" -gt 0 ]]; then
+    echo "$FAILURES failed"
+    exit 1
+fi
+echo "All passed"